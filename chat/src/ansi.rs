@@ -0,0 +1,197 @@
+use std::fmt::Write;
+
+use crate::{component::Format, Component};
+
+/// The SGR parameter of each format flag which terminals support.
+const ANSI_FORMATS: [(Format, u8); 4] = [
+    (Format::BOLD, 1),
+    (Format::ITALIC, 3),
+    (Format::UNDERLINE, 4),
+    (Format::STRIKETHROUGH, 9),
+];
+
+/// The colors a terminal supports, which decides how colors are written as escape codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnsiColors {
+    /// No escape codes are written at all.
+    None,
+    /// Colors are replaced with the nearest color of the 256-color xterm palette.
+    Palette256,
+    /// Colors are written exactly using 24-bit escape codes.
+    TrueColor,
+}
+
+impl AnsiColors {
+    /// Detects the colors supported by the terminal from the environment. Escape codes are
+    /// disabled if `NO_COLOR` is set, and 24-bit colors are only used if `COLORTERM` is
+    /// `truecolor` or `24bit`, otherwise the 256-color palette is used.
+    pub fn detect() -> Self {
+        if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return AnsiColors::None;
+        }
+
+        match std::env::var("COLORTERM").as_deref() {
+            Ok("truecolor" | "24bit") => AnsiColors::TrueColor,
+            _ => AnsiColors::Palette256,
+        }
+    }
+
+    fn color_param(self, (r, g, b): (u8, u8, u8)) -> String {
+        match self {
+            AnsiColors::TrueColor => format!("38;2;{r};{g};{b}"),
+            _ => format!("38;5;{}", nearest_palette_index(r, g, b)),
+        }
+    }
+}
+
+/// The levels of each channel in the 6x6x6 color cube of the 256-color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Returns the index of the closest color in the 256-color palette, choosing between the color
+/// cube and the grayscale ramp. The first 16 colors are skipped since terminals theme them.
+fn nearest_palette_index(r: u8, g: u8, b: u8) -> u8 {
+    fn nearest_level(value: u8) -> usize {
+        (0 .. CUBE_LEVELS.len())
+            .min_by_key(|&index| (CUBE_LEVELS[index] as i32 - value as i32).abs())
+            .unwrap_or(0)
+    }
+
+    fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> i32 {
+        (r1 as i32 - r2 as i32).pow(2)
+            + (g1 as i32 - g2 as i32).pow(2)
+            + (b1 as i32 - b2 as i32).pow(2)
+    }
+
+    let (cr, cg, cb) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube = (CUBE_LEVELS[cr], CUBE_LEVELS[cg], CUBE_LEVELS[cb]);
+
+    // The grayscale ramp runs from 8 to 238 in steps of 10
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_index = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_level = 8 + gray_index * 10;
+
+    if distance((r, g, b), (gray_level, gray_level, gray_level)) < distance((r, g, b), cube) {
+        232 + gray_index
+    } else {
+        16 + (36 * cr + 6 * cg + cb) as u8
+    }
+}
+
+impl Component {
+    /// Renders this component and its children as text with ANSI escape codes, resetting the style
+    /// at the end. The colors used are detected from the environment, see
+    /// [`AnsiColors::detect`].
+    pub fn to_ansi(&self) -> String {
+        self.to_ansi_with(AnsiColors::detect())
+    }
+
+    /// Renders this component and its children as text with ANSI escape codes, writing colors for
+    /// a terminal with the given color support.
+    pub fn to_ansi_with(&self, colors: AnsiColors) -> String {
+        let mut ansi = String::new();
+        let mut current = (None, Format::empty());
+        self.write_ansi(&mut ansi, colors, (None, Format::empty()), &mut current);
+
+        if current != (None, Format::empty()) {
+            ansi.push_str("\x1B[0m");
+        }
+        ansi
+    }
+
+    fn write_ansi(
+        &self,
+        ansi: &mut String,
+        colors: AnsiColors,
+        (parent_color, parent_format): (Option<(u8, u8, u8)>, Format),
+        current: &mut (Option<(u8, u8, u8)>, Format),
+    ) {
+        // Children inherit the style of their parent unless they override it
+        let color = match self.color {
+            Some(color) => color.rgb(),
+            None => parent_color,
+        };
+        let format = parent_format | self.format;
+
+        let text = self.to_plain_node_string();
+        if !text.is_empty() {
+            if colors != AnsiColors::None && (color, format) != *current {
+                let mut params = Vec::new();
+                if *current != (None, Format::empty()) {
+                    params.push("0".to_owned());
+                }
+                if let Some(rgb) = color {
+                    params.push(colors.color_param(rgb));
+                }
+                for (flag, param) in ANSI_FORMATS {
+                    if format.contains(flag) {
+                        params.push(param.to_string());
+                    }
+                }
+
+                if !params.is_empty() {
+                    let _ = write!(ansi, "\x1B[{}m", params.join(";"));
+                }
+                *current = (color, format);
+            }
+
+            ansi.push_str(&text);
+        }
+
+        for child in self.extra.iter().flatten() {
+            child.write_ansi(ansi, colors, (color, format), current);
+        }
+    }
+}
+
+#[test]
+fn render_ansi_styles() {
+    use crate::{color::Color, ComponentBuilder};
+
+    let component = ComponentBuilder::empty()
+        .color(Color::Red)
+        .format(Format::BOLD)
+        .add_text("Error")
+        .build();
+    assert_eq!(
+        component.to_ansi_with(AnsiColors::TrueColor),
+        "\x1B[38;2;255;85;85;1mError\x1B[0m"
+    );
+    assert_eq!(component.to_ansi_with(AnsiColors::None), "Error");
+
+    // The child keeps the parent's bold formatting but overrides its color, and the parent's style
+    // is restored for its next child
+    let mut component = Component::colored("a".to_owned(), Color::Custom(1, 2, 3));
+    component.format = Format::BOLD;
+    component.add_child(Component {
+        format: Format::ITALIC,
+        ..Component::colored("b".to_owned(), Color::Gold)
+    });
+    component.add_child(Component::text("c"));
+    assert_eq!(
+        component.to_ansi_with(AnsiColors::TrueColor),
+        "\x1B[38;2;1;2;3;1ma\x1B[0;38;2;255;170;0;1;3mb\x1B[0;38;2;1;2;3;1mc\x1B[0m"
+    );
+    assert_eq!(component.to_ansi_with(AnsiColors::None), "abc");
+    assert_eq!(
+        Component::text("plain").to_ansi_with(AnsiColors::TrueColor),
+        "plain"
+    );
+
+    // Colors are approximated on terminals without 24-bit color
+    let component = Component::colored("a".to_owned(), Color::Gold);
+    assert_eq!(
+        component.to_ansi_with(AnsiColors::Palette256),
+        "\x1B[38;5;214ma\x1B[0m"
+    );
+    assert_eq!(nearest_palette_index(0, 0, 0), 16);
+    assert_eq!(nearest_palette_index(255, 255, 255), 231);
+    assert_eq!(nearest_palette_index(128, 128, 128), 244);
+
+    // Translations are rendered rather than serialized, and children are only written once
+    let mut component = Component::translate("chat.type.text", vec![
+        Component::text("Steve"),
+        Component::text("hi"),
+    ]);
+    component.add_child(Component::text("!"));
+    assert_eq!(component.to_ansi_with(AnsiColors::None), "<Steve> hi!");
+}
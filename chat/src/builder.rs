@@ -52,6 +52,30 @@ impl ComponentBuilder {
         self.add(ComponentType::text(text))
     }
 
+    /// Finish the current component, making it a translate component with the given translation ID
+    /// and arguments, and prepare a new component which can have a different color, formatting, etc.
+    pub fn add_translate<T: ToString>(self, translate: T, with: Vec<Component>) -> Self {
+        self.add(Component::translate(translate, with).component_type)
+    }
+
+    /// Adds each character of the given text as its own component, coloring them with a gradient
+    /// which passes through the given colors. The colors are spread evenly across the text, so the
+    /// first and last characters have the first and last colors. [`Color::Reset`] has no color to
+    /// blend with so it is skipped, and if no colors remain the text is added uncolored.
+    pub fn add_gradient(mut self, text: &str, stops: &[Color]) -> Self {
+        let stops = stops.iter().filter_map(Color::rgb).collect::<Vec<_>>();
+        if stops.is_empty() {
+            return self.add_text(text);
+        }
+
+        let chars = text.chars().collect::<Vec<_>>();
+        for (ch, color) in chars.iter().zip(gradient(chars.len(), &stops)) {
+            self = self.color(color).add_text(ch);
+        }
+
+        self
+    }
+
     /// Finish the current component, setting its text field to an empty string, and prepare a new component
     /// which can have a different color, formatting, etc.
     pub fn add_empty(mut self) -> Self {
@@ -128,3 +152,72 @@ impl ComponentBuilder {
         self
     }
 }
+
+/// Returns the colors of `len` characters colored with a gradient passing through the given stops,
+/// which are spread evenly so that the first and last characters have the first and last colors.
+pub(crate) fn gradient(len: usize, stops: &[(u8, u8, u8)]) -> impl Iterator<Item = Color> + '_ {
+    (0 .. len).map(move |index| {
+        let (r, g, b) = match stops.len() {
+            0 => return Color::Reset,
+            1 => stops[0],
+            _ => {
+                // The position of the character along the gradient, in units of stops
+                let position = if len > 1 {
+                    index as f32 / (len - 1) as f32 * (stops.len() - 1) as f32
+                } else {
+                    0.0
+                };
+                let segment = (position as usize).min(stops.len() - 2);
+                let t = position - segment as f32;
+                let (from, to) = (stops[segment], stops[segment + 1]);
+                let lerp =
+                    |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * t).round() as u8;
+                (lerp(from.0, to.0), lerp(from.1, to.1), lerp(from.2, to.2))
+            }
+        };
+        Color::Custom(r, g, b)
+    })
+}
+
+#[test]
+fn gradient_colors() {
+    let children = ComponentBuilder::empty()
+        .add_empty()
+        .add_gradient("Hello", &[
+            Color::Custom(255, 0, 0),
+            Color::Custom(0, 0, 255),
+        ])
+        .build_children();
+    assert_eq!(children.len(), 5);
+    assert_eq!(children[0].color, Some(Color::Custom(255, 0, 0)));
+    assert_eq!(children[2].color, Some(Color::Custom(128, 0, 128)));
+    assert_eq!(children[4].color, Some(Color::Custom(0, 0, 255)));
+    assert_eq!(children[1].to_plain_string(), "e");
+
+    // Three stops put the middle stop on the middle character
+    let children = ComponentBuilder::empty()
+        .add_empty()
+        .add_gradient("abcde", &[
+            Color::Black,
+            Color::White,
+            Color::Custom(0, 0, 0),
+        ])
+        .build_children();
+    assert_eq!(children[1].color, Some(Color::Custom(128, 128, 128)));
+    assert_eq!(children[2].color, Some(Color::Custom(255, 255, 255)));
+    assert_eq!(children[4].color, Some(Color::Custom(0, 0, 0)));
+
+    // Reset stops are skipped rather than blended as white
+    let children = ComponentBuilder::empty()
+        .add_empty()
+        .add_gradient("ab", &[Color::Reset, Color::Black, Color::Reset])
+        .build_children();
+    assert_eq!(children[0].color, Some(Color::Custom(0, 0, 0)));
+    assert_eq!(children[1].color, Some(Color::Custom(0, 0, 0)));
+    let children = ComponentBuilder::empty()
+        .add_empty()
+        .add_gradient("ab", &[Color::Reset])
+        .build_children();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].color, None);
+}
@@ -0,0 +1,235 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use serde::{
+    de::value::{self, BorrowedStrDeserializer},
+    Deserialize,
+};
+
+use crate::{builder::gradient, color::Color, component::Format, Component};
+
+/// An error encountered while parsing text written in the CFMT format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CfmtError {
+    /// A `$(` was not closed with a `)`, or the directives were not followed by `{`.
+    ExpectedText(usize),
+    /// A `{` was not closed with a `}`.
+    UnclosedText(usize),
+    /// A directive was not a color, format, or gradient.
+    UnknownDirective(String),
+    /// A gradient had fewer than two stops, or a stop which was not a color with an RGB value.
+    InvalidGradient(String),
+}
+
+impl Display for CfmtError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CfmtError::ExpectedText(index) => write!(
+                f,
+                "Expected directives followed by text in braces at {index}"
+            ),
+            CfmtError::UnclosedText(index) => write!(f, "Unclosed text starting at {index}"),
+            CfmtError::UnknownDirective(directive) =>
+                write!(f, "Unknown directive \"{directive}\""),
+            CfmtError::InvalidGradient(stops) => write!(
+                f,
+                "Invalid gradient \"{stops}\", expected two or more named or hex colors"
+            ),
+        }
+    }
+}
+
+impl Error for CfmtError {}
+
+/// Parses text written in the CFMT format into a component.
+///
+/// Text is written as-is, except for styled spans which are written as `$(directives){text}`. The
+/// directives are separated by whitespace, and may be named or hex colors such as `red` or
+/// `#ff8000`, formats such as `bold` or `underline`, or a gradient such as
+/// `gradient:#ff0000,#0000ff` which colors each character of the text by blending evenly between
+/// two or more colors. A backslash escapes the character after it, such as `\$` or `\}`.
+///
+/// ```
+/// # use quartz_chat::{cfmt::parse_cfmt, color::Color};
+/// let component = parse_cfmt("Hello $(bold gold){world}").unwrap();
+/// let children = component.extra.unwrap();
+/// assert_eq!(children[1].color, Some(Color::Gold));
+/// ```
+pub fn parse_cfmt(input: &str) -> Result<Component, CfmtError> {
+    let mut component = Component::empty();
+    let mut plain = String::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((index, ch)) = chars.next() {
+        match ch {
+            '\\' => plain.extend(chars.next().map(|(_, ch)| ch)),
+            '$' if chars.peek().map(|&(_, ch)| ch) == Some('(') => {
+                chars.next();
+                let directives_start = index + 2;
+                let directives_end = input[directives_start ..]
+                    .find(')')
+                    .map(|end| directives_start + end)
+                    .ok_or(CfmtError::ExpectedText(index))?;
+                if !input[directives_end + 1 ..].starts_with('{') {
+                    return Err(CfmtError::ExpectedText(index));
+                }
+
+                // Skip the directives and opening brace
+                while chars.next_if(|&(i, _)| i <= directives_end + 1).is_some() {}
+
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) => text.extend(chars.next().map(|(_, ch)| ch)),
+                        Some((_, '}')) => break,
+                        Some((_, ch)) => text.push(ch),
+                        None => return Err(CfmtError::UnclosedText(directives_end + 1)),
+                    }
+                }
+
+                if !plain.is_empty() {
+                    component.add_child(Component::text(std::mem::take(&mut plain)));
+                }
+                add_span(
+                    &mut component,
+                    &input[directives_start .. directives_end],
+                    text,
+                )?;
+            }
+            _ => plain.push(ch),
+        }
+    }
+
+    if !plain.is_empty() {
+        component.add_child(Component::text(plain));
+    }
+
+    Ok(component)
+}
+
+/// Adds the given text styled with the given directives to the component.
+fn add_span(component: &mut Component, directives: &str, text: String) -> Result<(), CfmtError> {
+    let mut color = None;
+    let mut format = Format::empty();
+    let mut stops = None;
+
+    for directive in directives.split_whitespace() {
+        if let Some(gradient) = directive.strip_prefix("gradient:") {
+            stops = Some(parse_gradient(gradient)?);
+            continue;
+        }
+
+        match directive {
+            "bold" => format |= Format::BOLD,
+            "italic" => format |= Format::ITALIC,
+            "obfuscated" => format |= Format::OBFUSCATED,
+            "strikethrough" => format |= Format::STRIKETHROUGH,
+            "underline" => format |= Format::UNDERLINE,
+            _ =>
+                color = Some(
+                    parse_color(directive)
+                        .ok_or_else(|| CfmtError::UnknownDirective(directive.to_owned()))?,
+                ),
+        }
+    }
+
+    let styled = |text: String, color: Option<Color>| {
+        let mut child = Component::text(text);
+        child.color = color;
+        child.format = format;
+        child
+    };
+
+    match stops {
+        Some(stops) => {
+            let chars = text.chars().collect::<Vec<_>>();
+            for (ch, color) in chars.iter().zip(gradient(chars.len(), &stops)) {
+                component.add_child(styled(ch.to_string(), Some(color)));
+            }
+        }
+        None => component.add_child(styled(text, color)),
+    }
+
+    Ok(())
+}
+
+/// Parses a named color such as `red` or a hex color such as `#ff8000`.
+fn parse_color(color: &str) -> Option<Color> {
+    Color::deserialize(BorrowedStrDeserializer::<value::Error>::new(color)).ok()
+}
+
+fn parse_gradient(gradient: &str) -> Result<Vec<(u8, u8, u8)>, CfmtError> {
+    let invalid = || CfmtError::InvalidGradient(gradient.to_owned());
+    let stops = gradient
+        .split(',')
+        .map(|stop| {
+            parse_color(stop)
+                .and_then(|color| color.rgb())
+                .ok_or_else(invalid)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if stops.len() < 2 {
+        return Err(invalid());
+    }
+
+    Ok(stops)
+}
+
+#[test]
+fn parse_cfmt_gradient() {
+    let component = parse_cfmt("$(gradient:#ff0000,#0000ff){Hello World}").unwrap();
+    let children = component.extra.as_ref().unwrap();
+    assert_eq!(children.len(), 11);
+    assert_eq!(children[0].color, Some(Color::Custom(255, 0, 0)));
+    assert_eq!(children[5].color, Some(Color::Custom(128, 0, 128)));
+    assert_eq!(children[10].color, Some(Color::Custom(0, 0, 255)));
+    assert_eq!(component.to_plain_string(), "Hello World");
+
+    // Three stops put the middle stop on the middle character
+    let component = parse_cfmt("$(bold gradient:black,white,black){abcde}").unwrap();
+    let children = component.extra.as_ref().unwrap();
+    assert_eq!(children[2].color, Some(Color::Custom(255, 255, 255)));
+    assert_eq!(children[4].color, Some(Color::Custom(0, 0, 0)));
+    assert!(children.iter().all(|child| child.format == Format::BOLD));
+
+    assert_eq!(
+        parse_cfmt("$(gradient:red){a}").unwrap_err(),
+        CfmtError::InvalidGradient("red".to_owned())
+    );
+    assert_eq!(
+        parse_cfmt("$(gradient:red,reset){a}").unwrap_err(),
+        CfmtError::InvalidGradient("red,reset".to_owned())
+    );
+}
+
+#[test]
+fn parse_cfmt_spans() {
+    let component = parse_cfmt("Cost: \\$5 $(red underline){a \\} b}!").unwrap();
+    let children = component.extra.as_ref().unwrap();
+    assert_eq!(children.len(), 3);
+    assert_eq!(children[0].to_plain_string(), "Cost: $5 ");
+    assert_eq!(children[1].to_plain_string(), "a } b");
+    assert_eq!(children[1].color, Some(Color::Red));
+    assert_eq!(children[1].format, Format::UNDERLINE);
+    assert_eq!(children[2].to_plain_string(), "!");
+
+    assert_eq!(
+        parse_cfmt("$(red)a").unwrap_err(),
+        CfmtError::ExpectedText(0)
+    );
+    assert_eq!(
+        parse_cfmt("a $(red{b}").unwrap_err(),
+        CfmtError::ExpectedText(2)
+    );
+    assert_eq!(
+        parse_cfmt("$(red){b").unwrap_err(),
+        CfmtError::UnclosedText(6)
+    );
+    assert_eq!(
+        parse_cfmt("$(shiny){b}").unwrap_err(),
+        CfmtError::UnknownDirective("shiny".to_owned())
+    );
+}
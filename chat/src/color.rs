@@ -88,36 +88,117 @@ impl<'de> Deserialize<'de> for Color {
             "yellow" => Ok(Color::Yellow),
             "white" => Ok(Color::White),
             "reset" => Ok(Color::Reset),
-            _ => {
-                if string.is_empty() {
-                    return Err(de::Error::custom(
-                        "Expected hex color, found an empty string.",
-                    ));
-                }
-
-                if string.len() != 7 {
-                    return Err(de::Error::custom(
-                        "Expected hex color in the form of '#RRGGBB'",
-                    ));
-                }
-
-                if let Ok(rgb) = u32::from_str_radix(&string[1 ..], 16) {
-                    Ok(Color::Custom(
-                        (rgb >> 16) as u8,
-                        (rgb >> 8) as u8,
-                        rgb as u8,
-                    ))
-                } else {
-                    Err(de::Error::custom(
-                        "Invalid hex color, expected 6 hexadecimal digits (0-F).",
-                    ))
-                }
-            }
+            _ => Color::from_hex(string).ok_or_else(|| {
+                de::Error::custom(format!(
+                    "Invalid color \"{string}\", expected a named color or a hex color in the \
+                     form of '#RRGGBB'"
+                ))
+            }),
         }
     }
 }
 
 impl Color {
+    /// The named colors in the order of their legacy formatting codes, `0` through `f`.
+    const LEGACY_COLORS: [Color; 16] = [
+        Color::Black,
+        Color::DarkBlue,
+        Color::DarkGreen,
+        Color::DarkAqua,
+        Color::DarkRed,
+        Color::DarkPurple,
+        Color::Gold,
+        Color::Gray,
+        Color::DarkGray,
+        Color::Blue,
+        Color::Green,
+        Color::Aqua,
+        Color::Red,
+        Color::LightPurple,
+        Color::Yellow,
+        Color::White,
+    ];
+
+    /// Returns the named color with the given legacy formatting code, such as `c` for red. Codes
+    /// are case-insensitive.
+    pub fn from_legacy_code(code: char) -> Option<Self> {
+        code.to_digit(16)
+            .map(|index| Self::LEGACY_COLORS[index as usize])
+    }
+
+    /// Parses a hex color in the form of `#RRGGBB`, such as `#ff0080`.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let digits = hex.strip_prefix('#')?;
+        if digits.len() != 6 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let rgb = u32::from_str_radix(digits, 16).ok()?;
+        Some(Color::Custom(
+            (rgb >> 16) as u8,
+            (rgb >> 8) as u8,
+            rgb as u8,
+        ))
+    }
+
+    /// Returns the red, green and blue components of this color as displayed by the client, or
+    /// `None` for [`Color::Reset`].
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        let rgb: u32 = match self {
+            Color::Black => 0x000000,
+            Color::DarkBlue => 0x0000AA,
+            Color::DarkGreen => 0x00AA00,
+            Color::DarkAqua => 0x00AAAA,
+            Color::DarkRed => 0xAA0000,
+            Color::DarkPurple => 0xAA00AA,
+            Color::Gold => 0xFFAA00,
+            Color::Gray => 0xAAAAAA,
+            Color::DarkGray => 0x555555,
+            Color::Blue => 0x5555FF,
+            Color::Green => 0x55FF55,
+            Color::Aqua => 0x55FFFF,
+            Color::Red => 0xFF5555,
+            Color::LightPurple => 0xFF55FF,
+            Color::Yellow => 0xFFFF55,
+            Color::White => 0xFFFFFF,
+            Color::Reset => return None,
+            &Color::Custom(r, g, b) => return Some((r, g, b)),
+        };
+
+        Some(((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+    }
+
+    /// Returns the named color closest to this color, for clients which do not support custom
+    /// colors. Named colors and [`Color::Reset`] are returned as-is.
+    pub fn nearest_named(&self) -> Color {
+        let (r, g, b) = match self {
+            &Color::Custom(r, g, b) => (r as i32, g as i32, b as i32),
+            _ => return *self,
+        };
+
+        Self::LEGACY_COLORS
+            .iter()
+            .copied()
+            .min_by_key(|named| {
+                // Named colors always have an RGB value
+                let (nr, ng, nb) = named.rgb().unwrap();
+                (r - nr as i32).pow(2) + (g - ng as i32).pow(2) + (b - nb as i32).pow(2)
+            })
+            .unwrap_or(Color::White)
+    }
+
+    /// Returns the legacy formatting code of this color, or `None` if this is a custom color.
+    pub fn legacy_code(&self) -> Option<char> {
+        match self {
+            Color::Reset => Some('r'),
+            Color::Custom(..) => None,
+            _ => Self::LEGACY_COLORS
+                .iter()
+                .position(|color| color == self)
+                .and_then(|index| char::from_digit(index as u32, 16)),
+        }
+    }
+
     /// Applies the color to the terminal (unix only).
     #[cfg(unix)]
     pub fn apply(&self, f: &mut Formatter) -> fmt::Result {
@@ -148,3 +229,32 @@ impl Color {
         }
     }
 }
+
+#[test]
+fn hex_colors() {
+    assert_eq!(Color::from_hex("#ff0080"), Some(Color::Custom(255, 0, 128)));
+    assert_eq!(Color::from_hex("#00AAaa"), Some(Color::Custom(0, 170, 170)));
+    assert_eq!(Color::from_hex("ff0080"), None);
+    assert_eq!(Color::from_hex("#ff008"), None);
+    assert_eq!(Color::from_hex("#+f0080"), None);
+    assert_eq!(Color::from_hex("#ff00\u{e9}"), None);
+
+    assert_eq!(
+        serde_json::to_string(&Color::Custom(255, 0, 128)).unwrap(),
+        r##""#FF0080""##
+    );
+    assert_eq!(
+        serde_json::from_str::<Color>(r##""#ff0080""##).unwrap(),
+        Color::Custom(255, 0, 128)
+    );
+    assert!(serde_json::from_str::<Color>(r#""purple""#).is_err());
+
+    assert_eq!(Color::Custom(250, 80, 80).nearest_named(), Color::Red);
+    assert_eq!(Color::Custom(10, 10, 10).nearest_named(), Color::Black);
+    assert_eq!(Color::Custom(255, 170, 0).nearest_named(), Color::Gold);
+    assert_eq!(
+        Color::Custom(100, 100, 100).nearest_named(),
+        Color::DarkGray
+    );
+    assert_eq!(Color::Aqua.nearest_named(), Color::Aqua);
+}
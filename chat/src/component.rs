@@ -3,7 +3,7 @@ use bitflags::bitflags;
 use quartz_nbt::{NbtCompound, NbtList, NbtTag};
 use serde::{
     de::{self, Visitor},
-    ser::SerializeMap,
+    ser::{self, SerializeMap},
     Deserialize,
     Serialize,
 };
@@ -19,7 +19,7 @@ use termion::style;
 
 /// A chat component. All type-specific information is stored in the field `component_type`.
 #[skip_serializing_none]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Component {
     /// The type of this component and its type-specific data.
     #[serde(flatten)]
@@ -48,6 +48,52 @@ fn is_extra_empty(extra: &Option<Vec<Component>>) -> bool {
     extra.as_ref().map(|v| v.is_empty()).unwrap_or(true)
 }
 
+impl<'de> Deserialize<'de> for Component {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: serde::Deserializer<'de> {
+        // Vanilla accepts a bare string, an object, or an array where the first element is the
+        // parent of the rest
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum ComponentRepr {
+            Text(String),
+            Array(Vec<Component>),
+            Object(#[serde(with = "ComponentObject")] Component),
+        }
+
+        match ComponentRepr::deserialize(deserializer)? {
+            ComponentRepr::Text(text) => Ok(Component::text(text)),
+            ComponentRepr::Array(components) => {
+                let mut components = components.into_iter();
+                let mut parent = components
+                    .next()
+                    .ok_or_else(|| de::Error::invalid_length(0, &"a non-empty array"))?;
+                parent.extra.get_or_insert_with(Vec::new).extend(components);
+                Ok(parent)
+            }
+            ComponentRepr::Object(component) => Ok(component),
+        }
+    }
+}
+
+/// The object form of a component.
+#[derive(Deserialize)]
+#[serde(remote = "Component")]
+struct ComponentObject {
+    #[serde(flatten)]
+    component_type: ComponentType,
+    color: Option<Color>,
+    font: Option<Font>,
+    #[serde(flatten)]
+    format: Format,
+    insertion: Option<String>,
+    #[serde(rename = "clickEvent")]
+    click_event: Option<Box<ClickEvent>>,
+    #[serde(rename = "hoverEvent")]
+    hover_event: Option<Box<HoverEvent>>,
+    extra: Option<Vec<Component>>,
+}
+
 impl Component {
     /// Creates an empty text component with no color, formatting, etc.
     pub const fn empty() -> Self {
@@ -75,6 +121,18 @@ impl Component {
         }
     }
 
+    /// Creates a translate component with the given translation ID, inserting the given components
+    /// into the translation.
+    pub fn translate<T: ToString>(translate: T, with: Vec<Component>) -> Self {
+        Component {
+            component_type: ComponentType::translate(
+                translate.to_string(),
+                if with.is_empty() { None } else { Some(with) },
+            ),
+            ..Default::default()
+        }
+    }
+
     /// Creates a component with the given text and predefined color.
     pub fn colored<C: Into<Color>>(text: String, color: C) -> Self {
         Component {
@@ -84,15 +142,6 @@ impl Component {
         }
     }
 
-    /// Converts this component into plain, uncolored text.
-    pub fn as_plain_text(&self) -> String {
-        match &self.component_type {
-            ComponentType::Text { text } => text.clone(),
-            // TODO: Implement this for other component types
-            _ => serde_json::to_string(self).unwrap_or_else(|_| "{}".to_owned()),
-        }
-    }
-
     /// Adds the given child, creating the children vec if needed.
     pub fn add_child(&mut self, component: Self) {
         match &mut self.extra {
@@ -210,7 +259,7 @@ impl Display for Component {
 
 /// The type of a component and all fields pertinent to that component type.
 #[skip_serializing_none]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ComponentType {
     /// A text component.
@@ -224,6 +273,8 @@ pub enum ComponentType {
         translate: String,
         /// The components to insert into the translation.
         with: Option<Vec<Component>>,
+        /// The text to display if the client does not have a translation for the ID.
+        fallback: Option<String>,
     },
     /// Used to display the client's current keybind for the specified key.
     Keybind {
@@ -252,7 +303,11 @@ impl ComponentType {
 
     /// Creates a new translate component type with the given `translate` and `with` fields.
     pub fn translate(translate: String, with: Option<Vec<Component>>) -> Self {
-        ComponentType::Translate { translate, with }
+        ComponentType::Translate {
+            translate,
+            with,
+            fallback: None,
+        }
     }
 
     /// Creates a new keybind component type with the given keybing string.
@@ -424,7 +479,7 @@ impl<'de> Visitor<'de> for FormatVisitor {
 
 /// The data for the `Score` component type.
 #[skip_serializing_none]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScoreComponentData {
     /// The name or selector of the entity to which this data pertains.
     pub name: String,
@@ -449,7 +504,7 @@ pub trait ToComponent {
 }
 
 /// Defines click events for text components.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct ClickEvent {
     action: ClickEventType,
     value: EventArgument,
@@ -497,7 +552,38 @@ impl ClickEvent {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+impl Serialize for ClickEvent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde::Serializer {
+        // The client refuses to open anything other than web links, so catch those here rather
+        // than sending a component which silently does nothing
+        if self.action == ClickEventType::OpenUrl {
+            match &self.value {
+                EventArgument::Text(url) if is_web_url(url) => {}
+                _ =>
+                    return Err(ser::Error::custom(
+                        "open_url only accepts http and https URLs",
+                    )),
+            }
+        }
+
+        let mut serializer = serializer.serialize_map(Some(2))?;
+        serializer.serialize_entry("action", &self.action)?;
+        serializer.serialize_entry("value", &self.value)?;
+        serializer.end()
+    }
+}
+
+fn is_web_url(url: &str) -> bool {
+    match url.split_once("://") {
+        Some((scheme, rest)) =>
+            (scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https"))
+                && !rest.is_empty(),
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 enum ClickEventType {
     OpenUrl,
@@ -509,7 +595,7 @@ enum ClickEventType {
 
 /// Defines hover events for text components.
 #[skip_serializing_none]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HoverEvent {
     action: HoverEventType,
     contents: Option<HoverContents>,
@@ -578,7 +664,7 @@ impl HoverEvent {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[allow(clippy::enum_variant_names)]
 enum HoverEventType {
@@ -589,7 +675,7 @@ enum HoverEventType {
 
 // The contents variable in the hover event
 #[skip_serializing_none]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 enum HoverContents {
     Component(Component),
@@ -600,7 +686,7 @@ enum HoverContents {
 
 /// Defines an item profile which can be displayed through hover events.
 #[skip_serializing_none]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HoverItem {
     id: String,
     count: u8,
@@ -609,7 +695,7 @@ pub struct HoverItem {
 
 /// Defines an entity profile which can be displayed through hover events.
 #[skip_serializing_none]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HoverEntity {
     /// The uuid of the entity
     pub id: String,
@@ -621,7 +707,7 @@ pub struct HoverEntity {
 }
 
 // The generalized event argument
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 #[allow(clippy::large_enum_variant)]
 enum EventArgument {
@@ -783,3 +869,121 @@ impl ToComponent for NbtCompound {
         components
     }
 }
+
+#[test]
+fn deserialize_array_component() {
+    let component: Component =
+        serde_json::from_str(r#"["a", {"text": "b", "color": "red"}]"#).unwrap();
+
+    assert!(matches!(&component.component_type, ComponentType::Text { text } if text == "a"));
+    let children = component.extra.as_ref().unwrap();
+    assert_eq!(children.len(), 1);
+    assert!(matches!(&children[0].component_type, ComponentType::Text { text } if text == "b"));
+    assert_eq!(children[0].color, Some(Color::Red));
+
+    let component: Component = serde_json::from_str(r#""hello""#).unwrap();
+    assert_eq!(component.to_plain_string(), "hello");
+    assert!(serde_json::from_str::<Component>("[]").is_err());
+}
+
+#[test]
+fn serialize_component_events() {
+    let component = ComponentBuilder::empty()
+        .click_event(ClickEvent::run_command("/help".to_owned()))
+        .hover_event(HoverEvent::show_text(Component::text("Show help")))
+        .add_text("Help")
+        .build();
+
+    assert_eq!(
+        serde_json::to_value(&component).unwrap(),
+        serde_json::json!({
+            "text": "Help",
+            "clickEvent": {
+                "action": "run_command",
+                "value": "/help"
+            },
+            "hoverEvent": {
+                "action": "show_text",
+                "contents": {
+                    "text": "Show help"
+                }
+            }
+        })
+    );
+
+    let link = |url: &str| Component {
+        click_event: Some(Box::new(ClickEvent::open_url(url.to_owned()))),
+        ..Component::text("link")
+    };
+    assert!(serde_json::to_string(&link("https://example.com")).is_ok());
+    assert!(serde_json::to_string(&link("HTTP://example.com/a?b=c")).is_ok());
+    assert!(serde_json::to_string(&link("file:///etc/passwd")).is_err());
+    assert!(serde_json::to_string(&link("example.com")).is_err());
+
+    let component: Component =
+        serde_json::from_str(r#"{"text":"a","clickEvent":{"action":"change_page","value":2}}"#)
+            .unwrap();
+    assert_eq!(
+        serde_json::to_value(&component).unwrap()["clickEvent"]["value"],
+        2
+    );
+}
+
+#[test]
+fn translate_component_round_trip() {
+    let name = Component::colored("Steve".to_owned(), Color::Yellow);
+    let component = Component::translate("chat.type.text", vec![
+        name,
+        Component::translate("death.attack.generic", vec![Component::text("Alex")]),
+    ]);
+
+    let json = serde_json::json!({
+        "translate": "chat.type.text",
+        "with": [
+            {"text": "Steve", "color": "yellow"},
+            {"translate": "death.attack.generic", "with": [{"text": "Alex"}]}
+        ]
+    });
+    assert_eq!(serde_json::to_value(&component).unwrap(), json);
+
+    let component: Component = serde_json::from_str(&json.to_string()).unwrap();
+    assert_eq!(serde_json::to_value(&component).unwrap(), json);
+    match &component.component_type {
+        ComponentType::Translate {
+            translate,
+            with: Some(with),
+            fallback: None,
+        } => {
+            assert_eq!(translate, "chat.type.text");
+            assert!(matches!(
+                &with[1].component_type,
+                ComponentType::Translate { translate, .. } if translate == "death.attack.generic"
+            ));
+        }
+        _ => panic!("Expected a translate component"),
+    }
+
+    let component = ComponentBuilder::empty()
+        .add_translate("custom.key", Vec::new())
+        .build();
+    assert_eq!(
+        serde_json::to_value(&component).unwrap(),
+        serde_json::json!({"translate": "custom.key"})
+    );
+
+    let component = Component {
+        component_type: ComponentType::Translate {
+            translate: "custom.key".to_owned(),
+            with: None,
+            fallback: Some("Custom text".to_owned()),
+        },
+        ..Default::default()
+    };
+    let json = serde_json::json!({"translate": "custom.key", "fallback": "Custom text"});
+    assert_eq!(serde_json::to_value(&component).unwrap(), json);
+    let component: Component = serde_json::from_str(&json.to_string()).unwrap();
+    assert!(matches!(
+        component.component_type,
+        ComponentType::Translate { fallback: Some(fallback), .. } if fallback == "Custom text"
+    ));
+}
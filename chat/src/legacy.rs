@@ -0,0 +1,194 @@
+use crate::{
+    color::Color,
+    component::{ComponentType, Format},
+    Component,
+};
+
+/// The character vanilla uses to prefix legacy formatting codes.
+pub const SECTION_SIGN: char = '\u{00a7}';
+
+/// Returns the format flag with the given legacy formatting code, such as `l` for bold.
+fn format_from_legacy_code(code: char) -> Option<Format> {
+    match code.to_ascii_lowercase() {
+        'k' => Some(Format::OBFUSCATED),
+        'l' => Some(Format::BOLD),
+        'm' => Some(Format::STRIKETHROUGH),
+        'n' => Some(Format::UNDERLINE),
+        'o' => Some(Format::ITALIC),
+        _ => None,
+    }
+}
+
+impl Component {
+    /// Parses a string formatted with legacy codes, such as `§cError: §lfailed`, into a component
+    /// whose children are the differently formatted runs of text. `color_char` is the character
+    /// prefixing each code, which is usually `§` or `&`.
+    ///
+    /// Following vanilla, a color code clears any formatting set before it, and `r` resets both the
+    /// color and formatting. Unknown codes are kept as literal text.
+    pub fn from_legacy(input: &str, color_char: char) -> Component {
+        let mut component = Component::empty();
+        let mut current = Component::empty();
+        let mut chars = input.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            if ch != color_char {
+                push_char(&mut current, ch);
+                continue;
+            }
+
+            let code = match chars.peek() {
+                Some(&code) => code,
+                None => {
+                    push_char(&mut current, ch);
+                    continue;
+                }
+            };
+
+            let color = Color::from_legacy_code(code);
+            let format = format_from_legacy_code(code);
+            let reset = code.eq_ignore_ascii_case(&'r');
+            if color.is_none() && format.is_none() && !reset {
+                push_char(&mut current, ch);
+                continue;
+            }
+            chars.next();
+
+            let mut next = Component::empty();
+            if !reset {
+                next.color = color.or(current.color);
+                next.format = match format {
+                    Some(format) if color.is_none() => current.format | format,
+                    _ => Format::empty(),
+                };
+            }
+
+            let finished = std::mem::replace(&mut current, next);
+            if !finished.to_plain_string().is_empty() {
+                component.add_child(finished);
+            }
+        }
+
+        if !current.to_plain_string().is_empty() {
+            component.add_child(current);
+        }
+
+        component
+    }
+
+    /// Converts this component and its children into a string formatted with legacy codes, where
+    /// each code is prefixed with `color_char`. Custom colors are replaced with the nearest named
+    /// color, and click and hover events cannot be represented so they are dropped.
+    pub fn to_legacy(&self, color_char: char) -> String {
+        let mut legacy = String::new();
+        let mut current = (None, Format::empty());
+        self.write_legacy(
+            &mut legacy,
+            color_char,
+            (None, Format::empty()),
+            &mut current,
+        );
+        legacy
+    }
+
+    fn write_legacy(
+        &self,
+        legacy: &mut String,
+        color_char: char,
+        (parent_color, parent_format): (Option<char>, Format),
+        current: &mut (Option<char>, Format),
+    ) {
+        let color = match self.color {
+            Some(Color::Reset) => None,
+            Some(color) => color.nearest_named().legacy_code(),
+            None => parent_color,
+        };
+        let format = parent_format | self.format;
+
+        let text = self.to_plain_node_string();
+        if !text.is_empty() {
+            if (color, format) != *current {
+                match color {
+                    Some(code) => {
+                        legacy.push(color_char);
+                        legacy.push(code);
+                    }
+                    None =>
+                        if *current != (None, Format::empty()) {
+                            legacy.push(color_char);
+                            legacy.push('r');
+                        },
+                }
+
+                for (flag, code) in [
+                    (Format::OBFUSCATED, 'k'),
+                    (Format::BOLD, 'l'),
+                    (Format::STRIKETHROUGH, 'm'),
+                    (Format::UNDERLINE, 'n'),
+                    (Format::ITALIC, 'o'),
+                ] {
+                    if format.contains(flag) {
+                        legacy.push(color_char);
+                        legacy.push(code);
+                    }
+                }
+
+                *current = (color, format);
+            }
+
+            legacy.push_str(&text);
+        }
+
+        for child in self.extra.iter().flatten() {
+            child.write_legacy(legacy, color_char, (color, format), current);
+        }
+    }
+}
+
+fn push_char(component: &mut Component, ch: char) {
+    if let ComponentType::Text { text } = &mut component.component_type {
+        text.push(ch);
+    }
+}
+
+#[test]
+fn parse_legacy_formatting() {
+    let component = Component::from_legacy("§cError: §lfailed§r to §x§4load&a", SECTION_SIGN);
+    let children = component.extra.as_ref().unwrap();
+    let runs = children
+        .iter()
+        .map(|child| (child.to_plain_string(), child.color, child.format))
+        .collect::<Vec<_>>();
+    assert_eq!(runs, vec![
+        ("Error: ".to_owned(), Some(Color::Red), Format::empty()),
+        ("failed".to_owned(), Some(Color::Red), Format::BOLD),
+        (" to §x".to_owned(), None, Format::empty()),
+        ("load&a".to_owned(), Some(Color::DarkRed), Format::empty()),
+    ]);
+
+    // Color codes clear formatting, but formatting codes stack
+    let component = Component::from_legacy("&l&obold &eplain&", '&');
+    let children = component.extra.as_ref().unwrap();
+    assert_eq!(children[0].format, Format::BOLD | Format::ITALIC);
+    assert_eq!(children[1].color, Some(Color::Yellow));
+    assert_eq!(children[1].format, Format::empty());
+    assert_eq!(children[1].to_plain_string(), "plain&");
+
+    assert_eq!(
+        Component::from_legacy("§cError: §lfailed§r to load", SECTION_SIGN).to_legacy('&'),
+        "&cError: &c&lfailed&r to load"
+    );
+    assert_eq!(Component::from_legacy("plain", '&').to_legacy('&'), "plain");
+
+    let mut component = Component::colored("custom".to_owned(), Color::Custom(250, 250, 90));
+    component.add_child(Component::text(" text"));
+    assert_eq!(component.to_legacy('&'), "&ecustom text");
+
+    // Translations are rendered rather than serialized, and children are only written once
+    let mut component = Component::translate("chat.type.text", vec![
+        Component::text("Steve"),
+        Component::text("hi"),
+    ]);
+    component.add_child(Component::colored("!".to_owned(), Color::Red));
+    assert_eq!(component.to_legacy('&'), "<Steve> hi&c!");
+}
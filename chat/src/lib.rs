@@ -2,12 +2,22 @@
 
 //! Provides support for minecraft chat components.
 
+/// Renders components as text with ANSI escape codes.
+mod ansi;
 /// Contains component builders for in-code component creation.
 mod builder;
+/// Parses components written in the CFMT format.
+pub mod cfmt;
 /// Defines and handles the application of chat colors.
 pub mod color;
 /// Defines chat components and their variants.
 pub mod component;
+/// Converts components to and from strings formatted with legacy codes.
+mod legacy;
+/// Extracts the unformatted text of components.
+mod plain;
 
+pub use ansi::AnsiColors;
 pub use builder::ComponentBuilder;
 pub use component::Component;
+pub use legacy::SECTION_SIGN;
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{component::ComponentType, Component};
+
+/// The `en_us` translations of the keys the server commonly sends.
+const EN_US: &[(&str, &str)] = &[
+    ("chat.type.admin", "[%s: %s]"),
+    ("chat.type.announcement", "[%s] %s"),
+    ("chat.type.emote", "* %s %s"),
+    ("chat.type.text", "<%s> %s"),
+    (
+        "commands.message.display.incoming",
+        "%s whispers to you: %s",
+    ),
+    ("commands.message.display.outgoing", "You whisper to %s: %s"),
+    ("death.attack.generic", "%1$s died"),
+    ("death.attack.outOfWorld", "%1$s fell out of the world"),
+    ("multiplayer.disconnect.kicked", "Kicked by an operator"),
+    ("multiplayer.disconnect.server_shutdown", "Server closed"),
+    ("multiplayer.player.joined", "%s joined the game"),
+    ("multiplayer.player.left", "%s left the game"),
+];
+
+impl Component {
+    /// Returns the text of this component and all of its children without any formatting.
+    /// Translate components are rendered using a built-in subset of the `en_us` translations,
+    /// falling back to their fallback text or translation key if the key is unknown.
+    pub fn to_plain_string(&self) -> String {
+        self.to_plain_string_with(en_us_translation)
+    }
+
+    /// Returns the text of this component and all of its children without any formatting,
+    /// rendering translate components using the given language map.
+    pub fn to_plain_string_in(&self, language: &HashMap<String, String>) -> String {
+        self.to_plain_string_with(|key| language.get(key).map(String::as_str))
+    }
+
+    /// Returns the text of this component without its children, rendered the same way as
+    /// [`to_plain_string`](Self::to_plain_string). Used by renderers which style each child
+    /// separately.
+    pub(crate) fn to_plain_node_string(&self) -> String {
+        let mut plain = String::new();
+        self.write_plain_node(&mut plain, en_us_translation);
+        plain
+    }
+
+    fn to_plain_string_with<'a, F>(&self, translations: F) -> String
+    where F: Fn(&str) -> Option<&'a str> + Copy {
+        let mut plain = String::new();
+        self.write_plain(&mut plain, translations);
+        plain
+    }
+
+    fn write_plain<'a, F>(&self, plain: &mut String, translations: F)
+    where F: Fn(&str) -> Option<&'a str> + Copy {
+        self.write_plain_node(plain, translations);
+
+        for child in self.extra.iter().flatten() {
+            child.write_plain(plain, translations);
+        }
+    }
+
+    fn write_plain_node<'a, F>(&self, plain: &mut String, translations: F)
+    where F: Fn(&str) -> Option<&'a str> + Copy {
+        match &self.component_type {
+            ComponentType::Text { text } => plain.push_str(text),
+            ComponentType::Translate {
+                translate,
+                with,
+                fallback,
+            } => {
+                let args = with
+                    .iter()
+                    .flatten()
+                    .map(|arg| arg.to_plain_string_with(translations))
+                    .collect::<Vec<_>>();
+                match translations(translate).or(fallback.as_deref()) {
+                    Some(format) => format_translation(plain, format, &args),
+                    None => plain.push_str(translate),
+                }
+            }
+            ComponentType::Keybind { keybind } => plain.push_str(keybind),
+            ComponentType::Selector { selector } => plain.push_str(selector),
+            // Scores can only be resolved by the server, so only use the value if one was given
+            ComponentType::Score { score } => match &score.value {
+                Some(Value::String(value)) => plain.push_str(value),
+                Some(Value::Number(value)) => plain.push_str(&value.to_string()),
+                _ => {}
+            },
+        }
+    }
+}
+
+fn en_us_translation(key: &str) -> Option<&'static str> {
+    EN_US
+        .iter()
+        .find(|&&(en_us_key, _)| en_us_key == key)
+        .map(|&(_, translation)| translation)
+}
+
+/// Substitutes the given arguments into a translation, which uses Java's `%s` and `%1$s`
+/// placeholders. Placeholders without a matching argument are left empty.
+fn format_translation(plain: &mut String, format: &str, args: &[String]) {
+    let mut next_arg = 0;
+    let mut rest = format;
+
+    while let Some(index) = rest.find('%') {
+        plain.push_str(&rest[.. index]);
+        rest = &rest[index + 1 ..];
+
+        if let Some(after) = rest.strip_prefix('%') {
+            plain.push('%');
+            rest = after;
+            continue;
+        }
+
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let arg = match rest[digits ..].strip_prefix("$s") {
+            Some(after) if digits > 0 => {
+                let position = rest[.. digits].parse::<usize>().ok();
+                rest = after;
+                position.and_then(|position| position.checked_sub(1))
+            }
+            _ => match rest.strip_prefix('s') {
+                Some(after) => {
+                    rest = after;
+                    next_arg += 1;
+                    Some(next_arg - 1)
+                }
+                // Not a placeholder, so keep the percent sign
+                None => {
+                    plain.push('%');
+                    continue;
+                }
+            },
+        };
+
+        if let Some(arg) = arg.and_then(|arg| args.get(arg)) {
+            plain.push_str(arg);
+        }
+    }
+
+    plain.push_str(rest);
+}
+
+#[test]
+fn plain_text_of_nested_components() {
+    use crate::{color::Color, component::Format};
+
+    let mut component = Component::colored("Hello".to_owned(), Color::Red);
+    component.add_child(Component {
+        format: Format::BOLD,
+        ..Component::text(", ")
+    });
+    let mut world = Component::text("world");
+    world.add_child(Component::text("!"));
+    component.add_child(world);
+    assert_eq!(component.to_plain_string(), "Hello, world!");
+
+    let component = Component::translate("chat.type.text", vec![
+        Component::colored("Steve".to_owned(), Color::Yellow),
+        Component::translate("death.attack.generic", vec![Component::text("Alex")]),
+    ]);
+    assert_eq!(component.to_plain_string(), "<Steve> Alex died");
+
+    let language = HashMap::from([(
+        "chat.type.text".to_owned(),
+        "%2$s (from %1$s, 100%%)".to_owned(),
+    )]);
+    assert_eq!(
+        component.to_plain_string_in(&language),
+        "death.attack.generic (from Steve, 100%)"
+    );
+
+    let unknown = Component::translate("some.unknown.key", vec![Component::text("arg")]);
+    assert_eq!(unknown.to_plain_string(), "some.unknown.key");
+    let selector = Component {
+        component_type: ComponentType::selector("@p".to_owned()),
+        ..Component::empty()
+    };
+    assert_eq!(selector.to_plain_string(), "@p");
+}
@@ -2,12 +2,14 @@ use std::collections::HashMap;
 
 use qdat::UnlocalizedName;
 use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
 
 use crate::data::tags::IdsOrTag;
 
 use super::{features::Direction, loot_tables::NumberProvider, noise_settings::BlockState};
 
 /// A condition that matches on an amount of slots
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Slots {
     pub empty: Option<AmountOrRange<i32>>,
@@ -16,6 +18,7 @@ pub struct Slots {
 }
 
 /// A condition that matches on a instance of damage
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Damage {
     pub blocked: Option<bool>,
@@ -25,6 +28,7 @@ pub struct Damage {
 }
 
 /// A condition that matches on a certain damage type
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct DamageType {
     pub bypasses_armor: Option<bool>,
@@ -40,6 +44,7 @@ pub struct DamageType {
 }
 
 /// A condition that matches a on a certain location
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Location {
     pub biome: Option<String>,
@@ -47,6 +52,7 @@ pub struct Location {
 }
 
 /// Part of a condition that matches on a block
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct LocationBlock {
     pub blocks: Option<Vec<UnlocalizedName>>,
@@ -56,6 +62,7 @@ pub struct LocationBlock {
 }
 
 /// Part of a condition that matches on an entity
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Entity {
     pub distance: Option<Distance<f32>>,
@@ -64,7 +71,7 @@ pub struct Entity {
     pub flags: Option<EntityFlags>,
     pub lightning_bolt: Option<LightningBolt>,
     pub nbt: Option<String>,
-    pub pasenger: Option<Box<Entity>>,
+    pub passenger: Option<Box<Entity>>,
     pub player: Option<Player>,
     pub stepping_on: Option<Location>,
     pub team: Option<String>,
@@ -74,6 +81,7 @@ pub struct Entity {
     pub location: Option<PredicateLocation>,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Player {
     pub looking_at: Option<Box<Entity>>,
@@ -84,6 +92,7 @@ pub struct Player {
     pub stats: Option<Statistic>,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Statistic {
     pub r#type: Option<String>,
@@ -91,12 +100,14 @@ pub struct Statistic {
     pub value: Option<AmountOrRange<i32>>,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct LightningBolt {
     pub blocks_set_on_fire: Option<i32>,
-    pub entity_struct: Option<Box<Entity>>,
+    pub entity_struck: Option<Box<Entity>>,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct EntityFlags {
     pub is_on_fire: Option<bool>,
@@ -105,6 +116,7 @@ pub struct EntityFlags {
     pub is_baby: Option<bool>,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Equipment {
     pub mainhand: Option<Item>,
@@ -115,6 +127,7 @@ pub struct Equipment {
     pub feet: Option<Item>,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct StatusEffect {
     pub ambient: Option<bool>,
@@ -123,6 +136,7 @@ pub struct StatusEffect {
     pub visible: Option<bool>,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Distance<T> {
     pub absolute: Option<Range<T>>,
@@ -132,6 +146,7 @@ pub struct Distance<T> {
     pub z: Option<Range<T>>,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Range<T> {
     pub max: Option<T>,
@@ -139,6 +154,7 @@ pub struct Range<T> {
 }
 
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Item {
     pub count: Option<AmountOrRange<i32>>,
@@ -159,6 +175,7 @@ pub enum AmountOrRange<T> {
     Range(Range<T>),
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Enchantment {
     pub enchantment: Option<UnlocalizedName>,
@@ -174,6 +191,7 @@ pub enum EntityType {
     KillerPlayer,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct Position {
     pub x: Option<AmountOrRange<f64>>,
@@ -181,6 +199,7 @@ pub struct Position {
     pub z: Option<AmountOrRange<f64>>,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct PredicateLocation {
     pub position: Option<Position>,
@@ -198,6 +217,7 @@ pub struct LocationLight {
     pub light: NumberProvider<f32>,
 }
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 pub struct LocationFluid {
     pub fluid: Option<UnlocalizedName>,
@@ -1,28 +1,202 @@
-use std::io::{Read, Write};
+use std::{
+    fmt::Display,
+    io::{Read, Write},
+};
 
-/// A list of commands loaded from a mcfunction file
-///
-/// This is just the contents of the file but with comments stripped
-pub type Function = Vec<String>;
+use qdat::UnlocalizedName;
 
-/// Reads the lines of a string from `reader` and returns a Function after stripping the comments
+use crate::data::tags::TagRegistry;
+
+/// The lines of a mcfunction file
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub lines: Vec<FunctionLine>,
+}
+
+/// One line of a mcfunction file, without any surrounding whitespace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FunctionLine {
+    /// An empty line, which is only kept by [Function::parse_preserving_blank_lines]
+    Blank,
+    /// A comment, without the leading `#`
+    Comment(String),
+    /// A command, without a leading `/`
+    Command(String),
+    /// A command with `$(name)` placeholders which are substituted with the arguments the function
+    /// is run with, without the leading `$`
+    ///
+    /// Added in 1.20.2
+    Macro(String),
+}
+
+impl Function {
+    /// Parses the lines of a mcfunction file, skipping blank lines
+    pub fn parse(source: &str) -> Function {
+        let mut function = Self::parse_preserving_blank_lines(source);
+        function
+            .lines
+            .retain(|line| !matches!(line, FunctionLine::Blank));
+        function
+    }
+
+    /// Parses the lines of a mcfunction file, keeping blank lines so the file can be written back
+    /// with the same layout
+    pub fn parse_preserving_blank_lines(source: &str) -> Function {
+        let lines = source
+            .lines()
+            .map(|line| {
+                let line = line.trim();
+                if line.is_empty() {
+                    FunctionLine::Blank
+                } else if let Some(comment) = line.strip_prefix('#') {
+                    FunctionLine::Comment(comment.to_owned())
+                } else if let Some(command) = line.strip_prefix('$') {
+                    FunctionLine::Macro(command.to_owned())
+                } else {
+                    FunctionLine::Command(line.to_owned())
+                }
+            })
+            .collect();
+
+        Function { lines }
+    }
+
+    /// Returns the lines which are commands or macro lines, skipping comments and blank lines
+    pub fn commands(&self) -> impl Iterator<Item = &FunctionLine> {
+        self.lines
+            .iter()
+            .filter(|line| matches!(line, FunctionLine::Command(_) | FunctionLine::Macro(_)))
+    }
+
+    /// Returns whether any line of the function needs arguments substituted before it is run
+    pub fn is_macro(&self) -> bool {
+        self.lines
+            .iter()
+            .any(|line| matches!(line, FunctionLine::Macro(_)))
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, line) in self.lines.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for FunctionLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FunctionLine::Blank => Ok(()),
+            FunctionLine::Comment(comment) => write!(f, "#{comment}"),
+            FunctionLine::Command(command) => write!(f, "{command}"),
+            FunctionLine::Macro(command) => write!(f, "${command}"),
+        }
+    }
+}
+
+/// Reads the lines of a string from `reader` and parses them into a Function, skipping blank lines
 pub fn read_function<T: Read>(mut reader: T) -> std::io::Result<Function> {
     let mut buf = String::new();
     reader.read_to_string(&mut buf)?;
 
-    Ok(buf
-        .lines()
-        .filter_map(|l| {
-            // Filter out all comment lines
-            if !l.starts_with('#') {
-                Some(l.to_owned())
-            } else {
-                None
-            }
-        })
-        .collect())
+    Ok(Function::parse(&buf))
 }
 
 pub fn write_function<T: Write>(function: &Function, mut writer: T) -> std::io::Result<()> {
-    write!(writer, "{}", function.join("\n"))
+    write!(writer, "{function}")
+}
+
+/// The functions datapacks hook into the server lifecycle with, listed by the `#minecraft:load`
+/// and `#minecraft:tick` function tags
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct LifecycleFunctions {
+    /// The functions run once after datapacks are loaded, in order
+    pub load: Vec<UnlocalizedName>,
+    /// The functions run at the start of every tick, in order
+    pub tick: Vec<UnlocalizedName>,
+}
+
+impl LifecycleFunctions {
+    /// Resolves the load and tick functions from the given function tags
+    ///
+    /// A tag which is missing or references a missing required tag runs no functions
+    pub fn from_tags(function_tags: &TagRegistry) -> Self {
+        let resolve = |tag| {
+            function_tags
+                .resolve_ordered(&UnlocalizedName::minecraft(tag))
+                .unwrap_or_default()
+        };
+
+        LifecycleFunctions {
+            load: resolve("load"),
+            tick: resolve("tick"),
+        }
+    }
+}
+
+#[test]
+fn lifecycle_function_tags() {
+    use crate::data::tags::TagDef;
+
+    let mut function_tags = TagRegistry::new();
+    function_tags.insert(
+        UnlocalizedName::minecraft("tick"),
+        &serde_json::from_str::<TagDef>(
+            r##"{"replace": false, "values": ["pack:tick", "#pack:every_tick", "pack:late_tick"]}"##,
+        )
+        .unwrap(),
+    );
+    function_tags.insert(
+        UnlocalizedName::from_str("pack:every_tick").unwrap(),
+        &serde_json::from_str::<TagDef>(
+            r##"{"replace": false, "values": ["other:timer", "pack:tick"]}"##,
+        )
+        .unwrap(),
+    );
+
+    let functions = LifecycleFunctions::from_tags(&function_tags);
+    assert_eq!(functions.tick, vec![
+        UnlocalizedName::from_str("pack:tick").unwrap(),
+        UnlocalizedName::from_str("other:timer").unwrap(),
+        UnlocalizedName::from_str("pack:late_tick").unwrap(),
+    ]);
+    assert!(functions.load.is_empty());
+}
+
+#[test]
+fn parse_function_lines() {
+    let source = "# Greets everyone\nsay hello  \n\n  $tp @s $(x) $(y) $(z)\n#end";
+
+    let function = Function::parse(source);
+    assert_eq!(function.lines, vec![
+        FunctionLine::Comment(" Greets everyone".to_owned()),
+        FunctionLine::Command("say hello".to_owned()),
+        FunctionLine::Macro("tp @s $(x) $(y) $(z)".to_owned()),
+        FunctionLine::Comment("end".to_owned()),
+    ]);
+    assert!(function.is_macro());
+    assert_eq!(function.commands().count(), 2);
+    assert_eq!(
+        function.to_string(),
+        "# Greets everyone\nsay hello\n$tp @s $(x) $(y) $(z)\n#end"
+    );
+
+    let function = Function::parse_preserving_blank_lines(source);
+    assert_eq!(function.lines[2], FunctionLine::Blank);
+    assert_eq!(
+        function.to_string(),
+        "# Greets everyone\nsay hello\n\n$tp @s $(x) $(y) $(z)\n#end"
+    );
+    assert_eq!(
+        Function::parse_preserving_blank_lines(&function.to_string()),
+        function
+    );
+
+    assert!(!Function::parse("say hi").is_macro());
+    assert!(Function::parse("").lines.is_empty());
 }
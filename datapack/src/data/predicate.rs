@@ -3,14 +3,21 @@ use std::collections::HashMap;
 use crate::data::datatypes::*;
 use qdat::UnlocalizedName;
 use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
 
 use super::loot_tables::NumberProvider;
 
+#[skip_serializing_none]
 #[derive(Serialize, Deserialize)]
 #[serde(tag = "condition")]
 pub enum Predicate {
+    #[serde(rename = "minecraft:all_of")]
+    AllOf { terms: Vec<Predicate> },
     #[serde(rename = "minecraft:alternative")]
     Alternative { terms: Vec<Predicate> },
+    /// Replaces `minecraft:alternative` as of 1.20
+    #[serde(rename = "minecraft:any_of")]
+    AnyOf { terms: Vec<Predicate> },
     #[serde(rename = "minecraft:block_state_property")]
     BlockStateProperty {
         block: UnlocalizedName,
@@ -65,14 +72,139 @@ pub enum Predicate {
     },
     #[serde(rename = "minecraft:time_check")]
     TimeCheck {
-        value: NumberProvider<i32>,
-        period: i32,
+        value: AmountOrRange<NumberProvider<i32>>,
+        /// The time is taken modulo the period if one is given, such as 24000 to check the time of
+        /// day
+        period: Option<i64>,
     },
     #[serde(rename = "minecraft:weather_check")]
-    WeatherCheck { raining: bool, thundering: bool },
+    WeatherCheck {
+        raining: Option<bool>,
+        thundering: Option<bool>,
+    },
     #[serde(rename = "minecraft:value_check")]
     ValueCheck {
         value: NumberProvider<i32>,
         range: AmountOrRange<NumberProvider<i32>>,
     },
 }
+
+#[cfg(test)]
+fn assert_round_trip(json: &str) {
+    use serde_json::Value;
+
+    let predicate: Predicate = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&serde_json::to_string(&predicate).unwrap()).unwrap(),
+        serde_json::from_str::<Value>(json).unwrap()
+    );
+}
+
+#[test]
+fn all_of_any_of_round_trip() {
+    assert_round_trip(
+        r#"{
+            "condition": "minecraft:all_of",
+            "terms": [
+                {"condition": "minecraft:killed_by_player", "inverse": false},
+                {
+                    "condition": "minecraft:any_of",
+                    "terms": [
+                        {"condition": "minecraft:random_chance", "chance": 0.5},
+                        {"condition": "minecraft:survives_explosion"}
+                    ]
+                }
+            ]
+        }"#,
+    );
+}
+
+#[test]
+fn location_check_round_trip() {
+    assert_round_trip(
+        r#"{
+            "condition": "minecraft:location_check",
+            "offsetY": -1,
+            "predicate": {
+                "block": {
+                    "blocks": ["minecraft:campfire"],
+                    "state": {"lit": "true"}
+                },
+                "dimension": "minecraft:overworld"
+            }
+        }"#,
+    );
+}
+
+#[test]
+fn weather_check_round_trip() {
+    assert_round_trip(r#"{"condition": "minecraft:weather_check", "raining": true}"#);
+    assert_round_trip(
+        r#"{"condition": "minecraft:weather_check", "raining": true, "thundering": false}"#,
+    );
+}
+
+#[test]
+fn value_check_round_trip() {
+    assert_round_trip(
+        r#"{
+            "condition": "minecraft:value_check",
+            "value": {
+                "type": "minecraft:uniform",
+                "min": 1,
+                "max": 10
+            },
+            "range": {"min": 3, "max": 7}
+        }"#,
+    );
+    assert_round_trip(r#"{"condition": "minecraft:value_check", "value": 4, "range": 4}"#);
+}
+
+#[test]
+fn time_check_round_trip() {
+    assert_round_trip(
+        r#"{
+            "condition": "minecraft:time_check",
+            "value": {"min": 0, "max": 12000},
+            "period": 24000
+        }"#,
+    );
+    assert_round_trip(r#"{"condition": "minecraft:time_check", "value": 6000}"#);
+}
+
+#[test]
+fn table_bonus_round_trip() {
+    assert_round_trip(
+        r#"{
+            "condition": "minecraft:table_bonus",
+            "enchantment": "minecraft:fortune",
+            "chances": [0.05, 0.0625, 0.083333336, 0.1]
+        }"#,
+    );
+}
+
+#[test]
+fn reference_round_trip() {
+    assert_round_trip(r#"{"condition": "minecraft:reference", "name": "quartz:is_night"}"#);
+}
+
+#[test]
+fn nested_entity_properties_round_trip() {
+    assert_round_trip(
+        r#"{
+            "condition": "minecraft:entity_properties",
+            "entity": "this",
+            "predicate": {
+                "type": "minecraft:player",
+                "flags": {"is_sneaking": true},
+                "vehicle": {
+                    "type": "minecraft:horse",
+                    "passenger": {
+                        "type": "minecraft:player",
+                        "location": {"biome": "minecraft:plains"}
+                    }
+                }
+            }
+        }"#,
+    );
+}
@@ -1,4 +1,4 @@
-use qdat::UnlocalizedName;
+use qdat::{UlnStr, UnlocalizedName};
 use serde::{
     de::Visitor,
     ser::{SerializeMap, SerializeSeq},
@@ -6,6 +6,8 @@ use serde::{
     Serialize,
 };
 
+use crate::data::tags::TagRegistry;
+
 /// Represents an ingredient in a recipe
 #[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Debug)]
 pub enum Ingredient {
@@ -13,6 +15,10 @@ pub enum Ingredient {
     Item(UnlocalizedName),
     /// A tag to use as the item provider
     Tag(UnlocalizedName),
+    /// A tag along with the items it contains, see [Ingredient::resolve_tags]
+    ///
+    /// Serializes the same as [Ingredient::Tag]
+    ResolvedTag(Box<ResolvedTag>),
     /// A list of ingredients to use as an ingredient provider
     /// # Note
     ///Do not have nested Ingredient::Lists
@@ -25,11 +31,49 @@ impl Ingredient {
             Ingredient::Item(i) => i == "minecraft:air",
             // As long as the UnlocalizedName is valid we need to resolve the tag
             Ingredient::Tag(_) => false,
+            Ingredient::ResolvedTag(resolved) => resolved.items.is_empty(),
             // the iter.any call can be expensive if the array is large
             // in practice lists should p much not be used cause tags exist sooooooo
             Ingredient::List(l) => l.len() == 0 || l.iter().any(|i| i.is_empty()),
         }
     }
+
+    /// Replaces each tag that is loaded in the registry with the items it contains, so matching
+    /// against it doesn't have to look up the tag
+    pub fn resolve_tags(&mut self, tags: &TagRegistry) {
+        match self {
+            Ingredient::Tag(tag) =>
+                if let Some(items) = tags.resolve(tag) {
+                    *self = Ingredient::ResolvedTag(Box::new(ResolvedTag {
+                        tag: tag.clone(),
+                        items: items.into_boxed_slice(),
+                    }));
+                },
+            Ingredient::List(l) => l.iter_mut().for_each(|i| i.resolve_tags(tags)),
+            Ingredient::Item(_) | Ingredient::ResolvedTag(_) => {}
+        }
+    }
+
+    /// Returns whether the given item can be used as this ingredient
+    ///
+    /// Tags which have not been resolved are looked up in the registry
+    pub fn matches(&self, item: &UlnStr, tags: &TagRegistry) -> bool {
+        match self {
+            Ingredient::Item(i) => i == item,
+            Ingredient::Tag(tag) => tags.contains(tag, item),
+            Ingredient::ResolvedTag(resolved) =>
+                resolved.items.binary_search_by(|i| (**i).cmp(item)).is_ok(),
+            Ingredient::List(l) => l.iter().any(|i| i.matches(item, tags)),
+        }
+    }
+}
+
+/// A tag which has been resolved into the items it contains
+#[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Debug)]
+pub struct ResolvedTag {
+    pub tag: UnlocalizedName,
+    /// The items in the tag, sorted so they can be binary searched
+    pub items: Box<[UnlocalizedName]>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -54,6 +98,11 @@ impl Serialize for Ingredient {
                 struc.serialize_entry("tag", &t.to_string())?;
                 struc.end()
             }
+            Ingredient::ResolvedTag(resolved) => {
+                let mut struc = serializer.serialize_map(Some(1))?;
+                struc.serialize_entry("tag", &resolved.tag.to_string())?;
+                struc.end()
+            }
             Ingredient::List(l) => {
                 let mut arr = serializer.serialize_seq(Some(l.len()))?;
                 for ingr in l.iter() {
@@ -66,6 +115,11 @@ impl Serialize for Ingredient {
                         Ingredient::Tag(t) => {
                             arr.serialize_element(&TagJSON { tag: t.to_string() })?;
                         }
+                        Ingredient::ResolvedTag(resolved) => {
+                            arr.serialize_element(&TagJSON {
+                                tag: resolved.tag.to_string(),
+                            })?;
+                        }
                         Ingredient::List(_) =>
                             return Err(serde::ser::Error::custom(
                                 "You can't have a list of ingredients inside another list of \
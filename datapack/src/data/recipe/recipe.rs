@@ -1,12 +1,15 @@
 use qdat::UnlocalizedName;
 use serde::{Deserialize, Serialize};
 
-use crate::data::recipe::{
-    cooking::*,
-    shaped::ShapedCraftingRecipe,
-    shapeless::ShapelessCraftingRecipe,
-    smithing::SmithingRecipe,
-    stonecutting::StonecuttingRecipe,
+use crate::data::{
+    recipe::{
+        cooking::*,
+        shaped::ShapedCraftingRecipe,
+        shapeless::ShapelessCraftingRecipe,
+        smithing::{SmithingRecipe, SmithingTransformRecipe, SmithingTrimRecipe},
+        stonecutting::StonecuttingRecipe,
+    },
+    tags::TagRegistry,
 };
 
 
@@ -43,6 +46,10 @@ pub enum VanillaRecipeType {
     CampfireRecipe(CookingRecipe<CampfireRecipe>),
     #[serde(rename = "minecraft:smithing")]
     SmithingRecipe(SmithingRecipe),
+    #[serde(rename = "minecraft:smithing_transform")]
+    SmithingTransformRecipe(SmithingTransformRecipe),
+    #[serde(rename = "minecraft:smithing_trim")]
+    SmithingTrimRecipe(SmithingTrimRecipe),
     #[serde(rename = "minecraft:stonecutting")]
     StonecuttingRecipe(StonecuttingRecipe),
 
@@ -78,3 +85,93 @@ pub enum VanillaRecipeType {
     #[serde(other)]
     Unknown,
 }
+
+impl VanillaRecipeType {
+    /// Resolves the tags used by this recipe's ingredients, which should be run once the recipe
+    /// and the item tags are loaded
+    pub fn resolve_tags(&mut self, item_tags: &TagRegistry) {
+        match self {
+            VanillaRecipeType::ShapedRecipe(recipe) => recipe
+                .input
+                .iter_mut()
+                .flatten()
+                .flatten()
+                .for_each(|ingredient| ingredient.resolve_tags(item_tags)),
+            VanillaRecipeType::ShapelessRecipe(recipe) => recipe
+                .inputs
+                .iter_mut()
+                .for_each(|(ingredient, _)| ingredient.resolve_tags(item_tags)),
+            VanillaRecipeType::SmeltingRecipe(recipe) => recipe.input.resolve_tags(item_tags),
+            VanillaRecipeType::BlastingRecipe(recipe) => recipe.input.resolve_tags(item_tags),
+            VanillaRecipeType::SmokingRecipe(recipe) => recipe.input.resolve_tags(item_tags),
+            VanillaRecipeType::CampfireRecipe(recipe) => recipe.input.resolve_tags(item_tags),
+            VanillaRecipeType::SmithingRecipe(recipe) => {
+                recipe.base.resolve_tags(item_tags);
+                recipe.addition.resolve_tags(item_tags);
+            }
+            VanillaRecipeType::SmithingTransformRecipe(recipe) => {
+                recipe.template.resolve_tags(item_tags);
+                recipe.base.resolve_tags(item_tags);
+                recipe.addition.resolve_tags(item_tags);
+            }
+            VanillaRecipeType::SmithingTrimRecipe(recipe) => {
+                recipe.template.resolve_tags(item_tags);
+                recipe.base.resolve_tags(item_tags);
+                recipe.addition.resolve_tags(item_tags);
+            }
+            VanillaRecipeType::StonecuttingRecipe(recipe) => recipe.input.resolve_tags(item_tags),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn planks_tag_resolution() {
+    use crate::data::{recipe::ingredient::Ingredient, tags::TagDef};
+
+    let mut item_tags = TagRegistry::new();
+    item_tags.insert(
+        UnlocalizedName::minecraft("planks"),
+        &serde_json::from_str::<TagDef>(
+            r##"{"replace": false, "values": ["minecraft:oak_planks", "#minecraft:non_flammable_wood"]}"##,
+        )
+        .unwrap(),
+    );
+
+    let mut recipe: VanillaRecipeType = serde_json::from_str(
+        r#"{
+            "type": "minecraft:crafting_shapeless",
+            "ingredients": [{"tag": "minecraft:planks"}],
+            "result": {"item": "minecraft:oak_button"}
+        }"#,
+    )
+    .unwrap();
+    let input = |recipe: &VanillaRecipeType| match recipe {
+        VanillaRecipeType::ShapelessRecipe(recipe) =>
+            recipe.inputs.iter().next().unwrap().0.clone(),
+        _ => unreachable!(),
+    };
+
+    // The nested tag isn't loaded yet, so the tag can't be resolved
+    recipe.resolve_tags(&item_tags);
+    assert!(matches!(input(&recipe), Ingredient::Tag(_)));
+
+    item_tags.insert(
+        UnlocalizedName::minecraft("non_flammable_wood"),
+        &serde_json::from_str::<TagDef>(
+            r#"{"replace": false, "values": ["minecraft:crimson_planks", "minecraft:warped_planks"]}"#,
+        )
+        .unwrap(),
+    );
+    let unresolved = input(&recipe);
+    assert!(unresolved.matches(&UnlocalizedName::minecraft("warped_planks"), &item_tags));
+
+    recipe.resolve_tags(&item_tags);
+    let resolved = input(&recipe);
+    assert!(matches!(resolved, Ingredient::ResolvedTag(_)));
+    let no_tags = TagRegistry::new();
+    for plank in ["oak_planks", "crimson_planks", "warped_planks"] {
+        assert!(resolved.matches(&UnlocalizedName::minecraft(plank), &no_tags));
+    }
+    assert!(!resolved.matches(&UnlocalizedName::minecraft("stone"), &no_tags));
+}
@@ -18,6 +18,17 @@ pub struct ShapelessCraftingRecipe {
 #[derive(Debug, PartialEq, Eq)]
 pub struct ShapelessIngredients(Box<[(Ingredient, u8)]>);
 
+impl ShapelessIngredients {
+    /// Returns each distinct ingredient along with how many times it is needed
+    pub fn iter(&self) -> impl Iterator<Item = &(Ingredient, u8)> {
+        self.0.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut (Ingredient, u8)> {
+        self.0.iter_mut()
+    }
+}
+
 impl Serialize for ShapelessIngredients {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where S: serde::Serializer {
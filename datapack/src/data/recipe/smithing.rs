@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::data::recipe::ingredient::Ingredient;
 
-/// A smithing recipe
+/// A smithing recipe from before 1.20, which has no template
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
 pub struct SmithingRecipe {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -23,6 +23,32 @@ pub struct SmithingOutput {
     pub item: UnlocalizedName,
 }
 
+/// A 1.20 smithing recipe which transforms the base item into the result
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct SmithingTransformRecipe {
+    /// The smithing template, such as the netherite upgrade
+    pub template: Ingredient,
+    pub base: Ingredient,
+    pub addition: Ingredient,
+    /// In most cases, the nbt of enchantments will be carried over to the result
+    pub result: SmithingOutput,
+}
+
+/// A 1.20 smithing recipe which applies an armor trim to the base item
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct SmithingTrimRecipe {
+    /// The trim template
+    pub template: Ingredient,
+    /// The armor to trim
+    pub base: Ingredient,
+    /// The material of the trim
+    pub addition: Ingredient,
+    /// The trim pattern applied, which newer versions require rather than inferring it from the
+    /// template
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<UnlocalizedName>,
+}
+
 #[test]
 fn smithing_ser_test() {
     use crate::data::recipe::{ingredient::Ingredient, VanillaRecipeType};
@@ -75,3 +101,56 @@ fn smithing_de_test() {
         })
     )
 }
+
+#[test]
+fn smithing_transform_round_trip() {
+    use crate::data::recipe::VanillaRecipeType;
+
+    let input = r#"{"type":"minecraft:smithing_transform","template":{"item":"minecraft:netherite_upgrade_smithing_template"},"base":{"item":"minecraft:diamond_pickaxe"},"addition":{"tag":"minecraft:netherite_ingots"},"result":{"item":"minecraft:netherite_pickaxe"}}"#;
+
+    let recipe: VanillaRecipeType = serde_json::from_str(input).unwrap();
+    assert_eq!(
+        recipe,
+        VanillaRecipeType::SmithingTransformRecipe(SmithingTransformRecipe {
+            template: Ingredient::Item(UnlocalizedName::minecraft(
+                "netherite_upgrade_smithing_template"
+            )),
+            base: Ingredient::Item(UnlocalizedName::minecraft("diamond_pickaxe")),
+            addition: Ingredient::Tag(UnlocalizedName::minecraft("netherite_ingots")),
+            result: SmithingOutput {
+                item: UnlocalizedName::minecraft("netherite_pickaxe")
+            }
+        })
+    );
+    assert_eq!(serde_json::to_string(&recipe).unwrap(), input);
+}
+
+#[test]
+fn smithing_trim_round_trip() {
+    use crate::data::recipe::VanillaRecipeType;
+
+    let input = r#"{"type":"minecraft:smithing_trim","template":{"item":"minecraft:coast_armor_trim_smithing_template"},"base":{"tag":"minecraft:trimmable_armor"},"addition":{"tag":"minecraft:trim_materials"},"pattern":"minecraft:coast"}"#;
+
+    let recipe: VanillaRecipeType = serde_json::from_str(input).unwrap();
+    assert_eq!(
+        recipe,
+        VanillaRecipeType::SmithingTrimRecipe(SmithingTrimRecipe {
+            template: Ingredient::Item(UnlocalizedName::minecraft(
+                "coast_armor_trim_smithing_template"
+            )),
+            base: Ingredient::Tag(UnlocalizedName::minecraft("trimmable_armor")),
+            addition: Ingredient::Tag(UnlocalizedName::minecraft("trim_materials")),
+            pattern: Some(UnlocalizedName::minecraft("coast")),
+        })
+    );
+    assert_eq!(serde_json::to_string(&recipe).unwrap(), input);
+
+    // 1.20 trim recipes don't specify the pattern
+    let input = r#"{"type":"minecraft:smithing_trim","template":{"item":"minecraft:coast_armor_trim_smithing_template"},"base":{"tag":"minecraft:trimmable_armor"},"addition":{"tag":"minecraft:trim_materials"}}"#;
+    let recipe: VanillaRecipeType = serde_json::from_str(input).unwrap();
+    assert!(matches!(
+        &recipe,
+        VanillaRecipeType::SmithingTrimRecipe(recipe) if recipe.pattern.is_none()
+    ));
+    assert_eq!(serde_json::to_string(&recipe).unwrap(), input);
+}
@@ -1,4 +1,6 @@
-use qdat::UnlocalizedName;
+use std::collections::{BTreeSet, HashMap};
+
+use qdat::{UlnStr, UnlocalizedName};
 use serde::{
     de::Visitor,
     ser::{SerializeMap, SerializeSeq},
@@ -6,6 +8,8 @@ use serde::{
     Serialize,
 };
 
+use crate::Namespace;
+
 /// A [tag](https://minecraft.fandom.com/wiki/Tag)
 pub struct Tag {
     pub def: TagDef,
@@ -21,16 +25,26 @@ impl Tag {
         &self.def.values
     }
 
+    pub fn remove(&self) -> &Vec<TagEntry> {
+        &self.def.remove
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 }
 
 /// The raw json definition of a tag
-#[derive(Serialize, Debug, PartialEq)]
+#[derive(Serialize, Debug, PartialEq, Clone)]
 pub struct TagDef {
+    /// Whether this tag replaces the entries loaded by lower priority datapacks instead of adding
+    /// to them
     pub replace: bool,
     pub values: Vec<TagEntry>,
+    /// The entries removed from the tag loaded by lower priority datapacks, which is used by
+    /// overlays to take entries out of a tag without replacing it
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<TagEntry>,
 }
 
 impl<'de> Deserialize<'de> for TagDef {
@@ -54,16 +68,21 @@ impl<'de> Visitor<'de> for TagDefVisitor {
         map_visitor!(
             map,
             (replace, "replace", bool),
-            (values, "values", Vec<TagEntry>)
+            (values, "values", Vec<TagEntry>),
+            (remove, "remove", Vec<TagEntry>)
         );
-        missing_field_error!(replace, "replace", values, "values");
+        missing_field_error!(values, "values");
 
-        Ok(TagDef { replace, values })
+        Ok(TagDef {
+            replace: replace.unwrap_or(false),
+            values,
+            remove: remove.unwrap_or_default(),
+        })
     }
 }
 
 /// One value of a tag
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum TagEntry {
     /// A Namespace ID (aka UnlocalizedName), Ex: `minecraft:stone`
     NamespaceID(UnlocalizedName),
@@ -77,10 +96,29 @@ pub enum TagEntry {
     /// TagEntry in this context cannot be another FailableEntry
     /// this is enforced in the Serialize and Deserialze impls
     ///
+    /// Serialized as an object with the `id` and `required` fields<br>
     /// See [the minecraft wiki](https://minecraft.fandom.com/wiki/Tag#JSON_format) for more details
     FailableEntry(Box<TagEntry>, bool),
 }
 
+impl TagEntry {
+    /// Gets the id or tag this entry refers to, without the options of a [FailableEntry](TagEntry::FailableEntry)
+    pub fn entry(&self) -> &TagEntry {
+        match self {
+            TagEntry::FailableEntry(entry, _) => entry,
+            entry => entry,
+        }
+    }
+
+    /// Gets whether the tag fails to load if this entry cannot be found
+    pub fn required(&self) -> bool {
+        match self {
+            TagEntry::FailableEntry(_, required) => *required,
+            _ => true,
+        }
+    }
+}
+
 impl Serialize for TagEntry {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where S: serde::Serializer {
@@ -93,8 +131,8 @@ impl Serialize for TagEntry {
             TagEntry::FailableEntry(entry, required) => {
                 let mut map = serializer.serialize_map(Some(2))?;
                 match entry.as_ref() {
-                    TagEntry::NamespaceID(n) => map.serialize_entry("value", n)?,
-                    TagEntry::Tag(t) => map.serialize_entry("value", t)?,
+                    entry @ (TagEntry::NamespaceID(_) | TagEntry::Tag(_)) =>
+                        map.serialize_entry("id", entry)?,
                     TagEntry::FailableEntry(..) =>
                         return Err(serde::ser::Error::custom(
                             "TagEntry::EntryWithOptions cannot contain another EntryWithOptions",
@@ -147,9 +185,10 @@ impl<'de> Visitor<'de> for TagEntryVisitor {
 
         while let Some(key) = map.next_key()? {
             match key {
-                "value" => {
+                // Older versions of the format used value instead of id
+                "id" | "value" => {
                     if value.is_some() {
-                        return Err(serde::de::Error::duplicate_field("value"));
+                        return Err(serde::de::Error::duplicate_field("id"));
                     }
 
                     value = Some(map.next_value::<TagEntryChecker>()?.0)
@@ -161,12 +200,12 @@ impl<'de> Visitor<'de> for TagEntryVisitor {
 
                     required = Some(map.next_value::<bool>()?)
                 }
-                _ => return Err(serde::de::Error::unknown_field(key, &["value", "required"])),
+                _ => return Err(serde::de::Error::unknown_field(key, &["id", "required"])),
             }
         }
 
         if value.is_none() {
-            return Err(serde::de::Error::missing_field("value"));
+            return Err(serde::de::Error::missing_field("id"));
         }
 
         let value = value.unwrap();
@@ -241,7 +280,8 @@ fn tag_test() {
             TagEntry::NamespaceID(UnlocalizedName::minecraft("anvil")),
             TagEntry::NamespaceID(UnlocalizedName::minecraft("chipped_anvil")),
             TagEntry::NamespaceID(UnlocalizedName::minecraft("damaged_anvil"))
-        ]
+        ],
+        remove: Vec::new(),
     });
 }
 
@@ -269,10 +309,75 @@ fn tag_mixed_entry_test() {
                 Box::new(TagEntry::NamespaceID(UnlocalizedName::minecraft("stone"))),
                 true
             )
-        ]
+        ],
+        remove: Vec::new(),
     });
 }
 
+#[test]
+fn tag_references_and_removals_test() {
+    let json = r##"{
+        "replace": true,
+        "values": [
+            "minecraft:oak_log",
+            "#minecraft:logs",
+            {
+                "id": "#minecraft:planks",
+                "required": false
+            },
+            {
+                "id": "quartz:marble",
+                "required": false
+            }
+        ],
+        "remove": ["minecraft:oak_log"]
+    }"##;
+
+    let tag_def: TagDef = serde_json::from_str(json).unwrap();
+
+    assert!(tag_def.replace);
+    assert_eq!(tag_def.values, vec![
+        TagEntry::NamespaceID(UnlocalizedName::minecraft("oak_log")),
+        TagEntry::Tag("minecraft:logs".to_owned()),
+        TagEntry::FailableEntry(
+            Box::new(TagEntry::Tag("minecraft:planks".to_owned())),
+            false
+        ),
+        TagEntry::FailableEntry(
+            Box::new(TagEntry::NamespaceID(
+                UnlocalizedName::from_str("quartz:marble").unwrap()
+            )),
+            false
+        ),
+    ]);
+    assert_eq!(tag_def.remove, vec![TagEntry::NamespaceID(
+        UnlocalizedName::minecraft("oak_log")
+    )]);
+    assert!(!tag_def.values[2].required());
+    assert_eq!(
+        tag_def.values[2].entry(),
+        &TagEntry::Tag("minecraft:planks".to_owned())
+    );
+
+    let serialized = serde_json::to_string(&tag_def).unwrap();
+    assert_eq!(
+        serialized,
+        r##"{"replace":true,"values":["minecraft:oak_log","#minecraft:logs",{"id":"#minecraft:planks","required":false},{"id":"quartz:marble","required":false}],"remove":["minecraft:oak_log"]}"##
+    );
+    assert_eq!(
+        serde_json::from_str::<TagDef>(&serialized).unwrap(),
+        tag_def
+    );
+
+    // replace defaults to false and remove is omitted when empty
+    let tag_def: TagDef = serde_json::from_str(r#"{"values":["minecraft:stone"]}"#).unwrap();
+    assert!(!tag_def.replace);
+    assert_eq!(
+        serde_json::to_string(&tag_def).unwrap(),
+        r#"{"replace":false,"values":["minecraft:stone"]}"#
+    );
+}
+
 #[test]
 #[should_panic]
 fn failable_test() {
@@ -298,10 +403,126 @@ fn failable_test() {
                 true
             )),
             true
-        )]
+        )],
+        remove: Vec::new(),
     });
 }
 
+/// The loaded tags of a single type, such as item tags, which can be resolved into the ids they
+/// contain
+#[derive(Default)]
+pub struct TagRegistry {
+    tags: HashMap<UnlocalizedName, Vec<TagEntry>>,
+}
+
+impl TagRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the entries of a tag definition, replacing the existing entries if the definition has
+    /// `replace` set, then removes the entries the definition lists in `remove`
+    pub fn insert(&mut self, name: UnlocalizedName, def: &TagDef) {
+        let entries = self.tags.entry(name).or_default();
+        if def.replace {
+            entries.clear();
+        }
+        entries.extend(def.values.iter().cloned());
+        entries.retain(|entry| {
+            !def.remove
+                .iter()
+                .any(|removed| removed.entry() == entry.entry())
+        });
+    }
+
+    /// Adds every tag of the given type, such as `items`, from a datapack namespace
+    pub fn load_namespace(&mut self, namespace: &Namespace, tag_type: &str) {
+        for tag in &namespace.tags {
+            let path = match tag
+                .name()
+                .strip_prefix(tag_type)
+                .and_then(|path| path.strip_prefix('/'))
+            {
+                Some(path) => path,
+                None => continue,
+            };
+
+            if let Ok(name) = UnlocalizedName::from_str(&format!("{}:{path}", namespace.name)) {
+                self.insert(name, &tag.def);
+            }
+        }
+    }
+
+    pub fn contains_tag(&self, tag: &UlnStr) -> bool {
+        self.tags.contains_key(tag)
+    }
+
+    /// Returns the sorted ids in the given tag, including the ids of any tags it references
+    ///
+    /// Returns `None` if the tag, or a required tag it references, is not loaded
+    pub fn resolve(&self, tag: &UlnStr) -> Option<Vec<UnlocalizedName>> {
+        let mut ids = self.resolve_ordered(tag)?;
+        ids.sort();
+        Some(ids)
+    }
+
+    /// Returns the ids in the given tag in the order they are listed, with the ids of referenced
+    /// tags in place of the reference. Ids listed more than once only keep their first position
+    ///
+    /// Returns `None` if the tag, or a required tag it references, is not loaded
+    pub fn resolve_ordered(&self, tag: &UlnStr) -> Option<Vec<UnlocalizedName>> {
+        let mut ids = Vec::new();
+        self.resolve_into(tag, &mut ids, &mut BTreeSet::new(), &mut Vec::new())?;
+        Some(ids)
+    }
+
+    fn resolve_into(
+        &self,
+        tag: &UlnStr,
+        ids: &mut Vec<UnlocalizedName>,
+        seen: &mut BTreeSet<UnlocalizedName>,
+        visiting: &mut Vec<UnlocalizedName>,
+    ) -> Option<()> {
+        // Tags which reference themselves don't add anything new
+        if visiting.iter().any(|visited| visited == tag) {
+            return Some(());
+        }
+
+        let entries = self.tags.get(tag)?;
+        visiting.push(tag.to_owned());
+        for entry in entries {
+            let (entry, required) = (entry.entry(), entry.required());
+
+            let resolved = match entry {
+                TagEntry::NamespaceID(id) => {
+                    if seen.insert(id.clone()) {
+                        ids.push(id.clone());
+                    }
+                    Some(())
+                }
+                TagEntry::Tag(name) => UnlocalizedName::from_str(name)
+                    .ok()
+                    .and_then(|name| self.resolve_into(&name, ids, seen, visiting)),
+                TagEntry::FailableEntry(..) => None,
+            };
+
+            if resolved.is_none() && required {
+                return None;
+            }
+        }
+        visiting.pop();
+
+        Some(())
+    }
+
+    /// Looks up whether the given id is in a tag without caching the resolved tag
+    pub fn contains(&self, tag: &UlnStr, id: &UlnStr) -> bool {
+        self.resolve(tag)
+            .map(|ids| ids.iter().any(|tag_id| tag_id == id))
+            .unwrap_or(false)
+    }
+}
+
 pub struct TagProvider(UnlocalizedName);
 
 impl<'de> Deserialize<'de> for TagProvider {
@@ -371,7 +592,7 @@ impl<'de> Visitor<'de> for IdsOrTagVisitor {
         write!(formatter, "A reference to a tag or list of tags")
     }
 
-    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
     where E: serde::de::Error {
         if let Some(v) = v.strip_prefix('#') {
             match UnlocalizedName::from_str(v) {
@@ -411,11 +632,11 @@ impl Serialize for IdsOrTag {
     where S: serde::Serializer {
         match self {
             IdsOrTag::SingleTag(uln) => serializer.serialize_str(&format!("#{uln}")),
-            IdsOrTag::SingleId(uln) => serializer.serialize_str(uln.repr()),
+            IdsOrTag::SingleId(uln) => uln.serialize(serializer),
             IdsOrTag::IdList(ulns) => {
                 let mut seq = serializer.serialize_seq(Some(ulns.len()))?;
                 for uln in ulns {
-                    seq.serialize_element(uln.repr())?
+                    seq.serialize_element(uln)?
                 }
                 seq.end()
             }
@@ -464,7 +685,7 @@ fn ids_or_list_test() {
 
     assert_eq!(
         parsed.block3,
-        IdsOrTag::SingleTag(UnlocalizedName::minecraft("moss_replacable"))
+        IdsOrTag::SingleId(UnlocalizedName::minecraft("moss_replacable"))
     );
 
     let str = to_string_pretty(&parsed);
@@ -476,12 +697,12 @@ fn ids_or_list_test() {
     assert_eq!(
         r###"{
   "name": "hello",
-  "block": "logs",
+  "block": "#minecraft:logs",
   "block2": [
-    "logs",
-    "logs2"
+    "minecraft:logs",
+    "minecraft:logs2"
   ],
-  "block3": "moss_replacable"
+  "block3": "minecraft:moss_replacable"
 }"###,
         str
     )
@@ -7,20 +7,43 @@ use crate::data::tags::IdsOrTag;
 
 #[derive(Serialize, Deserialize)]
 pub struct Biome {
-    pub category: BiomeCategory,
-    pub precipitation: Precipitation,
+    /// Removed in 1.19
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<BiomeCategory>,
+    /// Replaced by [has_precipitation](Biome::has_precipitation) in 1.19.4
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub precipitation: Option<Precipitation>,
+    /// Added in 1.19.4, where the type of precipitation is decided by the temperature instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_precipitation: Option<bool>,
     pub temperature: f32,
-    pub tempearture_modifier: Option<TemperatureModifier>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature_modifier: Option<TemperatureModifier>,
     pub downfall: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub creature_spawn_probability: Option<f32>,
     pub effects: BiomeEffects,
     pub carvers: BiomeCarvers,
+    /// The placed features generated in each generation step
     pub features: Vec<IdsOrTag>,
     pub spawners: HashMap<MobCategory, Vec<MobSpawners>>,
     // This is literally never used by vanilla
+    #[serde(default)]
     pub spawn_costs: HashMap<String, SpawnCosts>,
 }
 
+impl Biome {
+    /// Gets whether it rains or snows in this biome, using whichever of the pre and post 1.19.4
+    /// formats the biome was defined with
+    pub fn has_precipitation(&self) -> bool {
+        match (self.has_precipitation, &self.precipitation) {
+            (Some(has_precipitation), _) => has_precipitation,
+            (None, Some(precipitation)) => !matches!(precipitation, Precipitation::None),
+            (None, None) => false,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Precipitation {
@@ -54,6 +77,7 @@ pub enum BiomeCategory {
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TemperatureModifier {
     None,
     Frozen,
@@ -61,15 +85,29 @@ pub enum TemperatureModifier {
 
 #[derive(Serialize, Deserialize)]
 pub struct BiomeEffects {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub fog_color: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub foliage_color: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub grass_color: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sky_color: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub water_color: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub water_fog_color: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grass_color_modifier: Option<GrassColorModifier>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub particle: Option<BiomeParticle>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub additions_sound: Option<AdditionalSound>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ambient_sound: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mood_sound: Option<MoodSound>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub music: Option<BiomeMusic>,
 }
 
@@ -174,7 +212,9 @@ pub enum MobCategory {
 
 #[derive(Serialize, Deserialize)]
 pub struct BiomeCarvers {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub air: Option<BiomeCarver>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub liquid: Option<BiomeCarver>,
 }
 
@@ -184,3 +224,51 @@ pub enum BiomeCarver {
     Singleton(UnlocalizedName),
     List(#[serde(default)] Vec<UnlocalizedName>),
 }
+
+#[test]
+fn plains_round_trip() {
+    use serde_json::Value;
+
+    let json = include_str!("../../../../assets/plains.json");
+    let biome: Biome = serde_json::from_str(json).unwrap();
+
+    assert!(biome.has_precipitation());
+    assert_eq!(biome.temperature, 0.8);
+    assert_eq!(biome.features.len(), 11);
+    assert_eq!(biome.spawners[&MobCategory::Creature].len(), 6);
+    assert_eq!(
+        biome.effects.mood_sound.as_ref().unwrap().sound,
+        "minecraft:ambient.cave"
+    );
+    assert!(matches!(
+        &biome.carvers.air,
+        Some(BiomeCarver::List(carvers)) if carvers.len() == 3
+    ));
+
+    let serialized = serde_json::to_string(&biome).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Value>(&serialized).unwrap(),
+        serde_json::from_str::<Value>(json).unwrap()
+    );
+}
+
+#[test]
+fn legacy_precipitation() {
+    let json = r#"{
+        "category": "icy",
+        "precipitation": "snow",
+        "temperature": 0.0,
+        "downfall": 0.5,
+        "effects": {},
+        "carvers": {},
+        "features": [],
+        "spawners": {}
+    }"#;
+    let biome: Biome = serde_json::from_str(json).unwrap();
+    assert!(biome.has_precipitation());
+    assert!(biome.has_precipitation.is_none());
+
+    let serialized = serde_json::to_value(&biome).unwrap();
+    assert_eq!(serialized["precipitation"], "snow");
+    assert!(serialized.get("has_precipitation").is_none());
+}
@@ -146,18 +146,19 @@ pub enum DensityFunction {
 
 #[derive(Serialize, Deserialize)]
 pub struct Spline {
-    coordinate: DensityFunctionProvider,
-    points: Vec<SplinePoint>,
+    pub coordinate: DensityFunctionProvider,
+    pub points: Vec<SplinePoint>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct SplinePoint {
-    location: f64,
-    value: SplineValue,
-    derivative: f64,
+    pub location: f64,
+    pub value: SplineValue,
+    pub derivative: f64,
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(untagged)]
 pub enum SplineValue {
     Constant(f64),
     Spline(Spline),
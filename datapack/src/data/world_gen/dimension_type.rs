@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
-use crate::data::tags::TagProvider;
+use super::features::IntProvider;
+use crate::data::tags::IdsOrTag;
 
 #[derive(Serialize, Deserialize)]
 pub struct DimensionType {
@@ -10,6 +11,8 @@ pub struct DimensionType {
     pub has_skylight: bool,
     pub has_ceiling: bool,
     pub ambient_light: f32,
+    /// The time of day the dimension is fixed at, if it has no daylight cycle
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub fixed_time: Option<i32>,
     pub piglin_safe: bool,
     pub bed_works: bool,
@@ -18,9 +21,21 @@ pub struct DimensionType {
     pub logical_height: i32,
     pub min_y: i32,
     pub height: i32,
-    pub infiniburn: TagProvider,
+    /// Note: this has to be a single tag, or a single id in registry codecs sent to pre-1.18.2
+    /// clients
+    pub infiniburn: IdsOrTag,
     #[serde(default = "Default::default")]
     pub effects: DimensionEffects,
+    /// The light level monsters can spawn at, which is either a constant or a distribution
+    ///
+    /// Added in 1.19
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monster_spawn_light_level: Option<IntProvider>,
+    /// The maximum block light level monsters can spawn at
+    ///
+    /// Added in 1.19
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monster_spawn_block_light_limit: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -38,3 +53,76 @@ impl Default for DimensionEffects {
         Self::Overworld
     }
 }
+
+#[test]
+fn overworld_round_trip() {
+    use serde_json::Value;
+
+    let json = r##"{
+        "ambient_light": 0.0,
+        "bed_works": true,
+        "coordinate_scale": 1.0,
+        "effects": "minecraft:overworld",
+        "has_ceiling": false,
+        "has_raids": true,
+        "has_skylight": true,
+        "height": 384,
+        "infiniburn": "#minecraft:infiniburn_overworld",
+        "logical_height": 384,
+        "min_y": -64,
+        "monster_spawn_block_light_limit": 0,
+        "monster_spawn_light_level": {
+            "type": "minecraft:uniform",
+            "value": {
+                "max_inclusive": 7,
+                "min_inclusive": 0
+            }
+        },
+        "natural": true,
+        "piglin_safe": false,
+        "respawn_anchor_works": false,
+        "ultrawarm": false
+    }"##;
+
+    let dimension_type: DimensionType = serde_json::from_str(json).unwrap();
+    assert!(matches!(
+        dimension_type.monster_spawn_light_level,
+        Some(IntProvider::Provider(
+            super::features::IntProviderInternal::Uniform {
+                min_inclusive: 0,
+                max_inclusive: 7
+            }
+        ))
+    ));
+    assert_eq!(
+        serde_json::from_str::<Value>(&serde_json::to_string(&dimension_type).unwrap()).unwrap(),
+        serde_json::from_str::<Value>(json).unwrap()
+    );
+
+    // The nether uses a constant light level
+    let json = json
+        .replace(
+            r#"{
+            "type": "minecraft:uniform",
+            "value": {
+                "max_inclusive": 7,
+                "min_inclusive": 0
+            }
+        }"#,
+            "11",
+        )
+        .replace(
+            r#""ambient_light": 0.0,"#,
+            r#""ambient_light": 0.1, "fixed_time": 18000,"#,
+        );
+    let dimension_type: DimensionType = serde_json::from_str(&json).unwrap();
+    assert!(matches!(
+        dimension_type.monster_spawn_light_level,
+        Some(IntProvider::Constant(11))
+    ));
+    assert_eq!(dimension_type.fixed_time, Some(18000));
+    assert_eq!(
+        serde_json::from_str::<Value>(&serde_json::to_string(&dimension_type).unwrap()).unwrap(),
+        serde_json::from_str::<Value>(&json).unwrap()
+    );
+}
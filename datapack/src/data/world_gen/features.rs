@@ -684,7 +684,7 @@ pub enum CarvingStep {
     Liquid,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum SurfaceType {
     Floor,
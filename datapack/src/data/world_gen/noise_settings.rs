@@ -17,6 +17,7 @@ pub struct NoiseSettings {
     pub default_block: BlockState,
     pub default_fluid: BlockState,
     pub noise: NoiseOptions,
+    pub noise_router: NoiseRouter,
     pub surface_rule: SurfaceRule,
 }
 
@@ -194,21 +195,89 @@ pub struct BlockState {
     pub properties: HashMap<String, String>,
 }
 
+/// The density functions used to shape terrain, place aquifers and ore veins, and pick biomes
 #[derive(Serialize, Deserialize)]
 pub struct NoiseRouter {
-    barrier: DensityFunctionProvider,
-    fluid_level_floodedness: DensityFunctionProvider,
-    fluid_level_spread: DensityFunctionProvider,
-    lava: DensityFunctionProvider,
-    temperature: DensityFunctionProvider,
-    vegetation: DensityFunctionProvider,
-    continents: DensityFunctionProvider,
-    erosion: DensityFunctionProvider,
-    depth: DensityFunctionProvider,
-    ridges: DensityFunctionProvider,
-    initial_density_without_jaggedness: DensityFunctionProvider,
-    final_density: DensityFunctionProvider,
-    vein_toggle: DensityFunctionProvider,
-    vein_ridged: DensityFunctionProvider,
-    vein_gap: DensityFunctionProvider,
+    pub barrier: DensityFunctionProvider,
+    pub fluid_level_floodedness: DensityFunctionProvider,
+    pub fluid_level_spread: DensityFunctionProvider,
+    pub lava: DensityFunctionProvider,
+    pub temperature: DensityFunctionProvider,
+    pub vegetation: DensityFunctionProvider,
+    pub continents: DensityFunctionProvider,
+    pub erosion: DensityFunctionProvider,
+    pub depth: DensityFunctionProvider,
+    pub ridges: DensityFunctionProvider,
+    pub initial_density_without_jaggedness: DensityFunctionProvider,
+    pub final_density: DensityFunctionProvider,
+    pub vein_toggle: DensityFunctionProvider,
+    pub vein_ridged: DensityFunctionProvider,
+    pub vein_gap: DensityFunctionProvider,
+}
+
+#[test]
+fn overworld_noise_router_de_test() {
+    use crate::data::density_function::DensityFunction;
+
+    let json = include_str!("../../../../assets/overworld_noise_router.json");
+    let router: NoiseRouter = serde_json::from_str(json).unwrap();
+
+    fn inline(provider: &DensityFunctionProvider) -> Option<&DensityFunction> {
+        match provider {
+            DensityFunctionProvider::Inline(function) => Some(function),
+            _ => None,
+        }
+    }
+    let is_noise = |provider: &DensityFunctionProvider, name: &str| {
+        matches!(
+            inline(provider),
+            Some(DensityFunction::Noise { noise, .. }) if noise == &UnlocalizedName::minecraft(name)
+        )
+    };
+    let is_reference = |provider: &DensityFunctionProvider, name: &str| {
+        matches!(
+            provider,
+            DensityFunctionProvider::Reference(reference)
+                if reference == &UnlocalizedName::minecraft(name)
+        )
+    };
+
+    assert!(is_noise(&router.barrier, "aquifer_barrier"));
+    assert!(is_noise(
+        &router.fluid_level_floodedness,
+        "aquifer_fluid_level_floodedness"
+    ));
+    assert!(is_noise(
+        &router.fluid_level_spread,
+        "aquifer_fluid_level_spread"
+    ));
+    assert!(is_noise(&router.lava, "aquifer_lava"));
+    for climate in [&router.temperature, &router.vegetation] {
+        assert!(matches!(
+            inline(climate),
+            Some(DensityFunction::ShiftedNoise { .. })
+        ));
+    }
+    assert!(is_reference(&router.continents, "overworld/continents"));
+    assert!(is_reference(&router.erosion, "overworld/erosion"));
+    assert!(is_reference(&router.depth, "overworld/depth"));
+    assert!(is_reference(&router.ridges, "overworld/ridges"));
+    assert!(matches!(
+        inline(&router.initial_density_without_jaggedness),
+        Some(DensityFunction::Slide { .. })
+    ));
+    assert!(matches!(
+        inline(&router.final_density),
+        Some(DensityFunction::Min { argument2, .. })
+            if is_reference(argument2, "overworld/caves/noodle")
+    ));
+    assert!(matches!(
+        inline(&router.vein_toggle),
+        Some(DensityFunction::Interpolated { .. })
+    ));
+    assert!(matches!(
+        inline(&router.vein_ridged),
+        Some(DensityFunction::Add { .. })
+    ));
+    assert!(is_noise(&router.vein_gap, "ore_gap"));
 }
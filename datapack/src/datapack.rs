@@ -1,37 +1,42 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet, HashMap},
     error::Error,
     fmt::Display,
-    fs::{DirEntry, File, OpenOptions},
-    io::Read,
+    fs::File,
+    io::{Read, Seek, Write},
     path::Path,
 };
 
-use quartz_nbt::io::{Flavor, NbtIoError};
+use qdat::UnlocalizedName;
+use quartz_chat::Component;
+use quartz_nbt::io::Flavor;
 use serde::{Deserialize, Serialize};
 
-use crate::data::{
-    advancement::Advancement,
-    biome::Biome,
-    carvers::Carver,
-    density_function::DensityFunctionProvider,
-    dimension::Dimension,
-    dimension_type::DimensionType,
-    features::{Feature, PlacedFeature},
-    functions::{read_function, write_function, Function},
-    item_modifiers::ItemModifier,
-    jigsaw_pool::JigsawPool,
-    loot_tables::LootTable,
-    noise::Noise,
-    noise_settings::NoiseSettings,
-    predicate::Predicate,
-    processors::ProcessorList,
-    recipe::VanillaRecipeType,
-    structure::Structure,
-    structure_features::StructureFeatures,
-    structure_set::StructureSet,
-    surface_builders::SurfaceBuilder,
-    tags::Tag,
+use crate::{
+    data::{
+        advancement::Advancement,
+        biome::Biome,
+        carvers::Carver,
+        density_function::DensityFunctionProvider,
+        dimension::Dimension,
+        dimension_type::DimensionType,
+        features::{Feature, PlacedFeature},
+        functions::{read_function, write_function, Function},
+        item_modifiers::ItemModifier,
+        jigsaw_pool::JigsawPool,
+        loot_tables::LootTable,
+        noise::Noise,
+        noise_settings::NoiseSettings,
+        predicate::Predicate,
+        processors::ProcessorList,
+        recipe::VanillaRecipeType,
+        structure::Structure,
+        structure_features::StructureFeatures,
+        structure_set::StructureSet,
+        surface_builders::SurfaceBuilder,
+        tags::Tag,
+    },
+    zip::{read_zip, ZipWriter},
 };
 
 /// Gets the datapack version for the minecraft version
@@ -44,7 +49,7 @@ use crate::data::{
 ///
 /// For snapshot versions use the version it is a snapshot for<br>
 /// Ex: for 21w03a you would use 1.17.0
-pub const fn datapack_version(major: u8, minor: u8, patch: u8) -> u8 {
+pub const fn datapack_version(major: u8, minor: u8, patch: u8) -> i32 {
     match (major, minor, patch) {
         // Any version before 1.13
         (0, ..) | (1, 0 ..= 12, _) => 0,
@@ -55,7 +60,15 @@ pub const fn datapack_version(major: u8, minor: u8, patch: u8) -> u8 {
         (1, 17, _) => 7,
         (1, 18, 2) => 9,
         (1, 18, _) => 8,
-        (1, 19, _) => 10,
+        (1, 19, 0 ..= 3) => 10,
+        (1, 19, 4) => 12,
+        (1, 20, 0 | 1) => 15,
+        (1, 20, 2) => 18,
+        (1, 20, 3 | 4) => 26,
+        (1, 20, 5 | 6) => 41,
+        (1, 21, 0 | 1) => 48,
+        (1, 21, 2 | 3) => 57,
+        (1, 21, 4) => 61,
         // Future versions we don't support
         _ => 1,
     }
@@ -63,12 +76,12 @@ pub const fn datapack_version(major: u8, minor: u8, patch: u8) -> u8 {
 /// The datapack version we currently support
 ///
 /// This takes into account experimental features and so will be the strictest possible filter for supported versions
-pub const SUPPORTED_VERSION: u8 = 9;
+pub const SUPPORTED_VERSION: i32 = 9;
 /// The lowest datapack version we support if the pack is not using experimental features
 ///
 /// The currently the only experimental features are the worldgen features
 // TODO: actually check that this is accurate 1.16 feels right because that was before the worldgen stuff was added iirc
-pub const LOWEST_SUPPORTED_STABLE_VERSION: u8 = 6;
+pub const LOWEST_SUPPORTED_STABLE_VERSION: i32 = 6;
 
 #[derive(Clone, Copy)]
 pub enum VersionFilter {
@@ -82,72 +95,256 @@ pub enum VersionFilter {
     None,
 }
 
-fn recursive_read(path: &Path, prefix: String) -> Result<Vec<(String, DirEntry)>> {
-    let mut entries = Vec::new();
-
-    for entry in path.read_dir()? {
-        let entry = entry?;
-
-        if entry.metadata()?.is_dir() {
-            entries.extend(recursive_read(
-                &entry.path(),
-                format!("{}{}/", prefix, entry.file_name().to_string_lossy()),
-            )?);
-        } else {
-            entries.push((
-                format!(
-                    "{}{}",
-                    prefix,
-                    entry
-                        .file_name()
-                        .to_string_lossy()
-                        .to_string()
-                        .split('.')
-                        .next()
-                        .unwrap()
-                ),
-                entry,
-            ));
+/// A source of the files in a datapack, such as a directory or a zip archive
+///
+/// All paths are relative to the root of the pack and use `/` as their separator
+trait PackSource {
+    /// Reads the contents of the file at the given path
+    fn read_file(&self, path: &str) -> Result<Vec<u8>>;
+
+    /// Returns the paths of every file below the given directory, relative to that directory
+    ///
+    /// Returns an empty list if the directory does not exist
+    fn list_files(&self, dir: &str) -> Result<Vec<String>>;
+
+    /// Returns the names of the directories directly inside the given directory
+    fn list_dirs(&self, dir: &str) -> Result<Vec<String>>;
+
+    /// Returns whether a file or directory exists at the given path
+    fn exists(&self, path: &str) -> bool;
+}
+
+/// A datapack stored as a directory
+struct DirectorySource<'a>(&'a Path);
+
+impl DirectorySource<'_> {
+    fn list_files_recursive(path: &Path, prefix: String, files: &mut Vec<String>) -> Result<()> {
+        for entry in path.read_dir()? {
+            let entry = entry?;
+            let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+
+            if entry.metadata()?.is_dir() {
+                Self::list_files_recursive(&entry.path(), format!("{name}/"), files)?;
+            } else {
+                files.push(name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl PackSource for DirectorySource<'_> {
+    fn read_file(&self, path: &str) -> Result<Vec<u8>> {
+        std::fs::read(self.0.join(path)).map_err(Into::into)
+    }
+
+    fn list_files(&self, dir: &str) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        let path = self.0.join(dir);
+        if path.is_dir() {
+            Self::list_files_recursive(&path, String::new(), &mut files)?;
+        }
+        Ok(files)
+    }
+
+    fn list_dirs(&self, dir: &str) -> Result<Vec<String>> {
+        let mut dirs = Vec::new();
+        for entry in self.0.join(dir).read_dir()? {
+            let entry = entry?;
+            if entry.metadata()?.is_dir() {
+                dirs.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        Ok(dirs)
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.0.join(path).exists()
+    }
+}
+
+/// A datapack stored as a zip archive, which has been read into memory
+struct ZipSource(BTreeMap<String, Vec<u8>>);
+
+impl ZipSource {
+    /// Returns the paths of every file below the given directory, relative to that directory
+    fn files_below<'a>(&'a self, dir: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.0
+            .keys()
+            .filter_map(move |path| path.strip_prefix(dir)?.strip_prefix('/'))
+    }
+}
+
+impl PackSource for ZipSource {
+    fn read_file(&self, path: &str) -> Result<Vec<u8>> {
+        self.0.get(path).cloned().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{path} is missing from the archive"),
+            )
+            .into()
+        })
+    }
+
+    fn list_files(&self, dir: &str) -> Result<Vec<String>> {
+        Ok(self.files_below(dir).map(str::to_owned).collect())
+    }
+
+    fn list_dirs(&self, dir: &str) -> Result<Vec<String>> {
+        let dirs = self
+            .files_below(dir)
+            .filter_map(|path| Some(path.split_once('/')?.0.to_owned()))
+            .collect::<BTreeSet<_>>();
+        Ok(dirs.into_iter().collect())
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.0.contains_key(path) || self.files_below(path).next().is_some()
+    }
+}
+
+/// A destination for the files of a datapack, such as a directory or a zip archive
+trait PackSink {
+    /// Writes a file at the given path relative to the root of the pack, creating any parent
+    /// directories and replacing an existing file
+    fn write_file(&mut self, path: &str, data: &[u8]) -> Result<()>;
+}
+
+impl PackSink for &Path {
+    fn write_file(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        let path = self.join(path);
+        // Every path written has at least the namespace directory as a parent
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
         }
+        std::fs::write(path, data).map_err(Into::into)
     }
+}
 
-    Ok(entries)
+impl<W: Write + Seek> PackSink for ZipWriter<W> {
+    fn write_file(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        self.add_file(path, data).map_err(Into::into)
+    }
 }
 
-fn write_file_recursive<P: AsRef<Path>>(path: P) -> Result<File> {
-    // TODO: This unwrap technically could fail if someone tried to write a datapack to the rootdir I think
-    std::fs::create_dir_all(path.as_ref().parent().unwrap())?;
-    OpenOptions::new()
-        .read(false)
-        .write(true)
-        .create(true)
-        .open(path)
-        .map_err(Into::into)
+/// Removes the extension from a path relative to a datapack directory to get the name of the
+/// resource it contains
+fn resource_name(path: &str) -> String {
+    let (dir, file_name) = match path.rsplit_once('/') {
+        Some((dir, file_name)) => (Some(dir), file_name),
+        None => (None, path),
+    };
+    let file_name = file_name.split('.').next().unwrap_or_default();
+
+    match dir {
+        Some(dir) => format!("{dir}/{file_name}"),
+        None => file_name.to_owned(),
+    }
 }
 
 /// Holds all the info about the datapack
 pub struct DataPack {
-    pub meta: McMeta,
+    /// The name of the pack, which is the name of its folder or zip archive
+    pub name: String,
+    pub meta: PackMeta,
     pub namespaces: Vec<Namespace>,
 }
 
 impl DataPack {
+    /// Gets the metadata read from the `pack.mcmeta` file
+    pub fn meta(&self) -> &PackMeta {
+        &self.meta
+    }
+
     /// Gets the version of the datapack format we are using
-    pub fn version(&self) -> u8 {
+    pub fn version(&self) -> i32 {
         self.meta.pack_format
     }
 
     /// Gets the description of the datapack
-    pub fn description(&self) -> &str {
+    pub fn description(&self) -> &Component {
         &self.meta.description
     }
 
     /// Gets the name of the datapack
     pub fn name(&self) -> &str {
-        &self.meta.name
+        &self.name
+    }
+
+    /// Gets the names of the namespaces in the datapack
+    pub fn namespaces(&self) -> impl Iterator<Item = &str> {
+        self.namespaces
+            .iter()
+            .map(|namespace| namespace.name.as_str())
+    }
+
+    /// Iterates over every advancement in the datapack along with its name
+    pub fn advancements(&self) -> impl Iterator<Item = (UnlocalizedName, &Advancement)> {
+        self.resources(|namespace| &namespace.advancements)
+    }
+
+    /// Iterates over every recipe in the datapack along with its name
+    pub fn recipes(&self) -> impl Iterator<Item = (UnlocalizedName, &VanillaRecipeType)> {
+        self.resources(|namespace| &namespace.recipes)
+    }
+
+    /// Iterates over every loot table in the datapack along with its name
+    pub fn loot_tables(&self) -> impl Iterator<Item = (UnlocalizedName, &LootTable)> {
+        self.resources(|namespace| &namespace.loot_tables)
+    }
+
+    /// Iterates over every predicate in the datapack along with its name
+    pub fn predicates(&self) -> impl Iterator<Item = (UnlocalizedName, &Predicate)> {
+        self.resources(|namespace| &namespace.predicates)
+    }
+
+    /// Iterates over every function in the datapack along with its name
+    pub fn functions(&self) -> impl Iterator<Item = (UnlocalizedName, &Function)> {
+        self.resources(|namespace| &namespace.functions)
+    }
+
+    /// Iterates over every tag of the given type, such as `items`, in the datapack along with its
+    /// name
+    ///
+    /// The name of the tag does not include its type, so `data/quartz/tags/items/logs.json` is
+    /// named `quartz:logs`
+    pub fn tags<'a>(
+        &'a self,
+        tag_type: &'a str,
+    ) -> impl Iterator<Item = (UnlocalizedName, &'a Tag)> + 'a {
+        self.namespaces.iter().flat_map(move |namespace| {
+            namespace.tags.iter().filter_map(move |tag| {
+                let path = tag.name().strip_prefix(tag_type)?.strip_prefix('/')?;
+                Some((
+                    UnlocalizedName::from_parts(&namespace.name, path).ok()?,
+                    tag,
+                ))
+            })
+        })
+    }
+
+    /// Iterates over the resources of one type in every namespace, naming each resource after its
+    /// namespace and path
+    ///
+    /// Resources whose path isn't a valid identifier are skipped
+    fn resources<'a, T: 'a>(
+        &'a self,
+        resources: fn(&Namespace) -> &HashMap<String, T>,
+    ) -> impl Iterator<Item = (UnlocalizedName, &'a T)> + 'a {
+        self.namespaces.iter().flat_map(move |namespace| {
+            resources(namespace)
+                .iter()
+                .filter_map(move |(path, resource)| {
+                    Some((
+                        UnlocalizedName::from_parts(&namespace.name, path).ok()?,
+                        resource,
+                    ))
+                })
+        })
     }
 
-    /// Reads in all the datapacks in a directory
+    /// Reads in all the datapacks in a directory, which can either be folders or zip archives
     ///
     /// `version_filter` allows you to provide a filter for which pack formats will be attempted to be loaded
     pub fn read_datapacks<P: AsRef<Path>>(
@@ -159,13 +356,16 @@ impl DataPack {
 
         for entry in files {
             let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
 
             if entry.metadata()?.is_dir() {
-                packs.push(Self::read(
-                    &entry.path(),
-                    entry.file_name().to_str().unwrap(),
-                    version_filter,
-                ))
+                packs.push(Self::read(&entry.path(), &file_name, version_filter))
+            } else if let Some(pack_name) = file_name.strip_suffix(".zip") {
+                packs.push(
+                    File::open(entry.path())
+                        .map_err(Into::into)
+                        .and_then(|file| Self::from_zip(file, pack_name, version_filter)),
+                )
             }
         }
 
@@ -176,80 +376,108 @@ impl DataPack {
     ///
     /// `version_filter` allows you to provide a filter for which pack formats will be attempted to be loaded
     pub fn read(path: &Path, pack_name: &str, version_filter: VersionFilter) -> Result<DataPack> {
-        let mut file = OpenOptions::new()
-            .read(true)
-            .write(false)
-            .open(path.join("pack.mcmeta"))?;
+        Self::read_from(&DirectorySource(path), pack_name, version_filter)
+    }
 
-        let mut json = String::new();
-        file.read_to_string(&mut json)?;
+    /// Reads in a datapack from a zip archive, whose root contains the `pack.mcmeta` file
+    ///
+    /// `version_filter` allows you to provide a filter for which pack formats will be attempted to be loaded
+    pub fn from_zip<R: Read + Seek>(
+        reader: R,
+        pack_name: &str,
+        version_filter: VersionFilter,
+    ) -> Result<DataPack> {
+        Self::read_from(&ZipSource(read_zip(reader)?), pack_name, version_filter)
+    }
 
-        let mut meta: RawMcMeta = serde_json::from_str(&json)?;
+    fn read_from(
+        source: &impl PackSource,
+        pack_name: &str,
+        version_filter: VersionFilter,
+    ) -> Result<DataPack> {
+        let meta: RawMcMeta = serde_json::from_slice(&source.read_file("pack.mcmeta")?)?;
+        let meta = meta.pack;
+        let pack_name = pack_name.to_owned();
 
         match version_filter {
             VersionFilter::Latest =>
-                if meta.pack.pack_format != SUPPORTED_VERSION {
+                if meta.pack_format != SUPPORTED_VERSION {
                     return Err(DatapackIoError::VersionError {
-                        pack_name: meta.pack.name,
+                        pack_name,
                         checked_version: SUPPORTED_VERSION,
-                        found_version: meta.pack.pack_format,
+                        found_version: meta.pack_format,
                     });
                 },
             VersionFilter::Stable =>
-                if meta.pack.pack_format < LOWEST_SUPPORTED_STABLE_VERSION {
+                if meta.pack_format < LOWEST_SUPPORTED_STABLE_VERSION {
                     return Err(DatapackIoError::VersionError {
-                        pack_name: meta.pack.name,
+                        pack_name,
                         checked_version: LOWEST_SUPPORTED_STABLE_VERSION,
-                        found_version: meta.pack.pack_format,
+                        found_version: meta.pack_format,
                     });
                 },
             VersionFilter::LatestOrStable =>
-                if meta.pack.pack_format != SUPPORTED_VERSION {
-                    if meta.pack.pack_format < LOWEST_SUPPORTED_STABLE_VERSION {
+                if meta.pack_format != SUPPORTED_VERSION {
+                    if meta.pack_format < LOWEST_SUPPORTED_STABLE_VERSION {
                         return Err(DatapackIoError::VersionError {
-                            pack_name: meta.pack.name,
+                            pack_name,
                             checked_version: LOWEST_SUPPORTED_STABLE_VERSION,
-                            found_version: meta.pack.pack_format,
+                            found_version: meta.pack_format,
                         });
-                    } else if path.join("data/worldgen").exists() {
+                    } else if source.exists("data/worldgen") {
                         return Err(DatapackIoError::VersionError {
-                            pack_name: meta.pack.name,
+                            pack_name,
                             checked_version: SUPPORTED_VERSION,
-                            found_version: meta.pack.pack_format,
+                            found_version: meta.pack_format,
                         });
                     }
                 },
             VersionFilter::None => {}
         }
 
-
-        meta.pack.name = pack_name.to_owned();
+        let mut namespaces = Vec::new();
+        for name in source.list_dirs("data")? {
+            namespaces.push(Namespace::read(source, name)?);
+        }
 
         Ok(DataPack {
-            meta: meta.pack,
-            namespaces: Self::read_namespaces(&path.join("data"))?,
+            name: pack_name,
+            meta,
+            namespaces,
         })
     }
 
-    fn read_namespaces(data_path: &Path) -> Result<Vec<Namespace>> {
-        let mut namespaces = Vec::new();
-
-        for entry in (data_path.read_dir()?).flatten() {
-            if entry.metadata()?.is_dir() {
-                namespaces.push(Namespace::read(&entry.path())?)
-            }
-        }
+    pub fn write_datapack<P: AsRef<Path>>(&self, path: &P) -> Result<()> {
+        self.write_to(&mut path.as_ref())
+    }
 
-        Ok(namespaces)
+    /// Writes this datapack to the directory at `root`, creating `pack.mcmeta` and a folder in
+    /// `data` for each namespace
+    ///
+    /// Resources are written as pretty-printed json, replacing any existing file at the same path.
+    /// Other files in the directory are left untouched
+    pub fn write_to_dir(&self, root: &Path) -> std::io::Result<()> {
+        let mut sink = root;
+        self.write_to(&mut sink).map_err(|e| match e {
+            DatapackIoError::IOError(e) => e,
+            e => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        })
     }
 
-    pub fn write_datapack<P: AsRef<Path>>(&self, path: &P) -> Result<()> {
-        let mcmeta_file = write_file_recursive(path.as_ref().join("pack.mcmeta"))?;
-        serde_json::to_writer(mcmeta_file, &self.meta).map_err(DatapackIoError::SerdeError)?;
+    /// Writes this datapack to a zip archive with the `pack.mcmeta` file at its root
+    pub fn to_zip<W: Write + Seek>(&self, writer: W) -> Result<()> {
+        let mut zip = ZipWriter::new(writer);
+        self.write_to(&mut zip)?;
+        zip.finish()?;
+        Ok(())
+    }
 
+    fn write_to(&self, sink: &mut impl PackSink) -> Result<()> {
+        let meta = RawMcMeta { pack: &self.meta };
+        sink.write_file("pack.mcmeta", &serde_json::to_vec_pretty(&meta)?)?;
 
         for namespace in &self.namespaces {
-            namespace.write(&path.as_ref().join(&namespace.name).join("data"))?;
+            namespace.write(sink)?;
         }
         Ok(())
     }
@@ -282,39 +510,33 @@ pub struct Namespace {
 }
 
 impl Namespace {
-    fn read(namespace_path: &Path) -> Result<Namespace> {
-        // will only fail if the datapack has a file / folder whose name ends with '..' which, to my knowledge is not possible
-        let name = namespace_path
-            .file_name()
-            .unwrap()
-            .to_string_lossy()
-            .to_string();
-
-        let tags = Self::read_tags(&namespace_path.join("tags"))?;
-        let recipes = Self::read_datatype(&namespace_path.join("recipes"))?;
-        let advancements = Self::read_datatype(&namespace_path.join("advancements"))?;
-        let functions = Self::read_functions(&namespace_path.join("functions"))?;
-        let loot_tables = Self::read_datatype(&namespace_path.join("loot_tables"))?;
-        let predicates = Self::read_datatype(&namespace_path.join("predicates"))?;
-        let item_modifiers = Self::read_datatype(&namespace_path.join("item_modifiers"))?;
-        let dimensions = Self::read_datatype(&namespace_path.join("dimension"))?;
-        let dimension_types = Self::read_datatype(&namespace_path.join("dimension_type"))?;
-        let biomes = Self::read_datatype(&namespace_path.join("worldgen/biome"))?;
-        let carvers = Self::read_datatype(&namespace_path.join("worldgen/configured_carver"))?;
-        let features = Self::read_datatype(&namespace_path.join("worldgen/configured_feature"))?;
+    fn read(source: &impl PackSource, name: String) -> Result<Namespace> {
+        let path = |dir: &str| format!("data/{name}/{dir}");
+
+        let tags = Self::read_tags(source, &path("tags"))?;
+        let recipes = Self::read_datatype(source, &path("recipes"))?;
+        let advancements = Self::read_datatype(source, &path("advancements"))?;
+        let functions = Self::read_functions(source, &path("functions"))?;
+        let loot_tables = Self::read_datatype(source, &path("loot_tables"))?;
+        let predicates = Self::read_datatype(source, &path("predicates"))?;
+        let item_modifiers = Self::read_datatype(source, &path("item_modifiers"))?;
+        let dimensions = Self::read_datatype(source, &path("dimension"))?;
+        let dimension_types = Self::read_datatype(source, &path("dimension_type"))?;
+        let biomes = Self::read_datatype(source, &path("worldgen/biome"))?;
+        let carvers = Self::read_datatype(source, &path("worldgen/configured_carver"))?;
+        let features = Self::read_datatype(source, &path("worldgen/configured_feature"))?;
         let structure_features =
-            Self::read_datatype(&namespace_path.join("worldgen/configured_structure_feature"))?;
+            Self::read_datatype(source, &path("worldgen/configured_structure_feature"))?;
         let surface_builders =
-            Self::read_datatype(&namespace_path.join("worldgen/configured_surface_builder"))?;
-        let noise = Self::read_datatype(&namespace_path.join("worldgen/noise"))?;
-        let noise_settings = Self::read_datatype(&namespace_path.join("worldgen/noise_settings"))?;
-        let processors = Self::read_datatype(&namespace_path.join("worldgen/processor_list"))?;
-        let jigsaw_pools = Self::read_datatype(&namespace_path.join("worldgen/template_pool"))?;
-        let density_functions =
-            Self::read_datatype(&namespace_path.join("worldgen/density_function"))?;
-        let placed_features = Self::read_datatype(&namespace_path.join("worldgen/placed_feature"))?;
-        let structure_sets = Self::read_datatype(&namespace_path.join("worldgen/structure_set"))?;
-        let structures = Self::read_structures(&namespace_path.join("structures"))?;
+            Self::read_datatype(source, &path("worldgen/configured_surface_builder"))?;
+        let noise = Self::read_datatype(source, &path("worldgen/noise"))?;
+        let noise_settings = Self::read_datatype(source, &path("worldgen/noise_settings"))?;
+        let processors = Self::read_datatype(source, &path("worldgen/processor_list"))?;
+        let jigsaw_pools = Self::read_datatype(source, &path("worldgen/template_pool"))?;
+        let density_functions = Self::read_datatype(source, &path("worldgen/density_function"))?;
+        let placed_features = Self::read_datatype(source, &path("worldgen/placed_feature"))?;
+        let structure_sets = Self::read_datatype(source, &path("worldgen/structure_set"))?;
+        let structures = Self::read_structures(source, &path("structures"))?;
 
         Ok(Namespace {
             name,
@@ -343,235 +565,264 @@ impl Namespace {
         })
     }
 
-    fn read_tags(tags_path: &Path) -> Result<Vec<Tag>> {
+    fn read_tags(source: &impl PackSource, tags_path: &str) -> Result<Vec<Tag>> {
         let mut tags = Vec::new();
 
-        let tag_files = match recursive_read(tags_path, String::new()) {
-            Ok(e) => e,
-            Err(_) => return Ok(tags),
-        };
-
-        for (name, entry) in tag_files {
-            println!("{name} {entry:?}");
-            let mut file = OpenOptions::new()
-                .read(true)
-                .write(false)
-                .open(entry.path())?;
-
-            let mut string = String::new();
-            file.read_to_string(&mut string)?;
-
-            let tag = match serde_json::from_str(&string) {
-                Ok(v) => v,
-                Err(e) => {
-                    return Err(e.into());
-                }
-            };
-
-            tags.push(Tag { name, def: tag })
+        for file in source.list_files(tags_path)? {
+            let def = serde_json::from_slice(&source.read_file(&format!("{tags_path}/{file}"))?)?;
+            tags.push(Tag {
+                name: resource_name(&file),
+                def,
+            })
         }
 
         Ok(tags)
     }
 
-    fn read_functions(functions_path: &Path) -> Result<HashMap<String, Function>> {
+    fn read_functions(
+        source: &impl PackSource,
+        functions_path: &str,
+    ) -> Result<HashMap<String, Function>> {
         let mut functions = HashMap::new();
 
-        let files = match recursive_read(functions_path, String::new()) {
-            Ok(e) => e,
-            Err(_) => return Ok(functions),
-        };
-
-        for (name, entry) in files {
-            println!("{name} {entry:?}");
-            let file = OpenOptions::new()
-                .read(true)
-                .write(false)
-                .open(entry.path())?;
-
-            functions.insert(name, read_function(file)?);
+        for file in source.list_files(functions_path)? {
+            let data = source.read_file(&format!("{functions_path}/{file}"))?;
+            functions.insert(resource_name(&file), read_function(&data[..])?);
         }
 
         Ok(functions)
     }
 
-    fn read_structures(functions_path: &Path) -> Result<HashMap<String, Structure>> {
+    fn read_structures(
+        source: &impl PackSource,
+        structures_path: &str,
+    ) -> Result<HashMap<String, Structure>> {
         let mut structures = HashMap::new();
 
-        let files = match recursive_read(functions_path, String::new()) {
-            Ok(e) => e,
-            Err(_) => return Ok(structures),
-        };
-
-        for (name, entry) in files {
-            println!("{name} {entry:?}");
-            let mut file = OpenOptions::new()
-                .read(true)
-                .write(false)
-                .open(entry.path())
-                .map_err(NbtIoError::StdIo)?;
+        for file in source.list_files(structures_path)? {
+            let data = source.read_file(&format!("{structures_path}/{file}"))?;
 
             // I'm pretty sure this is umcompressed
             structures.insert(
-                name,
-                quartz_nbt::serde::deserialize_from(&mut file, Flavor::GzCompressed)?.0,
+                resource_name(&file),
+                quartz_nbt::serde::deserialize_from(&mut &data[..], Flavor::GzCompressed)?.0,
             );
         }
 
         Ok(structures)
     }
 
-    fn read_datatype<T: for<'de> Deserialize<'de>>(data_path: &Path) -> Result<HashMap<String, T>> {
+    fn read_datatype<T: for<'de> Deserialize<'de>>(
+        source: &impl PackSource,
+        data_path: &str,
+    ) -> Result<HashMap<String, T>> {
         let mut output = HashMap::new();
-        let files = match recursive_read(data_path, String::new()) {
-            Ok(e) => e,
-            Err(_) => return Ok(output),
-        };
 
-        for (name, entry) in files {
-            println!("{name} {entry:?}");
-            let mut file = OpenOptions::new()
-                .read(true)
-                .write(false)
-                .open(entry.path())?;
-
-            let mut str = String::new();
-            file.read_to_string(&mut str)?;
-
-            let data = match serde_json::from_str(&str) {
-                Ok(v) => v,
-                Err(e) => {
-                    return Err(e.into());
-                }
-            };
-            output.insert(name, data);
+        for file in source.list_files(data_path)? {
+            let data = serde_json::from_slice(&source.read_file(&format!("{data_path}/{file}"))?)?;
+            output.insert(resource_name(&file), data);
         }
 
         Ok(output)
     }
 
-    fn write<P: AsRef<Path>>(&self, namespace_path: &P) -> Result<()> {
-        let namespace_path = namespace_path.as_ref();
-
-        Self::write_tags(&self.tags, &namespace_path.join("tags"))?;
-        Self::write_datatype(&self.recipes, &namespace_path.join("recipes"))?;
-        Self::write_datatype(&self.advancements, &namespace_path.join("advancements"))?;
-        Self::write_functions(&self.functions, &namespace_path.join("functions"))?;
-        Self::write_datatype(&self.loot_tables, &namespace_path.join("loot_tables"))?;
-        Self::write_datatype(&self.predicates, &namespace_path.join("predicates"))?;
-        Self::write_datatype(&self.item_modifiers, &namespace_path.join("item_modifiers"))?;
-        Self::write_datatype(&self.dimensions, &namespace_path.join("dimension"))?;
-        Self::write_datatype(
-            &self.dimension_types,
-            &namespace_path.join("dimension_type"),
-        )?;
-        Self::write_datatype(&self.biomes, &namespace_path.join("worldgen/biome"))?;
-        Self::write_datatype(
-            &self.carvers,
-            &namespace_path.join("worldgen/configured_carver"),
-        )?;
-        Self::write_datatype(
-            &self.features,
-            &namespace_path.join("worldgen/configured_feature"),
-        )?;
-
+    fn write(&self, sink: &mut impl PackSink) -> Result<()> {
+        let path = |dir: &str| format!("data/{}/{dir}", self.name);
+
+        Self::write_tags(sink, &self.tags, &path("tags"))?;
+        Self::write_datatype(sink, &self.recipes, &path("recipes"))?;
+        Self::write_datatype(sink, &self.advancements, &path("advancements"))?;
+        Self::write_functions(sink, &self.functions, &path("functions"))?;
+        Self::write_datatype(sink, &self.loot_tables, &path("loot_tables"))?;
+        Self::write_datatype(sink, &self.predicates, &path("predicates"))?;
+        Self::write_datatype(sink, &self.item_modifiers, &path("item_modifiers"))?;
+        Self::write_datatype(sink, &self.dimensions, &path("dimension"))?;
+        Self::write_datatype(sink, &self.dimension_types, &path("dimension_type"))?;
+        Self::write_datatype(sink, &self.biomes, &path("worldgen/biome"))?;
+        Self::write_datatype(sink, &self.carvers, &path("worldgen/configured_carver"))?;
+        Self::write_datatype(sink, &self.features, &path("worldgen/configured_feature"))?;
         Self::write_datatype(
+            sink,
             &self.structure_features,
-            &namespace_path.join("worldgen/configured_structure_feature"),
+            &path("worldgen/configured_structure_feature"),
         )?;
-
         Self::write_datatype(
+            sink,
             &self.surface_builders,
-            &namespace_path.join("worldgen/configured_surface_builder"),
-        )?;
-        Self::write_datatype(&self.noise, &namespace_path.join("worldgen/noise"))?;
-        Self::write_datatype(
-            &self.noise_settings,
-            &namespace_path.join("worldgen/noise_settings"),
-        )?;
-        Self::write_datatype(
-            &self.processors,
-            &namespace_path.join("worldgen/processor_list"),
-        )?;
-        Self::write_datatype(
-            &self.jigsaw_pools,
-            &namespace_path.join("worldgen/template_pool"),
+            &path("worldgen/configured_surface_builder"),
         )?;
+        Self::write_datatype(sink, &self.noise, &path("worldgen/noise"))?;
+        Self::write_datatype(sink, &self.noise_settings, &path("worldgen/noise_settings"))?;
+        Self::write_datatype(sink, &self.processors, &path("worldgen/processor_list"))?;
+        Self::write_datatype(sink, &self.jigsaw_pools, &path("worldgen/template_pool"))?;
         Self::write_datatype(
+            sink,
             &self.density_functions,
-            &namespace_path.join("worldgen/density_function"),
+            &path("worldgen/density_function"),
         )?;
         Self::write_datatype(
+            sink,
             &self.placed_features,
-            &namespace_path.join("worldgen/placed_feature"),
-        )?;
-        Self::write_datatype(
-            &self.structure_sets,
-            &namespace_path.join("worldgen/structure_set"),
+            &path("worldgen/placed_feature"),
         )?;
-        Self::write_structures(&self.structures, &namespace_path.join("structures"))?;
+        Self::write_datatype(sink, &self.structure_sets, &path("worldgen/structure_set"))?;
+        Self::write_structures(sink, &self.structures, &path("structures"))?;
 
         Ok(())
     }
 
-    fn write_tags<P: AsRef<Path>>(tags: &Vec<Tag>, path: &P) -> Result<()> {
+    fn write_tags(sink: &mut impl PackSink, tags: &[Tag], path: &str) -> Result<()> {
         for tag in tags {
-            let file = write_file_recursive(path.as_ref().join(format!("{}.json", tag.name())))?;
-
-            serde_json::to_writer(file, &tag.def)?;
+            sink.write_file(
+                &format!("{path}/{}.json", tag.name()),
+                &serde_json::to_vec_pretty(&tag.def)?,
+            )?;
         }
         Ok(())
     }
 
-    fn write_datatype<T: Serialize, P: AsRef<Path>>(
+    fn write_datatype<T: Serialize>(
+        sink: &mut impl PackSink,
         value: &HashMap<String, T>,
-        path: &P,
+        path: &str,
     ) -> Result<()> {
         for (name, val) in value {
-            let file = write_file_recursive(path.as_ref().join(format!("{name}.json")))?;
-
-            serde_json::to_writer(file, val)?;
+            sink.write_file(
+                &format!("{path}/{name}.json"),
+                &serde_json::to_vec_pretty(val)?,
+            )?;
         }
         Ok(())
     }
 
-    fn write_structures<P: AsRef<Path>>(
+    fn write_structures(
+        sink: &mut impl PackSink,
         structures: &HashMap<String, Structure>,
-        path: &P,
+        path: &str,
     ) -> Result<()> {
         for (name, val) in structures {
-            let mut file = write_file_recursive(path.as_ref().join(format!("{name}.nbt")))?;
-
-            quartz_nbt::serde::serialize_into(&mut file, val, None, Flavor::GzCompressed)?;
+            let mut data = Vec::new();
+            quartz_nbt::serde::serialize_into(&mut data, val, None, Flavor::GzCompressed)?;
+            sink.write_file(&format!("{path}/{name}.nbt"), &data)?;
         }
         Ok(())
     }
 
-    fn write_functions<P: AsRef<Path>>(
+    fn write_functions(
+        sink: &mut impl PackSink,
         functions: &HashMap<String, Function>,
-        path: &P,
+        path: &str,
     ) -> Result<()> {
         for (name, func) in functions {
-            let file = OpenOptions::new()
-                .read(false)
-                .write(true)
-                .create(true)
-                .open(path.as_ref().join(format!("{name}.mcfunction")))?;
-
-            write_function(func, file)?;
+            let mut data = Vec::new();
+            write_function(func, &mut data)?;
+            sink.write_file(&format!("{path}/{name}.mcfunction"), &data)?;
         }
         Ok(())
     }
 }
-/// Holds the metadata about the pack
-///
-/// Does not directly represent the format of the `pack.mcmeta` file because we also store the name of the pack
+/// The metadata about the pack stored in its `pack.mcmeta` file
 #[derive(Serialize, Deserialize)]
-pub struct McMeta {
-    pub pack_format: u8,
-    pub description: String,
-    #[serde(skip)]
-    pub name: String,
+pub struct PackMeta {
+    /// The version of the datapack format the pack was made for
+    pub pack_format: i32,
+    /// The description shown for the pack, which vanilla accepts as either a string or a component
+    pub description: Component,
+    /// The range of formats the pack supports, added in 1.20.2
+    ///
+    /// If present this must include `pack_format`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supported_formats: Option<SupportedFormats>,
+}
+
+impl PackMeta {
+    /// Creates the metadata for a pack with the given format and description
+    pub fn new(pack_format: i32, description: Component) -> Self {
+        PackMeta {
+            pack_format,
+            description,
+            supported_formats: None,
+        }
+    }
+
+    /// Returns whether the pack supports the given format
+    pub fn supports_format(&self, format: i32) -> bool {
+        match &self.supported_formats {
+            Some(formats) => formats.contains(format),
+            None => self.pack_format == format,
+        }
+    }
+
+    /// Returns whether the pack can be loaded by the given minecraft version, such as `1.18.2`
+    ///
+    /// Returns false if the version cannot be parsed or its datapack version is unknown
+    pub fn is_compatible_with(&self, game_version: &str) -> bool {
+        let mut parts = game_version.split('.').map(str::parse::<u8>);
+        let (major, minor, patch) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), None, None) => (major, minor, 0),
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => (major, minor, patch),
+            _ => return false,
+        };
+
+        match datapack_version(major, minor, patch) {
+            0 | 1 => false,
+            format => self.supports_format(format),
+        }
+    }
+}
+
+/// An inclusive range of pack formats
+///
+/// Vanilla accepts this as a single format, a `[min, max]` array, or an object with
+/// `min_inclusive` and `max_inclusive` fields, and always writes the object form
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct SupportedFormats {
+    pub min_inclusive: i32,
+    pub max_inclusive: i32,
+}
+
+impl SupportedFormats {
+    /// Returns whether the format is within this range
+    pub fn contains(&self, format: i32) -> bool {
+        (self.min_inclusive ..= self.max_inclusive).contains(&format)
+    }
+}
+
+impl<'de> Deserialize<'de> for SupportedFormats {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where D: serde::Deserializer<'de> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum SupportedFormatsRepr {
+            Single(i32),
+            Range([i32; 2]),
+            Object {
+                min_inclusive: i32,
+                max_inclusive: i32,
+            },
+        }
+
+        let (min_inclusive, max_inclusive) = match SupportedFormatsRepr::deserialize(deserializer)?
+        {
+            SupportedFormatsRepr::Single(format) => (format, format),
+            SupportedFormatsRepr::Range([min, max]) => (min, max),
+            SupportedFormatsRepr::Object {
+                min_inclusive,
+                max_inclusive,
+            } => (min_inclusive, max_inclusive),
+        };
+
+        if min_inclusive > max_inclusive {
+            return Err(serde::de::Error::custom(format!(
+                "Invalid pack format range {min_inclusive}-{max_inclusive}"
+            )));
+        }
+
+        Ok(SupportedFormats {
+            min_inclusive,
+            max_inclusive,
+        })
+    }
 }
 
 /// Represents the actual format of the `pack.mcmeta` file
@@ -579,8 +830,8 @@ pub struct McMeta {
 /// Only needed because the actual data of mcmeta is wrapped in the `pack` field
 // Mojang why do you not have the data in the root
 #[derive(Serialize, Deserialize)]
-struct RawMcMeta {
-    pub pack: McMeta,
+struct RawMcMeta<M = PackMeta> {
+    pub pack: M,
 }
 
 #[derive(Debug)]
@@ -588,8 +839,8 @@ pub enum DatapackIoError {
     IOError(std::io::Error),
     VersionError {
         pack_name: String,
-        checked_version: u8,
-        found_version: u8,
+        checked_version: i32,
+        found_version: i32,
     },
     NBTError(quartz_nbt::io::NbtIoError),
     SerdeError(serde_json::Error),
@@ -635,3 +886,286 @@ impl From<serde_json::Error> for DatapackIoError {
 }
 
 pub type Result<T> = core::result::Result<T, DatapackIoError>;
+
+#[test]
+fn zip_datapack_round_trip() {
+    use std::io::Cursor;
+
+    use crate::data::{functions::FunctionLine, recipe::VanillaRecipeType};
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let files: [(&str, &str); 5] = [
+        (
+            "pack.mcmeta",
+            r#"{"pack":{"pack_format":9,"description":"A zipped pack"}}"#,
+        ),
+        (
+            "data/quartz/recipes/stone/slab.json",
+            r#"{"type":"minecraft:stonecutting","ingredient":{"item":"minecraft:stone"},"result":"minecraft:stone_slab","count":2}"#,
+        ),
+        (
+            "data/quartz/advancements/root.json",
+            r#"{"criteria":{"never":{"trigger":"minecraft:impossible"}},"rewards":{"experience":5}}"#,
+        ),
+        ("data/quartz/functions/load.mcfunction", "say hello"),
+        (
+            "data/minecraft/tags/functions/load.json",
+            r#"{"replace":false,"values":["quartz:load"]}"#,
+        ),
+    ];
+    for (path, contents) in files {
+        zip.add_file(path, contents.as_bytes()).unwrap();
+    }
+    let archive = zip.finish().unwrap().into_inner();
+
+    let pack = DataPack::from_zip(Cursor::new(archive), "zipped", VersionFilter::Latest).unwrap();
+    let mut archive = Cursor::new(Vec::new());
+    pack.to_zip(&mut archive).unwrap();
+    archive.set_position(0);
+    let pack = DataPack::from_zip(archive, "zipped", VersionFilter::Latest).unwrap();
+
+    assert_eq!(pack.name(), "zipped");
+    assert_eq!(pack.description().to_plain_string(), "A zipped pack");
+    assert_eq!(pack.namespaces.len(), 2);
+
+    let quartz = pack.namespaces.iter().find(|n| n.name == "quartz").unwrap();
+    assert!(matches!(
+        quartz.recipes.get("stone/slab"),
+        Some(VanillaRecipeType::StonecuttingRecipe(recipe)) if recipe.result.count == 2
+    ));
+    let advancement = &quartz.advancements["root"];
+    assert!(advancement.criteria.contains_key("never"));
+    assert_eq!(advancement.rewards.as_ref().unwrap().experience, Some(5));
+    assert_eq!(quartz.functions["load"].lines, vec![FunctionLine::Command(
+        "say hello".to_owned()
+    )]);
+
+    let minecraft = pack
+        .namespaces
+        .iter()
+        .find(|n| n.name == "minecraft")
+        .unwrap();
+    assert_eq!(minecraft.tags[0].name(), "functions/load");
+}
+
+#[test]
+fn parse_pack_meta() {
+    let meta: RawMcMeta = serde_json::from_str(
+        r#"{
+            "pack": {
+                "pack_format": 15,
+                "description": [{"text": "Quartz ", "color": "gold"}, "test pack"],
+                "supported_formats": [15, 18]
+            }
+        }"#,
+    )
+    .unwrap();
+    let meta = meta.pack;
+    assert_eq!(meta.pack_format, 15);
+    assert_eq!(meta.description.to_plain_string(), "Quartz test pack");
+    assert_eq!(
+        meta.supported_formats,
+        Some(SupportedFormats {
+            min_inclusive: 15,
+            max_inclusive: 18
+        })
+    );
+    assert!(meta.is_compatible_with("1.20"));
+    assert!(meta.is_compatible_with("1.20.2"));
+    assert!(!meta.is_compatible_with("1.20.3"));
+    assert!(!meta.is_compatible_with("1.19.4"));
+    assert!(!meta.is_compatible_with("not a version"));
+
+    let written = serde_json::to_value(RawMcMeta { pack: &meta }).unwrap();
+    assert_eq!(written["pack"]["pack_format"], 15);
+    assert_eq!(
+        written["pack"]["supported_formats"],
+        serde_json::json!({
+            "min_inclusive": 15,
+            "max_inclusive": 18
+        })
+    );
+
+    let meta: RawMcMeta =
+        serde_json::from_str(r#"{"pack":{"pack_format":9,"description":"Old pack"}}"#).unwrap();
+    assert!(meta.pack.is_compatible_with("1.18.2"));
+    assert!(!meta.pack.is_compatible_with("1.18.1"));
+    let written = serde_json::to_value(RawMcMeta { pack: &meta.pack }).unwrap();
+    assert!(written["pack"].get("supported_formats").is_none());
+
+    assert!(serde_json::from_str::<RawMcMeta>(r#"{"pack":{"description":"No format"}}"#).is_err());
+    assert!(serde_json::from_str::<RawMcMeta>(
+        r#"{"pack":{"pack_format":15,"description":"","supported_formats":[18,15]}}"#
+    )
+    .is_err());
+}
+
+#[test]
+fn iterate_datapack_resources() {
+    use std::io::Cursor;
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let files: [(&str, &str); 7] = [
+        (
+            "pack.mcmeta",
+            r#"{"pack":{"pack_format":9,"description":"Resources"}}"#,
+        ),
+        (
+            "data/quartz/advancements/root.json",
+            r#"{"criteria":{"never":{"trigger":"minecraft:impossible"}}}"#,
+        ),
+        (
+            "data/quartz/advancements/story/mine.json",
+            r#"{"criteria":{"never":{"trigger":"minecraft:impossible"}}}"#,
+        ),
+        (
+            "data/minecraft/recipes/slab.json",
+            r#"{"type":"minecraft:stonecutting","ingredient":{"item":"minecraft:stone"},"result":"minecraft:stone_slab","count":2}"#,
+        ),
+        ("data/quartz/functions/load.mcfunction", "say hello"),
+        (
+            "data/minecraft/tags/functions/load.json",
+            r#"{"values":["quartz:load"]}"#,
+        ),
+        (
+            "data/minecraft/tags/items/logs.json",
+            r#"{"values":["minecraft:oak_log"]}"#,
+        ),
+    ];
+    for (path, contents) in files {
+        zip.add_file(path, contents.as_bytes()).unwrap();
+    }
+    let archive = zip.finish().unwrap().into_inner();
+    let pack = DataPack::from_zip(Cursor::new(archive), "resources", VersionFilter::None).unwrap();
+
+    let sorted = |names: Vec<UnlocalizedName>| {
+        let mut names = names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+
+    let mut namespaces = pack.namespaces().collect::<Vec<_>>();
+    namespaces.sort();
+    assert_eq!(namespaces, ["minecraft", "quartz"]);
+    assert_eq!(
+        sorted(pack.advancements().map(|(name, _)| name).collect()),
+        ["quartz:root", "quartz:story/mine"]
+    );
+    assert_eq!(sorted(pack.recipes().map(|(name, _)| name).collect()), [
+        "minecraft:slab"
+    ]);
+    assert_eq!(sorted(pack.functions().map(|(name, _)| name).collect()), [
+        "quartz:load"
+    ]);
+    assert_eq!(
+        sorted(pack.tags("functions").map(|(name, _)| name).collect()),
+        ["minecraft:load"]
+    );
+    assert_eq!(
+        sorted(pack.tags("items").map(|(name, _)| name).collect()),
+        ["minecraft:logs"]
+    );
+    assert_eq!(pack.loot_tables().count(), 0);
+    assert_eq!(pack.predicates().count(), 0);
+}
+
+#[test]
+fn directory_datapack_round_trip() {
+    // Each run gets its own directory so parallel or repeated runs don't collide
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let root = std::env::temp_dir().join(format!(
+        "quartz_directory_datapack_{}_{}",
+        std::process::id(),
+        nanos
+    ));
+    let source = root.join("source");
+    let output = root.join("output");
+
+    let files: [(&str, &str); 6] = [
+        (
+            "pack.mcmeta",
+            r#"{"pack":{"pack_format":9,"description":"A directory pack"}}"#,
+        ),
+        (
+            "data/quartz/recipes/stone/slab.json",
+            r#"{"type":"minecraft:stonecutting","ingredient":{"item":"minecraft:stone"},"result":"minecraft:stone_slab","count":2}"#,
+        ),
+        (
+            "data/quartz/advancements/root.json",
+            r#"{"criteria":{"never":{"trigger":"minecraft:impossible"}},"rewards":{"experience":5}}"#,
+        ),
+        (
+            "data/quartz/functions/load.mcfunction",
+            "# Runs on load\nsay hello",
+        ),
+        (
+            "data/minecraft/tags/functions/load.json",
+            r#"{"values":["quartz:load"]}"#,
+        ),
+        ("README.txt", "Not part of the pack"),
+    ];
+    for (path, contents) in files {
+        let path = source.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    std::fs::create_dir_all(&output).unwrap();
+    std::fs::write(output.join("notes.txt"), "Keep me").unwrap();
+
+    let pack = DataPack::read(&source, "directory", VersionFilter::Latest).unwrap();
+    pack.write_to_dir(&output).unwrap();
+    let written = DataPack::read(&output, "directory", VersionFilter::Latest).unwrap();
+
+    assert_eq!(written.version(), pack.version());
+    assert_eq!(
+        written.description().to_plain_string(),
+        pack.description().to_plain_string()
+    );
+    let mut namespaces = written.namespaces().collect::<Vec<_>>();
+    namespaces.sort();
+    assert_eq!(namespaces, ["minecraft", "quartz"]);
+    for namespace in &pack.namespaces {
+        let written = written
+            .namespaces
+            .iter()
+            .find(|written| written.name == namespace.name)
+            .unwrap();
+        assert_eq!(written.recipes, namespace.recipes);
+        assert_eq!(written.functions, namespace.functions);
+        assert_eq!(
+            written.tags.iter().map(|tag| &tag.def).collect::<Vec<_>>(),
+            namespace
+                .tags
+                .iter()
+                .map(|tag| &tag.def)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            serde_json::to_value(&written.advancements).unwrap(),
+            serde_json::to_value(&namespace.advancements).unwrap()
+        );
+    }
+
+    // Functions are written as plain text and everything else as pretty-printed json
+    assert_eq!(
+        std::fs::read_to_string(output.join("data/quartz/functions/load.mcfunction")).unwrap(),
+        "# Runs on load\nsay hello"
+    );
+    assert!(
+        std::fs::read_to_string(output.join("data/quartz/recipes/stone/slab.json"))
+            .unwrap()
+            .contains('\n')
+    );
+    assert_eq!(
+        std::fs::read_to_string(output.join("notes.txt")).unwrap(),
+        "Keep me"
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
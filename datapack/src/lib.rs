@@ -38,3 +38,6 @@ macro_rules! map_visitor {
 mod datapack;
 pub use datapack::*;
 pub mod data;
+mod registry_codec;
+pub use registry_codec::*;
+mod zip;
@@ -0,0 +1,218 @@
+use qdat::{UlnStr, UnlocalizedName};
+use quartz_nbt::{
+    io::{self, Flavor},
+    NbtCompound,
+    NbtList,
+    NbtTag,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    data::{
+        biome::{BiomeCategory, BiomeEffects, Precipitation, TemperatureModifier},
+        dimension_type::DimensionType,
+    },
+    Result,
+};
+
+/// The registries sent to clients when they join a world
+///
+/// The protocol version this server targets predates the chat type registry, so only dimension
+/// types and biomes are sent
+#[derive(Serialize, Deserialize)]
+pub struct RegistryCodec {
+    #[serde(rename = "minecraft:dimension_type")]
+    pub dimension_types: Registry<DimensionType>,
+    #[serde(rename = "minecraft:worldgen/biome")]
+    pub biomes: Registry<NetworkBiome>,
+}
+
+impl RegistryCodec {
+    /// Reads the registry codec from its NBT representation
+    pub fn from_nbt(nbt: &NbtCompound) -> Result<Self> {
+        let mut bytes = Vec::new();
+        io::write_nbt(&mut bytes, None, nbt, Flavor::Uncompressed)?;
+        Ok(quartz_nbt::serde::deserialize_from_buffer(&bytes)?.0)
+    }
+
+    /// Converts the registry codec back into NBT so it can be sent to clients
+    pub fn to_nbt(&self) -> Result<NbtCompound> {
+        // The NBT serializer writes unit variants as their index, but the client expects the
+        // variant names, so we go through json instead
+        match json_to_nbt(serde_json::to_value(self)?) {
+            Some(NbtTag::Compound(compound)) => Ok(compound),
+            _ => unreachable!("Registry codecs always serialize to a map"),
+        }
+    }
+}
+
+/// A registry of elements which are referred to by name or by their numeric id
+#[derive(Serialize, Deserialize)]
+pub struct Registry<T> {
+    #[serde(rename = "type")]
+    pub registry_type: UnlocalizedName,
+    #[serde(rename = "value")]
+    pub entries: Vec<RegistryEntry<T>>,
+}
+
+impl<T> Registry<T> {
+    pub fn new(registry_type: UnlocalizedName) -> Self {
+        Registry {
+            registry_type,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds an element to the registry, giving it the next free id
+    pub fn insert(&mut self, name: UnlocalizedName, element: T) -> i32 {
+        let id = self
+            .entries
+            .iter()
+            .map(|entry| entry.id + 1)
+            .max()
+            .unwrap_or(0);
+        self.entries.push(RegistryEntry { name, id, element });
+        id
+    }
+
+    pub fn get(&self, name: &UlnStr) -> Option<&T> {
+        self.entry(name).map(|entry| &entry.element)
+    }
+
+    pub fn get_by_id(&self, id: i32) -> Option<&T> {
+        self.entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .map(|entry| &entry.element)
+    }
+
+    pub fn id_of(&self, name: &UlnStr) -> Option<i32> {
+        self.entry(name).map(|entry| entry.id)
+    }
+
+    fn entry(&self, name: &UlnStr) -> Option<&RegistryEntry<T>> {
+        self.entries.iter().find(|entry| &*entry.name == name)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RegistryEntry<T> {
+    pub name: UnlocalizedName,
+    pub id: i32,
+    pub element: T,
+}
+
+/// The parts of a [Biome](crate::data::biome::Biome) which are sent to clients
+#[derive(Serialize, Deserialize)]
+pub struct NetworkBiome {
+    pub category: BiomeCategory,
+    pub precipitation: Precipitation,
+    pub temperature: f32,
+    pub temperature_modifier: Option<TemperatureModifier>,
+    pub downfall: f32,
+    pub depth: f32,
+    pub scale: f32,
+    pub effects: BiomeEffects,
+}
+
+/// Converts a json value into the closest NBT tag, returning `None` for nulls
+///
+/// Integers become ints or longs and floating point numbers become floats if they can be
+/// represented exactly, since the client accepts any numeric tag for numbers and booleans
+fn json_to_nbt(value: Value) -> Option<NbtTag> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(value) => NbtTag::Byte(value as i8),
+        Value::Number(number) =>
+            if let Some(value) = number.as_i64() {
+                match i32::try_from(value) {
+                    Ok(value) => NbtTag::Int(value),
+                    Err(_) => NbtTag::Long(value),
+                }
+            } else {
+                let value = number.as_f64().unwrap_or(f64::MAX);
+                if value as f32 as f64 == value {
+                    NbtTag::Float(value as f32)
+                } else {
+                    NbtTag::Double(value)
+                }
+            },
+        Value::String(value) => NbtTag::String(value),
+        Value::Array(values) => NbtTag::List(NbtList::from(
+            values
+                .into_iter()
+                .filter_map(json_to_nbt)
+                .collect::<Vec<_>>(),
+        )),
+        Value::Object(map) => {
+            let mut compound = NbtCompound::new();
+            for (key, value) in map {
+                if let Some(tag) = json_to_nbt(value) {
+                    compound.insert(key, tag);
+                }
+            }
+            NbtTag::Compound(compound)
+        }
+    })
+}
+
+/// Checks whether two tags are equal, treating numeric tags of different types as equal if they
+/// hold the same value
+#[cfg(test)]
+fn semantically_equal(a: &NbtTag, b: &NbtTag) -> bool {
+    fn as_f64(tag: &NbtTag) -> Option<f64> {
+        match *tag {
+            NbtTag::Byte(value) => Some(value as f64),
+            NbtTag::Short(value) => Some(value as f64),
+            NbtTag::Int(value) => Some(value as f64),
+            NbtTag::Long(value) => Some(value as f64),
+            NbtTag::Float(value) => Some(value as f64),
+            NbtTag::Double(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    match (a, b) {
+        (NbtTag::Compound(a), NbtTag::Compound(b)) =>
+            a.len() == b.len()
+                && a.inner().iter().all(|(key, a)| {
+                    b.inner()
+                        .get(key)
+                        .map(|b| semantically_equal(a, b))
+                        .unwrap_or(false)
+                }),
+        (NbtTag::List(a), NbtTag::List(b)) =>
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|(a, b)| semantically_equal(a, b)),
+        _ => match (as_f64(a), as_f64(b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => a == b,
+        },
+    }
+}
+
+#[test]
+fn registry_codec_round_trip() {
+    let nbt = quartz_nbt::snbt::parse(include_str!("../../assets/dimension_codec.snbt")).unwrap();
+    let codec = RegistryCodec::from_nbt(&nbt).unwrap();
+
+    let nether = codec
+        .dimension_types
+        .get(&UnlocalizedName::minecraft("the_nether"))
+        .unwrap();
+    assert!(nether.has_ceiling);
+    assert_eq!(nether.fixed_time, Some(18000));
+    assert_eq!(
+        codec.biomes.id_of(&UnlocalizedName::minecraft("plains")),
+        Some(1)
+    );
+
+    let reserialized = codec.to_nbt().unwrap();
+    assert!(semantically_equal(
+        &NbtTag::Compound(nbt),
+        &NbtTag::Compound(reserialized)
+    ));
+}
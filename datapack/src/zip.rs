@@ -0,0 +1,98 @@
+//! Reading and writing the zip archives datapacks can be distributed as
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Read, Seek, Write},
+};
+
+use zip::{read::ZipArchive, write::FileOptions, CompressionMethod};
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+/// Normalizes the name of an entry so it always uses forward slashes and has no leading slash
+pub fn normalize_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = path.trim_start_matches("./").trim_start_matches('/');
+    path.to_owned()
+}
+
+/// Reads every file in a zip archive, returning their contents keyed by their normalized path
+///
+/// Directory entries are skipped since they have no contents. Entries are never read past the
+/// size recorded in the archive, so a malformed or malicious archive results in an error rather
+/// than an unbounded allocation
+pub fn read_zip<R: Read + Seek>(reader: R) -> io::Result<BTreeMap<String, Vec<u8>>> {
+    let mut archive = ZipArchive::new(reader)?;
+
+    let mut files = BTreeMap::new();
+    for index in 0 .. archive.len() {
+        let file = archive.by_index(index)?;
+        if file.is_dir() || file.name().ends_with('\\') {
+            continue;
+        }
+
+        let name = normalize_path(file.name());
+        let size = file.size();
+        let mut data = Vec::new();
+        file.take(size).read_to_end(&mut data)?;
+        if data.len() as u64 != size {
+            return Err(invalid_data("Corrupt zip entry"));
+        }
+        files.insert(name, data);
+    }
+
+    Ok(files)
+}
+
+/// Writes a zip archive whose entries are all deflated
+pub struct ZipWriter<W: Write + Seek> {
+    inner: zip::ZipWriter<W>,
+}
+
+impl<W: Write + Seek> ZipWriter<W> {
+    pub fn new(writer: W) -> Self {
+        ZipWriter {
+            inner: zip::ZipWriter::new(writer),
+        }
+    }
+
+    /// Adds a file with the given path and contents to the archive
+    pub fn add_file(&mut self, path: &str, data: &[u8]) -> io::Result<()> {
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.inner.start_file(normalize_path(path), options)?;
+        self.inner.write_all(data)
+    }
+
+    /// Writes the central directory and returns the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        Ok(self.inner.finish()?)
+    }
+}
+
+#[test]
+fn zip_round_trip() {
+    use std::io::Cursor;
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    writer.add_file("pack.mcmeta", b"{}").unwrap();
+    writer
+        .add_file("data\\quartz\\functions\\a.mcfunction", &[b'a'; 1000])
+        .unwrap();
+    let archive = writer.finish().unwrap().into_inner();
+
+    let files = read_zip(Cursor::new(archive)).unwrap();
+    assert_eq!(files.len(), 2);
+    assert_eq!(files["pack.mcmeta"], b"{}");
+    assert_eq!(files["data/quartz/functions/a.mcfunction"], vec![
+        b'a';
+        1000
+    ]);
+
+    assert!(read_zip(Cursor::new(b"not a zip".to_vec())).is_err());
+    // Truncated archives are rejected rather than read out of bounds
+    for truncated in [&b""[..], &[1, 2], b"PK\x05\x06"] {
+        assert!(read_zip(Cursor::new(truncated.to_vec())).is_err());
+    }
+}
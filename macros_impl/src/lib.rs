@@ -39,6 +39,17 @@ pub fn is_boxed_slice(ty: &Type) -> bool {
     }
 }
 
+pub fn is_vec(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) =>
+            path.qself.is_none()
+                && path.path.leading_colon.is_none()
+                && !path.path.segments.is_empty()
+                && path.path.segments.last().unwrap().ident == "Vec",
+        _ => false,
+    }
+}
+
 pub fn is_option(ty: &Type) -> bool {
     match ty {
         Type::Path(path) =>
@@ -1,5 +1,5 @@
 use proc_macro2::{Span, TokenStream};
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, ToTokens};
 use syn::{DeriveInput, Ident};
 
 use super::{
@@ -31,26 +31,29 @@ pub fn gen_enum_serializer_impl(input: DeriveInput, variants: &[EnumStructVarian
     let any_unit = variants.iter().any(|variant| variant.fields.is_empty());
     let quartz_net = quartz_net();
 
+    let tagged = variants.iter().any(|variant| variant.id.is_some());
+
     let serialize_variants = variants
         .iter()
-        .filter(|variant| !variant.fields.is_empty())
+        .filter(|variant| tagged || !variant.fields.is_empty())
         .map(|variant| {
             let var_name = &variant.name;
-            let field_names = variant.fields.iter().map(|field| &field.name);
+            let write_id = variant
+                .id
+                .map(|id| quote! { __buffer.write_varying(&#id); });
             let serialize_fields = variant
                 .fields
                 .iter()
                 .map(|field| gen_serialize_enum_field(field, &format_ident!("__buffer")));
-            let field_unpacking = if variant.is_tuple {
-                quote! { ( #( #field_names ),* ) }
-            } else {
-                quote! { { #( #field_names ),* } }
-            };
+            let field_unpacking = variant.gen_field_pattern();
             quote! {
-                Self::#var_name #field_unpacking => { #( #serialize_fields )* }
+                Self::#var_name #field_unpacking => {
+                    #write_id
+                    #( #serialize_fields )*
+                }
             }
         });
-    let default_branch = if any_unit {
+    let default_branch = if any_unit && !tagged {
         Some(quote! { _ => {} })
     } else {
         None
@@ -87,22 +90,63 @@ pub fn gen_struct_deserializer_impl(input: DeriveInput, fields: &[Field]) -> Tok
     }
 }
 
+pub fn gen_enum_deserializer_impl(
+    input: DeriveInput,
+    variants: &[EnumStructVariant],
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let quartz_net = quartz_net();
+
+    let deserialize_variants = variants.iter().map(|variant| {
+        let var_name = &variant.name;
+        // Ids are assigned to every variant of an enum which can be read
+        let id = variant
+            .id
+            .expect("Variant ids incorrectly checked when parsing");
+        let deserialize_fields = variant
+            .fields
+            .iter()
+            .map(|field| gen_deserialize_field(&quartz_net, field, &format_ident!("__buffer")));
+        let field_packing = variant.gen_field_pattern();
+        quote! {
+            #id => {
+                #( #deserialize_fields )*
+                ::core::result::Result::Ok(Self::#var_name #field_packing)
+            }
+        }
+    });
+
+    quote! {
+        impl #impl_generics #quartz_net::ReadFromPacket for #name #ty_generics #where_clause {
+            fn read_from(__buffer: &mut #quartz_net::PacketBuffer) -> ::core::result::Result<Self, #quartz_net::PacketSerdeError> {
+                match __buffer.read_varying::<i32>()? {
+                    #( #deserialize_variants, )*
+                    __id => ::core::result::Result::Err(#quartz_net::PacketSerdeError::InvalidEnum(#name_str, __id)),
+                }
+            }
+        }
+    }
+}
+
 pub fn gen_serialize_struct_field(field: &Field, buffer_ident: &Ident) -> TokenStream {
     let name = &field.name;
     let write_fn = write_fn_for_field(field);
 
-    if field.is_option {
+    let write_impl = if field.is_option {
         let field_ref = quote! { __value };
         let write_condition = field.condition.as_ref().and_then(|condition| {
             condition.gen_write_condition(&quote! { self.#name }, buffer_ident)
         });
         let len_prefix = field.opt_write_length_prefix(&field_ref, buffer_ident);
+        let missing_value = field.opt_missing_value_panic();
         quote! {
             #write_condition
             if let ::core::option::Option::Some(#field_ref) = &self.#name {
                 #len_prefix
                 #buffer_ident.#write_fn(#field_ref);
-            }
+            } #missing_value
         }
     } else {
         let field_ref = quote! { self.#name };
@@ -111,7 +155,9 @@ pub fn gen_serialize_struct_field(field: &Field, buffer_ident: &Ident) -> TokenS
             #len_prefix
             #buffer_ident.#write_fn(&#field_ref);
         }
-    }
+    };
+
+    field.wrap_present_if(write_impl, |field_ref| quote! { &self.#field_ref })
 }
 
 pub fn gen_serialize_enum_field(field: &Field, buffer_ident: &Ident) -> TokenStream {
@@ -149,7 +195,7 @@ pub fn gen_serialize_enum_field(field: &Field, buffer_ident: &Ident) -> TokenStr
         }
     };
 
-    if field.is_option {
+    let write_impl = if field.is_option {
         let field_ref = quote! { __value };
         let write_condition = field
             .condition
@@ -157,13 +203,14 @@ pub fn gen_serialize_enum_field(field: &Field, buffer_ident: &Ident) -> TokenStr
             .and_then(|condition| condition.gen_write_condition(&quote! { #name }, buffer_ident));
         let len_prefix = field.opt_write_length_prefix(&field_ref, buffer_ident);
         let write_impl = gen_write_impl(&field_ref);
+        let missing_value = field.opt_missing_value_panic();
 
         quote! {
             #write_condition
             if let ::core::option::Option::Some(#field_ref) = #name {
                 #len_prefix
                 #write_impl
-            }
+            } #missing_value
         }
     } else {
         let field_ref = quote! { #name };
@@ -174,7 +221,9 @@ pub fn gen_serialize_enum_field(field: &Field, buffer_ident: &Ident) -> TokenStr
             #len_prefix
             #write_impl
         }
-    }
+    };
+
+    field.wrap_present_if(write_impl, |field_ref| quote! { #field_ref })
 }
 
 fn write_fn_for_field(field: &Field) -> Ident {
@@ -225,7 +274,7 @@ pub fn gen_deserialize_field(
             },
         FieldType::Array { len } => {
             let len = len.gen_read_length(buffer_ident);
-            if field.is_array_u8 {
+            let read_array = if field.is_array_u8 {
                 quote! {{
                     #len
                     let mut __array = vec![0u8; __len].into_boxed_slice();
@@ -263,7 +312,10 @@ pub fn gen_deserialize_field(
                     #len
                     #buffer_ident.read_array(__len)?
                 }}
-            }
+            };
+
+            // Arrays are read as boxed slices, which also need to be converted for vec fields
+            quote! { ::core::convert::Into::into(#read_array) }
         }
     };
     let read_impl = if field.is_option {
@@ -272,9 +324,14 @@ pub fn gen_deserialize_field(
         read_impl
     };
 
-    match &field.condition {
+    let condition = match (&field.present_if, &field.condition) {
+        (Some(present_if), _) => Some(present_if.expr.to_token_stream()),
+        (None, Some(condition)) => Some(condition.gen_read_condition(buffer_ident)),
+        (None, None) => None,
+    };
+
+    match condition {
         Some(condition) => {
-            let condition = condition.gen_read_condition(buffer_ident);
             quote! {
                 let #name: #ty = if #condition {
                     #read_impl
@@ -289,7 +346,62 @@ pub fn gen_deserialize_field(
     }
 }
 
+impl EnumStructVariant {
+    /// Generates the fields of this variant in braces or parentheses, for use in both patterns and
+    /// constructors.
+    fn gen_field_pattern(&self) -> TokenStream {
+        let field_names = self.fields.iter().map(|field| &field.name);
+        if self.is_tuple {
+            quote! { ( #( #field_names ),* ) }
+        } else {
+            quote! { { #( #field_names ),* } }
+        }
+    }
+}
+
 impl Field {
+    /// Wraps the given write implementation so that it only runs if this field is present. The
+    /// earlier fields referenced by the presence condition are bound by value while it's evaluated,
+    /// so the same expression is valid when both reading and writing.
+    fn wrap_present_if(
+        &self,
+        write_impl: TokenStream,
+        ref_to: impl Fn(&Ident) -> TokenStream,
+    ) -> TokenStream {
+        match &self.present_if {
+            Some(present_if) => {
+                let expr = &present_if.expr;
+                let bindings = present_if.refs.iter().map(|field_ref| {
+                    let reference = ref_to(field_ref);
+                    quote! { let #field_ref = ::core::clone::Clone::clone(#reference); }
+                });
+
+                quote! {
+                    if { #( #bindings )* #expr } {
+                        #write_impl
+                    }
+                }
+            }
+            None => write_impl,
+        }
+    }
+
+    /// Returns an else branch for writing an optional field which panics if the field has no
+    /// value, since a field whose presence condition holds is always read back.
+    fn opt_missing_value_panic(&self) -> Option<TokenStream> {
+        self.present_if.as_ref()?;
+
+        let message = format!(
+            "Field `{}` has no value but its presence condition holds",
+            self.name
+        );
+        Some(quote! {
+            else {
+                ::core::panic!(#message);
+            }
+        })
+    }
+
     fn opt_write_length_prefix(
         &self,
         field_ref: &TokenStream,
@@ -308,6 +420,9 @@ impl ArrayLength {
             ArrayLength::Expr(expr) => quote! { let __len = #expr; },
             ArrayLength::Prefixed =>
                 quote! { let __len = #buffer_ident.read_varying::<i32>()? as usize; },
+            ArrayLength::BytePrefixed =>
+                quote! { let __len = #buffer_ident.read::<u8>()? as usize; },
+            ArrayLength::Fixed(len) => quote! { let __len: usize = #len; },
             ArrayLength::Greedy => quote! { let __len = #buffer_ident.remaining(); },
             ArrayLength::None =>
                 unreachable!("Array length parameter incorrectly checked when parsing"),
@@ -324,6 +439,23 @@ impl ArrayLength {
             ArrayLength::Expr(_) => None,
             ArrayLength::Prefixed =>
                 Some(quote! { #buffer_ident.write_varying(&(#field_ref.len() as i32)); }),
+            ArrayLength::BytePrefixed => Some(quote! {
+                ::core::assert!(
+                    #field_ref.len() <= u8::MAX as usize,
+                    "Array of length {} is too long for a u8 length prefix",
+                    #field_ref.len()
+                );
+                #buffer_ident.write(&(#field_ref.len() as u8));
+            }),
+            // The length isn't written, but writing any other number of elements would corrupt the
+            // packet
+            ArrayLength::Fixed(len) => Some(quote! {
+                ::core::assert_eq!(
+                    #field_ref.len(),
+                    #len,
+                    "Fixed length array has the wrong length"
+                );
+            }),
             // Length inferred
             ArrayLength::Greedy => None,
             ArrayLength::None => None,
@@ -27,11 +27,22 @@ pub fn derive_write_to_packet_impl(input: DeriveInput) -> TokenStream {
 }
 
 pub fn derive_read_from_packet_impl(input: DeriveInput) -> TokenStream {
-    let fields = match parse_fields(&input, Side::Read) {
-        Ok(fields) => fields,
-        Err(e) => return e.to_compile_error(),
-    };
-    gen_struct_deserializer_impl(input, &fields)
+    match &input.data {
+        Data::Enum(data) => {
+            let variants = match parse_enum(data, Side::Read) {
+                Ok(variants) => variants,
+                Err(e) => return e.to_compile_error(),
+            };
+            gen_enum_deserializer_impl(input, &variants)
+        }
+        _ => {
+            let fields = match parse_fields(&input, Side::Read) {
+                Ok(fields) => fields,
+                Err(e) => return e.to_compile_error(),
+            };
+            gen_struct_deserializer_impl(input, &fields)
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -1,6 +1,7 @@
 use super::Side;
-use crate::{extract_type_from_container, is_boxed_slice, is_option};
-use quote::format_ident;
+use crate::{extract_type_from_container, is_boxed_slice, is_option, is_vec};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, ToTokens};
 use syn::{
     parenthesized,
     parse::{Parse, ParseStream},
@@ -11,6 +12,7 @@ use syn::{
     Expr,
     Fields,
     Ident,
+    LitInt,
     LitStr,
     Result,
     Token,
@@ -33,13 +35,24 @@ pub(crate) fn parse_fields(input: &DeriveInput, side: Side) -> Result<Vec<Field>
 
 pub(crate) fn parse_enum(input: &DataEnum, side: Side) -> Result<Vec<EnumStructVariant>> {
     let mut variants = Vec::new();
+    let mut explicit_ids = Vec::new();
     for variant in &input.variants {
+        let id = variant
+            .attrs
+            .iter()
+            .find(|&attr| attr.path.is_ident("packet_serde"))
+            .map(|attr| syn::parse2::<VariantParams>(attr.tokens.clone()))
+            .transpose()?
+            .and_then(|params| params.id);
+        explicit_ids.push(id);
+
         match &variant.fields {
             Fields::Named(named_fields) => {
                 variants.push(EnumStructVariant {
                     name: variant.ident.clone(),
                     fields: parse_fields_impl(&named_fields.named, true, side)?,
                     is_tuple: false,
+                    id: None,
                 });
             }
             Fields::Unnamed(unnamed_fields) => {
@@ -47,6 +60,7 @@ pub(crate) fn parse_enum(input: &DataEnum, side: Side) -> Result<Vec<EnumStructV
                     name: variant.ident.clone(),
                     fields: parse_fields_impl(&unnamed_fields.unnamed, false, side)?,
                     is_tuple: true,
+                    id: None,
                 });
             }
             Fields::Unit => {
@@ -54,10 +68,40 @@ pub(crate) fn parse_enum(input: &DataEnum, side: Side) -> Result<Vec<EnumStructV
                     name: variant.ident.clone(),
                     fields: Vec::new(),
                     is_tuple: false,
+                    id: None,
                 });
             }
         };
     }
+
+    // Enums without any explicit ids are untagged, and their discriminant is handled elsewhere
+    if explicit_ids.iter().all(Option::is_none) {
+        if side == Side::Read && !input.variants.is_empty() {
+            return Err(Error::new_spanned(
+                &input.variants,
+                "Enums must specify at least one variant id with `#[packet_serde(id = N)]` to be \
+                 read",
+            ));
+        }
+
+        return Ok(variants);
+    }
+
+    // Like Rust's discriminants, variants without an explicit id follow the previous variant
+    let mut assigned_ids = Vec::with_capacity(variants.len());
+    let mut next_id = 0;
+    for ((variant, explicit_id), def) in variants.iter_mut().zip(explicit_ids).zip(&input.variants)
+    {
+        let id = explicit_id.unwrap_or(next_id);
+        if assigned_ids.contains(&id) {
+            return Err(Error::new_spanned(&def.ident, "Duplicate variant id"));
+        }
+
+        assigned_ids.push(id);
+        variant.id = Some(id);
+        next_id = id.wrapping_add(1);
+    }
+
     Ok(variants)
 }
 
@@ -71,15 +115,29 @@ where
 {
     let mut fields = Vec::new();
 
+    /// Adds an array field of the given type, where the array itself is either a `Box<[T]>` or a
+    /// `Vec<T>` and may be wrapped in an option.
     fn process_array(
         fields: &mut Vec<Field>,
         name: Ident,
+        ty: Type,
         boxed_slice_ty: Type,
-        slice_ty: Type,
-        params: PacketSerdeParams,
+        mut params: PacketSerdeParams,
         is_option: bool,
         side: Side,
     ) -> Result<()> {
+        let slice_ty = if is_vec(&boxed_slice_ty) {
+            // Vecs are prefixed with their length unless told otherwise
+            if params.len.is_none() && !params.greedy {
+                params.len = Some(ArrayLength::Prefixed);
+            }
+
+            boxed_slice_ty.clone()
+        } else {
+            // Unwrap guaranteed by is_boxed_slice
+            extract_type_from_container(&boxed_slice_ty).unwrap()
+        };
+
         let is_array_u8 = match extract_type_from_container(&slice_ty)? {
             Type::Path(path) =>
                 path.qself.is_none()
@@ -100,7 +158,7 @@ where
             if !is_array_u8 {
                 return Err(Error::new_spanned(
                     boxed_slice_ty,
-                    "Only Box<[u8]> can be market as greedy",
+                    "Only Box<[u8]> or Vec<u8> can be marked as greedy",
                 ));
             }
 
@@ -113,14 +171,23 @@ where
                 ));
             }
 
+            if side == Side::Read && matches!(params.len, Some(ArrayLength::None)) {
+                return Err(Error::new_spanned(
+                    boxed_slice_ty,
+                    "Arrays without a length prefix cannot be read, use `len` or `fixed_len` \
+                     instead",
+                ));
+            }
+
             params.len.unwrap()
         };
 
         fields.push(Field::array(
             name,
-            boxed_slice_ty,
+            ty,
             len,
             params.condition,
+            params.present_if,
             is_option,
             params.varying,
             is_array_u8,
@@ -134,11 +201,15 @@ where
             .attrs
             .iter()
             .find(|&attr| attr.path.is_ident("packet_serde"));
-        let params = attr
+        let mut params = attr
             .map(|attr| syn::parse2::<PacketSerdeParams>(attr.tokens.clone()))
             .transpose()?
             .unwrap_or_default();
 
+        if let Some(present_if) = &mut params.present_if {
+            present_if.resolve_refs(&fields);
+        }
+
         let name = if require_names {
             match field_def.ident.clone() {
                 Some(ident) => ident,
@@ -152,22 +223,20 @@ where
         };
 
         let ty = field_def.ty.clone();
-        if is_boxed_slice(&ty) {
-            // Unwrap guaranteed by is_boxed_slice
-            let slice_ty = extract_type_from_container(&ty).unwrap();
-            process_array(&mut fields, name, ty, slice_ty, params, false, side)?;
+        if is_boxed_slice(&ty) || is_vec(&ty) {
+            process_array(&mut fields, name, ty.clone(), ty, params, false, side)?;
             continue;
         }
 
         if params.greedy {
             return Err(Error::new_spanned(
                 ty,
-                "Only Box<[u8]> can be market as greedy",
+                "Only Box<[u8]> or Vec<u8> can be marked as greedy",
             ));
         }
 
         if is_option(&ty) {
-            if side == Side::Read && params.condition.is_none() {
+            if side == Side::Read && params.condition.is_none() && params.present_if.is_none() {
                 return Err(Error::new_spanned(
                     ty,
                     "Options must have a condition expression",
@@ -175,10 +244,8 @@ where
             }
 
             let inner_ty = extract_type_from_container(&ty)?;
-            if is_boxed_slice(&inner_ty) {
-                // Unwrap guaranteed by is_boxed_slice
-                let slice_ty = extract_type_from_container(&inner_ty).unwrap();
-                process_array(&mut fields, name, inner_ty, slice_ty, params, true, side)?;
+            if is_boxed_slice(&inner_ty) || is_vec(&inner_ty) {
+                process_array(&mut fields, name, ty, inner_ty, params, true, side)?;
             } else {
                 if params.len.is_some() {
                     return Err(Error::new_spanned(
@@ -191,6 +258,7 @@ where
                     name,
                     ty,
                     params.condition,
+                    params.present_if,
                     true,
                     params.varying,
                     params.nbt,
@@ -203,7 +271,7 @@ where
         if params.len.is_some() {
             return Err(Error::new_spanned(
                 attr,
-                "Only boxed slices (arrays) can have a length",
+                "Only boxed slices and vecs (arrays) can have a length",
             ));
         }
 
@@ -242,6 +310,7 @@ where
             name,
             ty,
             params.condition,
+            params.present_if,
             false,
             params.varying,
             params.nbt,
@@ -258,6 +327,7 @@ struct PacketSerdeParams {
     nbt: bool,
     len: Option<ArrayLength>,
     condition: Option<OptionCondition>,
+    present_if: Option<PresenceCondition>,
 }
 
 impl Parse for PacketSerdeParams {
@@ -322,7 +392,7 @@ impl Parse for PacketSerdeParams {
                     params.len = Some(ArrayLength::Prefixed);
                 }
                 "condition" => {
-                    if params.condition.is_some() {
+                    if params.condition.is_some() || params.present_if.is_some() {
                         return Err(Error::new_spanned(ident, "Duplicate condition parameter"));
                     }
 
@@ -332,12 +402,40 @@ impl Parse for PacketSerdeParams {
                     )?));
                 }
                 "bool_prefixed" => {
-                    if params.condition.is_some() {
+                    if params.condition.is_some() || params.present_if.is_some() {
                         return Err(Error::new_spanned(ident, "Duplicate condition parameter"));
                     }
 
                     params.condition = Some(OptionCondition::Prefixed);
                 }
+                "len_prefix" => {
+                    if params.len.is_some() {
+                        return Err(Error::new_spanned(ident, "Duplicate length parameter"));
+                    }
+
+                    content.parse::<Token![=]>()?;
+                    let prefix = content.parse::<LitStr>()?;
+                    params.len = Some(match prefix.value().as_str() {
+                        "varint" => ArrayLength::Prefixed,
+                        "u8" => ArrayLength::BytePrefixed,
+                        "none" => ArrayLength::None,
+                        _ =>
+                            return Err(Error::new_spanned(
+                                prefix,
+                                "Unknown length prefix, expected one of `varint`, `u8`, or `none`",
+                            )),
+                    });
+                }
+                "fixed_len" => {
+                    if params.len.is_some() {
+                        return Err(Error::new_spanned(ident, "Duplicate length parameter"));
+                    }
+
+                    content.parse::<Token![=]>()?;
+                    params.len = Some(ArrayLength::Fixed(
+                        content.parse::<LitInt>()?.base10_parse()?,
+                    ));
+                }
                 "no_len" => {
                     if params.len.is_some() {
                         return Err(Error::new_spanned(ident, "Duplicate length parameter"));
@@ -345,8 +443,18 @@ impl Parse for PacketSerdeParams {
 
                     params.len = Some(ArrayLength::None);
                 }
+                "present_if" => {
+                    if params.condition.is_some() || params.present_if.is_some() {
+                        return Err(Error::new_spanned(ident, "Duplicate condition parameter"));
+                    }
+
+                    content.parse::<Token![=]>()?;
+                    params.present_if = Some(PresenceCondition::new(syn::parse_str(
+                        &content.parse::<LitStr>()?.value(),
+                    )?));
+                }
                 "no_condition" => {
-                    if params.condition.is_some() {
+                    if params.condition.is_some() || params.present_if.is_some() {
                         return Err(Error::new_spanned(ident, "Duplicate condition parameter"));
                     }
 
@@ -355,8 +463,46 @@ impl Parse for PacketSerdeParams {
                 _ =>
                     return Err(Error::new_spanned(
                         ident,
-                        "Unknown parameter, expected one of `varying`, `greedy`, `len`, or \
-                         `condition`",
+                        "Unknown parameter, expected one of `varying`, `greedy`, `len`, \
+                         `condition`, or `present_if`",
+                    )),
+            }
+
+            if !content.is_empty() {
+                content.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(params)
+    }
+}
+
+#[derive(Default)]
+struct VariantParams {
+    id: Option<i32>,
+}
+
+impl Parse for VariantParams {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut params = Self::default();
+        let content;
+        parenthesized!(content in input);
+
+        while !content.is_empty() {
+            let ident: Ident = content.parse()?;
+            match ident.to_string().as_str() {
+                "id" => {
+                    if params.id.is_some() {
+                        return Err(Error::new_spanned(ident, "Duplicate parameter"));
+                    }
+
+                    content.parse::<Token![=]>()?;
+                    params.id = Some(content.parse::<LitInt>()?.base10_parse()?);
+                }
+                _ =>
+                    return Err(Error::new_spanned(
+                        ident,
+                        "Unknown parameter, expected `id`",
                     )),
             }
 
@@ -372,7 +518,12 @@ impl Parse for PacketSerdeParams {
 #[allow(clippy::large_enum_variant)]
 pub enum ArrayLength {
     Expr(Expr),
+    /// Prefixed with a VarInt.
     Prefixed,
+    /// Prefixed with a single unsigned byte.
+    BytePrefixed,
+    /// A length known at compile time, which is not written.
+    Fixed(usize),
     Greedy,
     None,
 }
@@ -384,10 +535,50 @@ pub enum OptionCondition {
     None,
 }
 
+/// A condition under which a field is read or written at all, which may reference earlier fields
+/// by name.
+pub struct PresenceCondition {
+    pub expr: Expr,
+    /// The earlier fields referenced by the expression.
+    pub refs: Vec<Ident>,
+}
+
+impl PresenceCondition {
+    fn new(expr: Expr) -> Self {
+        PresenceCondition {
+            expr,
+            refs: Vec::new(),
+        }
+    }
+
+    fn resolve_refs(&mut self, earlier_fields: &[Field]) {
+        fn collect_idents(tokens: TokenStream, idents: &mut Vec<Ident>) {
+            for token in tokens {
+                match token {
+                    TokenTree::Ident(ident) => idents.push(ident),
+                    TokenTree::Group(group) => collect_idents(group.stream(), idents),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut idents = Vec::new();
+        collect_idents(self.expr.to_token_stream(), &mut idents);
+        self.refs = earlier_fields
+            .iter()
+            .map(|field| &field.name)
+            .filter(|name| idents.contains(name))
+            .cloned()
+            .collect();
+    }
+}
+
 pub struct EnumStructVariant {
     pub name: Ident,
     pub fields: Vec<Field>,
     pub is_tuple: bool,
+    /// The id written before the variant's fields, or `None` if the enum is untagged.
+    pub id: Option<i32>,
 }
 
 pub struct Field {
@@ -395,6 +586,7 @@ pub struct Field {
     pub raw_ty: Type,
     pub ty: FieldType,
     pub condition: Option<OptionCondition>,
+    pub present_if: Option<PresenceCondition>,
     pub is_option: bool,
     pub varying: bool,
     pub is_array_u8: bool,
@@ -406,6 +598,7 @@ impl Field {
         name: Ident,
         ty: Type,
         condition: Option<OptionCondition>,
+        present_if: Option<PresenceCondition>,
         is_option: bool,
         varying: bool,
         is_nbt: bool,
@@ -415,6 +608,7 @@ impl Field {
             raw_ty: ty,
             ty: FieldType::Regular,
             condition,
+            present_if,
             is_option,
             varying,
             is_array_u8: false,
@@ -428,6 +622,7 @@ impl Field {
         ty: Type,
         len: ArrayLength,
         condition: Option<OptionCondition>,
+        present_if: Option<PresenceCondition>,
         is_option: bool,
         varying: bool,
         is_array_u8: bool,
@@ -443,6 +638,7 @@ impl Field {
             raw_ty: ty,
             ty: FieldType::Array { len },
             condition,
+            present_if,
             is_option,
             varying,
             is_array_u8,
@@ -249,7 +249,7 @@ fn gen_packet_enum(
                     connection_state: crate::ConnectionState,
                 ) -> Result<#enum_name, crate::PacketSerdeError> {
                     let id;
-                    if connection_state == crate::ConnectionState::Handshake && buffer.peek_one()? == crate::LEGACY_PING_PACKET_ID as u8 {
+                    if connection_state == crate::ConnectionState::Handshake && crate::is_legacy_ping(&buffer[buffer.cursor() ..]) {
                         id = crate::LEGACY_PING_PACKET_ID;
                         buffer.read_one()?;
                     } else {
@@ -377,6 +377,7 @@ impl Packet {
                         ty,
                         len,
                         condition,
+                        None,
                         is_option,
                         varying,
                         field.var_type.starts_with("u8"),
@@ -390,6 +391,7 @@ impl Packet {
                         name,
                         ty,
                         condition,
+                        None,
                         is_option,
                         varying,
                         field.ser_as_nbt,
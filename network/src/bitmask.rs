@@ -20,6 +20,41 @@ impl BitMask {
     pub fn as_empty(&self) -> Self {
         Self(!self.0 | 1)
     }
+
+    /// Returns whether the bit at the given index is set.
+    pub fn is_set(&self, index: usize) -> bool {
+        index < 128 && self.0 & (1u128 << index) != 0
+    }
+
+    /// Converts this mask into the long array form used by the protocol, where bit `i` is stored in
+    /// long `i / 64`. Trailing longs with no bits set are left out.
+    pub fn to_long_array(&self) -> Vec<i64> {
+        let longs = [self.0 as u64 as i64, (self.0 >> 64) as u64 as i64];
+        let len = longs
+            .iter()
+            .rposition(|&long| long != 0)
+            .map_or(0, |i| i + 1);
+        longs[.. len].to_vec()
+    }
+
+    /// Creates a mask from the protocol's long array form, keeping only the first `bit_count`
+    /// bits.
+    pub fn from_long_array(longs: &[i64], bit_count: usize) -> Self {
+        if bit_count > 128 || longs.len() > 2 {
+            warn!("Encountered bit mask containing more than 128 bits");
+        }
+
+        let mut mask = 0;
+        for (i, &long) in longs.iter().take(2).enumerate() {
+            mask |= (long as u64 as u128) << (i * 64);
+        }
+
+        if bit_count < 128 {
+            mask &= (1u128 << bit_count) - 1;
+        }
+
+        Self(mask)
+    }
 }
 
 impl Default for BitMask {
@@ -32,32 +67,48 @@ impl ReadFromPacket for BitMask {
     fn read_from(buffer: &mut PacketBuffer) -> Result<Self, PacketSerdeError> {
         let len = buffer.read_varying::<i32>()? as usize;
 
-        if len > 2 {
-            warn!("Encountered bit mask containing more than 128 bits");
-        }
-
-        let mut mask = 0;
-        for _ in 0 .. len.min(2) {
-            mask <<= 64;
-            mask |= buffer.read::<u64>()? as u128;
+        let mut longs = Vec::with_capacity(len.min(2));
+        for _ in 0 .. len {
+            longs.push(buffer.read::<i64>()?);
         }
 
-        Ok(Self(mask))
+        Ok(Self::from_long_array(&longs, len.saturating_mul(64)))
     }
 }
 
 impl WriteToPacket for BitMask {
     fn write_to(&self, buffer: &mut PacketBuffer) {
-        let lo = (self.0 & u64::MAX as u128) as u64;
-        let hi = (self.0 >> 64) as u64;
-
-        if hi == 0 {
-            buffer.write_varying(&1i32);
-            buffer.write(&lo);
-        } else {
-            buffer.write_varying(&2i32);
-            buffer.write(&lo);
-            buffer.write(&hi);
-        };
+        let longs = self.to_long_array();
+
+        buffer.write_varying(&(longs.len() as i32));
+        for long in &longs {
+            buffer.write(long);
+        }
     }
 }
+
+#[test]
+fn long_array_round_trip() {
+    let mut mask = BitMask::new();
+    mask.set(0);
+    mask.set(64);
+
+    let longs = mask.to_long_array();
+    assert_eq!(longs, vec![1, 1]);
+    assert_eq!(
+        BitMask::from_long_array(&longs, 128).into_raw(),
+        mask.into_raw()
+    );
+    assert!(BitMask::from_long_array(&longs, 128).is_set(64));
+    assert!(!BitMask::from_long_array(&longs, 64).is_set(64));
+
+    let mut buffer = PacketBuffer::new(32);
+    buffer.write(&mask);
+    buffer.reset_cursor();
+    assert_eq!(
+        buffer.read::<BitMask>().unwrap().into_raw(),
+        mask.into_raw()
+    );
+
+    assert!(BitMask::new().to_long_array().is_empty());
+}
@@ -1,8 +1,10 @@
 mod bitmask;
+mod movement;
 mod netutil;
 pub mod packet_data;
 
 pub use bitmask::*;
+pub use movement::*;
 pub use netutil::*;
 
 /// All possible states of a client's connection to the server.
@@ -15,17 +17,86 @@ pub enum ConnectionState {
     Status,
     /// The client is logging into the server.
     Login,
+    /// The client has logged in and is being sent the registries, feature flags and other data
+    /// it needs before entering the world. The client enters this state by acknowledging a
+    /// successful login, and may return to it from `Play` when the server starts a
+    /// reconfiguration.
+    ///
+    /// In this state the client may send client information, plugin messages, keep-alives, pongs
+    /// and resource pack responses, as well as acknowledging the end of configuration, which
+    /// moves it into the `Play` state. The server may send plugin messages, keep-alives, pings,
+    /// registry data, feature flags, a disconnect, or finish the configuration.
+    Configuration,
     /// The client has successfully logged into the server and is playing the game.
     Play,
     /// The client has disconnected.
     Disconnected,
 }
 
+impl ConnectionState {
+    /// Returns whether or not a connection in this state may move into the given state. A
+    /// connection may always be disconnected.
+    pub const fn can_transition_to(self, next: ConnectionState) -> bool {
+        matches!(
+            (self, next),
+            (_, ConnectionState::Disconnected)
+                | (
+                    ConnectionState::Handshake,
+                    ConnectionState::Status | ConnectionState::Login
+                )
+                | (
+                    ConnectionState::Login,
+                    ConnectionState::Configuration | ConnectionState::Play
+                )
+                | (ConnectionState::Configuration, ConnectionState::Play)
+                | (ConnectionState::Play, ConnectionState::Configuration)
+        )
+    }
+}
+
 /// The numeric protocol version the server uses.
 pub const PROTOCOL_VERSION: i32 = 755;
+/// The first protocol version where clients acknowledge a successful login and are configured
+/// before they start playing, which was introduced in 1.20.2.
+pub const CONFIGURATION_PROTOCOL_VERSION: i32 = 764;
+/// The largest packet length accepted by vanilla, which is the largest value that fits in a
+/// three-byte VarInt.
+pub const MAX_PACKET_LEN: usize = 2097151;
+/// The largest length of a compressed packet's data once decompressed accepted by vanilla.
+pub const MAX_UNCOMPRESSED_PACKET_LEN: usize = 8388608;
 /// The ID for the legacy ping packet.
 pub const LEGACY_PING_PACKET_ID: i32 = 0xFE;
 
+/// Returns whether the given bytes, read at the start of a connection, are a legacy server list
+/// ping sent by a pre-1.7 client rather than the length prefix of a modern handshake.
+///
+/// Since a modern handshake may have a length which starts with `0xFE 0x01`, the ping is only
+/// recognized if it ends there or is followed by the `0xFA` plugin message sent by 1.6 clients,
+/// whereas a handshake would be followed by its packet ID of zero.
+pub fn is_legacy_ping(bytes: &[u8]) -> bool {
+    matches!(bytes, [0xFE] | [0xFE, 0x01] | [0xFE, 0x01, 0xFA, ..])
+}
+
+/// Writes the response to a legacy server list ping, which is a kick packet with a null-delimited
+/// UTF-16BE string starting with `§1` and containing the protocol version, server version, MOTD,
+/// and the online and maximum player counts.
+pub fn write_legacy_ping_response(
+    buffer: &mut PacketBuffer,
+    version: &str,
+    motd: &str,
+    online_players: usize,
+    max_players: usize,
+) {
+    let response =
+        format!("\u{a7}1\0{PROTOCOL_VERSION}\0{version}\0{motd}\0{online_players}\0{max_players}")
+            .encode_utf16()
+            .collect::<Vec<_>>();
+
+    buffer.write_one(0xFF);
+    buffer.write(&(response.len() as u16));
+    buffer.write_array(&response);
+}
+
 mod build {
     #![allow(clippy::redundant_pattern, clippy::match_single_binding)]
     use super::*;
@@ -33,3 +104,96 @@ mod build {
 }
 
 pub use build::*;
+
+#[test]
+fn state_transitions() {
+    use ConnectionState::*;
+
+    assert!(Handshake.can_transition_to(Status));
+    assert!(Handshake.can_transition_to(Login));
+    assert!(Login.can_transition_to(Play));
+    assert!(Play.can_transition_to(Disconnected));
+
+    assert!(!Handshake.can_transition_to(Play));
+    assert!(!Status.can_transition_to(Login));
+    assert!(!Play.can_transition_to(Login));
+    assert!(!Configuration.can_transition_to(Login));
+}
+
+#[test]
+fn configuration_state_transitions() {
+    use ConnectionState::*;
+
+    fn read(id: i32, state: ConnectionState) -> Result<ServerBoundPacket, PacketSerdeError> {
+        let mut buffer = PacketBuffer::new(4);
+        buffer.write_varying(&id);
+        let len = buffer.len();
+        buffer.reset_cursor();
+        ServerBoundPacket::read_from(&mut buffer, state, len)
+    }
+
+    let mut state = Login;
+    for (id, expected, next) in [
+        (0x03, "LoginAcknowledged", Configuration),
+        (0x02, "AcknowledgeFinishConfiguration", Play),
+        (0x30, "AcknowledgeConfiguration", Configuration),
+        (0x02, "AcknowledgeFinishConfiguration", Play),
+    ] {
+        let packet = read(id, state).unwrap();
+        assert_eq!(format!("{packet:?}"), expected);
+        assert!(state.can_transition_to(next));
+        state = next;
+    }
+
+    // Configuration packets are not accepted before the login is acknowledged
+    assert!(matches!(
+        read(0x05, Login),
+        Err(PacketSerdeError::InvalidId(0x05))
+    ));
+    assert!(!Status.can_transition_to(Configuration));
+    assert!(!Handshake.can_transition_to(Configuration));
+}
+
+#[test]
+fn legacy_ping() {
+    assert!(is_legacy_ping(&[0xFE]));
+    assert!(is_legacy_ping(&[0xFE, 0x01]));
+    assert!(is_legacy_ping(&[0xFE, 0x01, 0xFA, 0x00, 0x0B]));
+    // A modern handshake with a length of 254
+    assert!(!is_legacy_ping(&[0xFE, 0x01, 0x00, 0xF3, 0x05]));
+    assert!(!is_legacy_ping(&[0x10, 0x00]));
+
+    let mut buffer = PacketBuffer::new(8);
+    buffer.write_bytes([0xFE, 0x01, 0xFA, 0x00, 0x0B]);
+    let len = buffer.len();
+    buffer.reset_cursor();
+    assert!(matches!(
+        ServerBoundPacket::read_from(&mut buffer, ConnectionState::Handshake, len),
+        Ok(ServerBoundPacket::LegacyServerListPing { payload }) if payload[..] == [0x01, 0xFA, 0x00, 0x0B]
+    ));
+
+    let mut buffer = PacketBuffer::new(64);
+    write_legacy_ping_response(&mut buffer, "1.17", "Hi", 3, 20);
+    let response = format!("\u{a7}1\0{PROTOCOL_VERSION}\01.17\0Hi\03\020");
+    let mut expected = vec![0xFF, 0x00, response.encode_utf16().count() as u8];
+    for ch in response.encode_utf16() {
+        expected.extend_from_slice(&ch.to_be_bytes());
+    }
+    assert_eq!(&buffer[..], &expected[..]);
+    assert_eq!(&buffer[3 .. 7], &[0x00, 0xA7, 0x00, 0x31]);
+}
+
+#[test]
+fn out_of_state_packet() {
+    // A play-state chat message sent during the handshake
+    let mut buffer = PacketBuffer::new(16);
+    buffer.write_varying(&0x03i32);
+    buffer.write(&"hello");
+    let len = buffer.len();
+    buffer.reset_cursor();
+
+    assert!(matches!(
+        ServerBoundPacket::read_from(&mut buffer, ConnectionState::Handshake, len),
+        Err(PacketSerdeError::InvalidId(0x03))
+    ));
+}
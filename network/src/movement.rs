@@ -0,0 +1,46 @@
+/// The number of fixed-point units per block used by relative entity movement.
+const DELTA_UNITS_PER_BLOCK: f64 = 4096.0;
+
+/// Encodes the movement of an entity along one axis as a fixed-point delta, as used by the relative
+/// entity movement packets. Returns `None` if the entity moved too far to be encoded as a delta,
+/// roughly eight blocks, in which case an absolute teleport must be sent instead.
+pub fn encode_delta(old: f64, new: f64) -> Option<i16> {
+    // Rounding each position rather than the difference keeps the client from drifting after many
+    // small movements
+    let delta =
+        (new * DELTA_UNITS_PER_BLOCK).round() as i64 - (old * DELTA_UNITS_PER_BLOCK).round() as i64;
+    i16::try_from(delta).ok()
+}
+
+/// Encodes an angle in degrees as a step of 1/256th of a full turn. Angles outside of 0..360 wrap
+/// around.
+pub fn encode_angle(degrees: f32) -> u8 {
+    (degrees * 256.0 / 360.0).floor() as i32 as u8
+}
+
+#[test]
+fn delta_threshold() {
+    assert_eq!(encode_delta(10.0, 10.5), Some(2048));
+    assert_eq!(encode_delta(0.0, -1.0), Some(-4096));
+    assert_eq!(
+        encode_delta(0.0, i16::MAX as f64 / DELTA_UNITS_PER_BLOCK),
+        Some(i16::MAX)
+    );
+    assert_eq!(
+        encode_delta(0.0, (i16::MAX as f64 + 1.0) / DELTA_UNITS_PER_BLOCK),
+        None
+    );
+    assert_eq!(
+        encode_delta(0.0, i16::MIN as f64 / DELTA_UNITS_PER_BLOCK),
+        Some(i16::MIN)
+    );
+    assert_eq!(encode_delta(0.0, 8.0), None);
+}
+
+#[test]
+fn angle_encoding() {
+    assert_eq!(encode_angle(0.0), 0);
+    assert_eq!(encode_angle(90.0), 64);
+    assert_eq!(encode_angle(-90.0), 192);
+    assert_eq!(encode_angle(450.0), 64);
+}
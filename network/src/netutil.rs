@@ -475,19 +475,7 @@ impl ReadFromPacket for i32 {
     }
 
     fn varying_read_from(buffer: &mut PacketBuffer) -> Result<Self, PacketSerdeError> {
-        let mut result = 0i32;
-        let mut by;
-
-        for i in 0 .. 5 {
-            by = buffer.read_one()?;
-            result |= ((by & 0x7F) as i32) << (7 * i);
-
-            if (by & 0x80) == 0 {
-                return Ok(result);
-            }
-        }
-
-        Err(PacketSerdeError::VarIntOverflow)
+        crate::packet_data::read_varint(buffer)
     }
 }
 
@@ -503,19 +491,7 @@ impl ReadFromPacket for i64 {
     }
 
     fn varying_read_from(buffer: &mut PacketBuffer) -> Result<Self, PacketSerdeError> {
-        let mut result = 0i64;
-        let mut by;
-
-        for i in 0 .. 10 {
-            by = buffer.read_one()?;
-            result |= ((by & 0x7F) as i64) << (7 * i);
-
-            if (by & 0x80) == 0 {
-                return Ok(result);
-            }
-        }
-
-        Err(PacketSerdeError::VarIntOverflow)
+        crate::packet_data::read_varlong(buffer)
     }
 }
 
@@ -654,26 +630,7 @@ impl WriteToPacket for i32 {
     }
 
     fn varying_write_to(&self, buffer: &mut PacketBuffer) {
-        let mut value = *self as u32;
-        let mut next_byte: u8;
-        let mut buf = [0u8; 5];
-        let mut i = 0;
-
-        while i < 5 {
-            next_byte = (value & 0x7F) as u8;
-            value >>= 7;
-            if value != 0 {
-                next_byte |= 0x80;
-            }
-            buf[i] = next_byte;
-            i += 1;
-
-            if value == 0 {
-                break;
-            }
-        }
-
-        buffer.write_bytes(&buf[.. i]);
+        crate::packet_data::write_varint(buffer, *self)
     }
 }
 
@@ -685,26 +642,7 @@ impl WriteToPacket for i64 {
     }
 
     fn varying_write_to(&self, buffer: &mut PacketBuffer) {
-        let mut value = *self as u64;
-        let mut next_byte: u8;
-        let mut buf = [0u8; 10];
-        let mut i = 0;
-
-        while i < 10 {
-            next_byte = (value & 0x7F) as u8;
-            value >>= 7;
-            if value != 0 {
-                next_byte |= 0x80;
-            }
-            buf[i] = next_byte;
-            i += 1;
-
-            if value == 0 {
-                break;
-            }
-        }
-
-        buffer.write_bytes(&buf[.. i]);
+        crate::packet_data::write_varlong(buffer, *self)
     }
 }
 
@@ -800,6 +738,7 @@ pub enum PacketSerdeError {
     EndOfBuffer,
     VarIntOverflow,
     InvalidId(i32),
+    InvalidPacketLength(i32),
     Utf8Error(Utf8Error),
     InvalidUnlocalizedName(<UnlocalizedName as FromStr>::Err),
     SerdeJson(serde_json::Error),
@@ -820,6 +759,10 @@ impl Display for PacketSerdeError {
                 "Variable-length integer or long overflowed while reading"
             ),
             PacketSerdeError::InvalidId(id) => write!(f, "Invalid packet ID encountered: {id}"),
+            PacketSerdeError::InvalidPacketLength(len) => write!(
+                f,
+                "Packet length {len} is negative or exceeds the maximum length"
+            ),
             PacketSerdeError::Utf8Error(e) => Display::fmt(e, f),
             PacketSerdeError::InvalidUnlocalizedName(uln) => write!(
                 f,
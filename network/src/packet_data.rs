@@ -12,6 +12,83 @@ use quartz_macros::{ReadFromPacket, WriteToPacket};
 use quartz_nbt::NbtCompound;
 use uuid::Uuid;
 
+/// The maximum number of bytes in an encoded VarInt.
+pub const MAX_VARINT_LEN: usize = 5;
+/// The maximum number of bytes in an encoded VarLong.
+pub const MAX_VARLONG_LEN: usize = 10;
+
+/// Reads a variable-length integer, consisting of up to five bytes of seven bits each, least
+/// significant group first.
+///
+/// If the buffer ends partway through the integer then [`EndOfBuffer`] is returned and the cursor
+/// is left where it was, so the read can be retried once more data has arrived. An integer longer
+/// than five bytes is malformed and results in [`VarIntOverflow`].
+///
+/// [`EndOfBuffer`]: PacketSerdeError::EndOfBuffer
+/// [`VarIntOverflow`]: PacketSerdeError::VarIntOverflow
+pub fn read_varint(buffer: &mut PacketBuffer) -> Result<i32, PacketSerdeError> {
+    read_var_bits(buffer, MAX_VARINT_LEN).map(|value| value as i32)
+}
+
+/// Reads a variable-length long, consisting of up to ten bytes of seven bits each. See
+/// [`read_varint`] for how truncated and malformed input is handled.
+pub fn read_varlong(buffer: &mut PacketBuffer) -> Result<i64, PacketSerdeError> {
+    read_var_bits(buffer, MAX_VARLONG_LEN).map(|value| value as i64)
+}
+
+fn read_var_bits(buffer: &mut PacketBuffer, max_len: usize) -> Result<u64, PacketSerdeError> {
+    let start = buffer.cursor();
+    let mut result = 0u64;
+
+    for i in 0 .. max_len {
+        let by = match buffer.read_one() {
+            Ok(by) => by,
+            Err(error) => {
+                buffer.set_cursor(start);
+                return Err(error);
+            }
+        };
+        result |= ((by & 0x7F) as u64) << (7 * i);
+
+        if (by & 0x80) == 0 {
+            return Ok(result);
+        }
+    }
+
+    Err(PacketSerdeError::VarIntOverflow)
+}
+
+/// Writes the given integer as a VarInt. Negative values always take up five bytes.
+pub fn write_varint(buffer: &mut PacketBuffer, value: i32) {
+    write_var_bits(buffer, value as u32 as u64)
+}
+
+/// Writes the given long as a VarLong. Negative values always take up ten bytes.
+pub fn write_varlong(buffer: &mut PacketBuffer, value: i64) {
+    write_var_bits(buffer, value as u64)
+}
+
+fn write_var_bits(buffer: &mut PacketBuffer, mut value: u64) {
+    let mut buf = [0u8; MAX_VARLONG_LEN];
+    let mut i = 0;
+
+    loop {
+        let mut next_byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value != 0 {
+            next_byte |= 0x80;
+        }
+        buf[i] = next_byte;
+        i += 1;
+
+        if value == 0 {
+            break;
+        }
+    }
+
+    buffer.write_bytes(&buf[.. i]);
+}
+
 #[derive(Debug, WriteToPacket)]
 #[allow(clippy::large_enum_variant)]
 pub enum EntityMetadata {
@@ -42,6 +119,77 @@ pub struct EntityMetadataWrapper {
     data: EntityMetadata,
 }
 
+impl EntityMetadataWrapper {
+    pub fn new(index: u8, data: EntityMetadata) -> Self {
+        EntityMetadataWrapper { index, data }
+    }
+
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    pub fn data(&self) -> &EntityMetadata {
+        &self.data
+    }
+}
+
+/// Accumulates entity metadata fields by index. Setting the same index more than once replaces the
+/// earlier value.
+#[derive(Debug, Default)]
+pub struct EntityMetadataBuilder {
+    fields: Vec<EntityMetadataWrapper>,
+}
+
+impl EntityMetadataBuilder {
+    /// The index of the custom name shared by all entities.
+    pub const CUSTOM_NAME_INDEX: u8 = 2;
+    /// The index of the custom name visibility flag shared by all entities.
+    pub const CUSTOM_NAME_VISIBLE_INDEX: u8 = 3;
+    /// The index of the flags byte shared by all entities.
+    pub const SHARED_FLAGS_INDEX: u8 = 0;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the field at the given index to the given value.
+    pub fn set(mut self, index: u8, data: EntityMetadata) -> Self {
+        match self.fields.iter_mut().find(|field| field.index == index) {
+            Some(field) => field.data = data,
+            None => self.fields.push(EntityMetadataWrapper::new(index, data)),
+        }
+
+        self
+    }
+
+    /// Sets the flags byte shared by all entities (on fire, crouching, invisible, etc.).
+    pub fn shared_flags(self, flags: i8) -> Self {
+        self.set(Self::SHARED_FLAGS_INDEX, EntityMetadata::Byte(flags))
+    }
+
+    /// Sets the custom name of the entity, or clears it if `None` is given.
+    pub fn custom_name(self, name: Option<Component>) -> Self {
+        self.set(
+            Self::CUSTOM_NAME_INDEX,
+            EntityMetadata::OptChat(name.map(Box::new)),
+        )
+    }
+
+    /// Sets whether the custom name of the entity is always visible.
+    pub fn custom_name_visible(self, visible: bool) -> Self {
+        self.set(
+            Self::CUSTOM_NAME_VISIBLE_INDEX,
+            EntityMetadata::Boolean(visible),
+        )
+    }
+
+    /// Returns the accumulated fields sorted by index, ready to be written to a packet.
+    pub fn build(mut self) -> Box<[EntityMetadataWrapper]> {
+        self.fields.sort_by_key(|field| field.index);
+        self.fields.into_boxed_slice()
+    }
+}
+
 impl WriteToPacket for Box<[EntityMetadataWrapper]> {
     fn write_to(&self, buffer: &mut PacketBuffer) {
         for wrapper in self.as_ref() {
@@ -129,7 +277,7 @@ impl ReadFromPacket for Box<[EntityMetadataWrapper]> {
     }
 }
 
-#[derive(Debug, WriteToPacket)]
+#[derive(Debug, Clone, WriteToPacket)]
 pub enum ParticleData {
     AmbientEntityEffect,
     AngryVillager,
@@ -244,6 +392,101 @@ pub enum ParticleData {
 }
 
 impl ParticleData {
+    /// Returns the id of this particle, which is written before its data in packets.
+    pub fn id(&self) -> i32 {
+        match self {
+            ParticleData::AmbientEntityEffect => 0,
+            ParticleData::AngryVillager => 1,
+            ParticleData::Barrier => 2,
+            ParticleData::Light => 3,
+            ParticleData::Block(_) => 4,
+            ParticleData::Bubble => 5,
+            ParticleData::Cloud => 6,
+            ParticleData::Crit => 7,
+            ParticleData::DamageIndicator => 8,
+            ParticleData::DragonBreath => 9,
+            ParticleData::DrippingLava => 10,
+            ParticleData::FallingLava => 11,
+            ParticleData::LandingLava => 12,
+            ParticleData::DrippingWater => 13,
+            ParticleData::FallingWater => 14,
+            ParticleData::Dust { .. } => 15,
+            ParticleData::DustColorTransition { .. } => 16,
+            ParticleData::Effect => 17,
+            ParticleData::ElderGuardian => 18,
+            ParticleData::EnchantedHit => 19,
+            ParticleData::Enchant => 20,
+            ParticleData::EndRod => 21,
+            ParticleData::EntityEffect => 22,
+            ParticleData::ExplosionEmitter => 23,
+            ParticleData::Explosion => 24,
+            ParticleData::FallingDust(_) => 25,
+            ParticleData::Firework => 26,
+            ParticleData::Fishing => 27,
+            ParticleData::Flame => 28,
+            ParticleData::SoulFireFlame => 29,
+            ParticleData::Soul => 30,
+            ParticleData::Flash => 31,
+            ParticleData::HappyVillager => 32,
+            ParticleData::Composter => 33,
+            ParticleData::Heart => 34,
+            ParticleData::InstantEffect => 35,
+            ParticleData::Item(_) => 36,
+            ParticleData::Vibration { .. } => 37,
+            ParticleData::ItemSlime => 38,
+            ParticleData::ItemSnowball => 39,
+            ParticleData::LargeSmoke => 40,
+            ParticleData::Lava => 41,
+            ParticleData::Mycelium => 42,
+            ParticleData::Note => 43,
+            ParticleData::Poof => 44,
+            ParticleData::Portal => 45,
+            ParticleData::Rain => 46,
+            ParticleData::Smoke => 47,
+            ParticleData::Sneeze => 48,
+            ParticleData::Spit => 49,
+            ParticleData::SquidInk => 50,
+            ParticleData::SweepAttack => 51,
+            ParticleData::TotemOfUndying => 52,
+            ParticleData::Underwater => 53,
+            ParticleData::Splash => 54,
+            ParticleData::Witch => 55,
+            ParticleData::BubblePop => 56,
+            ParticleData::CurrentDown => 57,
+            ParticleData::BubbleColumnUp => 58,
+            ParticleData::Nautilus => 59,
+            ParticleData::Dolphin => 60,
+            ParticleData::CampfireCosySmoke => 61,
+            ParticleData::CampfireSignalSmoke => 62,
+            ParticleData::DrippingHoney => 63,
+            ParticleData::FallingHoney => 64,
+            ParticleData::LandingHoney => 65,
+            ParticleData::FallingNectar => 66,
+            ParticleData::FallingSporeBlossom => 67,
+            ParticleData::Ash => 68,
+            ParticleData::CrimsonSpore => 69,
+            ParticleData::WarpedSpore => 70,
+            ParticleData::SporeBlossomAir => 71,
+            ParticleData::DrippingObsidianTear => 72,
+            ParticleData::FallingObsidianTear => 73,
+            ParticleData::LandingObsidianTear => 74,
+            ParticleData::ReversePortal => 75,
+            ParticleData::WhiteAsh => 76,
+            ParticleData::SmallFlame => 77,
+            ParticleData::Snowflake => 78,
+            ParticleData::DrippingDripstoneLava => 79,
+            ParticleData::FallingDripstoneLava => 80,
+            ParticleData::DrippingDripstoneWater => 81,
+            ParticleData::FallingDripstoneWater => 82,
+            ParticleData::GlowSquidInk => 83,
+            ParticleData::Glow => 84,
+            ParticleData::WaxOn => 85,
+            ParticleData::WaxOff => 86,
+            ParticleData::ElectricSpark => 87,
+            ParticleData::Scrape => 88,
+        }
+    }
+
     pub fn read_particle_data(
         id: i32,
         buffer: &mut PacketBuffer,
@@ -499,6 +742,12 @@ pub struct TabCompleteMatch {
     tooltip: Option<Box<Component>>,
 }
 
+impl TabCompleteMatch {
+    pub fn new(tab_match: String, tooltip: Option<Box<Component>>) -> Self {
+        TabCompleteMatch { tab_match, tooltip }
+    }
+}
+
 #[derive(Debug, WriteToPacket, ReadFromPacket)]
 pub struct Statistic {
     #[packet_serde(varying)]
@@ -654,6 +903,16 @@ pub struct EntityProperty {
     modifiers: Box<[AttributeModifier]>,
 }
 
+impl EntityProperty {
+    pub fn new(key: UnlocalizedName, value: f64, modifiers: Box<[AttributeModifier]>) -> Self {
+        EntityProperty {
+            key,
+            value,
+            modifiers,
+        }
+    }
+}
+
 #[derive(Debug, WriteToPacket, ReadFromPacket)]
 pub struct AttributeModifier {
     uuid: Uuid,
@@ -661,6 +920,16 @@ pub struct AttributeModifier {
     operation: i8,
 }
 
+impl AttributeModifier {
+    pub fn new(uuid: Uuid, amount: f64, operation: i8) -> Self {
+        AttributeModifier {
+            uuid,
+            amount,
+            operation,
+        }
+    }
+}
+
 #[derive(Debug, WriteToPacket)]
 pub struct Recipe {
     recipe_type: UnlocalizedName,
@@ -1116,3 +1385,288 @@ impl WriteToPacket for BlockFace {
         }
     }
 }
+
+#[test]
+fn varint_vectors() {
+    for (value, bytes) in [
+        (0, &[0x00][..]),
+        (1, &[0x01]),
+        (127, &[0x7F]),
+        (128, &[0x80, 0x01]),
+        (255, &[0xFF, 0x01]),
+        (25565, &[0xDD, 0xC7, 0x01]),
+        (2097151, &[0xFF, 0xFF, 0x7F]),
+        (2147483647, &[0xFF, 0xFF, 0xFF, 0xFF, 0x07]),
+        (-1, &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+        (-2147483648, &[0x80, 0x80, 0x80, 0x80, 0x08]),
+    ] {
+        let mut buffer = PacketBuffer::new(5);
+        write_varint(&mut buffer, value);
+        assert_eq!(&buffer[..], bytes);
+        assert_eq!(buffer.len(), PacketBuffer::varint_size(value));
+
+        buffer.reset_cursor();
+        assert_eq!(read_varint(&mut buffer).unwrap(), value);
+    }
+}
+
+#[test]
+fn varlong_vectors() {
+    for (value, bytes) in [
+        (0, &[0x00][..]),
+        (127, &[0x7F]),
+        (128, &[0x80, 0x01]),
+        (2147483647, &[0xFF, 0xFF, 0xFF, 0xFF, 0x07]),
+        (9223372036854775807, &[
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F,
+        ]),
+        (-1, &[
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01,
+        ]),
+        (-9223372036854775808, &[
+            0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01,
+        ]),
+    ] {
+        let mut buffer = PacketBuffer::new(10);
+        write_varlong(&mut buffer, value);
+        assert_eq!(&buffer[..], bytes);
+
+        buffer.reset_cursor();
+        assert_eq!(read_varlong(&mut buffer).unwrap(), value);
+    }
+}
+
+#[test]
+fn malformed_and_truncated_varints() {
+    let mut buffer = PacketBuffer::new(11);
+    buffer.write_bytes([0xFF; 6]);
+    buffer.reset_cursor();
+    assert!(matches!(
+        read_varint(&mut buffer),
+        Err(PacketSerdeError::VarIntOverflow)
+    ));
+
+    let mut buffer = PacketBuffer::new(11);
+    buffer.write_bytes([0xFF; 11]);
+    buffer.reset_cursor();
+    assert!(matches!(
+        read_varlong(&mut buffer),
+        Err(PacketSerdeError::VarIntOverflow)
+    ));
+
+    // The cursor is restored so the read can be retried once the rest arrives
+    let mut buffer = PacketBuffer::new(3);
+    buffer.write_bytes([0xDD, 0xC7]);
+    buffer.reset_cursor();
+    assert!(matches!(
+        read_varint(&mut buffer),
+        Err(PacketSerdeError::EndOfBuffer)
+    ));
+    assert_eq!(buffer.cursor(), 0);
+
+    buffer.set_cursor(2);
+    buffer.write_one(0x01);
+    buffer.reset_cursor();
+    assert_eq!(read_varint(&mut buffer).unwrap(), 25565);
+}
+
+#[test]
+fn entity_metadata_layout() {
+    let metadata = EntityMetadataBuilder::new()
+        .custom_name(Some(Component::text("Bob")))
+        .shared_flags(0x20)
+        .build();
+
+    let mut buffer = PacketBuffer::new(32);
+    buffer.write(&metadata);
+
+    let name = br#"{"text":"Bob"}"#;
+    let mut expected = vec![0x00, 0x00, 0x20, 0x02, 0x05, 0x01, name.len() as u8];
+    expected.extend_from_slice(name);
+    expected.push(0xFF);
+
+    assert_eq!(&buffer[..], &expected[..]);
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq, WriteToPacket, ReadFromPacket)]
+enum TestAction {
+    #[packet_serde(id = 3)]
+    Rename {
+        name: String,
+    },
+    #[packet_serde(id = 7)]
+    Resize(#[packet_serde(varying)] i32),
+    // Follows the previous id
+    Reset,
+}
+
+#[test]
+fn enum_variant_ids_round_trip() {
+    for (action, id) in [
+        (
+            TestAction::Rename {
+                name: "crate".to_owned(),
+            },
+            3,
+        ),
+        (TestAction::Resize(300), 7),
+        (TestAction::Reset, 8),
+    ] {
+        let mut buffer = PacketBuffer::new(16);
+        buffer.write(&action);
+        assert_eq!(buffer[0], id);
+
+        buffer.reset_cursor();
+        assert_eq!(buffer.read::<TestAction>().unwrap(), action);
+    }
+
+    let mut buffer = PacketBuffer::new(1);
+    buffer.write_varying(&4_i32);
+    buffer.reset_cursor();
+    assert!(matches!(
+        buffer.read::<TestAction>(),
+        Err(PacketSerdeError::InvalidEnum(_, 4))
+    ));
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq, WriteToPacket, ReadFromPacket)]
+struct TestConditional {
+    #[packet_serde(varying)]
+    flags: i32,
+    #[packet_serde(present_if = "flags != 0")]
+    count: i64,
+    #[packet_serde(present_if = "flags & 0x02 != 0")]
+    name: Option<String>,
+}
+
+#[test]
+fn conditional_fields() {
+    for (value, len) in [
+        (
+            TestConditional {
+                flags: 0,
+                count: 0,
+                name: None,
+            },
+            1,
+        ),
+        (
+            TestConditional {
+                flags: 1,
+                count: 5,
+                name: None,
+            },
+            9,
+        ),
+        (
+            TestConditional {
+                flags: 3,
+                count: 5,
+                name: Some("a".to_owned()),
+            },
+            11,
+        ),
+    ] {
+        let mut buffer = PacketBuffer::new(16);
+        buffer.write(&value);
+        assert_eq!(buffer.len(), len);
+
+        buffer.reset_cursor();
+        assert_eq!(buffer.read::<TestConditional>().unwrap(), value);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Field `name` has no value but its presence condition holds")]
+fn conditional_field_missing_value() {
+    let value = TestConditional {
+        flags: 2,
+        count: 0,
+        name: None,
+    };
+    PacketBuffer::new(16).write(&value);
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq, WriteToPacket, ReadFromPacket)]
+struct TestArrays {
+    #[packet_serde(len_prefix = "varint")]
+    data: Box<[u8]>,
+    #[packet_serde(len_prefix = "u8")]
+    names: Box<[String]>,
+    #[packet_serde(fixed_len = 8)]
+    offsets: Box<[i16]>,
+}
+
+#[test]
+fn array_length_encodings() {
+    let value = TestArrays {
+        data: vec![1, 2, 3].into_boxed_slice(),
+        names: vec!["a".to_owned(), "b".to_owned()].into_boxed_slice(),
+        offsets: vec![-1; 8].into_boxed_slice(),
+    };
+
+    let mut buffer = PacketBuffer::new(32);
+    buffer.write(&value);
+    // Prefix and data, prefix and two strings, then eight shorts without a prefix
+    assert_eq!(buffer.len(), 4 + 5 + 16);
+    assert_eq!(buffer[4], 2);
+
+    buffer.reset_cursor();
+    assert_eq!(buffer.read::<TestArrays>().unwrap(), value);
+}
+
+#[test]
+#[should_panic(expected = "Fixed length array has the wrong length")]
+fn fixed_length_mismatch() {
+    let value = TestArrays {
+        data: Box::new([]),
+        names: Box::new([]),
+        offsets: vec![0; 7].into_boxed_slice(),
+    };
+    PacketBuffer::new(32).write(&value);
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq, WriteToPacket, ReadFromPacket)]
+struct TestEntry {
+    #[packet_serde(varying)]
+    id: i32,
+    name: String,
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq, WriteToPacket, ReadFromPacket)]
+struct TestVecs {
+    entries: Vec<TestEntry>,
+    #[packet_serde(fixed_len = 2)]
+    pair: Vec<u8>,
+    #[packet_serde(bool_prefixed)]
+    extra: Option<Vec<TestEntry>>,
+}
+
+#[test]
+fn vec_fields_round_trip() {
+    let entry = |id: i32, name: &str| TestEntry {
+        id,
+        name: name.to_owned(),
+    };
+    let value = TestVecs {
+        entries: vec![entry(1, "a"), entry(300, "bc")],
+        pair: vec![4, 5],
+        extra: Some(vec![entry(2, "d")]),
+    };
+
+    let mut buffer = PacketBuffer::new(32);
+    buffer.write(&value);
+    // Vecs are prefixed with their length by default
+    assert_eq!(buffer[0], 2);
+    assert_eq!(&buffer[1 .. 4], &[0x01, 0x01, b'a']);
+    // Prefix and entries, the fixed length pair, then the option and its prefixed entry
+    assert_eq!(buffer.len(), 1 + 3 + 5 + 2 + 1 + 1 + 3);
+
+    buffer.reset_cursor();
+    assert_eq!(buffer.read::<TestVecs>().unwrap(), value);
+}
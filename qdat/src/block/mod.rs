@@ -24,6 +24,66 @@ pub struct Block {
     pub behavior: BlockBehaviorSMT,
 }
 
+impl Block {
+    /// Returns the value of each of this block's properties in the given state, in the same order
+    /// as `properties`, or `None` if the state does not belong to this block.
+    pub fn state_properties(&self, state: StateID) -> Option<Vec<(&str, &str)>> {
+        let indices = self.value_indices(state)?;
+        Some(
+            self.properties
+                .iter()
+                .zip(indices)
+                .map(|((name, values), index)| (name.as_str(), values[index].as_str()))
+                .collect(),
+        )
+    }
+
+    /// Returns the ID of this block's state with the given property values, taking the value of
+    /// the default state for any property left out. Returns `None` if a property doesn't exist on
+    /// this block or is given a value it can't have.
+    pub fn state_with(&self, props: &[(&str, &str)]) -> Option<StateID> {
+        let mut indices = self.value_indices(self.default_state)?;
+        for &(name, value) in props {
+            let property = self
+                .properties
+                .iter()
+                .position(|(property, _)| property == name)?;
+            indices[property] = self.properties[property]
+                .1
+                .iter()
+                .position(|valid| valid == value)?;
+        }
+
+        let mut offset = 0;
+        for ((_, values), index) in self.properties.iter().zip(indices) {
+            offset = offset * values.len() + index;
+        }
+        Some(self.base_state + offset as StateID)
+    }
+
+    /// Splits a state of this block into the index of each property's value. Properties are
+    /// ordered by name, and the last one changes the fastest as the state ID increases.
+    fn value_indices(&self, state: StateID) -> Option<Vec<usize>> {
+        let state_count = self
+            .properties
+            .iter()
+            .map(|(_, values)| values.len())
+            .product::<usize>();
+        let mut offset = state.checked_sub(self.base_state)? as usize;
+        if offset >= state_count {
+            return None;
+        }
+
+        let mut indices = vec![0; self.properties.len()];
+        for (index, (_, values)) in indices.iter_mut().zip(&self.properties).rev() {
+            *index = offset % values.len();
+            offset /= values.len();
+        }
+
+        Some(indices)
+    }
+}
+
 impl Display for Block {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         Display::fmt(&self.name, f)
@@ -35,3 +95,96 @@ impl Debug for Block {
         Display::fmt(self, f)
     }
 }
+
+#[test]
+fn state_properties_from_id() {
+    let block = Block {
+        name: UnlocalizedName::minecraft("lever"),
+        properties: [
+            ("face", vec!["floor", "wall", "ceiling"]),
+            ("facing", vec!["north", "south", "west", "east"]),
+            ("powered", vec!["true", "false"]),
+        ]
+        .into_iter()
+        .map(|(name, values)| {
+            (
+                name.to_owned(),
+                values.into_iter().map(str::to_owned).collect(),
+            )
+        })
+        .collect(),
+        base_state: 100,
+        default_state: 109,
+        behavior: BlockBehaviorSMT::new(),
+    };
+
+    assert_eq!(block.state_properties(100).unwrap(), [
+        ("face", "floor"),
+        ("facing", "north"),
+        ("powered", "true")
+    ]);
+    assert_eq!(block.state_properties(111).unwrap(), [
+        ("face", "wall"),
+        ("facing", "south"),
+        ("powered", "false")
+    ]);
+    assert!(block.state_properties(99).is_none());
+    assert!(block.state_properties(124).is_none());
+}
+
+#[test]
+fn state_with_properties() {
+    let block = Block {
+        name: UnlocalizedName::minecraft("oak_stairs"),
+        properties: [
+            ("facing", vec!["north", "south", "west", "east"]),
+            ("half", vec!["top", "bottom"]),
+            ("shape", vec![
+                "straight",
+                "inner_left",
+                "inner_right",
+                "outer_left",
+                "outer_right",
+            ]),
+            ("waterlogged", vec!["true", "false"]),
+        ]
+        .into_iter()
+        .map(|(name, values)| {
+            (
+                name.to_owned(),
+                values.into_iter().map(str::to_owned).collect(),
+            )
+        })
+        .collect(),
+        base_state: 2010,
+        default_state: 2021,
+        behavior: BlockBehaviorSMT::new(),
+    };
+
+    assert_eq!(block.state_with(&[]), Some(2021));
+    assert_eq!(
+        block.state_with(&[
+            ("facing", "east"),
+            ("half", "bottom"),
+            ("shape", "outer_right")
+        ]),
+        Some(2089)
+    );
+    // Waterlogged keeps its default of false
+    let state = block
+        .state_with(&[("facing", "west"), ("shape", "inner_left")])
+        .unwrap();
+    assert_eq!(block.state_properties(state).unwrap(), [
+        ("facing", "west"),
+        ("half", "bottom"),
+        ("shape", "inner_left"),
+        ("waterlogged", "false")
+    ]);
+    assert_eq!(
+        block.state_with(&block.state_properties(state).unwrap()),
+        Some(state)
+    );
+
+    assert_eq!(block.state_with(&[("facing", "up")]), None);
+    assert_eq!(block.state_with(&[("powered", "true")]), None);
+}
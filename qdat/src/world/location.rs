@@ -192,6 +192,41 @@ impl From<Coordinate> for CoordinatePair {
     }
 }
 
+/// The position of a 16x16x16 section of a chunk, in section coordinates.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct SectionPosition {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl SectionPosition {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        SectionPosition { x, y, z }
+    }
+
+    /// Returns the position of the section containing the given block.
+    pub const fn of_block(pos: BlockPosition) -> Self {
+        SectionPosition {
+            x: pos.x >> 4,
+            y: (pos.y >> 4) as i32,
+            z: pos.z >> 4,
+        }
+    }
+
+    /// Packs this position into a long, with 22 bits for x and z and 20 bits for y.
+    pub const fn as_i64(&self) -> i64 {
+        ((self.x as i64 & 0x3FFFFF) << 42)
+            | ((self.z as i64 & 0x3FFFFF) << 20)
+            | (self.y as i64 & 0xFFFFF)
+    }
+
+    /// Packs the position of the given block within this section into 12 bits as x, z, then y.
+    pub const fn pack_local(pos: BlockPosition) -> i16 {
+        ((pos.x & 0xF) << 8 | (pos.z & 0xF) << 4) as i16 | (pos.y & 0xF)
+    }
+}
+
 // We allow this because we make sure that hash and partialeq are both satisfied
 #[allow(clippy::derived_hash_with_manual_eq)]
 impl Hash for CoordinatePair {
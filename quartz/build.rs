@@ -150,7 +150,7 @@ fn gen_handle_packet(states: &[StatePacketInfo], mappings: &Mappings) -> TokenSt
                 let buffer = &mut conn.read_buffer;
 
                 let id;
-                if conn.connection_state == ConnectionState::Handshake && buffer.peek_one()? == quartz_net::LEGACY_PING_PACKET_ID as u8 {
+                if conn.connection_state == ConnectionState::Handshake && quartz_net::is_legacy_ping(&buffer[buffer.cursor() ..]) {
                     id = quartz_net::LEGACY_PING_PACKET_ID;
                     buffer.read_one()?;
                 } else {
@@ -207,7 +207,7 @@ fn gen_packet_dispatch(packet: &Packet, mappings: &Mappings) -> TokenStream {
     let field_names = packet
         .fields
         .iter()
-        .filter(|field| !field.unused)
+        .filter(|field| field.dispatched())
         .map(|field| format_ident!("{}", field.name));
     let field_derefs = packet.field_derefs(mappings);
     let handler_name = format_ident!("handle_{}", packet.name.to_ascii_lowercase());
@@ -315,6 +315,7 @@ impl Packet {
                         ty,
                         len,
                         condition,
+                        None,
                         is_option,
                         varying,
                         field.var_type.starts_with("u8"),
@@ -328,6 +329,7 @@ impl Packet {
                         name,
                         ty,
                         condition,
+                        None,
                         is_option,
                         varying,
                         field.ser_as_nbt,
@@ -344,7 +346,7 @@ impl Packet {
     ) -> impl Iterator<Item = TokenStream> + 'a {
         self.fields
             .iter()
-            .filter(|field| !field.unused)
+            .filter(|field| field.dispatched())
             .map(move |field| {
                 let field_name = format_ident!("{}", field.name);
                 if (field.array || !mappings.primitives.contains(&field.var_type))
@@ -363,7 +365,7 @@ impl Packet {
     ) -> impl Iterator<Item = TokenStream> + 'a {
         self.fields
             .iter()
-            .filter(|field| !field.unused)
+            .filter(|field| field.dispatched())
             .map(move |field| {
                 let field_name = format_ident!("{}", field.name);
                 // This performs a copy, so ignore the pass raw condition
@@ -413,6 +415,12 @@ impl Field {
             .expect("Invalid type or type mapping encountered in JSON")
         })
     }
+
+    /// Whether the field is passed to the packet's handler. Fields unused by the protocol are
+    /// still passed if they are referenced by the server.
+    pub fn dispatched(&self) -> bool {
+        !self.unused || self.referenced
+    }
 }
 
 #[derive(Deserialize)]
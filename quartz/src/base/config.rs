@@ -1,3 +1,7 @@
+use crate::{
+    network::{ProxyForwarding, ResourcePack, MAX_PACKET_LEN, MAX_UNCOMPRESSED_PACKET_LEN},
+    world::chunk::region::ChunkCompression,
+};
 use log::*;
 use qdat::Gamemode;
 use quartz_chat::Component;
@@ -5,18 +9,25 @@ use serde::{Deserialize, Serialize};
 use std::{
     fs::{File, OpenOptions},
     io::{self, prelude::*, Read, SeekFrom, Write},
+    net::IpAddr,
     path::Path,
 };
 
-/// The main server configuration.
+/// The range of view distances accepted by the client.
+pub const VIEW_DISTANCE_RANGE: (u8, u8) = (2, 32);
+
+/// The main server configuration. Any keys missing from the config file take their default value.
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
-    /// The maximum number of players the server will allow, defaults to 50.
+    /// The maximum number of players the server will allow, defaults to 20.
     pub max_players: u16,
     /// The server IP, defaults to "0.0.0.0"
     pub server_ip: String,
     /// The server port, defaults to 25565.
     pub port: u16,
+    /// The view distance in chunks sent to clients, defaults to 10.
+    pub view_distance: u8,
     /// The server's message of the day, written using CFMT format (see `chat::cfmt::parse_cfmt`).
     pub motd: Component,
     /// Whether to run the server in online or offline mode
@@ -24,24 +35,130 @@ pub struct Config {
     pub online_mode: bool,
     /// The default gamemode for a player who joins the server
     pub default_gamemode: Gamemode,
+    /// The maximum length in bytes of a packet sent by a client, defaults to and may not exceed
+    /// 2097151.
+    pub max_packet_size: usize,
+    /// The maximum length in bytes of a compressed packet's data once it is decompressed, defaults
+    /// to and may not exceed 8388608.
+    pub max_uncompressed_packet_size: usize,
+    /// The size in bytes at which packets begin to be compressed, defaults to 256. A negative
+    /// threshold disables compression.
+    pub compression_threshold: i32,
+    /// The number of packets a client may send each second before being disconnected, defaults to
+    /// 500. A limit of zero disables rate limiting.
+    pub packet_rate_limit: u32,
+    /// How a proxy in front of the server forwards player identities, defaults to `none`. When
+    /// forwarding is enabled, players are not authenticated by the server.
+    pub proxy_forwarding: ProxyForwarding,
+    /// The resource pack sent to players when they join, defaults to none. If the pack is
+    /// required, players who decline it are disconnected.
+    pub resource_pack: Option<ResourcePack>,
+    /// The compression used for chunks written to region files, one of `gzip`, `zlib`,
+    /// `uncompressed`, or `lz4` if enabled, defaults to `zlib`.
+    pub chunk_compression: ChunkCompression,
 }
 
 // Instantiate a config with default values
 impl Default for Config {
     fn default() -> Self {
         Config {
-            max_players: 50,
+            max_players: 20,
             server_ip: "0.0.0.0".to_owned(),
             port: 25565,
+            view_distance: 10,
             motd: Component::text("A Minecraft Server".to_owned()),
             online_mode: true,
             default_gamemode: Gamemode::Survival,
+            max_packet_size: MAX_PACKET_LEN,
+            max_uncompressed_packet_size: MAX_UNCOMPRESSED_PACKET_LEN,
+            compression_threshold: 256,
+            packet_rate_limit: 500,
+            proxy_forwarding: ProxyForwarding::None,
+            resource_pack: None,
+            chunk_compression: ChunkCompression::Zlib,
+        }
+    }
+}
+
+impl Config {
+    /// Writes this config to the given path in JSON format, overwriting any existing file.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        file.write_all(json.as_bytes())
+    }
+
+    /// Checks the values in this config, resetting or clamping any invalid values to valid ones. A
+    /// warning describing each change is returned.
+    pub fn validate(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let default = Config::default();
+
+        if self.port == 0 {
+            warnings.push(format!(
+                "Port 0 is not a valid server port, using {} instead",
+                default.port
+            ));
+            self.port = default.port;
+        }
+
+        let (min_vd, max_vd) = VIEW_DISTANCE_RANGE;
+        if !(min_vd ..= max_vd).contains(&self.view_distance) {
+            let clamped = self.view_distance.clamp(min_vd, max_vd);
+            warnings.push(format!(
+                "View distance {} is outside of the range {}-{}, clamping to {}",
+                self.view_distance, min_vd, max_vd, clamped
+            ));
+            self.view_distance = clamped;
         }
+
+        if self.max_packet_size == 0 || self.max_packet_size > MAX_PACKET_LEN {
+            warnings.push(format!(
+                "Maximum packet size must be between 1 and {}, using {} instead",
+                MAX_PACKET_LEN, default.max_packet_size
+            ));
+            self.max_packet_size = default.max_packet_size;
+        }
+
+        if self.max_uncompressed_packet_size == 0
+            || self.max_uncompressed_packet_size > MAX_UNCOMPRESSED_PACKET_LEN
+        {
+            warnings.push(format!(
+                "Maximum uncompressed packet size must be between 1 and {}, using {} instead",
+                MAX_UNCOMPRESSED_PACKET_LEN, default.max_uncompressed_packet_size
+            ));
+            self.max_uncompressed_packet_size = default.max_uncompressed_packet_size;
+        }
+
+        if self.server_ip.parse::<IpAddr>().is_err() {
+            warnings.push(format!(
+                "Invalid server IP \"{}\", using {} instead",
+                self.server_ip, default.server_ip
+            ));
+            self.server_ip = default.server_ip;
+        }
+
+        if let Some(pack) = &mut self.resource_pack {
+            if !pack.has_valid_hash() {
+                warnings.push(format!(
+                    "Invalid resource pack SHA-1 hash \"{}\", the pack will not be cached by \
+                     clients",
+                    pack.sha1
+                ));
+                pack.sha1.clear();
+            }
+        }
+
+        warnings
     }
 }
 
 /// Attempts to parse the server configuration at the given path. The config should be in JSON format.
-pub fn load_config(path: &Path) -> io::Result<Config> {
+/// Once loaded, the config is validated and written back to the file so that any missing keys are
+/// filled in with their default values. Any warnings produced by validation are logged and returned
+/// alongside the config.
+pub fn load_config(path: &Path) -> io::Result<(Config, Vec<String>)> {
     let std_path = Path::new(path);
 
     if std_path.exists() {
@@ -53,18 +170,27 @@ pub fn load_config(path: &Path) -> io::Result<Config> {
         file.read_to_string(&mut json)?;
 
         // Parse the json
-        let config: Config = match serde_json::from_str(&json) {
+        let mut config: Config = match serde_json::from_str(&json) {
             Ok(cfg) => cfg,
             Err(e) => {
                 error!("Invalid config JSON: {}", e);
-                return use_default(&mut file);
+                return use_default(&mut file).map(|config| (config, Vec::new()));
             }
         };
+        drop(file);
 
-        Ok(config)
+        let warnings = config.validate();
+        for warning in &warnings {
+            warn!("{}", warning);
+        }
+
+        // Write back the config so new keys are added to old configs
+        config.save(std_path)?;
+
+        Ok((config, warnings))
     } else {
         info!("Config file not found, creating file");
-        use_default(&mut File::create(std_path)?)
+        use_default(&mut File::create(std_path)?).map(|config| (config, Vec::new()))
     }
 }
 
@@ -86,3 +212,102 @@ fn use_default(file: &mut File) -> io::Result<Config> {
 
     Ok(default)
 }
+
+#[test]
+fn empty_config_uses_defaults() {
+    let config: Config = serde_json::from_str("{}").unwrap();
+    let default = Config::default();
+
+    assert_eq!(config.max_players, default.max_players);
+    assert_eq!(config.server_ip, default.server_ip);
+    assert_eq!(config.port, default.port);
+    assert_eq!(config.view_distance, 10);
+    assert_eq!(config.online_mode, default.online_mode);
+    assert!(matches!(config.default_gamemode, Gamemode::Survival));
+}
+
+#[test]
+fn view_distance_is_clamped() {
+    let mut config: Config = serde_json::from_str(r#"{"view_distance": 100}"#).unwrap();
+    let warnings = config.validate();
+
+    assert_eq!(config.view_distance, 32);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("View distance"));
+}
+
+#[test]
+fn saved_config_is_complete() {
+    let path = std::env::temp_dir().join("quartz_saved_config_is_complete.json");
+    let config: Config = serde_json::from_str(r#"{"port": 25570}"#).unwrap();
+    config.save(&path).unwrap();
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    for key in [
+        "max_players",
+        "server_ip",
+        "port",
+        "view_distance",
+        "motd",
+        "online_mode",
+        "default_gamemode",
+        "max_packet_size",
+        "max_uncompressed_packet_size",
+        "compression_threshold",
+        "packet_rate_limit",
+        "proxy_forwarding",
+        "resource_pack",
+        "chunk_compression",
+    ] {
+        assert!(json.get(key).is_some(), "Missing key {key}");
+    }
+    assert_eq!(json["port"], 25570);
+    assert_eq!(json["chunk_compression"], "zlib");
+}
+
+#[test]
+fn load_config_from_file() {
+    let path = std::env::temp_dir().join(format!(
+        "quartz_load_config_from_file_{}.json",
+        std::process::id()
+    ));
+    std::fs::write(
+        &path,
+        r#"{
+            "port": 25570,
+            "view_distance": 40,
+            "proxy_forwarding": "bungee_cord",
+            "resource_pack": {
+                "url": "https://example.com/pack.zip",
+                "sha1": "not a hash",
+                "required": true
+            },
+            "chunk_compression": "gzip"
+        }"#,
+    )
+    .unwrap();
+
+    let (config, warnings) = load_config(&path).unwrap();
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(config.port, 25570);
+    assert_eq!(config.view_distance, 32);
+    assert_eq!(config.max_players, 20);
+    assert_eq!(config.proxy_forwarding, ProxyForwarding::BungeeCord);
+    assert_eq!(config.chunk_compression, ChunkCompression::GZip);
+    let pack = config.resource_pack.as_ref().unwrap();
+    assert!(pack.required);
+    assert!(pack.sha1.is_empty());
+    assert_eq!(warnings.len(), 2);
+
+    // Missing keys and corrected values are written back to the file
+    assert_eq!(saved["max_players"], 20);
+    assert_eq!(saved["view_distance"], 32);
+    assert_eq!(saved["packet_rate_limit"], 500);
+    assert_eq!(saved["resource_pack"]["sha1"], "");
+}
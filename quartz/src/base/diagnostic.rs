@@ -1,11 +1,21 @@
+use log::warn;
+use std::{collections::VecDeque, time::Duration};
+
+/// The number of ticks over which phase timings are averaged.
+const PROFILE_WINDOW: usize = 100;
+/// The time a single tick may take while keeping the server at 20 TPS.
+pub const TICK_BUDGET: Duration = Duration::from_millis(50);
+
 pub struct Diagnostics {
     pub(crate) microseconds_per_tick: f64,
+    pub(crate) tick_report: TickReport,
 }
 
 impl Diagnostics {
     pub const fn new() -> Self {
         Diagnostics {
             microseconds_per_tick: 0.0,
+            tick_report: TickReport::new(),
         }
     }
 
@@ -15,4 +25,193 @@ impl Diagnostics {
     pub fn mspt(&self) -> f64 {
         self.microseconds_per_tick / 1000.0
     }
+
+    /// Returns the average and worst time spent in each phase of the last 100 ticks.
+    #[inline]
+    pub fn tick_report(&self) -> TickReport {
+        self.tick_report
+    }
+}
+
+/// The parts of a server tick which are timed separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TickPhase {
+    /// Handling packets from clients and keeping connections alive.
+    Network,
+    /// Ticking each world.
+    World,
+    /// Ticking entities. The server doesn't tick entities yet, so this phase is always zero.
+    Entities,
+    /// Running scheduled tasks. The server doesn't run a scheduler yet, so this phase is always
+    /// zero.
+    Scheduler,
+    /// Storing chunks which have finished loading.
+    Chunks,
+}
+
+impl TickPhase {
+    pub const ALL: [TickPhase; 5] = [
+        TickPhase::Network,
+        TickPhase::World,
+        TickPhase::Entities,
+        TickPhase::Scheduler,
+        TickPhase::Chunks,
+    ];
+}
+
+/// The time spent in each phase of a tick.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PhaseTimings([Duration; TickPhase::ALL.len()]);
+
+impl PhaseTimings {
+    pub const fn new() -> Self {
+        PhaseTimings([Duration::ZERO; TickPhase::ALL.len()])
+    }
+
+    #[inline]
+    pub fn get(&self, phase: TickPhase) -> Duration {
+        self.0[phase as usize]
+    }
+
+    /// Returns the time spent in every phase combined.
+    pub fn total(&self) -> Duration {
+        self.0.iter().sum()
+    }
+
+    /// Returns whether or not these phases took longer than a tick is allowed to take.
+    pub fn is_over_budget(&self) -> bool {
+        self.total() > TICK_BUDGET
+    }
+}
+
+/// A summary of the phase timings of recent ticks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TickReport {
+    /// The average time spent in each phase.
+    pub average: PhaseTimings,
+    /// The phase timings of the tick which took the longest overall.
+    pub worst: PhaseTimings,
+}
+
+impl TickReport {
+    pub const fn new() -> Self {
+        TickReport {
+            average: PhaseTimings::new(),
+            worst: PhaseTimings::new(),
+        }
+    }
+}
+
+/// Records the time spent in each phase of the most recent ticks.
+pub struct TickProfiler {
+    current: PhaseTimings,
+    history: VecDeque<PhaseTimings>,
+}
+
+impl TickProfiler {
+    pub fn new() -> Self {
+        TickProfiler {
+            current: PhaseTimings::new(),
+            history: VecDeque::with_capacity(PROFILE_WINDOW),
+        }
+    }
+
+    /// Adds the given time to the time spent in a phase of the current tick.
+    pub fn record(&mut self, phase: TickPhase, elapsed: Duration) {
+        self.current.0[phase as usize] += elapsed;
+    }
+
+    /// Finishes the current tick and returns its timings, logging a warning if it went over the
+    /// tick budget.
+    pub fn finish_tick(&mut self) -> PhaseTimings {
+        let timings = std::mem::take(&mut self.current);
+
+        if timings.is_over_budget() {
+            warn!(
+                "Tick took {:?}, exceeding the budget of {:?} (network: {:?}, world: {:?}, \
+                 entities: {:?}, scheduler: {:?}, chunks: {:?})",
+                timings.total(),
+                TICK_BUDGET,
+                timings.get(TickPhase::Network),
+                timings.get(TickPhase::World),
+                timings.get(TickPhase::Entities),
+                timings.get(TickPhase::Scheduler),
+                timings.get(TickPhase::Chunks)
+            );
+        }
+
+        if self.history.len() == PROFILE_WINDOW {
+            self.history.pop_front();
+        }
+        self.history.push_back(timings);
+
+        timings
+    }
+
+    /// Summarizes the timings of the recorded ticks.
+    pub fn report(&self) -> TickReport {
+        if self.history.is_empty() {
+            return TickReport::new();
+        }
+
+        let mut average = PhaseTimings::new();
+        for phase in TickPhase::ALL {
+            let total: Duration = self.history.iter().map(|timings| timings.get(phase)).sum();
+            average.0[phase as usize] = total / self.history.len() as u32;
+        }
+
+        // Unwrap is safe since we checked that the history is not empty
+        let worst = *self
+            .history
+            .iter()
+            .max_by_key(|timings| timings.total())
+            .unwrap();
+
+        TickReport { average, worst }
+    }
+}
+
+impl Default for TickProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn profiler_averages_phases() {
+    let mut profiler = TickProfiler::new();
+
+    profiler.record(TickPhase::Network, Duration::from_millis(2));
+    profiler.record(TickPhase::World, Duration::from_millis(10));
+    assert!(!profiler.finish_tick().is_over_budget());
+
+    profiler.record(TickPhase::Network, Duration::from_millis(4));
+    profiler.record(TickPhase::World, Duration::from_millis(30));
+    profiler.record(TickPhase::Entities, Duration::from_millis(12));
+    profiler.record(TickPhase::Scheduler, Duration::from_millis(8));
+    profiler.record(TickPhase::Chunks, Duration::from_millis(6));
+    assert!(profiler.finish_tick().is_over_budget());
+
+    let report = profiler.report();
+    assert_eq!(
+        report.average.get(TickPhase::Network),
+        Duration::from_millis(3)
+    );
+    assert_eq!(
+        report.average.get(TickPhase::World),
+        Duration::from_millis(20)
+    );
+    assert_eq!(
+        report.average.get(TickPhase::Entities),
+        Duration::from_millis(6)
+    );
+    assert_eq!(
+        report.average.get(TickPhase::Scheduler),
+        Duration::from_millis(4)
+    );
+    assert_eq!(
+        report.average.get(TickPhase::Chunks),
+        Duration::from_millis(3)
+    );
+    assert_eq!(report.worst.total(), Duration::from_millis(60));
 }
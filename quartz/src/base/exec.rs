@@ -32,6 +32,12 @@ pub fn config() -> &'static RwLock<Config> {
     CONFIG.get().expect("Config not initialized yet")
 }
 
+/// Sets the config to its defaults if it hasn't been set yet, for tests which don't run the server.
+#[cfg(test)]
+pub(crate) fn init_test_config() {
+    let _ = CONFIG.set(RwLock::new(Config::default()));
+}
+
 pub fn raw_console() -> &'static Interface<DefaultTerminal> {
     &**RAW_CONSOLE.get().expect("Raw console not initialized")
 }
@@ -89,6 +95,7 @@ pub fn run(config: Config, raw_console: Arc<Interface<DefaultTerminal>>) {
         while RUNNING.load(Ordering::Acquire) {
             if let Some(mut guard) = DIAGNOSTICS.try_lock() {
                 guard.microseconds_per_tick = clock.micros_ema;
+                guard.tick_report = server.profiler.report();
             }
 
             clock.start();
@@ -10,7 +10,7 @@ use quartz_datapack::{
         density_function::DensityFunctionProvider,
         dimension_type::DimensionType,
         features::{Feature, PlacedFeature},
-        functions::Function,
+        functions::{Function, LifecycleFunctions},
         item_modifiers::ItemModifier,
         jigsaw_pool::JigsawPool,
         loot_tables::LootTable,
@@ -23,7 +23,7 @@ use quartz_datapack::{
         structure_features::StructureFeatures,
         structure_set::StructureSet,
         surface_builders::SurfaceBuilder,
-        tags::Tag,
+        tags::{Tag, TagRegistry},
     },
     DataPack,
     VersionFilter,
@@ -63,6 +63,8 @@ pub struct Registries {
     pub structure_sets: Registry<StructureSet>,
     pub structures: Registry<Structure>,
     pub placed_features: Registry<PlacedFeature>,
+    /// The functions run by the `#minecraft:load` and `#minecraft:tick` function tags
+    pub lifecycle_functions: LifecycleFunctions,
 }
 
 macro_rules! registry_datapack_load {
@@ -124,9 +126,12 @@ impl Registries {
     /// Loads the datapacks provided into a new registry collection
     fn from_datapacks(datapacks: Vec<DataPack>) -> Registries {
         let mut registries: Registries = Default::default();
+        let mut function_tags = TagRegistry::new();
 
         for pack in datapacks {
             for namespace in pack.namespaces {
+                function_tags.load_namespace(&namespace, "functions");
+
                 registries
                     .tags
                     .insert_all(namespace.tags.into_iter().map(|t| {
@@ -163,6 +168,7 @@ impl Registries {
             }
         }
 
+        registries.lifecycle_functions = LifecycleFunctions::from_tags(&function_tags);
         registries
     }
 
@@ -2,11 +2,12 @@ use crate::{
     command::{CommandContext, CommandModule, CommandSender},
     command_executor,
     config,
+    diagnostic::{TickPhase, TickProfiler},
     display_to_console,
     item::init_items,
     network::*,
     raw_console,
-    world::world::WorldStore,
+    world::{game_rules::GameRules, world::WorldStore},
     Registry,
     RUNNING,
 };
@@ -19,7 +20,6 @@ use linefeed::{
 };
 use log::*;
 use openssl::rsa::Rsa;
-use parking_lot::Mutex;
 use quartz_chat::{
     color::Color,
     component::{ClickEvent, ComponentType, HoverEntity, HoverEvent},
@@ -31,17 +31,21 @@ use rand::{thread_rng, Rng};
 use std::{
     collections::HashMap,
     error::Error,
-    net::TcpStream as StdTcpStream,
+    net::{IpAddr, TcpStream as StdTcpStream},
     process::abort,
-    sync::{
-        atomic::Ordering,
-        mpsc::{self, Receiver, Sender},
-        Arc,
-    },
+    sync::{atomic::Ordering, Arc},
     thread::{self, JoinHandle},
     time::{Duration, Instant},
 };
-use tokio::{net::TcpListener, runtime::Runtime, task};
+use tokio::{
+    net::TcpListener,
+    runtime::Runtime,
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        oneshot,
+    },
+    task,
+};
 use uuid::Uuid;
 
 /// The string form of the minecraft version quartz currently supports.
@@ -57,10 +61,14 @@ pub struct QuartzServer {
     console_command_handler: Option<JoinHandle<()>>,
     ///The World manager
     pub world_store: WorldStore,
+    /// The game rules shared by every world.
+    pub game_rules: GameRules,
     /// A cloneable channel to send packets to the main server thread.
-    sync_packet_sender: Sender<WrappedServerBoundPacket>,
+    sync_packet_sender: UnboundedSender<WrappedServerBoundPacket>,
     /// The receiver for packets that need to be handled on the server thread.
-    sync_packet_receiver: Receiver<WrappedServerBoundPacket>,
+    sync_packet_receiver: UnboundedReceiver<WrappedServerBoundPacket>,
+    /// Times each phase of the server tick.
+    pub(crate) profiler: TickProfiler,
 }
 
 impl QuartzServer {
@@ -72,7 +80,7 @@ impl QuartzServer {
             panic!("Attempted to create a server instance after one was already created.");
         }
 
-        let (sender, receiver) = mpsc::channel::<WrappedServerBoundPacket>();
+        let (sender, receiver) = mpsc::unbounded_channel::<WrappedServerBoundPacket>();
         let world_store =
             WorldStore::new(Arc::clone(&rt), "./world").expect("Error making world store");
 
@@ -83,6 +91,8 @@ impl QuartzServer {
             sync_packet_receiver: receiver,
             console_command_handler: None,
             world_store,
+            game_rules: GameRules::new(),
+            profiler: TickProfiler::new(),
         }
     }
 
@@ -120,7 +130,7 @@ impl QuartzServer {
     fn init_command_handler(&mut self) {
         // A simple tab-completer for console
         struct ConsoleCompleter {
-            packet_pipe: Mutex<Sender<WrappedServerBoundPacket>>,
+            packet_pipe: UnboundedSender<WrappedServerBoundPacket>,
         }
 
         impl Completer<DefaultTerminal> for ConsoleCompleter {
@@ -131,21 +141,19 @@ impl QuartzServer {
                 _start: usize,
                 _end: usize,
             ) -> Option<Vec<Completion>> {
-                // Retrieve the packet pipe
-                let pipe = self.packet_pipe.lock();
-
-                // Build pipes to transfer the completions
-                let (sender, receiver) = mpsc::channel::<Vec<String>>();
+                // Build a pipe to transfer the completions
+                let (sender, receiver) = oneshot::channel::<Vec<String>>();
 
                 // Send the completion request
-                pipe.send(WrappedServerBoundPacket::ConsoleCompletion {
-                    command: prompter.buffer()[.. prompter.cursor()].to_owned(),
-                    response: sender,
-                })
-                .ok()?;
-
-                // Get the completion response
-                receiver.recv().ok().map(|completions| {
+                self.packet_pipe
+                    .send(WrappedServerBoundPacket::ConsoleCompletion {
+                        command: prompter.buffer()[.. prompter.cursor()].to_owned(),
+                        response: sender,
+                    })
+                    .ok()?;
+
+                // Get the completion response, blocking is fine since this runs on the console thread
+                receiver.blocking_recv().ok().map(|completions| {
                     completions
                         .into_iter()
                         .map(|completion| Completion {
@@ -164,7 +172,7 @@ impl QuartzServer {
 
         // Set the console completer
         raw_console().set_completer(Arc::new(ConsoleCompleter {
-            packet_pipe: Mutex::new(self.sync_packet_sender.clone()),
+            packet_pipe: self.sync_packet_sender.clone(),
         }));
 
         // Drive the command reader
@@ -228,7 +236,7 @@ impl QuartzServer {
 
     async fn tcp_server(
         listener: TcpListener,
-        sync_packet_sender: Sender<WrappedServerBoundPacket>,
+        sync_packet_sender: UnboundedSender<WrappedServerBoundPacket>,
     ) {
         let mut next_connection_id: usize = 0;
 
@@ -240,7 +248,7 @@ impl QuartzServer {
         loop {
             match listener.accept().await {
                 // Successful connection
-                Ok((socket, _addr)) => {
+                Ok((socket, addr)) => {
                     // Don't bother handling the connection if the server is shutting down
                     if !RUNNING.load(Ordering::Acquire) {
                         return;
@@ -251,6 +259,7 @@ impl QuartzServer {
                     // Construct a connection wrapper around the socket
                     let (conn, driver) = AsyncClientConnection::new(
                         next_connection_id,
+                        addr.ip(),
                         socket,
                         sync_packet_sender.clone(),
                     );
@@ -268,7 +277,7 @@ impl QuartzServer {
 
                     task::spawn(driver);
 
-                    // Spawn a thread to handle the connection asynchronously
+                    // Spawn a task on the runtime to handle the connection
                     let key_pair_clone = key_pair.clone();
                     task::spawn(async move { handle_async_connection(conn, key_pair_clone).await });
 
@@ -282,9 +291,22 @@ impl QuartzServer {
     }
 
     pub(crate) async fn tick(&mut self) {
+        let start = Instant::now();
         self.handle_packets().await;
         self.client_list.update_keep_alive();
+        let network_end = Instant::now();
+        self.profiler
+            .record(TickPhase::Network, network_end - start);
+
+        self.world_store.tick(&self.client_list, &self.game_rules);
+        let world_end = Instant::now();
+        self.profiler
+            .record(TickPhase::World, world_end - network_end);
+
         self.world_store.flush_ready().await;
+        self.profiler.record(TickPhase::Chunks, world_end.elapsed());
+
+        self.profiler.finish_tick();
     }
 
     async fn handle_packets(&mut self) {
@@ -292,8 +314,15 @@ impl QuartzServer {
             match wrapped_packet {
                 WrappedServerBoundPacket::External { sender, ref packet } =>
                     dispatch_sync_packet(sender, packet, self).await,
-                WrappedServerBoundPacket::LoginSuccess { id, uuid, username } =>
-                    self.handle_login_success_server(id, uuid, &username).await,
+                WrappedServerBoundPacket::LoginSuccess {
+                    id,
+                    uuid,
+                    username,
+                    address,
+                    properties,
+                } =>
+                    self.handle_login_success_server(id, uuid, &username, address, properties)
+                        .await,
                 WrappedServerBoundPacket::ClientConnected { id, write_handle } =>
                     self.client_list.add_client(id, write_handle),
                 WrappedServerBoundPacket::ClientDisconnected { id } => {
@@ -328,6 +357,44 @@ impl QuartzServer {
     }
 }
 
+#[cfg(test)]
+impl QuartzServer {
+    /// Creates a server which isn't started, with its worlds in a new temporary directory, for
+    /// tests which run commands or handle packets.
+    pub(crate) fn for_test() -> Self {
+        use std::sync::atomic::AtomicUsize;
+
+        static NEXT_WORLD: AtomicUsize = AtomicUsize::new(0);
+
+        crate::init_test_config();
+        let rt = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to build test runtime"),
+        );
+        let world_path = std::env::temp_dir().join(format!(
+            "quartz_test_world_{}_{}",
+            std::process::id(),
+            NEXT_WORLD.fetch_add(1, Ordering::Relaxed)
+        ));
+        let (sender, receiver) = mpsc::unbounded_channel::<WrappedServerBoundPacket>();
+        let world_store =
+            WorldStore::new(Arc::clone(&rt), world_path).expect("Error making world store");
+
+        QuartzServer {
+            rt,
+            client_list: ClientList::new(),
+            sync_packet_sender: sender,
+            sync_packet_receiver: receiver,
+            console_command_handler: None,
+            world_store,
+            game_rules: GameRules::new(),
+            profiler: TickProfiler::new(),
+        }
+    }
+}
+
 impl Drop for QuartzServer {
     fn drop(&mut self) {
         // In case this is reached due to a panic
@@ -471,6 +538,19 @@ impl ClientList {
         Some(())
     }
 
+    /// Sets the address and profile properties of the client once it has logged in.
+    pub fn set_profile(
+        &mut self,
+        client_id: ClientId,
+        address: IpAddr,
+        properties: Vec<ProfileProperty>,
+    ) -> Option<()> {
+        let client = self.0.get_mut(&client_id)?;
+        client.address = Some(address);
+        client.properties = properties;
+        Some(())
+    }
+
     pub fn username(&self, client_id: ClientId) -> Option<&str> {
         Some(self.0.get(&client_id)?.username())
     }
@@ -497,6 +577,34 @@ impl ClientList {
         self.0.get(&client_id)?.send_message(message, None, true);
         Some(())
     }
+
+    /// Records the client's response to the resource pack it was sent.
+    pub fn set_resource_pack_status(
+        &mut self,
+        client_id: ClientId,
+        status: ResourcePackStatus,
+    ) -> Option<()> {
+        self.0.get_mut(&client_id)?.resource_pack_status = Some(status);
+        Some(())
+    }
+
+    /// Closes the connection of the client with the given ID without giving a reason, once any
+    /// packets already sent to it have been written.
+    pub fn shutdown(&self, client_id: ClientId) {
+        match self.0.get(&client_id) {
+            Some(client) => client.connection.shutdown(),
+            None => warn!("Attempted to shut down a disconnected client."),
+        }
+    }
+
+    /// Disconnects the player with the given ID, showing them the given reason, and removes them
+    /// from the list.
+    pub fn disconnect(&mut self, client_id: ClientId, reason: Component) {
+        match self.0.remove(&client_id) {
+            Some(client) => client.connection.disconnect(ConnectionState::Play, reason),
+            None => warn!("Attempted to disconnect a disconnected client."),
+        }
+    }
 }
 
 pub struct ClientListIter<'a>(std::collections::hash_map::Iter<'a, ClientId, Client>);
@@ -524,6 +632,12 @@ pub struct Client {
     username: String,
     // The minecraft uuid of the client
     uuid: Uuid,
+    /// The client's IP address, known once it logs in.
+    address: Option<IpAddr>,
+    /// The properties of the client's profile, such as their skin.
+    properties: Vec<ProfileProperty>,
+    /// The client's latest response to the server's resource pack, if one was sent.
+    pub resource_pack_status: Option<ResourcePackStatus>,
 }
 
 impl Client {
@@ -535,6 +649,9 @@ impl Client {
             last_keep_alive_exchange: Instant::now(),
             username: Default::default(),
             uuid: Uuid::default(),
+            address: None,
+            properties: Vec::new(),
+            resource_pack_status: None,
         }
     }
 
@@ -572,6 +689,14 @@ impl Client {
         &self.uuid
     }
 
+    pub fn address(&self) -> Option<IpAddr> {
+        self.address
+    }
+
+    pub fn properties(&self) -> &[ProfileProperty] {
+        &self.properties
+    }
+
     fn send_message(&self, message: &str, user_info: Option<(Uuid, &str)>, sys_error: bool) {
         match user_info {
             Some((uuid, username)) => self.connection.send_packet(ClientBoundPacket::ChatMessage {
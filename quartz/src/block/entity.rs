@@ -1,6 +1,7 @@
 use crate::block::entities::FurnaceBlockEntity;
 use enum_dispatch::enum_dispatch;
-use quartz_nbt::NbtCompound;
+use qdat::world::location::BlockPosition;
+use quartz_nbt::{NbtCompound, NbtReprError};
 
 // All block entities must impl this
 /// Trait for block entities
@@ -19,3 +20,24 @@ pub trait BlockEntity {
 pub enum StaticBlockEntity {
     FurnaceBlockEntity,
 }
+
+impl StaticBlockEntity {
+    /// Creates the block entity stored in the given compound, returning `None` if its type isn't
+    /// implemented yet
+    pub fn load(pos: BlockPosition, nbt: &NbtCompound) -> Result<Option<Self>, NbtReprError> {
+        let mut entity: StaticBlockEntity = match nbt.get::<_, &str>("id")? {
+            "minecraft:furnace" => FurnaceBlockEntity::new(pos, None).into(),
+            _ => return Ok(None),
+        };
+
+        entity.from_nbt(nbt);
+        Ok(Some(entity))
+    }
+
+    /// Returns the ID this block entity is saved under, the inverse of [`load`](Self::load)
+    pub fn id(&self) -> &'static str {
+        match self {
+            StaticBlockEntity::FurnaceBlockEntity(_) => "minecraft:furnace",
+        }
+    }
+}
@@ -1,8 +1,13 @@
 use qdat::{
     block::{states::BlockStateData, Block, StateID},
     UlnStr,
+    UnlocalizedName,
+};
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    hint::unreachable_unchecked,
 };
-use std::{fmt::Debug, hint::unreachable_unchecked};
 
 use crate::StaticRegistry;
 
@@ -40,6 +45,154 @@ impl BlockStateImpl for StaticBlockState {
     }
 }
 
+impl StaticBlockState {
+    /// Returns the name and value of each of this state's properties, ordered by name.
+    pub fn properties(&self) -> Vec<(String, String)> {
+        self.handle
+            .state_properties(self.id())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    /// Returns this state with one property changed, or `None` if the block doesn't have the
+    /// property or it can't take the given value.
+    pub fn with(&self, name: &str, value: &str) -> Option<&'static StaticBlockState> {
+        let properties = self.handle.state_properties(self.id())?;
+        let mut properties = properties
+            .into_iter()
+            .filter(|&(property, _)| property != name)
+            .collect::<Vec<_>>();
+        properties.push((name, value));
+        StaticRegistry::state_for_id(self.handle.state_with(&properties)?)
+    }
+}
+
+impl Display for StaticBlockState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.handle.name, f)?;
+
+        let properties = self.handle.state_properties(self.id()).unwrap_or_default();
+        if !properties.is_empty() {
+            write!(
+                f,
+                "[{}]",
+                properties
+                    .iter()
+                    .map(|(name, value)| format!("{name}={value}"))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a block state written like `minecraft:oak_stairs[facing=north,half=top]`, where the
+/// namespace and property list are optional. Properties which aren't listed take the value of the
+/// block's default state.
+pub fn parse_block_state(input: &str) -> Result<StateID, ParseBlockStateError> {
+    let (name, properties) = match input.split_once('[') {
+        Some((name, properties)) => match properties.strip_suffix(']') {
+            Some(properties) => (name, Some(properties)),
+            None => return Err(ParseBlockStateError::UnclosedProperties),
+        },
+        None => (input, None),
+    };
+
+    let state = UnlocalizedName::from_str(name)
+        .ok()
+        .and_then(|name| StaticRegistry::default_state(&name))
+        .ok_or_else(|| ParseBlockStateError::UnknownBlock(name.to_owned()))?;
+    let block = state.handle;
+
+    let mut values = Vec::new();
+    for property in properties
+        .into_iter()
+        .flat_map(|properties| properties.split(','))
+        .map(str::trim)
+        .filter(|property| !property.is_empty())
+    {
+        let (name, value) = property
+            .split_once('=')
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .ok_or_else(|| ParseBlockStateError::MissingValue(property.to_owned()))?;
+
+        let valid_values = match block.properties.iter().find(|(valid, _)| valid == name) {
+            Some((_, valid_values)) => valid_values,
+            None =>
+                return Err(ParseBlockStateError::UnknownProperty {
+                    block: block.name.clone(),
+                    property: name.to_owned(),
+                }),
+        };
+        if values.iter().any(|&(given, _)| given == name) {
+            return Err(ParseBlockStateError::DuplicateProperty(name.to_owned()));
+        }
+        if !valid_values.iter().any(|valid| valid == value) {
+            return Err(ParseBlockStateError::InvalidValue {
+                property: name.to_owned(),
+                value: value.to_owned(),
+            });
+        }
+
+        values.push((name, value));
+    }
+
+    // Every property was checked above, so this only fails if the registry is inconsistent
+    block
+        .state_with(&values)
+        .ok_or_else(|| ParseBlockStateError::UnknownBlock(name.to_owned()))
+}
+
+/// Writes the block state with the given ID in the form read by [`parse_block_state`], returning
+/// `None` if there is no such state.
+pub fn state_id_to_string(id: StateID) -> Option<String> {
+    StaticRegistry::state_for_id(id).map(ToString::to_string)
+}
+
+/// An error encountered while parsing a block state with [`parse_block_state`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseBlockStateError {
+    /// The block name was invalid or not a known block.
+    UnknownBlock(String),
+    /// The property list was opened with `[` but not closed.
+    UnclosedProperties,
+    /// A property was listed without `=` and a value.
+    MissingValue(String),
+    /// The block does not have a property with the given name.
+    UnknownProperty {
+        block: UnlocalizedName,
+        property: String,
+    },
+    /// The property was given more than once.
+    DuplicateProperty(String),
+    /// The property cannot take the given value.
+    InvalidValue { property: String, value: String },
+}
+
+impl Display for ParseBlockStateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBlockStateError::UnknownBlock(name) => write!(f, "Unknown block type '{name}'"),
+            ParseBlockStateError::UnclosedProperties =>
+                write!(f, "Expected closing ']' for block state properties"),
+            ParseBlockStateError::MissingValue(property) =>
+                write!(f, "Expected value for property '{property}'"),
+            ParseBlockStateError::UnknownProperty { block, property } =>
+                write!(f, "Block {block} does not have property '{property}'"),
+            ParseBlockStateError::DuplicateProperty(property) =>
+                write!(f, "Property '{property}' can only be set once"),
+            ParseBlockStateError::InvalidValue { property, value } =>
+                write!(f, "Property '{property}' cannot be set to '{value}'"),
+        }
+    }
+}
+
+impl Error for ParseBlockStateError {}
+
 /*#[derive(Clone)]
 pub struct DynamicBlockState {
     pub handle: &'static Block<DynamicStateID>,
@@ -269,3 +422,102 @@ impl DynamicStateBuilder {
         self.state
     }
 }*/
+
+#[test]
+fn state_properties_round_trip() {
+    use crate::Registry;
+
+    let _ = Registry::init();
+
+    let mut builder = StaticBlockState::builder(UlnStr::minecraft("oak_stairs")).unwrap();
+    for (name, value) in [("facing", "east"), ("half", "top"), ("shape", "outer_left")] {
+        builder.add_property(name, value).unwrap();
+    }
+    let stairs = builder.build();
+
+    assert_eq!(
+        stairs
+            .handle
+            .state_with(&[("facing", "east"), ("half", "top"), ("shape", "outer_left")]),
+        Some(stairs.id())
+    );
+    assert_eq!(
+        stairs.properties(),
+        [
+            ("facing", "east"),
+            ("half", "top"),
+            ("shape", "outer_left"),
+            ("waterlogged", "false")
+        ]
+        .map(|(name, value)| (name.to_owned(), value.to_owned()))
+    );
+
+    let flipped = stairs.with("half", "bottom").unwrap();
+    assert_eq!(
+        flipped.id(),
+        stairs
+            .handle
+            .state_with(&[
+                ("facing", "east"),
+                ("half", "bottom"),
+                ("shape", "outer_left")
+            ])
+            .unwrap()
+    );
+    assert!(stairs.with("half", "middle").is_none());
+    assert!(stairs.with("powered", "true").is_none());
+}
+
+#[test]
+fn parse_block_state_strings() {
+    use crate::Registry;
+
+    let _ = Registry::init();
+
+    let stone = BlockStateData::Stone.id();
+    assert_eq!(parse_block_state("minecraft:stone"), Ok(stone));
+    assert_eq!(parse_block_state("stone[]"), Ok(stone));
+    assert_eq!(
+        state_id_to_string(stone).as_deref(),
+        Some("minecraft:stone")
+    );
+
+    let stairs = parse_block_state("minecraft:oak_stairs[facing=north, half=bottom]").unwrap();
+    let string = state_id_to_string(stairs).unwrap();
+    assert_eq!(
+        string,
+        "minecraft:oak_stairs[facing=north,half=bottom,shape=straight,waterlogged=false]"
+    );
+    assert_eq!(parse_block_state(&string), Ok(stairs));
+
+    assert_eq!(
+        parse_block_state("minecraft:not_a_block"),
+        Err(ParseBlockStateError::UnknownBlock(
+            "minecraft:not_a_block".to_owned()
+        ))
+    );
+    assert_eq!(
+        parse_block_state("stone[facing=north]")
+            .unwrap_err()
+            .to_string(),
+        "Block minecraft:stone does not have property 'facing'"
+    );
+    assert_eq!(
+        parse_block_state("oak_stairs[facing=up]")
+            .unwrap_err()
+            .to_string(),
+        "Property 'facing' cannot be set to 'up'"
+    );
+    assert_eq!(
+        parse_block_state("oak_stairs[facing]"),
+        Err(ParseBlockStateError::MissingValue("facing".to_owned()))
+    );
+    assert_eq!(
+        parse_block_state("oak_stairs[half=top,half=bottom]"),
+        Err(ParseBlockStateError::DuplicateProperty("half".to_owned()))
+    );
+    assert_eq!(
+        parse_block_state("oak_stairs[facing=north"),
+        Err(ParseBlockStateError::UnclosedProperties)
+    );
+}
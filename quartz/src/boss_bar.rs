@@ -0,0 +1,272 @@
+use crate::{
+    network::ClientBoundPacket,
+    server::{ClientId, ClientList},
+};
+use quartz_chat::Component;
+use uuid::Uuid;
+
+/// The color of a boss bar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum BossBarColor {
+    Pink = 0,
+    Blue = 1,
+    Red = 2,
+    Green = 3,
+    Yellow = 4,
+    Purple = 5,
+    White = 6,
+}
+
+/// The number of segments a boss bar is divided into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum BossBarDivision {
+    None = 0,
+    Notches6 = 1,
+    Notches10 = 2,
+    Notches12 = 3,
+    Notches20 = 4,
+}
+
+/// A part of a boss bar which is sent to clients.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum BossBarAction {
+    /// Shows the boss bar, sending all of its parts.
+    Add = 0,
+    /// Hides the boss bar.
+    Remove = 1,
+    UpdateProgress = 2,
+    UpdateTitle = 3,
+    UpdateStyle = 4,
+    UpdateFlags = 5,
+}
+
+impl BossBarAction {
+    const UPDATES: [BossBarAction; 4] = [
+        BossBarAction::UpdateProgress,
+        BossBarAction::UpdateTitle,
+        BossBarAction::UpdateStyle,
+        BossBarAction::UpdateFlags,
+    ];
+
+    const fn dirty_bit(self) -> u8 {
+        1 << self as i32
+    }
+}
+
+/// A boss bar shown to a set of viewers. Changes are collected and sent to the viewers when the bar
+/// is flushed, with each changed part sent as its own action so the rest of the bar is not resent.
+pub struct BossBar {
+    uuid: Uuid,
+    title: Component,
+    progress: f32,
+    color: BossBarColor,
+    division: BossBarDivision,
+    darken_sky: bool,
+    play_boss_music: bool,
+    create_fog: bool,
+    viewers: Vec<ClientId>,
+    added_viewers: Vec<ClientId>,
+    removed_viewers: Vec<ClientId>,
+    dirty: u8,
+}
+
+impl BossBar {
+    pub fn new(title: Component, color: BossBarColor, division: BossBarDivision) -> Self {
+        BossBar {
+            uuid: Uuid::new_v4(),
+            title,
+            progress: 1.0,
+            color,
+            division,
+            darken_sky: false,
+            play_boss_music: false,
+            create_fog: false,
+            viewers: Vec::new(),
+            added_viewers: Vec::new(),
+            removed_viewers: Vec::new(),
+            dirty: 0,
+        }
+    }
+
+    #[inline]
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    #[inline]
+    pub fn title(&self) -> &Component {
+        &self.title
+    }
+
+    pub fn set_title(&mut self, title: Component) {
+        self.title = title;
+        self.mark_dirty(BossBarAction::UpdateTitle);
+    }
+
+    #[inline]
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    /// Sets the progress of the bar, which is clamped between zero and one.
+    pub fn set_progress(&mut self, progress: f32) {
+        self.progress = progress.clamp(0.0, 1.0);
+        self.mark_dirty(BossBarAction::UpdateProgress);
+    }
+
+    pub fn set_style(&mut self, color: BossBarColor, division: BossBarDivision) {
+        self.color = color;
+        self.division = division;
+        self.mark_dirty(BossBarAction::UpdateStyle);
+    }
+
+    pub fn set_flags(&mut self, darken_sky: bool, play_boss_music: bool, create_fog: bool) {
+        self.darken_sky = darken_sky;
+        self.play_boss_music = play_boss_music;
+        self.create_fog = create_fog;
+        self.mark_dirty(BossBarAction::UpdateFlags);
+    }
+
+    #[inline]
+    pub fn viewers(&self) -> &[ClientId] {
+        &self.viewers
+    }
+
+    /// Shows this boss bar to the given client. Returns false if the client is already a viewer.
+    pub fn add_viewer(&mut self, client_id: ClientId) -> bool {
+        if self.viewers.contains(&client_id) {
+            return false;
+        }
+
+        self.viewers.push(client_id);
+        self.removed_viewers.retain(|&id| id != client_id);
+        self.added_viewers.push(client_id);
+        true
+    }
+
+    /// Hides this boss bar from the given client. Returns false if the client was not a viewer.
+    pub fn remove_viewer(&mut self, client_id: ClientId) -> bool {
+        let index = match self.viewers.iter().position(|&id| id == client_id) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        self.viewers.swap_remove(index);
+        let len = self.added_viewers.len();
+        self.added_viewers.retain(|&id| id != client_id);
+        // Clients which never received the add action don't need to be told to remove the bar
+        if self.added_viewers.len() == len {
+            self.removed_viewers.push(client_id);
+        }
+        true
+    }
+
+    #[inline]
+    fn mark_dirty(&mut self, action: BossBarAction) {
+        self.dirty |= action.dirty_bit();
+    }
+
+    /// Creates the boss bar packet for the given action using the current state of this bar.
+    pub fn packet(&self, action: BossBarAction) -> ClientBoundPacket {
+        // The add action sends every part of the bar
+        let sends = |part| action == BossBarAction::Add || action == part;
+        let flags = self.darken_sky as u8
+            | (self.play_boss_music as u8) << 1
+            | (self.create_fog as u8) << 2;
+
+        ClientBoundPacket::BossBar {
+            uuid: self.uuid,
+            action: action as i32,
+            title: sends(BossBarAction::UpdateTitle).then(|| Box::new(self.title.clone())),
+            health: sends(BossBarAction::UpdateProgress).then_some(self.progress),
+            color: sends(BossBarAction::UpdateStyle).then_some(self.color as i32),
+            division: (action == BossBarAction::Add).then_some(self.division as i32),
+            flags: sends(BossBarAction::UpdateFlags).then_some(flags),
+            dividers: (action == BossBarAction::UpdateStyle).then_some(self.division as i32),
+        }
+    }
+
+    /// Returns the packets for every change since the last flush along with the client each should
+    /// be sent to, and clears the pending changes.
+    pub fn take_packets(&mut self) -> Vec<(ClientId, ClientBoundPacket)> {
+        let mut packets = Vec::new();
+
+        for &client_id in &self.removed_viewers {
+            packets.push((client_id, self.packet(BossBarAction::Remove)));
+        }
+
+        for &client_id in &self.added_viewers {
+            packets.push((client_id, self.packet(BossBarAction::Add)));
+        }
+
+        // New viewers already received the current state with the add action
+        for action in BossBarAction::UPDATES {
+            if self.dirty & action.dirty_bit() == 0 {
+                continue;
+            }
+
+            for &client_id in &self.viewers {
+                if !self.added_viewers.contains(&client_id) {
+                    packets.push((client_id, self.packet(action)));
+                }
+            }
+        }
+
+        self.removed_viewers.clear();
+        self.added_viewers.clear();
+        self.dirty = 0;
+        packets
+    }
+
+    /// Sends every change since the last flush to the affected clients.
+    pub fn flush(&mut self, clients: &ClientList) {
+        for (client_id, packet) in self.take_packets() {
+            clients.send_packet(client_id, packet);
+        }
+    }
+}
+
+#[test]
+fn progress_update_only() {
+    let mut boss_bar = BossBar::new(
+        Component::text("Dragon"),
+        BossBarColor::Purple,
+        BossBarDivision::None,
+    );
+    boss_bar.add_viewer(1);
+
+    let packets = boss_bar.take_packets();
+    assert_eq!(packets.len(), 1);
+    assert!(matches!(
+        packets[0],
+        (1, ClientBoundPacket::BossBar {
+            action: 0,
+            title: Some(_),
+            health: Some(_),
+            division: Some(0),
+            ..
+        })
+    ));
+
+    boss_bar.set_progress(0.5);
+    let packets = boss_bar.take_packets();
+    assert_eq!(packets.len(), 1);
+    assert!(matches!(
+        packets[0],
+        (1, ClientBoundPacket::BossBar {
+            action: 2,
+            title: None,
+            health: Some(health),
+            color: None,
+            division: None,
+            flags: None,
+            dividers: None,
+            ..
+        }) if health == 0.5
+    ));
+
+    assert!(boss_bar.take_packets().is_empty());
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use qdat::{
+    item::Item,
+    uln::ParseUnlocalizedNameError,
+    world::location::BlockPosition,
+    UnlocalizedName,
+};
+use quartz_nbt::{snbt, NbtCompound};
+
+use crate::{
+    command::{CommandSender, Coordinates, NbtPath, StringReader},
+    item::get_item_list,
+};
+
+/// An argument which accepts any valid resource location, equivalent to vanilla's
+/// `minecraft:resource_location` argument type.
+///
+/// `UnlocalizedName` and the argument traits of `quartz_commands` are both defined in other
+/// crates, so commands declare a `String` argument and parse it with this in their executor.
+pub struct ResourceLocationArgument;
+
+impl ResourceLocationArgument {
+    /// Parses the given argument as an unlocalized name, defaulting to the `minecraft` namespace.
+    pub fn parse(arg: &str) -> Result<UnlocalizedName, ArgumentError> {
+        UnlocalizedName::from_str(arg).map_err(ArgumentError::InvalidResourceLocation)
+    }
+}
+
+/// An argument which accepts the key of an entry in a registry, equivalent to vanilla's
+/// `minecraft:resource` argument type.
+///
+/// The registry is only known to the command using this, so the argument is declared as a
+/// `String` and parsed here, with [`suggestions`](Self::suggestions) backing its `suggests` arm.
+pub struct ResourceArgument<'r, V> {
+    /// The name of the registry, used in error messages.
+    pub registry_name: &'static str,
+    /// The entries which this argument accepts.
+    pub registry: &'r BTreeMap<UnlocalizedName, V>,
+}
+
+impl<'r, V> ResourceArgument<'r, V> {
+    pub const fn new(
+        registry_name: &'static str,
+        registry: &'r BTreeMap<UnlocalizedName, V>,
+    ) -> Self {
+        ResourceArgument {
+            registry_name,
+            registry,
+        }
+    }
+
+    /// Parses the given argument and looks it up in the registry.
+    pub fn parse(&self, arg: &str) -> Result<(&'r UnlocalizedName, &'r V), ArgumentError> {
+        let name = ResourceLocationArgument::parse(arg)?;
+        self.registry
+            .get_key_value(&name)
+            .ok_or(ArgumentError::UnknownResource {
+                registry: self.registry_name,
+                name,
+            })
+    }
+
+    /// Returns the keys of the registry which could complete the given partial argument. Keys in
+    /// the `minecraft` namespace are also suggested if only their identifier has been typed.
+    pub fn suggestions(&self, partial: &str) -> Vec<String> {
+        self.registry
+            .keys()
+            .map(|key| key.to_string())
+            .filter(|key| resource_matches(key, partial))
+            .collect()
+    }
+}
+
+/// Returns whether the given resource location could complete the partial argument, also
+/// matching names in the `minecraft` namespace if only their identifier has been typed.
+pub(crate) fn resource_matches(name: &str, partial: &str) -> bool {
+    name.starts_with(partial)
+        || name
+            .strip_prefix("minecraft:")
+            .is_some_and(|identifier| identifier.starts_with(partial))
+}
+
+impl ResourceArgument<'static, Item> {
+    /// Creates an argument accepting any item.
+    pub fn items() -> Self {
+        Self::new("item", get_item_list())
+    }
+}
+
+/// An argument which accepts a compound tag written in SNBT, such as `{CustomName:'"Steve"'}`.
+pub struct NbtCompoundArgument;
+
+impl NbtCompoundArgument {
+    /// Parses a compound from the given reader, leaving the reader just after the closing brace.
+    pub fn parse(reader: &mut StringReader<'_>) -> Result<NbtCompound, ArgumentError> {
+        let (compound, len) = snbt::parse_and_size(reader.remaining())
+            .map_err(|error| ArgumentError::InvalidNbt(error.to_string()))?;
+        reader.advance(len);
+        Ok(compound)
+    }
+}
+
+/// An argument which accepts an NBT path, such as `Inventory[0].tag`.
+pub struct NbtPathArgument;
+
+impl NbtPathArgument {
+    /// Parses a path from the given reader, leaving the reader at the whitespace after the path.
+    #[inline]
+    pub fn parse(reader: &mut StringReader<'_>) -> Result<NbtPath, ArgumentError> {
+        NbtPath::parse(reader)
+    }
+}
+
+/// An argument which accepts a block position, equivalent to vanilla's `minecraft:block_pos`
+/// argument type. Coordinates may be absolute, relative to the sender such as `~ ~1 ~`, or local
+/// to where the sender is looking such as `^ ^ ^2`.
+///
+/// This can't be one of the `quartz_commands` argument types, which each parse a single word,
+/// since a position spans three. Commands read it from their raw input with a [`StringReader`]
+/// instead.
+pub struct BlockPosArgument;
+
+impl BlockPosArgument {
+    /// Parses a block position from the given reader and resolves it against the sender's
+    /// position, leaving the reader just after the third coordinate.
+    pub fn parse(
+        reader: &mut StringReader<'_>,
+        sender: &CommandSender,
+    ) -> Result<BlockPosition, ArgumentError> {
+        Coordinates::parse_block_pos(reader).map(|coords| coords.resolve_block(&sender.origin()))
+    }
+}
+
+/// An error encountered while parsing a command argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgumentError {
+    InvalidResourceLocation(ParseUnlocalizedNameError),
+    /// The resource location was valid but not present in the registry.
+    UnknownResource {
+        registry: &'static str,
+        name: UnlocalizedName,
+    },
+    /// The SNBT could not be parsed, with the parser's description of the problem.
+    InvalidNbt(String),
+    /// The NBT path contained an invalid element starting at the given position.
+    InvalidNbtPath(usize),
+    /// Some coordinates of a position used `^` while others did not.
+    MixedCoordinates,
+    /// Fewer than three coordinates were given for a position.
+    IncompleteCoordinates,
+    /// The coordinate starting at the given position was not a valid number.
+    InvalidCoordinate(usize),
+}
+
+impl Display for ArgumentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgumentError::InvalidResourceLocation(error) =>
+                write!(f, "Invalid resource location: {error}"),
+            ArgumentError::UnknownResource { registry, name } =>
+                write!(f, "Unknown {registry} '{name}'"),
+            ArgumentError::InvalidNbt(error) => write!(f, "Invalid NBT: {error}"),
+            ArgumentError::InvalidNbtPath(position) =>
+                write!(f, "Invalid NBT path element at position {position}"),
+            ArgumentError::MixedCoordinates => write!(
+                f,
+                "Cannot mix world & local coordinates (everything must either use ^ or not)"
+            ),
+            ArgumentError::IncompleteCoordinates =>
+                write!(f, "Incomplete (expected 3 coordinates)"),
+            ArgumentError::InvalidCoordinate(position) =>
+                write!(f, "Invalid coordinate at position {position}"),
+        }
+    }
+}
+
+impl Error for ArgumentError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ArgumentError::InvalidResourceLocation(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn parse_item_argument() {
+    assert_eq!(
+        ResourceLocationArgument::parse("minecraft:diamond"),
+        Ok(UnlocalizedName::minecraft("diamond"))
+    );
+    assert_eq!(
+        ResourceLocationArgument::parse("quartz:"),
+        Err(ArgumentError::InvalidResourceLocation(
+            ParseUnlocalizedNameError::EmptyIdentifier
+        ))
+    );
+
+    let items = [
+        "minecraft:diamond",
+        "minecraft:diamond_sword",
+        "minecraft:stone",
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, name)| (UnlocalizedName::from_str(name).unwrap(), i))
+    .collect::<BTreeMap<_, _>>();
+    let arg = ResourceArgument::new("item", &items);
+
+    assert_eq!(arg.parse("diamond").map(|(_, &id)| id), Ok(0));
+    let error = arg.parse("minecraft:diamonds").unwrap_err();
+    assert_eq!(error.to_string(), "Unknown item 'minecraft:diamonds'");
+
+    assert_eq!(arg.suggestions("diamond"), vec![
+        "minecraft:diamond",
+        "minecraft:diamond_sword"
+    ]);
+    assert_eq!(arg.suggestions("minecraft:s"), vec!["minecraft:stone"]);
+}
+
+#[test]
+fn parse_data_merge_arguments() {
+    let mut reader = StringReader::new("data merge entity @s {foo:1b} Pos[0]");
+    for _ in 0 .. 4 {
+        reader.read_word();
+        reader.skip_whitespace();
+    }
+
+    let compound = NbtCompoundArgument::parse(&mut reader).unwrap();
+    assert_eq!(compound.get::<_, i8>("foo").unwrap(), 1);
+    assert_eq!(reader.remaining(), " Pos[0]");
+
+    reader.skip_whitespace();
+    let path = NbtPathArgument::parse(&mut reader).unwrap();
+    assert_eq!(path.nodes().len(), 2);
+    assert!(!reader.can_read());
+
+    assert!(matches!(
+        NbtCompoundArgument::parse(&mut StringReader::new("{foo:}")),
+        Err(ArgumentError::InvalidNbt(_))
+    ));
+}
+
+#[test]
+fn parse_block_pos_argument() {
+    use crate::{command::CommandOrigin, network::AsyncWriteHandle};
+
+    let (handle, _packets) = AsyncWriteHandle::detached();
+    let sender = CommandSender::Client {
+        id: 0,
+        handle,
+        origin: CommandOrigin::new(10.5, 64.0, -3.2, Default::default()),
+        permission_level: 0,
+    };
+    let parse = |input| {
+        BlockPosArgument::parse(&mut StringReader::new(input), &sender)
+            .map(|pos| (pos.x, pos.y, pos.z))
+    };
+
+    assert_eq!(parse("~ ~1 ~"), Ok((10, 65, -4)));
+    assert_eq!(parse("1 2 3"), Ok((1, 2, 3)));
+    assert_eq!(parse("~-1 ~ 5"), Ok((9, 64, 5)));
+    // Facing south, forwards is +z and left is +x
+    assert_eq!(parse("^ ^ ^2"), Ok((10, 64, -2)));
+    assert_eq!(parse("^1 ^ ^"), Ok((11, 64, -4)));
+
+    let mut reader = StringReader::new("~ ~ ~ stone");
+    assert!(BlockPosArgument::parse(&mut reader, &sender).is_ok());
+    assert_eq!(reader.remaining(), " stone");
+
+    assert_eq!(parse("^ ~ ^"), Err(ArgumentError::MixedCoordinates));
+    assert_eq!(parse("~ ^ ~"), Err(ArgumentError::MixedCoordinates));
+    assert_eq!(parse("1 2"), Err(ArgumentError::IncompleteCoordinates));
+    assert_eq!(parse("1.5 2 3"), Err(ArgumentError::InvalidCoordinate(0)));
+    assert_eq!(parse("~x ~ ~"), Err(ArgumentError::InvalidCoordinate(0)));
+    let console_pos =
+        BlockPosArgument::parse(&mut StringReader::new("~ ~ ^"), &CommandSender::Console);
+    assert_eq!(console_pos, Err(ArgumentError::MixedCoordinates));
+}
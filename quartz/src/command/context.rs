@@ -1,5 +1,16 @@
-use crate::{display_to_console, network::AsyncWriteHandle, CommandExecutor, QuartzServer};
-use quartz_chat::component::Component;
+use crate::{
+    display_to_console,
+    entities::entity_type::Rotation,
+    network::AsyncWriteHandle,
+    server::ClientId,
+    world::{
+        game_rules::GameRules,
+        world::{Dimension, World, WorldStore},
+    },
+    CommandExecutor,
+    QuartzServer,
+};
+use quartz_chat::{color::Color, component::Component};
 use quartz_net::ClientBoundPacket;
 use uuid::Uuid;
 
@@ -27,25 +38,190 @@ impl<'ctx> CommandContext<'ctx> {
             sender,
         }
     }
+
+    /// Sends the result of a command to the sender, respecting the server's game rules.
+    pub fn send_feedback(&self, message: Component) {
+        self.sender.send_feedback(message, &self.server.game_rules);
+    }
 }
 
 /// A command sender, can be command block, player, or the console.
 pub enum CommandSender {
     /// The console sender type.
     Console,
-    Client(AsyncWriteHandle),
+    Client {
+        /// The ID of the player's connection.
+        id: ClientId,
+        handle: AsyncWriteHandle,
+        /// Where the player was when they sent the command.
+        origin: CommandOrigin,
+        /// The player's permission level, from zero to four.
+        permission_level: u8,
+    },
+}
+
+/// The position and rotation a command is run from, which relative and local coordinates are
+/// resolved against.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CommandOrigin {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub rotation: Rotation,
+}
+
+impl CommandOrigin {
+    pub const fn new(x: f64, y: f64, z: f64, rotation: Rotation) -> Self {
+        CommandOrigin { x, y, z, rotation }
+    }
 }
 
 impl CommandSender {
+    /// Returns where commands from this sender are run. The console runs them from the world
+    /// origin, facing south.
+    pub fn origin(&self) -> CommandOrigin {
+        match self {
+            CommandSender::Console => CommandOrigin::default(),
+            CommandSender::Client { origin, .. } => *origin,
+        }
+    }
+
+    /// Returns the world commands from this sender are run in. The console runs them in the
+    /// overworld.
+    pub fn world<'w>(&self, world_store: &'w mut WorldStore) -> Option<&'w mut World> {
+        match self {
+            CommandSender::Console => world_store.get_world_mut(Dimension::Overworld),
+            CommandSender::Client { id, .. } => world_store.get_player_world_mut(*id),
+        }
+    }
+
+    /// Returns the highest permission level of the commands this sender may run. The console may
+    /// run every command.
+    pub fn permission_level(&self) -> u8 {
+        match self {
+            CommandSender::Console => 4,
+            CommandSender::Client {
+                permission_level, ..
+            } => *permission_level,
+        }
+    }
+
+    /// Checks whether this sender may run commands needing the given permission level, returning
+    /// the message to send them if not.
+    pub fn require_permission(&self, permission_level: u8) -> Result<(), Component> {
+        if self.permission_level() < permission_level {
+            return Err(Component::text(
+                "You do not have permission to use this command",
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Sends a message to the sender.
     pub fn send_message(&self, message: Component) {
         match self {
             CommandSender::Console => display_to_console(&message),
-            CommandSender::Client(handle) => handle.send_packet(ClientBoundPacket::ChatMessage {
-                sender: Uuid::from_u128(0),
-                position: 1,
-                json_data: Box::new(message),
-            }),
+            CommandSender::Client { handle, .. } =>
+                handle.send_packet(ClientBoundPacket::ChatMessage {
+                    sender: Uuid::from_u128(0),
+                    position: 1,
+                    json_data: Box::new(message),
+                }),
+        }
+    }
+
+    /// Sends a message to the sender, colored red unless it already has a color.
+    pub fn send_error(&self, mut message: Component) {
+        message.color.get_or_insert(Color::Red);
+        self.send_message(message);
+    }
+
+    /// Sends the result of a command to the sender if they receive command feedback.
+    pub fn send_feedback(&self, message: Component, game_rules: &GameRules) {
+        if self.receives_feedback(game_rules) {
+            self.send_message(message);
         }
     }
+
+    /// Whether or not the sender is sent the results of commands. The console always receives
+    /// feedback, while players only receive it if the `sendCommandFeedback` game rule is enabled.
+    pub fn receives_feedback(&self, game_rules: &GameRules) -> bool {
+        match self {
+            CommandSender::Console => true,
+            CommandSender::Client { .. } => game_rules.send_command_feedback,
+        }
+    }
+}
+
+#[test]
+fn player_feedback_queues_packets() {
+    use crate::network::WrappedClientBoundPacket;
+
+    let (handle, mut packets) = AsyncWriteHandle::detached();
+    let sender = CommandSender::Client {
+        id: 0,
+        handle,
+        origin: CommandOrigin::default(),
+        permission_level: 0,
+    };
+    let mut game_rules = GameRules::new();
+
+    sender.send_feedback(Component::text("Set the time to 1000"), &game_rules);
+    assert!(matches!(
+        packets.try_recv(),
+        Ok(WrappedClientBoundPacket::Singleton(
+            ClientBoundPacket::ChatMessage { position: 1, .. }
+        ))
+    ));
+
+    game_rules.send_command_feedback = false;
+    sender.send_feedback(Component::text("Set the time to 1000"), &game_rules);
+    assert!(packets.try_recv().is_err());
+
+    sender.send_error(Component::text("Unknown command"));
+    assert!(matches!(
+        packets.try_recv(),
+        Ok(WrappedClientBoundPacket::Singleton(
+            ClientBoundPacket::ChatMessage { json_data, .. }
+        )) if json_data.color == Some(Color::Red)
+    ));
+}
+
+#[test]
+fn console_always_receives_feedback() {
+    let (handle, _packets) = AsyncWriteHandle::detached();
+    let player = CommandSender::Client {
+        id: 0,
+        handle,
+        origin: CommandOrigin::default(),
+        permission_level: 0,
+    };
+    let mut game_rules = GameRules::new();
+    assert!(CommandSender::Console.receives_feedback(&game_rules));
+    assert!(player.receives_feedback(&game_rules));
+
+    game_rules.send_command_feedback = false;
+    assert!(CommandSender::Console.receives_feedback(&game_rules));
+    assert!(!player.receives_feedback(&game_rules));
+}
+
+#[test]
+fn require_permission_level() {
+    let (handle, _packets) = AsyncWriteHandle::detached();
+    let player = CommandSender::Client {
+        id: 0,
+        handle,
+        origin: CommandOrigin::default(),
+        permission_level: 0,
+    };
+
+    let denied = player.require_permission(2).unwrap_err();
+    assert_eq!(
+        denied.to_plain_string(),
+        "You do not have permission to use this command"
+    );
+    assert!(player.require_permission(0).is_ok());
+    assert!(CommandSender::Console.require_permission(2).is_ok());
+    assert!(CommandSender::Console.require_permission(4).is_ok());
 }
@@ -0,0 +1,145 @@
+use qdat::world::location::BlockPosition;
+
+use crate::command::{ArgumentError, CommandOrigin, StringReader};
+
+/// A single component of a position written with world coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorldCoordinate {
+    /// A fixed coordinate, such as `5`.
+    Absolute(f64),
+    /// An offset from the origin along the same axis, such as `~-2`.
+    Relative(f64),
+}
+
+impl WorldCoordinate {
+    /// Resolves this coordinate against the origin's coordinate on the same axis.
+    #[inline]
+    pub fn resolve(self, origin: f64) -> f64 {
+        match self {
+            WorldCoordinate::Absolute(value) => value,
+            WorldCoordinate::Relative(offset) => origin + offset,
+        }
+    }
+}
+
+/// A position typed into a command, either as three world coordinates such as `~ ~1 5`, or as
+/// local coordinates such as `^ ^ ^2` which are offsets to the left, up, and forwards from where
+/// the sender is looking.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Coordinates {
+    World([WorldCoordinate; 3]),
+    Local([f64; 3]),
+}
+
+impl Coordinates {
+    /// Parses the coordinates of a block position from the given reader, leaving the reader just
+    /// after the third coordinate. Absolute coordinates must be whole numbers.
+    pub fn parse_block_pos(reader: &mut StringReader<'_>) -> Result<Self, ArgumentError> {
+        let local = reader.peek() == Some('^');
+        let mut world = [WorldCoordinate::Absolute(0.0); 3];
+        let mut offsets = [0.0; 3];
+
+        for i in 0 .. 3 {
+            if i > 0 {
+                if !reader.peek().is_some_and(char::is_whitespace) {
+                    return Err(ArgumentError::IncompleteCoordinates);
+                }
+                reader.skip_whitespace();
+            }
+
+            if !reader.can_read() {
+                return Err(ArgumentError::IncompleteCoordinates);
+            }
+
+            let start = reader.cursor();
+            let token = reader.read_word();
+            match (local, token.strip_prefix('^')) {
+                (true, Some(offset)) => offsets[i] = parse_offset(offset, start)?,
+                (false, None) => world[i] = parse_world_coordinate(token, start)?,
+                _ => return Err(ArgumentError::MixedCoordinates),
+            }
+        }
+
+        if local {
+            Ok(Coordinates::Local(offsets))
+        } else {
+            Ok(Coordinates::World(world))
+        }
+    }
+
+    /// Resolves these coordinates against the given origin, returning the exact position.
+    pub fn resolve(&self, origin: &CommandOrigin) -> (f64, f64, f64) {
+        match *self {
+            Coordinates::World([x, y, z]) => (
+                x.resolve(origin.x),
+                y.resolve(origin.y),
+                z.resolve(origin.z),
+            ),
+            Coordinates::Local([left, up, forwards]) => {
+                let yaw = (origin.rotation.yaw as f64 + 90.0).to_radians();
+                let pitch = -(origin.rotation.pitch as f64).to_radians();
+                let pitch_up = pitch + 90f64.to_radians();
+
+                let forwards_axis = [
+                    yaw.cos() * pitch.cos(),
+                    pitch.sin(),
+                    yaw.sin() * pitch.cos(),
+                ];
+                let up_axis = [
+                    yaw.cos() * pitch_up.cos(),
+                    pitch_up.sin(),
+                    yaw.sin() * pitch_up.cos(),
+                ];
+                // The left axis is perpendicular to the other two
+                let left_axis = [
+                    up_axis[1] * forwards_axis[2] - up_axis[2] * forwards_axis[1],
+                    up_axis[2] * forwards_axis[0] - up_axis[0] * forwards_axis[2],
+                    up_axis[0] * forwards_axis[1] - up_axis[1] * forwards_axis[0],
+                ];
+
+                let offset = |axis: usize| {
+                    left_axis[axis] * left + up_axis[axis] * up + forwards_axis[axis] * forwards
+                };
+                (
+                    origin.x + offset(0),
+                    origin.y + offset(1),
+                    origin.z + offset(2),
+                )
+            }
+        }
+    }
+
+    /// Resolves these coordinates against the given origin, returning the block containing the
+    /// resolved position.
+    pub fn resolve_block(&self, origin: &CommandOrigin) -> BlockPosition {
+        let (x, y, z) = self.resolve(origin);
+        BlockPosition {
+            x: x.floor() as i32,
+            y: y.floor() as i16,
+            z: z.floor() as i32,
+        }
+    }
+}
+
+fn parse_world_coordinate(token: &str, start: usize) -> Result<WorldCoordinate, ArgumentError> {
+    match token.strip_prefix('~') {
+        Some(offset) => parse_offset(offset, start).map(WorldCoordinate::Relative),
+        None => token
+            .parse::<i32>()
+            .map(|value| WorldCoordinate::Absolute(value as f64))
+            .map_err(|_| ArgumentError::InvalidCoordinate(start)),
+    }
+}
+
+/// Parses the offset after a `~` or `^`, which is zero if left out.
+fn parse_offset(offset: &str, start: usize) -> Result<f64, ArgumentError> {
+    if offset.is_empty() {
+        return Ok(0.0);
+    }
+
+    offset
+        .parse::<f64>()
+        .ok()
+        .filter(|offset| offset.is_finite())
+        .ok_or(ArgumentError::InvalidCoordinate(start))
+}
@@ -1,12 +1,55 @@
 use quartz_commands::CommandModule;
 
-use crate::command::CommandContext;
+use crate::command::{last_word_start, resource_matches, CommandContext, Suggestion};
 
-pub struct StaticCommandExecutor;
+/// A module of commands which all need the same permission level to run.
+pub struct CommandSet {
+    pub permission_level: u8,
+    /// The names of the commands in the module, which commands are looked up by when they're run
+    /// or completed.
+    pub names: &'static [&'static str],
+    pub commands: &'static (dyn for<'ctx> CommandModule<CommandContext<'ctx>> + Send + Sync),
+}
+
+pub struct StaticCommandExecutor {
+    command_sets: Vec<CommandSet>,
+}
 
 impl StaticCommandExecutor {
     pub fn new() -> Self {
-        StaticCommandExecutor
+        StaticCommandExecutor {
+            // NOTE: the names need to be kept in sync with the commands in each module
+            command_sets: vec![
+                CommandSet {
+                    permission_level: 0,
+                    names: &["help", "tps"],
+                    commands: &cmds::NativeCommandSet,
+                },
+                CommandSet {
+                    permission_level: 4,
+                    names: &["stop", "setblock", "loglevel"],
+                    commands: &cmds::OperatorCommandSet,
+                },
+            ],
+        }
+    }
+
+    /// Returns the completions for the last word of a partially typed command, without the
+    /// leading slash. Commands the sender may not run are not suggested.
+    pub fn suggestions(&self, input: &str, context: &CommandContext<'_>) -> Vec<Suggestion> {
+        let partial = &input[last_word_start(input) ..];
+        self.get_suggestions(input, context)
+            .into_iter()
+            .filter(|text| resource_matches(text, partial))
+            .map(|text| Suggestion::for_last_word(input, text))
+            .collect()
+    }
+
+    /// Returns the set the command with the given name is registered in.
+    fn command_set(&self, name: &str) -> Option<&CommandSet> {
+        self.command_sets
+            .iter()
+            .find(|set| set.names.contains(&name))
     }
 }
 
@@ -22,11 +65,39 @@ impl<'ctx> CommandModule<CommandContext<'ctx>> for StaticCommandExecutor {
         command: &str,
         context: CommandContext<'ctx>,
     ) -> Result<(), quartz_commands::Error> {
-        (cmds::NativeCommandSet).dispatch(command, context)
+        let name = command.split_whitespace().next().unwrap_or_default();
+        // Unknown commands are left for the native commands to report
+        let set = self.command_set(name).unwrap_or(&self.command_sets[0]);
+
+        if let Err(message) = context.sender.require_permission(set.permission_level) {
+            context.sender.send_error(message);
+            return Ok(());
+        }
+
+        set.commands.dispatch(command, context)
     }
 
     fn get_suggestions(&self, command: &str, context: &CommandContext<'ctx>) -> Vec<String> {
-        (cmds::NativeCommandSet).get_suggestions(command, context)
+        let permitted =
+            |set: &&CommandSet| context.sender.permission_level() >= set.permission_level;
+
+        // Only the command name is being typed
+        if last_word_start(command) == 0 {
+            return self
+                .command_sets
+                .iter()
+                .filter(permitted)
+                .flat_map(|set| set.names.iter())
+                .filter(|name| name.starts_with(command))
+                .map(|name| name.to_string())
+                .collect();
+        }
+
+        let name = command.split_whitespace().next().unwrap_or_default();
+        match self.command_set(name).filter(permitted) {
+            Some(set) => set.commands.get_suggestions(command, context),
+            None => Vec::new(),
+        }
     }
 }
 
@@ -34,10 +105,82 @@ impl<'ctx> CommandModule<CommandContext<'ctx>> for StaticCommandExecutor {
 // We have to wrap the commands in a module so we can disable clippy cause I can't find a way to do it any other way
 #[allow(clippy::redundant_pattern)]
 mod cmds {
-    use crate::{command::CommandContext, ServerClock, DIAGNOSTICS, RUNNING};
+    use crate::{
+        base::BlockState,
+        block::{BlockStateImpl, StateBuilder},
+        command::{
+            block_suggestions,
+            ArgumentError,
+            BlockPosArgument,
+            CommandContext,
+            ResourceLocationArgument,
+            StringReader,
+        },
+        util::logging,
+        world::chunk::Chunk,
+        ServerClock,
+        DIAGNOSTICS,
+        RUNNING,
+    };
+    use log::LevelFilter;
     use quartz_chat::{color::Color, Component, ComponentBuilder};
     use quartz_commands::{self, module, CommandModule, Help};
     use std::sync::atomic::Ordering;
+
+    /// Replaces the block at the given position in the sender's world with the default state of
+    /// the given block, and shows the change to every client.
+    fn set_block(ctx: CommandContext<'_>, pos: &str, block: &str) {
+        let pos = match BlockPosArgument::parse(&mut StringReader::new(pos), &ctx.sender) {
+            Ok(pos) => pos,
+            Err(error) => {
+                ctx.sender.send_error(Component::text(error.to_string()));
+                return;
+            }
+        };
+        let state = match ResourceLocationArgument::parse(block).and_then(|name| {
+            BlockState::builder(&name).ok_or(ArgumentError::UnknownResource {
+                registry: "block",
+                name,
+            })
+        }) {
+            Ok(builder) => builder.build().id(),
+            Err(error) => {
+                ctx.sender.send_error(Component::text(error.to_string()));
+                return;
+            }
+        };
+
+        let server = ctx.server;
+        let mut chunk = match ctx
+            .sender
+            .world(&mut server.world_store)
+            .and_then(|world| world.get_loaded_chunk_mut(pos.into()))
+        {
+            Some(chunk) => chunk,
+            None => {
+                ctx.sender
+                    .send_error(Component::text("That position is not loaded"));
+                return;
+            }
+        };
+        if chunk.set_block_state_at(pos, state).is_none() {
+            ctx.sender
+                .send_error(Component::text("Could not set the block"));
+            return;
+        }
+
+        server
+            .client_list
+            .send_to_all(|_| Chunk::block_change_packet(pos, state));
+        ctx.sender.send_feedback(
+            Component::text(format!(
+                "Changed the block at {}, {}, {}",
+                pos.x, pos.y, pos.z
+            )),
+            &server.game_rules,
+        );
+    }
+
     module! {
         pub mod native_command_set;
         type Context<'ctx> = CommandContext<'ctx>;
@@ -83,19 +226,6 @@ mod cmds {
             };
         }
 
-        command stop where
-            help: Help<'cmd> {
-            root executes |_ctx| {
-                let _ = RUNNING.compare_exchange(true, false, Ordering::Acquire, Ordering::Relaxed);
-                Ok(())
-            };
-
-            help executes |ctx| {
-                ctx.sender.send_message(Component::text("Stops the server"));
-                Ok(())
-            }
-        }
-
         command tps where
             help: Help<'cmd> {
             root executes |ctx| {
@@ -148,4 +278,175 @@ mod cmds {
             }
         }
     }
+
+    // Commands which only operators may run
+    module! {
+        pub mod operator_command_set;
+        type Context<'ctx> = CommandContext<'ctx>;
+
+        command stop where
+            help: Help<'cmd> {
+            root executes |_ctx| {
+                let _ = RUNNING.compare_exchange(true, false, Ordering::Acquire, Ordering::Relaxed);
+                Ok(())
+            };
+
+            help executes |ctx| {
+                ctx.sender.send_message(Component::text("Stops the server"));
+                Ok(())
+            }
+        }
+
+        command setblock where
+            x: String
+            y: String
+            z: String
+            block: String
+            help: Help<'cmd> {
+            x => y => z => block executes |ctx| {
+                set_block(ctx, &format!("{x} {y} {z}"), &block);
+                Ok(())
+            };
+
+            block suggests |ctx, arg| {
+                block_suggestions(arg)
+            };
+
+            help executes |ctx| {
+                ctx.sender.send_message(Component::text(
+                    "Changes the block at a position, such as setblock ~ ~-1 ~ stone",
+                ));
+                Ok(())
+            }
+        }
+
+        command loglevel where
+            target: String
+            level: String
+            help: Help<'cmd> {
+            target => level executes |ctx| {
+                match level.parse::<LevelFilter>() {
+                    Ok(level) => {
+                        logging::set_level(&target, level);
+                        ctx.sender.send_message(Component::text(format!(
+                            "Set the log level of {target} to {level}"
+                        )));
+                    }
+                    Err(_) => ctx
+                        .sender
+                        .send_error(Component::text(format!("Unknown log level \"{level}\""))),
+                }
+                Ok(())
+            };
+
+            level suggests |ctx, arg| {
+                ["off", "error", "warn", "info", "debug", "trace"]
+                    .into_iter()
+                    .map(str::to_owned)
+                    .collect()
+            };
+
+            help executes |ctx| {
+                ctx.sender.send_message(Component::text(
+                    "Sets the log level of a module and its submodules, such as quartz::network",
+                ));
+                Ok(())
+            }
+        }
+    }
+}
+
+#[test]
+fn suggest_commands_and_blocks() {
+    use crate::{command::CommandSender, QuartzServer};
+
+    let mut server = QuartzServer::for_test();
+    let executor = StaticCommandExecutor::new();
+    let context = CommandContext::new(&mut server, &executor, CommandSender::Console);
+
+    // Command names are literals, whether typed as the command or as the argument of help
+    assert_eq!(executor.suggestions("sto", &context), vec![
+        Suggestion::new(0 .. 3, "stop")
+    ]);
+    assert_eq!(executor.suggestions("help t", &context), vec![
+        Suggestion::new(5 .. 6, "tps")
+    ]);
+    assert_eq!(
+        executor.suggestions("loglevel quartz::network d", &context),
+        vec![Suggestion::new(25 .. 26, "debug")]
+    );
+
+    // Block ids are completed from their identifier without the namespace
+    assert_eq!(
+        executor.suggestions("setblock ~ ~-1 ~ oak_sta", &context),
+        vec![Suggestion::new(17 .. 24, "minecraft:oak_stairs")]
+    );
+    assert_eq!(
+        executor.suggestions("setblock ~ ~-1 ~ minecraft:dirt", &context),
+        vec![
+            Suggestion::new(17 .. 31, "minecraft:dirt"),
+            Suggestion::new(17 .. 31, "minecraft:dirt_path")
+        ]
+    );
+}
+
+#[test]
+fn dispatch_requires_permission() {
+    use crate::{
+        command::{CommandOrigin, CommandSender},
+        network::{AsyncWriteHandle, WrappedClientBoundPacket},
+        QuartzServer,
+        RUNNING,
+    };
+    use quartz_net::ClientBoundPacket;
+    use std::sync::atomic::Ordering;
+
+    let mut server = QuartzServer::for_test();
+    let executor = StaticCommandExecutor::new();
+    let (handle, mut packets) = AsyncWriteHandle::detached();
+    let player = || CommandSender::Client {
+        id: 0,
+        handle: handle.clone(),
+        origin: CommandOrigin::default(),
+        permission_level: 0,
+    };
+
+    for command in ["stop", "loglevel quartz::network debug"] {
+        executor
+            .dispatch(
+                command,
+                CommandContext::new(&mut server, &executor, player()),
+            )
+            .unwrap();
+        match packets.try_recv() {
+            Ok(WrappedClientBoundPacket::Singleton(ClientBoundPacket::ChatMessage {
+                json_data,
+                ..
+            })) => assert_eq!(
+                json_data.to_plain_string(),
+                "You do not have permission to use this command"
+            ),
+            _ => panic!("Expected {command} to be refused"),
+        }
+    }
+
+    // Operator commands aren't suggested to players who can't run them
+    let context = CommandContext::new(&mut server, &executor, player());
+    assert_eq!(executor.suggestions("s", &context), vec![]);
+    let context = CommandContext::new(&mut server, &executor, CommandSender::Console);
+    assert_eq!(executor.suggestions("s", &context), vec![
+        Suggestion::new(0 .. 1, "stop"),
+        Suggestion::new(0 .. 1, "setblock")
+    ]);
+
+    // The console may run every command. Being refused would send it a message, which panics
+    // without a console to write to.
+    RUNNING.store(true, Ordering::Release);
+    executor
+        .dispatch(
+            "stop",
+            CommandContext::new(&mut server, &executor, CommandSender::Console),
+        )
+        .unwrap();
+    assert!(!RUNNING.load(Ordering::Acquire));
 }
@@ -1,6 +1,16 @@
+mod arg;
 mod context;
+mod coordinates;
 mod executor;
+mod nbt_path;
+mod reader;
+mod suggestion;
 
+pub use arg::*;
 pub use context::*;
+pub use coordinates::*;
 pub use executor::*;
+pub use nbt_path::*;
 pub use quartz_commands::*;
+pub use reader::*;
+pub use suggestion::*;
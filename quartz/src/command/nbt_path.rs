@@ -0,0 +1,128 @@
+use quartz_nbt::NbtCompound;
+
+use crate::command::{ArgumentError, NbtCompoundArgument, StringReader};
+
+/// A path to one or more tags within an NBT compound, such as `Inventory[{Slot:0b}].tag.Damage`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NbtPath {
+    nodes: Vec<NbtPathNode>,
+}
+
+/// A single element of an NBT path.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NbtPathNode {
+    /// The root compound if it contains the given tags, written as `{...}` at the start of a path.
+    MatchRootObject(NbtCompound),
+    /// The tag with the given key.
+    Key(String),
+    /// The compound with the given key if it contains the given tags, written as `key{...}`.
+    MatchObject(String, NbtCompound),
+    /// Every element of a list or array, written as `[]`.
+    AllElements,
+    /// The element at the given index, counting from the end if negative.
+    Index(i32),
+    /// Every compound in a list which contains the given tags, written as `[{...}]`.
+    MatchElement(NbtCompound),
+}
+
+impl NbtPath {
+    #[inline]
+    pub fn nodes(&self) -> &[NbtPathNode] {
+        &self.nodes
+    }
+
+    /// Parses a path from the given reader, stopping at the first whitespace after the path.
+    pub fn parse(reader: &mut StringReader<'_>) -> Result<Self, ArgumentError> {
+        let mut nodes = Vec::new();
+
+        while reader.peek().is_some_and(|ch| !ch.is_whitespace()) {
+            nodes.push(Self::parse_node(reader, nodes.is_empty())?);
+
+            match reader.peek() {
+                Some('.') => {
+                    reader.read_char();
+                }
+                Some('[' | '{') => {}
+                Some(ch) if ch.is_whitespace() => {}
+                None => {}
+                Some(_) => return Err(ArgumentError::InvalidNbtPath(reader.cursor())),
+            }
+        }
+
+        if nodes.is_empty() {
+            return Err(ArgumentError::InvalidNbtPath(reader.cursor()));
+        }
+
+        Ok(NbtPath { nodes })
+    }
+
+    fn parse_node(reader: &mut StringReader<'_>, root: bool) -> Result<NbtPathNode, ArgumentError> {
+        let start = reader.cursor();
+
+        let key = match reader.peek() {
+            Some('{') if root =>
+                return NbtCompoundArgument::parse(reader).map(NbtPathNode::MatchRootObject),
+            Some('[') => {
+                reader.read_char();
+                let node = match reader.peek() {
+                    Some('{') => NbtPathNode::MatchElement(NbtCompoundArgument::parse(reader)?),
+                    Some(']') => NbtPathNode::AllElements,
+                    _ => reader
+                        .read_while(|ch| ch == '-' || ch.is_ascii_digit())
+                        .parse::<i32>()
+                        .map(NbtPathNode::Index)
+                        .map_err(|_| ArgumentError::InvalidNbtPath(start))?,
+                };
+
+                if reader.read_char() != Some(']') {
+                    return Err(ArgumentError::InvalidNbtPath(start));
+                }
+                return Ok(node);
+            }
+            Some('"' | '\'') => reader
+                .read_quoted()
+                .ok_or(ArgumentError::InvalidNbtPath(start))?,
+            _ => reader
+                .read_while(|ch| {
+                    !ch.is_whitespace() && !matches!(ch, '"' | '\'' | '[' | ']' | '.' | '{' | '}')
+                })
+                .to_owned(),
+        };
+
+        if key.is_empty() {
+            return Err(ArgumentError::InvalidNbtPath(start));
+        }
+
+        if reader.peek() == Some('{') {
+            Ok(NbtPathNode::MatchObject(
+                key,
+                NbtCompoundArgument::parse(reader)?,
+            ))
+        } else {
+            Ok(NbtPathNode::Key(key))
+        }
+    }
+}
+
+#[test]
+fn parse_nbt_path() {
+    use quartz_nbt::compound;
+
+    let mut reader =
+        StringReader::new(r#"{a:1}.Inventory[{Slot:0b}]."custom key"[-1].b{c:2}[] Pos"#);
+    let path = NbtPath::parse(&mut reader).unwrap();
+    assert_eq!(path.nodes(), &[
+        NbtPathNode::MatchRootObject(compound! { "a": 1 }),
+        NbtPathNode::Key("Inventory".to_owned()),
+        NbtPathNode::MatchElement(compound! { "Slot": 0i8 }),
+        NbtPathNode::Key("custom key".to_owned()),
+        NbtPathNode::Index(-1),
+        NbtPathNode::MatchObject("b".to_owned(), compound! { "c": 2 }),
+        NbtPathNode::AllElements
+    ]);
+    assert_eq!(reader.remaining(), " Pos");
+
+    for invalid in ["", "a..b", "a[x]", "a[0", "a.{b:1}"] {
+        assert!(NbtPath::parse(&mut StringReader::new(invalid)).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+/// A cursor over the text of a command, used by arguments which need to consume a variable
+/// number of characters.
+#[derive(Clone, Copy, Debug)]
+pub struct StringReader<'a> {
+    string: &'a str,
+    cursor: usize,
+}
+
+impl<'a> StringReader<'a> {
+    pub const fn new(string: &'a str) -> Self {
+        StringReader { string, cursor: 0 }
+    }
+
+    /// Returns the byte index of the next character to be read.
+    #[inline]
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns the text which has not been read yet.
+    #[inline]
+    pub fn remaining(&self) -> &'a str {
+        &self.string[self.cursor ..]
+    }
+
+    #[inline]
+    pub fn can_read(&self) -> bool {
+        self.cursor < self.string.len()
+    }
+
+    #[inline]
+    pub fn peek(&self) -> Option<char> {
+        self.remaining().chars().next()
+    }
+
+    /// Consumes the next character, returning it.
+    pub fn read_char(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.cursor += ch.len_utf8();
+        Some(ch)
+    }
+
+    /// Moves the cursor forward by the given number of bytes, which must end on a character
+    /// boundary.
+    pub fn advance(&mut self, len: usize) {
+        assert!(
+            self.string.is_char_boundary(self.cursor + len),
+            "Cannot advance reader into the middle of a character"
+        );
+        self.cursor += len;
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.read_char();
+        }
+    }
+
+    /// Reads characters until the next whitespace or the end of the input.
+    pub fn read_word(&mut self) -> &'a str {
+        self.read_while(|ch| !ch.is_whitespace())
+    }
+
+    /// Reads characters for as long as they match the given predicate.
+    pub fn read_while(&mut self, mut predicate: impl FnMut(char) -> bool) -> &'a str {
+        let start = self.cursor;
+        while self.peek().is_some_and(&mut predicate) {
+            self.read_char();
+        }
+        &self.string[start .. self.cursor]
+    }
+
+    /// Reads a string surrounded by single or double quotes, handling backslash escapes. Returns
+    /// `None` if the next character is not a quote or the string is not terminated.
+    pub fn read_quoted(&mut self) -> Option<String> {
+        let start = self.cursor;
+        let quote = self.peek().filter(|&ch| ch == '"' || ch == '\'')?;
+        self.read_char();
+
+        let mut string = String::new();
+        loop {
+            match self.read_char() {
+                Some('\\') => match self.read_char() {
+                    Some(ch) if ch == '\\' || ch == quote => string.push(ch),
+                    _ => break,
+                },
+                Some(ch) if ch == quote => return Some(string),
+                Some(ch) => string.push(ch),
+                None => break,
+            }
+        }
+
+        self.cursor = start;
+        None
+    }
+}
+
+#[test]
+fn read_quoted_strings() {
+    let mut reader = StringReader::new(r#""a \"b\"" 'c'd"#);
+    assert_eq!(reader.read_quoted().as_deref(), Some(r#"a "b""#));
+    reader.skip_whitespace();
+    assert_eq!(reader.read_quoted().as_deref(), Some("c"));
+    assert_eq!(reader.read_word(), "d");
+    assert!(!reader.can_read());
+
+    let mut reader = StringReader::new("'unterminated");
+    assert_eq!(reader.read_quoted(), None);
+    assert_eq!(reader.cursor(), 0);
+}
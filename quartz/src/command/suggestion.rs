@@ -0,0 +1,67 @@
+use std::ops::Range;
+
+use qdat::block::states::BLOCK_LOOKUP_BY_NAME;
+
+use crate::command::resource_matches;
+
+/// A completion for the word being typed, which replaces the given range of the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Suggestion {
+    /// The byte range of the input replaced by this suggestion.
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl Suggestion {
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        Suggestion {
+            range,
+            text: text.into(),
+        }
+    }
+
+    /// Creates a suggestion which replaces the last word of the input, or is inserted at the end
+    /// of the input if it ends with whitespace.
+    pub fn for_last_word(input: &str, text: impl Into<String>) -> Self {
+        Self::new(last_word_start(input) .. input.len(), text)
+    }
+}
+
+/// Returns the byte index at which the last word of the input starts.
+pub fn last_word_start(input: &str) -> usize {
+    input.trim_end_matches(|ch: char| !ch.is_whitespace()).len()
+}
+
+/// Returns the block ids which could complete the given partial argument, such as
+/// `minecraft:oak_stairs` for `oak_sta`.
+pub fn block_suggestions(partial: &str) -> Vec<String> {
+    let mut blocks = BLOCK_LOOKUP_BY_NAME
+        .keys()
+        .map(|identifier| format!("minecraft:{identifier}"))
+        .filter(|name| resource_matches(name, partial))
+        .collect::<Vec<_>>();
+    blocks.sort_unstable();
+    blocks
+}
+
+#[test]
+fn suggest_last_word_and_blocks() {
+    assert_eq!(
+        Suggestion::for_last_word("time s", "set"),
+        Suggestion::new(5 .. 6, "set")
+    );
+    assert_eq!(
+        Suggestion::for_last_word("time ", "set"),
+        Suggestion::new(5 .. 5, "set")
+    );
+    assert_eq!(
+        Suggestion::for_last_word("ti", "time"),
+        Suggestion::new(0 .. 2, "time")
+    );
+
+    assert_eq!(block_suggestions("oak_sta"), vec!["minecraft:oak_stairs"]);
+    assert_eq!(block_suggestions("minecraft:dirt"), vec![
+        "minecraft:dirt",
+        "minecraft:dirt_path"
+    ]);
+}
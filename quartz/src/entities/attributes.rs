@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use qdat::{UlnStr, UnlocalizedName};
+use quartz_net::packet_data::{self, EntityProperty};
+use uuid::Uuid;
+
+/// How a modifier is applied to the value of an attribute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i8)]
+pub enum ModifierOperation {
+    /// Adds the modifier's amount to the base value.
+    Add = 0,
+    /// Adds the modified base value multiplied by the modifier's amount.
+    MultiplyBase = 1,
+    /// Multiplies the value by one plus the modifier's amount.
+    MultiplyTotal = 2,
+}
+
+#[derive(Clone, Debug)]
+pub struct AttributeModifier {
+    pub uuid: Uuid,
+    pub amount: f64,
+    pub operation: ModifierOperation,
+}
+
+impl AttributeModifier {
+    pub fn new(uuid: Uuid, amount: f64, operation: ModifierOperation) -> Self {
+        AttributeModifier {
+            uuid,
+            amount,
+            operation,
+        }
+    }
+}
+
+/// A single attribute of an entity with a base value and a set of modifiers.
+#[derive(Clone, Debug)]
+pub struct Attribute {
+    base: f64,
+    modifiers: Vec<AttributeModifier>,
+}
+
+impl Attribute {
+    pub fn new(base: f64) -> Self {
+        Attribute {
+            base,
+            modifiers: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn base(&self) -> f64 {
+        self.base
+    }
+
+    #[inline]
+    pub fn set_base(&mut self, base: f64) {
+        self.base = base;
+    }
+
+    #[inline]
+    pub fn modifiers(&self) -> &[AttributeModifier] {
+        &self.modifiers
+    }
+
+    /// Adds the given modifier, replacing any existing modifier with the same UUID.
+    pub fn add_modifier(&mut self, modifier: AttributeModifier) {
+        self.remove_modifier(modifier.uuid);
+        self.modifiers.push(modifier);
+    }
+
+    /// Removes and returns the modifier with the given UUID.
+    pub fn remove_modifier(&mut self, uuid: Uuid) -> Option<AttributeModifier> {
+        let index = self
+            .modifiers
+            .iter()
+            .position(|modifier| modifier.uuid == uuid)?;
+        Some(self.modifiers.remove(index))
+    }
+
+    /// Computes the final value of this attribute. Like vanilla, all additions are applied first,
+    /// then all base multipliers, then all total multipliers, regardless of the order in which the
+    /// modifiers were added.
+    pub fn value(&self) -> f64 {
+        let amounts = |operation| {
+            self.modifiers
+                .iter()
+                .filter(move |modifier| modifier.operation == operation)
+                .map(|modifier| modifier.amount)
+        };
+
+        let base = self.base + amounts(ModifierOperation::Add).sum::<f64>();
+        let value = base + base * amounts(ModifierOperation::MultiplyBase).sum::<f64>();
+        amounts(ModifierOperation::MultiplyTotal)
+            .fold(value, |value, amount| value * (1.0 + amount))
+    }
+}
+
+/// The attributes of an entity, such as its max health or movement speed.
+#[derive(Clone, Debug, Default)]
+pub struct Attributes {
+    attributes: HashMap<UnlocalizedName, Attribute>,
+}
+
+impl Attributes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an attribute with the given base value, replacing any existing attribute with the same
+    /// name.
+    pub fn insert(&mut self, name: UnlocalizedName, base: f64) -> &mut Attribute {
+        self.attributes.insert(name.clone(), Attribute::new(base));
+        // Unwrap is safe since we just inserted the attribute
+        self.attributes.get_mut(&name).unwrap()
+    }
+
+    pub fn get(&self, name: &UlnStr) -> Option<&Attribute> {
+        self.attributes.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &UlnStr) -> Option<&mut Attribute> {
+        self.attributes.get_mut(name)
+    }
+
+    /// Returns the final value of the given attribute if the entity has it.
+    pub fn value(&self, name: &UlnStr) -> Option<f64> {
+        self.get(name).map(Attribute::value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&UnlocalizedName, &Attribute)> {
+        self.attributes.iter()
+    }
+
+    /// Converts these attributes into the properties sent in the entity properties packet.
+    pub fn to_properties(&self) -> Box<[EntityProperty]> {
+        self.attributes
+            .iter()
+            .map(|(name, attribute)| {
+                let modifiers = attribute
+                    .modifiers
+                    .iter()
+                    .map(|modifier| {
+                        packet_data::AttributeModifier::new(
+                            modifier.uuid,
+                            modifier.amount,
+                            modifier.operation as i8,
+                        )
+                    })
+                    .collect();
+
+                EntityProperty::new(name.clone(), attribute.base, modifiers)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn multiply_total_modifier() {
+    let mut attribute = Attribute::new(20.0);
+    attribute.add_modifier(AttributeModifier::new(
+        Uuid::from_u128(1),
+        0.1,
+        ModifierOperation::MultiplyTotal,
+    ));
+
+    assert!((attribute.value() - 22.0).abs() < 1e-9);
+}
+
+#[test]
+fn modifier_operation_order() {
+    let mut attribute = Attribute::new(10.0);
+    attribute.add_modifier(AttributeModifier::new(
+        Uuid::from_u128(1),
+        1.0,
+        ModifierOperation::MultiplyTotal,
+    ));
+    attribute.add_modifier(AttributeModifier::new(
+        Uuid::from_u128(2),
+        0.5,
+        ModifierOperation::MultiplyBase,
+    ));
+    attribute.add_modifier(AttributeModifier::new(
+        Uuid::from_u128(3),
+        2.0,
+        ModifierOperation::Add,
+    ));
+
+    // ((10 + 2) + 12 * 0.5) * (1 + 1)
+    assert!((attribute.value() - 36.0).abs() < 1e-9);
+}
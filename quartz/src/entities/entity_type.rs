@@ -0,0 +1,349 @@
+use qdat::{world::location::BlockPosition, UlnStr};
+use uuid::Uuid;
+
+use crate::{
+    entities::Position,
+    network::{encode_angle, ClientBoundPacket},
+};
+
+/// The size of an entity's bounding box in blocks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntityDimensions {
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Which packet the client expects when an entity of a given type comes into view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpawnPacketType {
+    /// Non-living entities such as items and arrows, which have a type-specific data value.
+    Object,
+    Living,
+    /// Experience orbs send the amount of experience instead of a type and UUID.
+    ExperienceOrb,
+    /// Players are spawned with the UUID of their profile, which the client must already know
+    /// through the player info packet.
+    Player,
+    /// Paintings are placed on a block face, with their motive sent in place of the type.
+    Painting,
+}
+
+/// A kind of entity, such as an item or a zombie.
+#[derive(Debug, PartialEq)]
+pub struct EntityType {
+    /// The identifier of the type in the `minecraft` namespace.
+    pub name: &'static str,
+    /// The ID the client knows this type by.
+    pub network_id: i32,
+    pub dimensions: EntityDimensions,
+    pub spawn_packet: SpawnPacketType,
+}
+
+macro_rules! entity_types {
+    ($($name: literal = $id: literal, $width: literal x $height: literal, $spawn: ident;)*) => {
+        /// The entity types known to the server, in order of their network ID.
+        pub static ENTITY_TYPES: &[EntityType] = &[$(
+            EntityType {
+                name: $name,
+                network_id: $id,
+                dimensions: EntityDimensions {
+                    width: $width,
+                    height: $height,
+                },
+                spawn_packet: SpawnPacketType::$spawn,
+            }
+        ),*];
+    };
+}
+
+entity_types! {
+    "area_effect_cloud" = 0, 6.0 x 0.5, Object;
+    "armor_stand" = 1, 0.5 x 1.975, Living;
+    "arrow" = 2, 0.5 x 0.5, Object;
+    "axolotl" = 3, 0.75 x 0.42, Living;
+    "bat" = 4, 0.5 x 0.9, Living;
+    "bee" = 5, 0.7 x 0.6, Living;
+    "blaze" = 6, 0.6 x 1.8, Living;
+    "boat" = 7, 1.375 x 0.5625, Object;
+    "cat" = 8, 0.6 x 0.7, Living;
+    "cave_spider" = 9, 0.7 x 0.5, Living;
+    "chicken" = 10, 0.4 x 0.7, Living;
+    "cod" = 11, 0.5 x 0.3, Living;
+    "cow" = 12, 0.9 x 1.4, Living;
+    "creeper" = 13, 0.6 x 1.7, Living;
+    "dolphin" = 14, 0.9 x 0.6, Living;
+    "donkey" = 15, 1.3964844 x 1.5, Living;
+    "dragon_fireball" = 16, 1.0 x 1.0, Object;
+    "drowned" = 17, 0.6 x 1.95, Living;
+    "elder_guardian" = 18, 1.9975 x 1.9975, Living;
+    "end_crystal" = 19, 2.0 x 2.0, Object;
+    "ender_dragon" = 20, 16.0 x 8.0, Living;
+    "enderman" = 21, 0.6 x 2.9, Living;
+    "endermite" = 22, 0.4 x 0.3, Living;
+    "evoker" = 23, 0.6 x 1.95, Living;
+    "evoker_fangs" = 24, 0.5 x 0.8, Object;
+    "experience_orb" = 25, 0.5 x 0.5, ExperienceOrb;
+    "eye_of_ender" = 26, 0.25 x 0.25, Object;
+    "falling_block" = 27, 0.98 x 0.98, Object;
+    "firework_rocket" = 28, 0.25 x 0.25, Object;
+    "fox" = 29, 0.6 x 0.7, Living;
+    "ghast" = 30, 4.0 x 4.0, Living;
+    "giant" = 31, 3.6 x 12.0, Living;
+    "glow_item_frame" = 32, 0.5 x 0.5, Object;
+    "glow_squid" = 33, 0.8 x 0.8, Living;
+    "goat" = 34, 0.9 x 1.3, Living;
+    "guardian" = 35, 0.85 x 0.85, Living;
+    "hoglin" = 36, 1.3964844 x 1.4, Living;
+    "horse" = 37, 1.3964844 x 1.6, Living;
+    "husk" = 38, 0.6 x 1.95, Living;
+    "illusioner" = 39, 0.6 x 1.95, Living;
+    "iron_golem" = 40, 1.4 x 2.7, Living;
+    "item" = 41, 0.25 x 0.25, Object;
+    "item_frame" = 42, 0.5 x 0.5, Object;
+    "fireball" = 43, 1.0 x 1.0, Object;
+    "leash_knot" = 44, 0.375 x 0.5, Object;
+    "lightning_bolt" = 45, 0.0 x 0.0, Object;
+    "llama" = 46, 0.9 x 1.87, Living;
+    "llama_spit" = 47, 0.25 x 0.25, Object;
+    "magma_cube" = 48, 2.04 x 2.04, Living;
+    "marker" = 49, 0.0 x 0.0, Object;
+    "minecart" = 50, 0.98 x 0.7, Object;
+    "chest_minecart" = 51, 0.98 x 0.7, Object;
+    "command_block_minecart" = 52, 0.98 x 0.7, Object;
+    "furnace_minecart" = 53, 0.98 x 0.7, Object;
+    "hopper_minecart" = 54, 0.98 x 0.7, Object;
+    "spawner_minecart" = 55, 0.98 x 0.7, Object;
+    "tnt_minecart" = 56, 0.98 x 0.7, Object;
+    "mule" = 57, 1.3964844 x 1.6, Living;
+    "mooshroom" = 58, 0.9 x 1.4, Living;
+    "ocelot" = 59, 0.6 x 0.7, Living;
+    "painting" = 60, 0.5 x 0.5, Painting;
+    "panda" = 61, 1.3 x 1.25, Living;
+    "parrot" = 62, 0.5 x 0.9, Living;
+    "phantom" = 63, 0.9 x 0.5, Living;
+    "pig" = 64, 0.9 x 0.9, Living;
+    "piglin" = 65, 0.6 x 1.95, Living;
+    "piglin_brute" = 66, 0.6 x 1.95, Living;
+    "pillager" = 67, 0.6 x 1.95, Living;
+    "polar_bear" = 68, 1.4 x 1.4, Living;
+    "tnt" = 69, 0.98 x 0.98, Object;
+    "pufferfish" = 70, 0.7 x 0.7, Living;
+    "rabbit" = 71, 0.4 x 0.5, Living;
+    "ravager" = 72, 1.95 x 2.2, Living;
+    "salmon" = 73, 0.7 x 0.4, Living;
+    "sheep" = 74, 0.9 x 1.3, Living;
+    "shulker" = 75, 1.0 x 1.0, Living;
+    "shulker_bullet" = 76, 0.3125 x 0.3125, Object;
+    "silverfish" = 77, 0.4 x 0.3, Living;
+    "skeleton" = 78, 0.6 x 1.99, Living;
+    "skeleton_horse" = 79, 1.3964844 x 1.6, Living;
+    "slime" = 80, 2.04 x 2.04, Living;
+    "small_fireball" = 81, 0.3125 x 0.3125, Object;
+    "snow_golem" = 82, 0.7 x 1.9, Living;
+    "snowball" = 83, 0.25 x 0.25, Object;
+    "spectral_arrow" = 84, 0.5 x 0.5, Object;
+    "spider" = 85, 1.4 x 0.9, Living;
+    "squid" = 86, 0.8 x 0.8, Living;
+    "stray" = 87, 0.6 x 1.99, Living;
+    "strider" = 88, 0.9 x 1.7, Living;
+    "egg" = 89, 0.25 x 0.25, Object;
+    "ender_pearl" = 90, 0.25 x 0.25, Object;
+    "experience_bottle" = 91, 0.25 x 0.25, Object;
+    "potion" = 92, 0.25 x 0.25, Object;
+    "trident" = 93, 0.5 x 0.5, Object;
+    "trader_llama" = 94, 0.9 x 1.87, Living;
+    "tropical_fish" = 95, 0.5 x 0.4, Living;
+    "turtle" = 96, 1.2 x 0.4, Living;
+    "vex" = 97, 0.4 x 0.8, Living;
+    "villager" = 98, 0.6 x 1.95, Living;
+    "vindicator" = 99, 0.6 x 1.95, Living;
+    "wandering_trader" = 100, 0.6 x 1.95, Living;
+    "witch" = 101, 0.6 x 1.95, Living;
+    "wither" = 102, 0.9 x 3.5, Living;
+    "wither_skeleton" = 103, 0.7 x 2.4, Living;
+    "wither_skull" = 104, 0.3125 x 0.3125, Object;
+    "wolf" = 105, 0.6 x 0.85, Living;
+    "zoglin" = 106, 1.3964844 x 1.4, Living;
+    "zombie" = 107, 0.6 x 1.95, Living;
+    "zombie_horse" = 108, 1.3964844 x 1.6, Living;
+    "zombie_villager" = 109, 0.6 x 1.95, Living;
+    "zombified_piglin" = 110, 0.6 x 1.95, Living;
+    "player" = 111, 0.6 x 1.8, Player;
+    "fishing_bobber" = 112, 0.25 x 0.25, Object;
+}
+
+/// The rotation of an entity in degrees.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rotation {
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Rotation {
+    pub const fn new(yaw: f32, pitch: f32) -> Self {
+        Rotation { yaw, pitch }
+    }
+}
+
+impl EntityType {
+    /// Looks up a type by name, which must be in the `minecraft` namespace.
+    pub fn from_name(name: &UlnStr) -> Option<&'static EntityType> {
+        if name.namespace() != "minecraft" {
+            return None;
+        }
+
+        ENTITY_TYPES
+            .iter()
+            .find(|entity_type| entity_type.name == name.identifier())
+    }
+
+    pub fn from_network_id(network_id: i32) -> Option<&'static EntityType> {
+        ENTITY_TYPES
+            .binary_search_by_key(&network_id, |entity_type| entity_type.network_id)
+            .ok()
+            .map(|index| &ENTITY_TYPES[index])
+    }
+
+    /// Creates the packet which makes an entity of this type appear on the client. The meaning of
+    /// `data` depends on the type, such as the block state of a falling block or the amount of
+    /// experience in an experience orb or the motive of a painting, and is ignored by living entities
+    /// and players.
+    pub fn spawn_packet(
+        &self,
+        entity_id: i32,
+        uuid: Uuid,
+        position: &Position,
+        rotation: Rotation,
+        data: i32,
+    ) -> ClientBoundPacket {
+        let yaw = encode_angle(rotation.yaw);
+        let pitch = encode_angle(rotation.pitch);
+
+        match self.spawn_packet {
+            SpawnPacketType::Object => ClientBoundPacket::SpawnEntity {
+                entity_id,
+                object_uuid: uuid,
+                r#type: self.network_id,
+                x: position.x,
+                y: position.y,
+                z: position.z,
+                pitch,
+                yaw,
+                data,
+                velocity_x: 0,
+                velocity_y: 0,
+                velocity_z: 0,
+            },
+            SpawnPacketType::Living => ClientBoundPacket::SpawnLivingEntity {
+                entity_id,
+                entity_uuid: uuid,
+                r#type: self.network_id,
+                x: position.x,
+                y: position.y,
+                z: position.z,
+                yaw,
+                pitch,
+                head_pitch: yaw,
+                velocity_x: 0,
+                velocity_y: 0,
+                velocity_z: 0,
+            },
+            SpawnPacketType::ExperienceOrb => ClientBoundPacket::SpawnExperienceOrb {
+                entity_id,
+                x: position.x,
+                y: position.y,
+                z: position.z,
+                count: data.clamp(0, i16::MAX as i32) as i16,
+            },
+            SpawnPacketType::Player => ClientBoundPacket::SpawnPlayer {
+                entity_id,
+                player_uuid: uuid,
+                x: position.x,
+                y: position.y,
+                z: position.z,
+                yaw,
+                pitch,
+            },
+            SpawnPacketType::Painting => ClientBoundPacket::SpawnPainting {
+                entity_id,
+                entity_uuid: uuid,
+                motive: data,
+                location: BlockPosition {
+                    x: position.x.floor() as i32,
+                    y: position.y.floor() as i16,
+                    z: position.z.floor() as i32,
+                },
+                // The horizontal direction the painting faces, starting at south
+                direction: ((rotation.yaw / 90.0 + 0.5).floor() as i32 & 3) as i8,
+            },
+        }
+    }
+}
+
+#[test]
+fn spawn_item_and_player() {
+    use qdat::UnlocalizedName;
+
+    let item = EntityType::from_name(&UnlocalizedName::minecraft("item")).unwrap();
+    let position = Position {
+        x: 1.5,
+        y: 64.0,
+        z: -3.25,
+    };
+    match item.spawn_packet(
+        7,
+        Uuid::from_u128(1),
+        &position,
+        Rotation::new(90.0, -45.0),
+        1,
+    ) {
+        ClientBoundPacket::SpawnEntity {
+            entity_id,
+            r#type,
+            x,
+            y,
+            z,
+            pitch,
+            yaw,
+            data,
+            ..
+        } => {
+            assert_eq!(entity_id, 7);
+            assert_eq!(r#type, 41);
+            assert_eq!((x, y, z), (1.5, 64.0, -3.25));
+            assert_eq!((yaw, pitch), (64, 224));
+            assert_eq!(data, 1);
+        }
+        _ => panic!("Items should use the spawn entity packet"),
+    }
+
+    let player = EntityType::from_network_id(111).unwrap();
+    assert_eq!(player.name, "player");
+    match player.spawn_packet(
+        8,
+        Uuid::from_u128(2),
+        &position,
+        Rotation::new(-90.0, 0.0),
+        0,
+    ) {
+        ClientBoundPacket::SpawnPlayer {
+            entity_id,
+            player_uuid,
+            yaw,
+            pitch,
+            ..
+        } => {
+            assert_eq!(entity_id, 8);
+            assert_eq!(player_uuid, Uuid::from_u128(2));
+            assert_eq!((yaw, pitch), (192, 0));
+        }
+        _ => panic!("Players should use the spawn player packet"),
+    }
+
+    assert!(EntityType::from_name(&UnlocalizedName::from_str("mod:item").unwrap()).is_none());
+    assert_eq!(ENTITY_TYPES.len(), 113);
+    assert!(ENTITY_TYPES
+        .iter()
+        .enumerate()
+        .all(|(index, entity_type)| entity_type.network_id == index as i32));
+}
@@ -1,5 +1,7 @@
 use qdat::world::location::BlockPosition;
 
+pub mod attributes;
+pub mod entity_type;
 pub mod player;
 
 
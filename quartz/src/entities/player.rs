@@ -4,7 +4,7 @@ use qdat::Gamemode;
 
 use crate::{
     entities::Position,
-    item::{Inventory, OptionalItemStack, EMPTY_ITEM_STACK},
+    item::{ClickMode, Inventory, OptionalItemStack, EMPTY_ITEM_STACK},
     network::AsyncWriteHandle,
 };
 
@@ -73,6 +73,31 @@ impl PlayerInventory {
     pub fn swap_slots(&mut self, a: usize, b: usize) {
         self.inv.swap(a, b);
     }
+
+    /// Handles a click in the player's inventory window. Shift clicks move stacks between the
+    /// hotbar and the main inventory, and out of the crafting and armor slots.
+    pub fn handle_click(
+        &mut self,
+        slot: i16,
+        button: i8,
+        mode: ClickMode,
+        carried: &mut OptionalItemStack,
+    ) -> bool {
+        if mode != ClickMode::QuickMove {
+            return self.inv.handle_click(slot, button, mode, carried);
+        }
+
+        let target = match slot {
+            // Hotbar
+            36 ..= 44 => 9 .. 36,
+            // Main inventory
+            9 ..= 35 => 36 .. 45,
+            // Crafting, armor and offhand
+            0 ..= 8 | 45 => 9 .. 45,
+            _ => return false,
+        };
+        self.inv.quick_move(slot as usize, target)
+    }
 }
 
 impl Default for PlayerInventory {
@@ -80,3 +105,30 @@ impl Default for PlayerInventory {
         Self::new()
     }
 }
+
+#[test]
+fn pickup_and_shift_click() {
+    use crate::item::ItemStack;
+    use qdat::item::ITEM_LOOKUP_BY_NUMERIC_ID;
+
+    let mut inventory = PlayerInventory::new();
+    let mut stack = ItemStack::new(&ITEM_LOOKUP_BY_NUMERIC_ID[&1]);
+    stack.count = 32;
+    inventory.set_slot(36, stack.into());
+
+    // Left click the hotbar slot to pick up the stack
+    let mut carried = EMPTY_ITEM_STACK;
+    assert!(inventory.handle_click(36, 0, ClickMode::Pickup, &mut carried));
+    assert!(inventory.inv.get(36).is_empty());
+    assert_eq!(carried.stack().map(|stack| stack.count), Some(32));
+
+    // Put it back and shift click it into the main inventory
+    assert!(inventory.handle_click(36, 0, ClickMode::Pickup, &mut carried));
+    assert!(carried.is_empty());
+    assert!(inventory.handle_click(36, 0, ClickMode::QuickMove, &mut carried));
+    assert!(inventory.inv.get(36).is_empty());
+    assert_eq!(
+        inventory.inv.get(9).stack().map(|stack| stack.count),
+        Some(32)
+    );
+}
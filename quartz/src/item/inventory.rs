@@ -1,5 +1,42 @@
 use crate::item::{ItemStack, OptionalItemStack};
 use quartz_nbt::{NbtCompound, NbtList};
+use std::ops::Range;
+
+/// The kinds of clicks a client can make in an inventory window, given by the mode field of the
+/// click window packet
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClickMode {
+    /// A left or right click which picks up or places items
+    Pickup,
+    /// A shift click which moves a stack to another part of the inventory
+    QuickMove,
+    /// A number key or offhand key press which swaps a stack with a hotbar slot
+    Swap,
+    /// A middle click in creative mode which copies a stack
+    Clone,
+    /// A drop key press which throws items out of the inventory
+    Throw,
+    /// A drag across multiple slots which spreads the carried stack over them
+    QuickCraft,
+    /// A double click which collects matching items into the carried stack
+    PickupAll,
+}
+
+impl ClickMode {
+    /// Gets the click mode with the given network id
+    pub const fn from_id(id: i32) -> Option<Self> {
+        Some(match id {
+            0 => ClickMode::Pickup,
+            1 => ClickMode::QuickMove,
+            2 => ClickMode::Swap,
+            3 => ClickMode::Clone,
+            4 => ClickMode::Throw,
+            5 => ClickMode::QuickCraft,
+            6 => ClickMode::PickupAll,
+            _ => return None,
+        })
+    }
+}
 
 /// Represents a basic inventory
 #[derive(Clone)]
@@ -54,6 +91,135 @@ impl Inventory {
         self.items.swap(a, b);
     }
 
+    /// Handles a click on a slot, updating the inventory and the stack carried by the cursor
+    ///
+    /// Only pickup and quick move clicks are currently supported, other clicks and clicks outside
+    /// of the inventory are rejected. Returns whether or not the click was valid
+    pub fn handle_click(
+        &mut self,
+        slot: i16,
+        button: i8,
+        mode: ClickMode,
+        carried: &mut OptionalItemStack,
+    ) -> bool {
+        let index = match usize::try_from(slot) {
+            Ok(index) if index < self.size => index,
+            _ => return false,
+        };
+
+        match mode {
+            ClickMode::Pickup => self.pickup(index, button, carried),
+            ClickMode::QuickMove => self.quick_move(index, 0 .. self.size),
+            _ => false,
+        }
+    }
+
+    /// Handles a left (button 0) or right (button 1) click on a slot
+    fn pickup(&mut self, index: usize, button: i8, carried: &mut OptionalItemStack) -> bool {
+        let left_click = match button {
+            0 => true,
+            1 => false,
+            _ => return false,
+        };
+        let slot = &mut self.items[index];
+
+        match (slot.stack_mut(), carried.stack_mut()) {
+            (Some(slot_stack), Some(carried_stack))
+                if !slot_stack.is_empty() && !carried_stack.is_empty() =>
+                if slot_stack.can_stack_with(carried_stack) {
+                    let space = slot_stack.item.stack_size.saturating_sub(slot_stack.count);
+                    let moved = if left_click {
+                        carried_stack.count.min(space)
+                    } else {
+                        space.min(1)
+                    };
+
+                    slot_stack.count += moved;
+                    carried_stack.count -= moved;
+                    if carried_stack.count == 0 {
+                        carried.take();
+                    }
+                } else {
+                    std::mem::swap(slot, carried);
+                },
+            // Take the whole stack, or half of it rounded up
+            (Some(slot_stack), _) if !slot_stack.is_empty() =>
+                if left_click {
+                    *carried = slot.take();
+                } else {
+                    let half = slot_stack.count.div_ceil(2);
+                    *carried = Self::split(slot, half);
+                },
+            // Place the whole stack, or a single item
+            (_, Some(carried_stack)) if !carried_stack.is_empty() =>
+                if left_click {
+                    *slot = carried.take();
+                } else {
+                    *slot = Self::split(carried, 1);
+                },
+            _ => {}
+        }
+
+        true
+    }
+
+    /// Removes the given number of items from the stack, returning them as a new stack
+    fn split(stack: &mut OptionalItemStack, count: u8) -> OptionalItemStack {
+        let inner = match stack.stack_mut() {
+            Some(inner) => inner,
+            None => return OptionalItemStack::new(None),
+        };
+
+        let mut split = inner.clone();
+        split.count = count.min(inner.count);
+        inner.count -= split.count;
+        if inner.count == 0 {
+            stack.take();
+        }
+
+        split.into()
+    }
+
+    /// Moves the stack in the given slot into the slots in the target range, first filling up
+    /// matching stacks and then the first empty slot
+    pub fn quick_move(&mut self, index: usize, target: Range<usize>) -> bool {
+        if index >= self.size {
+            return false;
+        }
+
+        let target = target.start .. target.end.min(self.size);
+        let mut moving = self.items[index].take();
+        let stack = match moving.stack_mut() {
+            Some(stack) if !stack.is_empty() => stack,
+            _ => {
+                self.items[index] = moving;
+                return true;
+            }
+        };
+
+        for i in target.clone().filter(|&i| i != index) {
+            if let Some(other) = self.items[i].stack_mut() {
+                if !other.is_empty() && other.can_stack_with(stack) {
+                    let moved = stack
+                        .count
+                        .min(other.item.stack_size.saturating_sub(other.count));
+                    other.count += moved;
+                    stack.count -= moved;
+
+                    if stack.count == 0 {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        let empty_slot = target
+            .filter(|&i| i != index)
+            .find(|&i| self.items[i].is_empty());
+        self.items[empty_slot.unwrap_or(index)] = moving;
+        true
+    }
+
     /// Creates a new Inventory from a NbtCompound
     ///
     /// # NBT Format
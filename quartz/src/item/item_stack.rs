@@ -1,5 +1,6 @@
 use qdat::{item::Item, UlnStr};
 use quartz_nbt::NbtCompound;
+use quartz_net::packet_data::Slot;
 
 use super::get_item;
 
@@ -99,6 +100,35 @@ impl ItemStack {
     pub fn is_empty(&self) -> bool {
         self.count == 0 || self.item.id == UlnStr::minecraft("air")
     }
+
+    /// Returns whether the items in this stack and the given stack can be merged into one stack
+    pub fn can_stack_with(&self, other: &ItemStack) -> bool {
+        self.item.id == other.item.id && self.damage == other.damage && self.nbt == other.nbt
+    }
+}
+
+impl From<&ItemStack> for Slot {
+    fn from(stack: &ItemStack) -> Self {
+        if stack.is_empty() {
+            return Slot {
+                present: false,
+                item_id: None,
+                item_count: None,
+                nbt: None,
+            };
+        }
+
+        Slot {
+            present: true,
+            item_id: Some(stack.item.num_id as i32),
+            item_count: Some(stack.count as i8),
+            nbt: if stack.nbt.is_empty() {
+                None
+            } else {
+                Some(stack.nbt.clone())
+            },
+        }
+    }
 }
 
 /// An ItemStack wrapped in an Option to save memory when it is empty
@@ -139,6 +169,16 @@ impl OptionalItemStack {
         self.0.clone()
     }
 
+    /// Gets a reference to the inner stack
+    pub fn stack(&self) -> Option<&ItemStack> {
+        self.0.as_deref()
+    }
+
+    /// Gets a mutable reference to the inner stack
+    pub fn stack_mut(&mut self) -> Option<&mut ItemStack> {
+        self.0.as_deref_mut()
+    }
+
     /// Takes the stack and replaces it with an empty stack
     pub fn take(&mut self) -> OptionalItemStack {
         std::mem::take(self)
@@ -22,6 +22,8 @@ pub use quartz_util as util;
 mod base;
 /// Contains all relevant code to blocks and their implementations.
 pub mod block;
+/// Contains boss bars and the packets which display them.
+pub mod boss_bar;
 /// Defines a brigadier-like command system for rust.
 pub mod command;
 
@@ -33,6 +35,8 @@ pub mod item;
 /// Contains packet definitions and connection handlers.
 pub mod network;
 pub mod scheduler;
+/// Contains scoreboard objectives, scores and teams.
+pub mod scoreboard;
 /// Contains world and chunk implementations, including chunk I/O utilities.
 pub mod world;
 
@@ -13,7 +13,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     )?;
 
     let config = match load_config(Path::new("./config.json")) {
-        Ok(cfg) => cfg,
+        Ok((cfg, _warnings)) => cfg,
         Err(error) => {
             error!("Failed to load config: {}", error);
             return Ok(());
@@ -1,4 +1,4 @@
-use crate::{network::*, server::ClientId};
+use crate::{config, network::*, server::ClientId};
 
 use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
 use log::*;
@@ -7,19 +7,24 @@ use openssl::{
     symm::{Cipher, Crypter, Mode},
 };
 use parking_lot::Mutex;
+use quartz_chat::Component;
 use quartz_net::{
+    is_legacy_ping,
     ClientBoundPacket,
     ConnectionState,
     PacketBuffer,
     PacketSerdeError,
     ServerBoundPacket,
-    LEGACY_PING_PACKET_ID,
+    MAX_PACKET_LEN,
+    MAX_UNCOMPRESSED_PACKET_LEN,
 };
 use std::{
     future::Future,
     io::{Error as IoError, ErrorKind as IoErrorKind, Read, Result, Write},
+    net::IpAddr,
     result::Result as StdResult,
-    sync::{mpsc::Sender as StdSender, Arc},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
@@ -37,6 +42,10 @@ pub struct IoHandle {
     compression_threshold: i32,
     encrypter: Option<Crypter>,
     decrypter: Option<Crypter>,
+    max_packet_size: usize,
+    max_uncompressed_size: usize,
+    /// Whether the client was sent Start Configuration and has not acknowledged it yet.
+    configuration_requested: bool,
 }
 
 impl IoHandle {
@@ -46,9 +55,20 @@ impl IoHandle {
             compression_threshold: -1,
             encrypter: None,
             decrypter: None,
+            max_packet_size: MAX_PACKET_LEN,
+            max_uncompressed_size: MAX_UNCOMPRESSED_PACKET_LEN,
+            configuration_requested: false,
         }
     }
 
+    /// Sets the maximum length of a received packet, and the maximum length of a compressed
+    /// packet's data once it is decompressed. Packets declaring a larger length are rejected before
+    /// their data is read. The limits can be lowered but never raised above those used by vanilla.
+    pub fn set_size_limits(&mut self, max_packet_size: usize, max_uncompressed_size: usize) {
+        self.max_packet_size = max_packet_size.min(MAX_PACKET_LEN);
+        self.max_uncompressed_size = max_uncompressed_size.min(MAX_UNCOMPRESSED_PACKET_LEN);
+    }
+
     /// Encrypts the given source bytes if encryption is enabled and writes them to the stream
     /// using the temporary buffer for the encryption.
     fn write_encrypted<'a>(
@@ -113,6 +133,14 @@ impl IoHandle {
         self.compression_threshold = compression_threshold;
     }
 
+    /// Notes that the given packet is being sent, so that the client is only allowed to return to
+    /// the configuration state once it has been sent Start Configuration.
+    fn record_sent(&mut self, packet: &ClientBoundPacket) {
+        if matches!(packet, ClientBoundPacket::StartConfiguration) {
+            self.configuration_requested = true;
+        }
+    }
+
     fn preprocess_packet<'a>(
         packet_data: &'a mut PacketBuffer,
         aux_buffer: &'a mut PacketBuffer,
@@ -222,34 +250,7 @@ impl IoHandle {
             self.decrypt_buffer(&mut *packet_buffer, aux_buffer, 0);
         }
 
-        // Read the packet header
-
-        // Length of the packet in its raw, unaltered form
-        let raw_len = packet_buffer.read_varying::<i32>()? as usize;
-        // Length of the uncompressed packet data excluding the raw length header
-        let mut data_len: usize;
-        let compressed: bool;
-
-        // Compression is active
-        if self.compression_threshold >= 0 {
-            // Read the length of the uncompressed packet data
-            data_len = packet_buffer.read_varying::<i32>()? as usize;
-
-            // If that length is zero, the packet was not compressed
-            if data_len == 0 {
-                data_len = raw_len - PacketBuffer::varint_size(raw_len as i32);
-                compressed = false;
-            }
-            // The packet is compressed
-            else {
-                compressed = true;
-            }
-        }
-        // Compression is not active
-        else {
-            data_len = raw_len;
-            compressed = false;
-        }
+        let (raw_len, data_len, compressed) = self.read_header(packet_buffer)?;
 
         Ok(async move {
             if raw_len <= packet_buffer.len() {
@@ -281,6 +282,50 @@ impl IoHandle {
         })
     }
 
+    /// Reads the packet header, returning the raw length of the packet, the length of its data once
+    /// uncompressed, and whether or not it is compressed. Lengths over the size limits are rejected
+    /// so that we never allocate space for them.
+    fn read_header(
+        &self,
+        packet_buffer: &mut PacketBuffer,
+    ) -> StdResult<(usize, usize, bool), PacketSerdeError> {
+        // Length of the packet in its raw, unaltered form
+        let raw_len = Self::check_length(packet_buffer.read_varying()?, self.max_packet_size)?;
+
+        // Compression is active
+        if self.compression_threshold >= 0 {
+            // Length of the uncompressed packet data excluding the raw length header
+            let data_len =
+                Self::check_length(packet_buffer.read_varying()?, self.max_uncompressed_size)?;
+
+            // If that length is zero, the packet was not compressed and its data follows the single
+            // byte used for the data length
+            if data_len == 0 {
+                match raw_len.checked_sub(1) {
+                    Some(len) => Ok((raw_len, len, false)),
+                    None => Err(PacketSerdeError::InvalidPacketLength(0)),
+                }
+            }
+            // The packet is compressed, which it should only be if it's at least the threshold size
+            else if data_len < self.compression_threshold as usize {
+                Err(PacketSerdeError::InvalidPacketLength(data_len as i32))
+            } else {
+                Ok((raw_len, data_len, true))
+            }
+        }
+        // Compression is not active
+        else {
+            Ok((raw_len, raw_len, false))
+        }
+    }
+
+    fn check_length(len: i32, max: usize) -> StdResult<usize, PacketSerdeError> {
+        match usize::try_from(len) {
+            Ok(checked) if checked <= max => Ok(checked),
+            _ => Err(PacketSerdeError::InvalidPacketLength(len)),
+        }
+    }
+
     fn finalize_packet(
         this: &Mutex<Self>,
         packet: CollectedPacket<'_>,
@@ -307,25 +352,26 @@ impl IoHandle {
 
             // Only decompress to the end of this packet
             // Cursor = vsize(raw_len) + vsize(data_len)
-            // compressed_end = raw_len - vsize(raw_len)
-            let compressed_end =
-                (raw_len + PacketBuffer::varint_size(data_len as i32)) - packet_buffer.cursor();
+            // compressed_end = raw_len - vsize(data_len)
+            let compressed_end = match raw_len
+                .checked_sub(PacketBuffer::varint_size(data_len as i32))
+                .filter(|&end| end <= aux_buffer.len())
+            {
+                Some(end) => end,
+                None => return Err(PacketSerdeError::InvalidPacketLength(raw_len as i32)),
+            };
             let mut decoder = ZlibDecoder::new(&aux_buffer[.. compressed_end]);
 
             // Prepare the packet buffer for decompression
             packet_buffer.resize(data_len);
             packet_buffer.reset_cursor();
 
-            match decoder.read(&mut packet_buffer[..]) {
-                Ok(read) =>
-                    if read != data_len {
-                        return Err(PacketSerdeError::Network(IoError::new(
-                            IoErrorKind::InvalidData,
-                            "Failed to decompress packet",
-                        )));
-                    },
-                Err(e) => return Err(PacketSerdeError::Network(e)),
-            };
+            if let Err(e) = decoder.read_exact(&mut packet_buffer[..]) {
+                return Err(PacketSerdeError::Network(IoError::new(
+                    IoErrorKind::InvalidData,
+                    format!("Failed to decompress packet: {e}"),
+                )));
+            }
 
             // Copy any bytes at the end of the buffer that were not part of this packet
             if aux_buffer.len() > compressed_end {
@@ -397,6 +443,32 @@ impl AsyncWriteHandle {
     pub fn shutdown(&self) {
         let _ = self.0.send(WrappedClientBoundPacket::Disconnect);
     }
+
+    /// Sends the disconnect packet for the given state with the given reason, and then closes the
+    /// connection. Connections in the handshake or status state are closed without a reason since
+    /// there is no disconnect packet for those states.
+    pub fn disconnect(&self, state: ConnectionState, reason: Component) {
+        let reason = Box::new(reason);
+        match state {
+            ConnectionState::Login => self.send_packet(ClientBoundPacket::Disconnect { reason }),
+            ConnectionState::Configuration =>
+                self.send_packet(ClientBoundPacket::DisconnectConfiguration { reason }),
+            ConnectionState::Play => self.send_packet(ClientBoundPacket::DisconnectPlay { reason }),
+            _ => {}
+        }
+
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+impl AsyncWriteHandle {
+    /// Creates a handle which is not attached to a client, returning the receiving end of its
+    /// packet channel.
+    pub(crate) fn detached() -> (Self, mpsc::UnboundedReceiver<WrappedClientBoundPacket>) {
+        let (packet_sender, packet_receiver) = mpsc::unbounded_channel();
+        (AsyncWriteHandle(packet_sender), packet_receiver)
+    }
 }
 
 /// Manages a connection to a client. The name is a bit misleading, as this struct and its methods
@@ -405,6 +477,8 @@ impl AsyncWriteHandle {
 pub struct AsyncClientConnection {
     /// The client ID.
     pub id: ClientId,
+    /// The client's IP address, which is the one forwarded by the proxy if there is one.
+    pub address: IpAddr,
     read_handle: OwnedReadHalf,
     pub write_handle: AsyncWriteHandle,
     /// The packet buffer used when reading packet bytes.
@@ -415,22 +489,32 @@ pub struct AsyncClientConnection {
     /// A handle to the packet pre-processor.
     io_handle: Arc<Mutex<IoHandle>>,
     /// A channel to forward packets to the server thread.
-    sync_packet_sender: StdSender<WrappedServerBoundPacket>,
+    sync_packet_sender: UnboundedSender<WrappedServerBoundPacket>,
+    rate_limiter: PacketRateLimiter,
 }
 
 impl AsyncClientConnection {
     /// Creates a new connection wrapper around the given stream.
     pub fn new(
         id: ClientId,
+        address: IpAddr,
         stream: TcpStream,
-        sync_packet_sender: StdSender<WrappedServerBoundPacket>,
+        sync_packet_sender: UnboundedSender<WrappedServerBoundPacket>,
     ) -> (Self, impl Future<Output = ()>) {
         let (read_handle, write_handle) = stream.into_split();
-        let io_handle = Arc::new(Mutex::new(IoHandle::new()));
+
+        let config = config().read();
+        let mut io_handle = IoHandle::new();
+        io_handle.set_size_limits(config.max_packet_size, config.max_uncompressed_packet_size);
+        let rate_limiter = PacketRateLimiter::new(config.packet_rate_limit);
+        drop(config);
+
+        let io_handle = Arc::new(Mutex::new(io_handle));
         let (write_handle, driver) = Self::create_write_handle(write_handle, io_handle.clone());
 
         let conn = AsyncClientConnection {
             id,
+            address,
             read_handle,
             write_handle,
             read_buffer: PacketBuffer::new(4096),
@@ -438,6 +522,7 @@ impl AsyncClientConnection {
             io_handle,
             connection_state: ConnectionState::Handshake,
             sync_packet_sender,
+            rate_limiter,
         };
 
         (conn, driver)
@@ -487,6 +572,7 @@ impl AsyncClientConnection {
     ) -> bool {
         match wrapped_packet {
             WrappedClientBoundPacket::Singleton(packet) => {
+                io_handle.lock().record_sent(&packet);
                 buffer.clear();
                 buffer.write(&packet);
                 Self::write_buffer(buffer, aux_buffer, write_handle, io_handle).await;
@@ -554,7 +640,10 @@ impl AsyncClientConnection {
             buffer.clear();
 
             match packet {
-                WrappedClientBoundPacket::Singleton(packet) => buffer.write(packet),
+                WrappedClientBoundPacket::Singleton(packet) => {
+                    io_handle.lock().record_sent(packet);
+                    buffer.write(packet);
+                }
                 WrappedClientBoundPacket::Custom(packet) => buffer.write(&**packet),
                 WrappedClientBoundPacket::Flush => flush = true,
                 WrappedClientBoundPacket::Disconnect => disconnect_when_done = true,
@@ -611,6 +700,31 @@ impl AsyncClientConnection {
         }
     }
 
+    /// Moves the connection into the given state. If the client is not allowed to move from its
+    /// current state into the given state, then it is disconnected instead.
+    pub fn transition_to(&mut self, next: ConnectionState) {
+        if self.connection_state.can_transition_to(next) {
+            self.connection_state = next;
+        } else {
+            warn!(
+                "Client {} attempted invalid state transition from {:?} to {:?}",
+                self.id, self.connection_state, next
+            );
+            self.disconnect(Component::text("Invalid connection state"));
+        }
+    }
+
+    /// Sends the client the given reason for disconnecting if its state allows it, then closes the
+    /// connection and marks this client as disconnected.
+    pub fn disconnect(&mut self, reason: Component) {
+        if self.connection_state == ConnectionState::Disconnected {
+            return;
+        }
+
+        self.write_handle.disconnect(self.connection_state, reason);
+        self.connection_state = ConnectionState::Disconnected;
+    }
+
     /// Forwards the given packet to the server thread for handling.
     pub fn forward_to_server(&mut self, packet: ServerBoundPacket) {
         if let Err(e) = self
@@ -639,10 +753,29 @@ impl AsyncClientConnection {
             .map_err(Into::into)
     }
 
+    /// Sends the client a Set Compression packet with the given threshold, after which all packets
+    /// in both directions whose data is at least that many bytes long are zlib-compressed. Smaller
+    /// packets are still framed with a zero data length. A negative threshold such as `-1` disables
+    /// compression.
+    ///
+    /// The threshold takes effect once all previously sent packets have been written.
     pub fn set_compression_threshold(&self, compression_threshold: i32) {
-        self.io_handle
-            .lock()
-            .set_compression_threshold(compression_threshold);
+        self.write_handle
+            .send_packet(WrappedClientBoundPacket::EnableCompression {
+                threshold: compression_threshold,
+            });
+    }
+
+    /// Returns whether the client was sent Start Configuration since it last acknowledged one,
+    /// clearing the request so that each one is only acknowledged once.
+    pub fn take_configuration_request(&self) -> bool {
+        std::mem::take(&mut self.io_handle.lock().configuration_requested)
+    }
+
+    /// Records that a packet was received from the client, returning whether or not the client is
+    /// still within the packet rate limit.
+    pub fn record_packet(&mut self) -> bool {
+        self.rate_limiter.record(Instant::now())
     }
 
     /// Reads packet data from the underlying stream, blocking the current thread. After the initial read,
@@ -690,7 +823,7 @@ impl AsyncClientConnection {
 
             // The legacy ping packet has no length prefix, so only collect the packet if it's not legacy
             if !(self.connection_state == ConnectionState::Handshake
-                && self.read_buffer.peek_one().unwrap_or(0) as i32 == LEGACY_PING_PACKET_ID)
+                && is_legacy_ping(&self.read_buffer[..]))
             {
                 let collect_fut = self.io_handle.lock().collect_packet(
                     &mut self.read_buffer,
@@ -707,3 +840,184 @@ impl AsyncClientConnection {
         Ok(read)
     }
 }
+
+/// Counts the packets received from a client in one second intervals.
+struct PacketRateLimiter {
+    max_per_second: u32,
+    interval_start: Instant,
+    count: u32,
+}
+
+impl PacketRateLimiter {
+    /// Creates a rate limiter allowing the given number of packets per second, where zero allows any
+    /// number of packets.
+    fn new(max_per_second: u32) -> Self {
+        PacketRateLimiter {
+            max_per_second,
+            interval_start: Instant::now(),
+            count: 0,
+        }
+    }
+
+    /// Records a packet received at the given time, returning false if too many packets have been
+    /// received in the current interval.
+    fn record(&mut self, now: Instant) -> bool {
+        if self.max_per_second == 0 {
+            return true;
+        }
+
+        if now.saturating_duration_since(self.interval_start) >= Duration::from_secs(1) {
+            self.interval_start = now;
+            self.count = 0;
+        }
+
+        self.count += 1;
+        self.count <= self.max_per_second
+    }
+}
+
+#[test]
+fn login_disconnect_sends_reason() {
+    let (handle, mut packets) = AsyncWriteHandle::detached();
+    let reason = Component::text("Server closed");
+    handle.disconnect(ConnectionState::Login, reason.clone());
+
+    let packet = match packets.try_recv() {
+        Ok(WrappedClientBoundPacket::Singleton(packet)) => packet,
+        _ => panic!("Expected a disconnect packet"),
+    };
+    let mut buffer = PacketBuffer::new(64);
+    buffer.write(&packet);
+    buffer.reset_cursor();
+    assert_eq!(buffer.read_varying::<i32>().unwrap(), 0x00);
+    assert_eq!(
+        buffer.read::<String>().unwrap(),
+        serde_json::to_string(&reason).unwrap()
+    );
+
+    assert!(matches!(
+        packets.try_recv(),
+        Ok(WrappedClientBoundPacket::Disconnect)
+    ));
+    assert!(packets.try_recv().is_err());
+}
+
+#[test]
+fn oversized_packet_rejected() {
+    let io_handle = IoHandle::new();
+    let mut buffer = PacketBuffer::new(8);
+    buffer.write_varying(&i32::MAX);
+    buffer.reset_cursor();
+
+    assert!(matches!(
+        io_handle.read_header(&mut buffer),
+        Err(PacketSerdeError::InvalidPacketLength(i32::MAX))
+    ));
+    // The buffer should not have grown to hold the declared length
+    assert!(buffer.capacity() < 1024);
+
+    let mut io_handle = IoHandle::new();
+    io_handle.set_compression_threshold(256);
+    let mut buffer = PacketBuffer::new(8);
+    buffer.write_varying(&16_i32);
+    buffer.write_varying(&i32::MAX);
+    buffer.reset_cursor();
+
+    assert!(matches!(
+        io_handle.read_header(&mut buffer),
+        Err(PacketSerdeError::InvalidPacketLength(i32::MAX))
+    ));
+
+    // Limits cannot be raised past vanilla's
+    let mut io_handle = IoHandle::new();
+    io_handle.set_size_limits(usize::MAX, usize::MAX);
+    let mut buffer = PacketBuffer::new(8);
+    buffer.write_varying(&(MAX_PACKET_LEN as i32 + 1));
+    buffer.reset_cursor();
+
+    assert!(matches!(
+        io_handle.read_header(&mut buffer),
+        Err(PacketSerdeError::InvalidPacketLength(len)) if len == MAX_PACKET_LEN as i32 + 1
+    ));
+
+    // A compressed packet must be at least the threshold size once decompressed
+    let mut io_handle = IoHandle::new();
+    io_handle.set_compression_threshold(256);
+    let mut buffer = PacketBuffer::new(8);
+    buffer.write_varying(&16_i32);
+    buffer.write_varying(&8_i32);
+    buffer.reset_cursor();
+
+    assert!(matches!(
+        io_handle.read_header(&mut buffer),
+        Err(PacketSerdeError::InvalidPacketLength(8))
+    ));
+}
+
+#[test]
+fn rate_limit_exceeded() {
+    let start = Instant::now();
+    let mut limiter = PacketRateLimiter::new(3);
+    limiter.interval_start = start;
+
+    for _ in 0 .. 3 {
+        assert!(limiter.record(start));
+    }
+    assert!(!limiter.record(start + Duration::from_millis(500)));
+    assert!(limiter.record(start + Duration::from_secs(1)));
+}
+
+#[test]
+fn compression_round_trip() {
+    // Thresholds and packet body lengths, covering packets above and below the threshold as well
+    // as headers whose lengths take up a different number of bytes
+    for (threshold, body_len, compressed) in [
+        (64, 16, false),
+        (64, 1024, true),
+        (4096, 300, false),
+        (0, 1, true),
+        (-1, 1024, false),
+    ] {
+        let mut io_handle = IoHandle::new();
+        io_handle.set_compression_threshold(threshold);
+
+        let mut packet = PacketBuffer::new(body_len + 1);
+        packet.write_varying(&0x18_i32);
+        packet.write_bytes((0 .. body_len).map(|i| (i % 7) as u8).collect::<Vec<_>>());
+        let expected = packet[..].to_vec();
+
+        let mut aux_buffer = PacketBuffer::new(64);
+        let framed = IoHandle::preprocess_packet(&mut packet, &mut aux_buffer, threshold)
+            .unwrap()
+            .data
+            .to_vec();
+
+        let mut buffer = PacketBuffer::new(framed.len());
+        buffer.write_bytes(&framed);
+        buffer.reset_cursor();
+        let (raw_len, data_len, is_compressed) = io_handle.read_header(&mut buffer).unwrap();
+        assert_eq!(is_compressed, compressed);
+        assert_eq!(data_len, expected.len());
+        assert_eq!(
+            PacketBuffer::varint_size(raw_len as i32) + raw_len,
+            framed.len()
+        );
+        if threshold >= 0 && !compressed {
+            // Packets below the threshold still carry a data length of zero
+            assert_eq!(framed[PacketBuffer::varint_size(raw_len as i32)], 0);
+        }
+
+        let io_handle = Mutex::new(io_handle);
+        let len = IoHandle::finalize_packet(&io_handle, CollectedPacket {
+            packet_buffer: &mut buffer,
+            aux_buffer: &mut aux_buffer,
+            raw_len,
+            data_len,
+            encrypted_offset: None,
+            compressed: is_compressed,
+        })
+        .unwrap();
+        let start = buffer.cursor();
+        assert_eq!(&buffer[start .. start + len], &expected[..]);
+    }
+}
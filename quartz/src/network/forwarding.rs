@@ -0,0 +1,133 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    net::IpAddr,
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The method a proxy in front of the server uses to forward the identity of connecting players.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyForwarding {
+    /// Players connect directly and are authenticated by the server.
+    None,
+    /// BungeeCord's legacy forwarding, which appends the player's address, UUID and profile
+    /// properties to the server address of the handshake. This format is unsigned, so the server
+    /// must only be reachable through the proxy.
+    BungeeCord,
+}
+
+/// A property of a player's profile, such as their skin.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+/// The identity of a player as forwarded by a proxy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForwardedIdentity {
+    /// The address the player used to connect to the proxy.
+    pub host: String,
+    /// The player's real IP address.
+    pub address: IpAddr,
+    /// The player's UUID, as authenticated by the proxy.
+    pub uuid: Uuid,
+    pub properties: Vec<ProfileProperty>,
+}
+
+impl ForwardedIdentity {
+    /// Parses the server address field of a handshake sent by BungeeCord, which has the form
+    /// `host\0ip\0uuid` optionally followed by `\0properties`.
+    pub fn from_bungeecord(server_address: &str) -> Result<Self, ForwardingError> {
+        let mut parts = server_address.split('\0');
+
+        let host = parts.next().unwrap_or_default().to_owned();
+        let (address, uuid) = match (parts.next(), parts.next()) {
+            (Some(address), Some(uuid)) => (address, uuid),
+            _ => return Err(ForwardingError::MissingData),
+        };
+        let address = address
+            .parse::<IpAddr>()
+            .map_err(|_| ForwardingError::InvalidAddress(address.to_owned()))?;
+        let uuid =
+            Uuid::parse_str(uuid).map_err(|_| ForwardingError::InvalidUuid(uuid.to_owned()))?;
+        let properties = match parts.next() {
+            Some(properties) => serde_json::from_str(properties)
+                .map_err(|error| ForwardingError::InvalidProperties(error.to_string()))?,
+            None => Vec::new(),
+        };
+
+        Ok(ForwardedIdentity {
+            host,
+            address,
+            uuid,
+            properties,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForwardingError {
+    /// The handshake did not contain forwarded data, usually because forwarding is disabled on
+    /// the proxy.
+    MissingData,
+    InvalidAddress(String),
+    InvalidUuid(String),
+    InvalidProperties(String),
+}
+
+impl Display for ForwardingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ForwardingError::MissingData => write!(f, "Handshake is missing forwarded data"),
+            ForwardingError::InvalidAddress(address) =>
+                write!(f, "Invalid forwarded address \"{address}\""),
+            ForwardingError::InvalidUuid(uuid) => write!(f, "Invalid forwarded UUID \"{uuid}\""),
+            ForwardingError::InvalidProperties(error) =>
+                write!(f, "Invalid forwarded profile properties: {error}"),
+        }
+    }
+}
+
+impl Error for ForwardingError {}
+
+#[test]
+fn parse_bungeecord_address() {
+    let identity = ForwardedIdentity::from_bungeecord(
+        "play.example.com\u{0}203.0.113.7\u{0}069a79f444e94726a5befca90e38aaf5\u{0}[{\"name\":\"\
+         textures\",\"value\":\"e30=\",\"signature\":\"c2ln\"}]",
+    )
+    .unwrap();
+
+    assert_eq!(identity.host, "play.example.com");
+    assert_eq!(identity.address, "203.0.113.7".parse::<IpAddr>().unwrap());
+    assert_eq!(
+        identity.uuid,
+        Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()
+    );
+    assert_eq!(identity.properties, vec![ProfileProperty {
+        name: "textures".to_owned(),
+        value: "e30=".to_owned(),
+        signature: Some("c2ln".to_owned())
+    }]);
+
+    let identity = ForwardedIdentity::from_bungeecord(
+        "localhost\u{0}::1\u{0}069a79f444e94726a5befca90e38aaf5",
+    )
+    .unwrap();
+    assert!(identity.address.is_loopback());
+    assert!(identity.properties.is_empty());
+
+    assert_eq!(
+        ForwardedIdentity::from_bungeecord("localhost"),
+        Err(ForwardingError::MissingData)
+    );
+    assert!(matches!(
+        ForwardedIdentity::from_bungeecord("localhost\u{0}not an ip\u{0}0"),
+        Err(ForwardingError::InvalidAddress(_))
+    ));
+}
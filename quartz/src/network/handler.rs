@@ -1,5 +1,5 @@
 use crate::{
-    command::{CommandContext, CommandSender},
+    command::{CommandContext, CommandOrigin, CommandSender},
     command_executor,
     config,
     entities::{
@@ -17,7 +17,7 @@ use qdat::{
 };
 
 use hex::ToHex;
-use log::{debug, error};
+use log::{debug, error, warn};
 use once_cell::sync::Lazy;
 use openssl::{
     pkey::Private,
@@ -32,7 +32,7 @@ use rand::{thread_rng, Rng};
 use regex::Regex;
 use serde::Deserialize;
 use serde_json::json;
-use std::{str::FromStr, sync::Arc, time::Instant};
+use std::{net::IpAddr, str::FromStr, sync::Arc, time::Instant};
 use uuid::Uuid;
 
 mod build {
@@ -42,11 +42,17 @@ mod build {
 }
 pub use build::*;
 
+/// Whether clients acknowledge their login and go through the configuration state before they
+/// start playing, rather than playing as soon as the login succeeds.
+const CONFIGURE_AFTER_LOGIN: bool = PROTOCOL_VERSION >= CONFIGURATION_PROTOCOL_VERSION;
 
 pub(crate) struct AsyncPacketHandler {
     key_pair: Arc<Rsa<Private>>,
     username: String,
     verify_token: Vec<u8>,
+    forwarded_identity: Option<ForwardedIdentity>,
+    /// The player which logged in, kept until the client finishes configuration and joins.
+    pending_login: Option<(Uuid, String, Vec<ProfileProperty>)>,
 }
 
 impl AsyncPacketHandler {
@@ -55,6 +61,8 @@ impl AsyncPacketHandler {
             key_pair,
             username: String::new(),
             verify_token: Vec::new(),
+            forwarded_identity: None,
+            pending_login: None,
         }
     }
 }
@@ -64,17 +72,56 @@ impl AsyncPacketHandler {
         &mut self,
         conn: &mut AsyncClientConnection,
         version: i32,
+        server_address: &str,
         next_state: i32,
     ) {
         if version != PROTOCOL_VERSION {
-            conn.connection_state = ConnectionState::Disconnected;
+            // Clients only display a reason if they were trying to log in
+            if next_state == 2 {
+                conn.transition_to(ConnectionState::Login);
+            }
+
+            let reason = if version < PROTOCOL_VERSION {
+                format!("Outdated client! Please use {}", server::VERSION)
+            } else {
+                format!("Outdated server! I'm still on {}", server::VERSION)
+            };
+            conn.disconnect(Component::text(reason));
             return;
         }
 
-        if next_state == 1 {
-            conn.connection_state = ConnectionState::Status;
-        } else if next_state == 2 {
-            conn.connection_state = ConnectionState::Login;
+        match next_state {
+            1 => conn.transition_to(ConnectionState::Status),
+            2 => {
+                conn.transition_to(ConnectionState::Login);
+
+                if config().read().proxy_forwarding == ProxyForwarding::BungeeCord {
+                    match ForwardedIdentity::from_bungeecord(server_address) {
+                        Ok(identity) => {
+                            debug!(
+                                "Client {} forwarded from {} as {}",
+                                conn.id, identity.address, identity.uuid
+                            );
+                            conn.address = identity.address;
+                            self.forwarded_identity = Some(identity);
+                        }
+                        Err(e) => {
+                            warn!("Client {} sent invalid forwarding data: {}", conn.id, e);
+                            conn.disconnect(Component::text(
+                                "If you wish to use IP forwarding, please enable it in your \
+                                 BungeeCord config as well!",
+                            ));
+                        }
+                    }
+                }
+            }
+            _ => {
+                debug!(
+                    "Client {} requested invalid next state {}",
+                    conn.id, next_state
+                );
+                conn.disconnect(Component::text("Invalid next state"));
+            }
         }
     }
 
@@ -83,23 +130,27 @@ impl AsyncPacketHandler {
             .send_packet(ClientBoundPacket::Pong { payload });
     }
 
+    /// Tells the client to start compressing packets if compression is enabled in the config. This
+    /// must be sent before the login succeeds.
+    fn enable_compression(conn: &AsyncClientConnection) {
+        let threshold = config().read().compression_threshold;
+        if threshold >= 0 {
+            conn.set_compression_threshold(threshold);
+        }
+    }
+
     async fn handle_login_start(&mut self, conn: &mut AsyncClientConnection, name: &str) {
+        // The proxy has already authenticated the player, so we trust the identity it forwarded
+        if let Some(identity) = self.forwarded_identity.take() {
+            Self::enable_compression(conn);
+            self.finish_login(conn, identity.uuid, name.to_owned(), identity.properties);
+            return;
+        }
+
         // If we are not running in online mode we just send LoginSuccess and skip encryption
         if !config().read().online_mode {
-            conn.write_handle
-                .send_packet(ClientBoundPacket::LoginSuccess {
-                    uuid: Uuid::from_u128(0),
-                    username: name.to_owned(),
-                });
-
-            conn.forward_internal_to_server(WrappedServerBoundPacket::LoginSuccess {
-                id: conn.id,
-                uuid: Uuid::from_u128(0),
-                username: name.to_owned(),
-            });
-
-            conn.connection_state = ConnectionState::Play;
-
+            Self::enable_compression(conn);
+            self.finish_login(conn, Uuid::from_u128(0), name.to_owned(), Vec::new());
             return;
         }
 
@@ -116,7 +167,7 @@ impl AsyncPacketHandler {
             Ok(der) => der,
             Err(e) => {
                 error!("Failed to convert public key to der: {}", e);
-                conn.write_handle.shutdown();
+                conn.disconnect(Component::text("Internal server error"));
                 return;
             }
         };
@@ -142,7 +193,7 @@ impl AsyncPacketHandler {
                 .private_decrypt(verify_token, &mut decrypted_verify, Padding::PKCS1)
         {
             error!("Failed to decrypt verify token: {}", e);
-            conn.write_handle.shutdown();
+            conn.disconnect(Component::text("Error verifying encryption"));
             return;
         }
         decrypted_verify = decrypted_verify[.. self.verify_token.len()].to_vec();
@@ -152,14 +203,10 @@ impl AsyncPacketHandler {
                 "verify for client {} didn't match, {:x?}, {:x?}",
                 conn.id, self.verify_token, decrypted_verify
             );
-            return conn
-                .write_handle
-                .send_packet(ClientBoundPacket::Disconnect {
-                    reason: Box::new(Component::colored(
-                        "Error verifying encryption".to_owned(),
-                        Color::Red,
-                    )),
-                });
+            return conn.disconnect(Component::colored(
+                "Error verifying encryption".to_owned(),
+                Color::Red,
+            ));
         }
 
         // Decrypt shared secret
@@ -169,7 +216,7 @@ impl AsyncPacketHandler {
                 .private_decrypt(shared_secret, &mut decrypted_secret, Padding::PKCS1)
         {
             error!("Failed to decrypt secret key: {}", e);
-            conn.write_handle.shutdown();
+            conn.disconnect(Component::text("Error verifying encryption"));
             return;
         }
         decrypted_secret = decrypted_secret[.. 16].to_vec();
@@ -181,7 +228,7 @@ impl AsyncPacketHandler {
                 "Failed to initialize encryption for client connetion: {}",
                 e
             );
-            conn.write_handle.shutdown();
+            conn.disconnect(Component::text("Internal server error"));
             return;
         }
 
@@ -193,7 +240,7 @@ impl AsyncPacketHandler {
             Ok(der) => hasher.update(&*der),
             Err(e) => {
                 error!("Failed to convert public key to der: {}", e);
-                conn.write_handle.shutdown();
+                conn.disconnect(Component::text("Internal server error"));
                 return;
             }
         }
@@ -233,59 +280,36 @@ impl AsyncPacketHandler {
             &self.username, &hash_hex
         );
 
-        // Structs used to allow serde to parse response json into struct
-        #[derive(Deserialize)]
-        #[allow(unused)]
-        struct Properties {
-            name: String,
-            value: String,
-            signature: String,
-        }
-
+        // Struct used to allow serde to parse response json into struct
         #[derive(Deserialize)]
         #[allow(unused)]
         struct AuthResponse {
             id: String,
             name: String,
-            properties: [Properties; 1],
+            properties: Vec<ProfileProperty>,
         }
 
-        // TODO: enable compression properly here
-        const TEST_THRESHOLD: i32 = 0;
-        conn.write_handle
-            .send_packet(WrappedClientBoundPacket::EnableCompression {
-                threshold: TEST_THRESHOLD,
-            });
+        Self::enable_compression(conn);
 
         // Make a get request
         let mojang_req = ureq::get(&url).call();
-        let string_uuid = match mojang_req.map(|response| response.into_json::<AuthResponse>()) {
-            Ok(Ok(AuthResponse { id, .. })) => id,
-            Ok(Err(e)) => {
-                error!("Failed to parse response JSON: {}", e);
-                return;
-            }
-            Err(e) => {
-                error!("Failed to parse authentication response: {}", e);
-                return;
-            }
-        };
+        let (string_uuid, properties) =
+            match mojang_req.map(|response| response.into_json::<AuthResponse>()) {
+                Ok(Ok(AuthResponse { id, properties, .. })) => (id, properties),
+                Ok(Err(e)) => {
+                    error!("Failed to parse response JSON: {}", e);
+                    return;
+                }
+                Err(e) => {
+                    error!("Failed to parse authentication response: {}", e);
+                    return;
+                }
+            };
 
         match Uuid::from_str(&string_uuid) {
             Ok(uuid) => {
-                conn.write_handle
-                    .send_packet(ClientBoundPacket::LoginSuccess {
-                        uuid,
-                        username: self.username.clone(),
-                    });
-
-                conn.connection_state = ConnectionState::Play;
-
-                conn.forward_internal_to_server(WrappedServerBoundPacket::LoginSuccess {
-                    id: conn.id,
-                    uuid,
-                    username: self.username.clone(),
-                });
+                let username = self.username.clone();
+                self.finish_login(conn, uuid, username, properties);
             }
             Err(e) => error!("Failed to parse malformed UUID: {}", e),
         }
@@ -299,6 +323,101 @@ impl AsyncPacketHandler {
     ) {
         // TODO: Implement login_plugin_response
     }
+
+    /// Tells the client its login succeeded. Clients which are configured before playing stay in
+    /// the login state until they acknowledge it, and only join the server once configuration
+    /// finishes, while older clients join straight away.
+    fn finish_login(
+        &mut self,
+        conn: &mut AsyncClientConnection,
+        uuid: Uuid,
+        username: String,
+        properties: Vec<ProfileProperty>,
+    ) {
+        conn.write_handle
+            .send_packet(ClientBoundPacket::LoginSuccess {
+                uuid,
+                username: username.clone(),
+            });
+
+        if CONFIGURE_AFTER_LOGIN {
+            self.pending_login = Some((uuid, username, properties));
+            return;
+        }
+
+        conn.transition_to(ConnectionState::Play);
+        conn.forward_internal_to_server(WrappedServerBoundPacket::LoginSuccess {
+            id: conn.id,
+            uuid,
+            username,
+            address: conn.address,
+            properties,
+        });
+    }
+
+    async fn handle_login_acknowledged(&mut self, conn: &mut AsyncClientConnection) {
+        // Only a login which succeeded can be acknowledged
+        if self.pending_login.is_none() {
+            warn!(
+                "Client {} acknowledged a login which did not succeed",
+                conn.id
+            );
+            conn.disconnect(Component::text("Invalid connection state"));
+            return;
+        }
+
+        // The registries are still sent when joining the game, so there's nothing else to
+        // configure yet
+        conn.transition_to(ConnectionState::Configuration);
+        conn.write_handle
+            .send_packet(ClientBoundPacket::FinishConfiguration);
+    }
+
+    async fn handle_acknowledge_finish_configuration(&mut self, conn: &mut AsyncClientConnection) {
+        conn.transition_to(ConnectionState::Play);
+
+        // Clients coming back from a reconfiguration are already in the game
+        if let Some((uuid, username, properties)) = self.pending_login.take() {
+            conn.forward_internal_to_server(WrappedServerBoundPacket::LoginSuccess {
+                id: conn.id,
+                uuid,
+                username,
+                address: conn.address,
+                properties,
+            });
+        }
+    }
+
+    async fn handle_acknowledge_configuration(&mut self, conn: &mut AsyncClientConnection) {
+        // Clients may only go back to configuration once the server has asked them to
+        if !conn.take_configuration_request() {
+            warn!(
+                "Client {} acknowledged a configuration which was not started",
+                conn.id
+            );
+            return;
+        }
+
+        conn.transition_to(ConnectionState::Configuration);
+    }
+
+    async fn handle_client_information(&mut self, _conn: &mut AsyncClientConnection) {
+        // TODO: Implement client_information
+    }
+
+    async fn handle_configuration_plugin_message(&mut self, _conn: &mut AsyncClientConnection) {
+        // TODO: Implement configuration_plugin_message
+    }
+
+    async fn handle_configuration_keep_alive(&mut self, _conn: &mut AsyncClientConnection) {}
+
+    async fn handle_configuration_pong(&mut self, _conn: &mut AsyncClientConnection) {}
+
+    async fn handle_configuration_resource_pack_response(
+        &mut self,
+        _conn: &mut AsyncClientConnection,
+    ) {
+    }
 }
 
 impl QuartzServer {
@@ -307,9 +426,11 @@ impl QuartzServer {
         sender: ClientId,
         mut uuid: Uuid,
         username: &str,
+        address: IpAddr,
+        properties: Vec<ProfileProperty>,
     ) {
         let config = config().read();
-        if !config.online_mode {
+        if !config.online_mode && config.proxy_forwarding == ProxyForwarding::None {
             // I have no idea what to use as the namespace here lol
             uuid = Uuid::new_v4()
             // Use a random namespace so we can have multiple players with the same username
@@ -320,6 +441,7 @@ impl QuartzServer {
         self.client_list.set_username(sender, username);
         log::debug!("{}", uuid);
         self.client_list.set_uuid(sender, uuid);
+        self.client_list.set_profile(sender, address, properties);
 
         /*
 
@@ -369,8 +491,8 @@ impl QuartzServer {
                 dimension,
                 world_name: UnlocalizedName::minecraft("overworld"),
                 hashed_seed: 0,
-                max_players: 10,
-                view_distance: 12,
+                max_players: config.max_players as i32,
+                view_distance: config.view_distance as i32,
                 reduced_debug_info: false,
                 enable_respawn_screen: true,
                 is_debug: false,
@@ -386,56 +508,29 @@ impl QuartzServer {
                 data: brand_buf[..].to_vec().into_boxed_slice(),
             });
 
+        if let Some(pack) = &config.resource_pack {
+            self.client_list.send_packet(sender, pack.send_packet());
+        }
+
         // Since at this point keep_alive on the AsyncPackeHandler is still -1 it won't check what this id is
         // So it doesn't matter if we hard code the id
         self.client_list.start_keep_alive(sender);
     }
 
-    async fn handle_legacy_server_list_ping(&mut self, sender: ClientId, _payload: u8) {
-        // Load in all needed values from server object
-        let protocol_version = u16::to_string(&(PROTOCOL_VERSION as u16));
-        let version = server::VERSION;
-        let player_count = self.client_list.online_count().to_string();
+    async fn handle_legacy_server_list_ping(&mut self, sender: ClientId, _payload: &[u8]) {
         let config = config().read();
-        let motd = &config.motd;
-        let max_players = config.max_players.to_string();
-
-        // Add String header
-        let mut string_vec: Vec<u16> = vec![0x00A7, 0x0031, 0x0000];
-
-        // Add all fields to vector
-        string_vec.extend(
-            protocol_version
-                .chars()
-                .rev()
-                .collect::<String>()
-                .encode_utf16(),
+        let mut buffer = PacketBuffer::new(64);
+        write_legacy_ping_response(
+            &mut buffer,
+            server::VERSION,
+            &config.motd.to_plain_string(),
+            self.client_list.online_count(),
+            config.max_players as usize,
         );
-        string_vec.push(0x0000);
-
-        string_vec.extend(version.encode_utf16());
-        string_vec.push(0x0000);
-
-        string_vec.extend(motd.as_plain_text().encode_utf16());
-        string_vec.push(0x0000);
-
-        string_vec.extend(player_count.encode_utf16());
-        string_vec.push(0x0000);
-
-        string_vec.extend(max_players.encode_utf16());
-
-        let mut buffer = PacketBuffer::new(3 + string_vec.len());
-
-        // Write FF and length
-        buffer.write_bytes([0xFF]);
-        buffer.write(&(string_vec.len() as u16));
-
-        // Write String
-        for bytes in string_vec {
-            buffer.write(&bytes);
-        }
 
+        // Legacy clients expect the connection to be closed after the response
         self.client_list.send_buffer(sender, buffer);
+        self.client_list.shutdown(sender);
     }
 
     async fn handle_status_request(&mut self, sender: ClientId) {
@@ -671,8 +766,24 @@ impl QuartzServer {
     ) {
     }
 
-    #[allow(unused_variables)]
-    async fn handle_resource_pack_status(&mut self, sender: ClientId, result: i32) {}
+    async fn handle_resource_pack_status(&mut self, sender: ClientId, result: i32) {
+        let status = match ResourcePackStatus::from_id(result) {
+            Some(status) => status,
+            None => {
+                warn!("Received invalid resource pack status {}", result);
+                return;
+            }
+        };
+        self.client_list.set_resource_pack_status(sender, status);
+
+        let reason = match &config().read().resource_pack {
+            Some(pack) => pack.disconnect_reason(status),
+            None => None,
+        };
+        if let Some(reason) = reason {
+            self.client_list.disconnect(sender, reason);
+        }
+    }
 
     #[allow(unused_variables)]
     async fn handle_name_item(&mut self, sender: ClientId, item_name: &str) {}
@@ -941,8 +1052,41 @@ impl QuartzServer {
     async fn handle_click_window_button(&mut self, sender: ClientId, window_id: i8, button_id: i8) {
     }
 
-    #[allow(unused_variables)]
-    async fn handle_tab_complete(&mut self, sender: ClientId, trasaction_id: i32, text: &str) {}
+    async fn handle_tab_complete(&mut self, sender: ClientId, transaction_id: i32, text: &str) {
+        let command = match text.strip_prefix('/') {
+            Some(command) => command,
+            None => return,
+        };
+
+        let command_sender = match self.player_command_sender(sender).await {
+            Some(command_sender) => command_sender,
+            None => return,
+        };
+
+        let executor = command_executor();
+        let suggestions = executor.suggestions(
+            command,
+            &CommandContext::new(self, executor, command_sender),
+        );
+        let range = match suggestions.first() {
+            Some(suggestion) => suggestion.range.clone(),
+            None => return,
+        };
+
+        // The client counts UTF-16 code units, and its text still has the leading slash
+        let start = text[.. range.start + 1].encode_utf16().count();
+        let length = text[range.start + 1 ..].encode_utf16().count();
+        self.client_list
+            .send_packet(sender, ClientBoundPacket::TabComplete {
+                id: transaction_id,
+                start: start as i32,
+                length: length as i32,
+                matches: suggestions
+                    .into_iter()
+                    .map(|suggestion| TabCompleteMatch::new(suggestion.text, None))
+                    .collect(),
+            });
+    }
 
     #[allow(unused_variables)]
     #[allow(clippy::too_many_arguments)]
@@ -1091,7 +1235,7 @@ impl QuartzServer {
                     chunk_z: chunk_coords.z(),
                     primary_bit_mask,
                     heightmaps: chunk.get_heightmaps(),
-                    biomes: Box::from(chunk.biomes()),
+                    biomes: chunk.biome_ids(),
                     // TODO: send block entities for chunk when we support them
                     block_entities: vec![].into_boxed_slice(),
                     data: section_data,
@@ -1195,9 +1339,9 @@ impl QuartzServer {
     #[allow(unused_variables)]
     async fn handle_chat_message(&mut self, sender: ClientId, message: &str) {
         if let Some(command) = message.strip_prefix('/') {
-            let write_handle = self.client_list.create_write_handle(sender).unwrap();
+            let command_sender = self.player_command_sender(sender).await.unwrap();
             let executor = command_executor();
-            let ctx = CommandContext::new(self, executor, CommandSender::Client(write_handle));
+            let ctx = CommandContext::new(self, executor, command_sender);
 
             match executor.dispatch(command, ctx) {
                 Ok(_) => {}
@@ -1208,6 +1352,37 @@ impl QuartzServer {
         }
     }
 
+    /// Creates the sender for a player's commands, or `None` if the player is not connected.
+    /// Operators aren't tracked yet, so players may only run commands open to everyone.
+    async fn player_command_sender(&self, sender: ClientId) -> Option<CommandSender> {
+        let handle = self.client_list.create_write_handle(sender)?;
+        Some(CommandSender::Client {
+            id: sender,
+            handle,
+            origin: self.player_command_origin(sender).await,
+            permission_level: 0,
+        })
+    }
+
+    /// Returns where a player's commands are run from. Player rotation isn't tracked yet, so
+    /// commands are always run facing south.
+    async fn player_command_origin(&self, sender: ClientId) -> CommandOrigin {
+        let world = match self.world_store.get_player_world(sender) {
+            Some(world) => world,
+            None => return CommandOrigin::default(),
+        };
+        let player_entity = match world.get_player_entity(sender) {
+            Some(&entity) => entity,
+            None => return CommandOrigin::default(),
+        };
+
+        let entities = world.get_entities().await;
+        match entities.get::<Position>(player_entity) {
+            Ok(pos) => CommandOrigin::new(pos.x, pos.y, pos.z, Default::default()),
+            Err(_) => CommandOrigin::default(),
+        }
+    }
+
     #[allow(unused_variables)]
     async fn handle_set_difficulty(&mut self, sender: ClientId, new_difficulty: i8) {}
 
@@ -1248,7 +1423,8 @@ impl QuartzServer {
     }
 }
 
-/// Handles the given asynchronos connecting using blocking I/O opperations.
+/// Handles the given connection until the client disconnects. This is run as its own task on the
+/// server runtime.
 pub async fn handle_async_connection(
     mut conn: AsyncClientConnection,
     private_key: Arc<Rsa<Private>>,
@@ -1262,19 +1438,25 @@ pub async fn handle_async_connection(
                 if packet_len == 0 {
                     break;
                 }
+                // Client is sending too many packets
+                else if !conn.record_packet() {
+                    warn!("Client {} exceeded the packet rate limit", conn.id);
+                    conn.disconnect(Component::text("Sending packets too fast"));
+                    break;
+                }
                 // Handle the packet
                 else if let Err(e) =
                     handle_packet(&mut conn, &mut async_handler, packet_len).await
                 {
                     error!("Failed to handle packet: {}", e);
-                    conn.write_handle.shutdown();
+                    conn.disconnect(Component::text(format!("Internal Exception: {e}")));
                     break;
                 }
             }
 
             Err(e) => {
                 error!("Error in connection handler: {}", e);
-                conn.write_handle.shutdown();
+                conn.disconnect(Component::text(format!("Internal Exception: {e}")));
                 break;
             }
         }
@@ -1283,3 +1465,56 @@ pub async fn handle_async_connection(
     conn.forward_internal_to_server(WrappedServerBoundPacket::ClientDisconnected { id: conn.id });
     debug!("Client disconnected");
 }
+
+#[test]
+fn oversized_handshake_closes_connection() {
+    use std::time::Duration;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        runtime::Builder,
+        sync::mpsc,
+        time::timeout,
+    };
+
+    crate::init_test_config();
+    let rt = Builder::new_current_thread().enable_all().build().unwrap();
+    rt.block_on(async {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (socket, address) = listener.accept().await.unwrap();
+
+        let (sync_packet_sender, mut sync_packets) = mpsc::unbounded_channel();
+        let (conn, driver) =
+            AsyncClientConnection::new(7, address.ip(), socket, sync_packet_sender);
+        let driver = tokio::spawn(driver);
+
+        // A handshake which claims to be i32::MAX bytes long
+        client
+            .write_all(&[0xFF, 0xFF, 0xFF, 0xFF, 0x07, 0x00])
+            .await
+            .unwrap();
+        let key_pair = Arc::new(Rsa::generate(1024).unwrap());
+        timeout(
+            Duration::from_secs(5),
+            handle_async_connection(conn, key_pair),
+        )
+        .await
+        .expect("The connection handler kept reading from the client");
+
+        assert!(matches!(
+            sync_packets.try_recv(),
+            Ok(WrappedServerBoundPacket::ClientDisconnected { id: 7 })
+        ));
+
+        // The server shuts down its end of the socket, so the client reaches the end of the stream
+        timeout(Duration::from_secs(5), driver)
+            .await
+            .expect("The connection was never closed")
+            .unwrap();
+        let mut buffer = [0; 16];
+        assert_eq!(client.read(&mut buffer).await.unwrap(), 0);
+    });
+}
@@ -1,8 +1,12 @@
 mod connection;
+mod forwarding;
 mod handler;
 mod packet;
+mod resource_pack;
 
 pub use connection::*;
+pub use forwarding::*;
 pub use handler::*;
 pub use packet::*;
 pub use quartz_net::*;
+pub use resource_pack::*;
@@ -1,9 +1,9 @@
-use std::sync::mpsc::Sender;
-
 use crate::server::ClientId;
 
-use super::AsyncWriteHandle;
+use super::{AsyncWriteHandle, ProfileProperty};
 use quartz_net::{ClientBoundPacket, PacketBuffer, ServerBoundPacket, WriteToPacket};
+use std::net::IpAddr;
+use tokio::sync::oneshot;
 use uuid::Uuid;
 
 pub enum WrappedServerBoundPacket {
@@ -22,13 +22,15 @@ pub enum WrappedServerBoundPacket {
         id: ClientId,
         uuid: Uuid,
         username: String,
+        address: IpAddr,
+        properties: Vec<ProfileProperty>,
     },
     ConsoleCommand {
         command: String,
     },
     ConsoleCompletion {
         command: String,
-        response: Sender<Vec<String>>,
+        response: oneshot::Sender<Vec<String>>,
     },
 }
 
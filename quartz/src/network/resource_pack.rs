@@ -0,0 +1,166 @@
+use quartz_chat::{color::Color, Component, ComponentBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::network::ClientBoundPacket;
+
+/// A resource pack which is sent to players when they join.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ResourcePack {
+    /// The URL the client downloads the pack from.
+    pub url: String,
+    /// The hex-encoded SHA-1 hash of the pack, which the client uses to cache it. If this is
+    /// empty then the client downloads the pack every time it joins.
+    #[serde(default)]
+    pub sha1: String,
+    /// Whether players must accept the pack to play on the server.
+    #[serde(default)]
+    pub required: bool,
+    /// A message shown on the prompt asking the player to accept the pack.
+    #[serde(default)]
+    pub prompt: Option<Component>,
+}
+
+/// The response of a client to a resource pack being sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResourcePackStatus {
+    Loaded,
+    Declined,
+    FailedDownload,
+    Accepted,
+}
+
+impl ResourcePackStatus {
+    /// Returns the status with the given ID, as sent in the resource pack status packet.
+    pub fn from_id(id: i32) -> Option<Self> {
+        match id {
+            0 => Some(ResourcePackStatus::Loaded),
+            1 => Some(ResourcePackStatus::Declined),
+            2 => Some(ResourcePackStatus::FailedDownload),
+            3 => Some(ResourcePackStatus::Accepted),
+            _ => None,
+        }
+    }
+}
+
+impl ResourcePack {
+    /// Returns whether the hash is either empty or a valid hex-encoded SHA-1 hash.
+    pub fn has_valid_hash(&self) -> bool {
+        self.sha1.is_empty()
+            || (self.sha1.len() == 40 && self.sha1.bytes().all(|b| b.is_ascii_hexdigit()))
+    }
+
+    /// Creates the packet which prompts the client to download this pack.
+    pub fn send_packet(&self) -> ClientBoundPacket {
+        ClientBoundPacket::ResourcePackSend {
+            url: self.url.clone(),
+            hash: self.sha1.to_ascii_lowercase(),
+            forced: self.required,
+            has_prompt_message: self.prompt.is_some(),
+            prompt_message: self.prompt.clone().map(Box::new),
+        }
+    }
+
+    /// Returns the reason to disconnect a client which responded to this pack with the given
+    /// status, or `None` if the client may continue playing.
+    pub fn disconnect_reason(&self, status: ResourcePackStatus) -> Option<Component> {
+        if !self.required {
+            return None;
+        }
+
+        match status {
+            ResourcePackStatus::Declined => Some(
+                ComponentBuilder::new()
+                    .color(Color::Red)
+                    .add_text("You must accept the resource pack to play on this server")
+                    .build(),
+            ),
+            ResourcePackStatus::FailedDownload => Some(
+                ComponentBuilder::new()
+                    .color(Color::Red)
+                    .add_text("Failed to download the server's resource pack")
+                    .build(),
+            ),
+            ResourcePackStatus::Loaded | ResourcePackStatus::Accepted => None,
+        }
+    }
+}
+
+#[test]
+fn required_pack_disconnects_on_decline() {
+    let mut pack = ResourcePack {
+        url: "https://example.com/pack.zip".to_owned(),
+        sha1: "2AAE6C35C94FCFB415DBE95F408B9CE91EE846ED".to_owned(),
+        required: true,
+        prompt: Some(Component::text("Please accept the pack")),
+    };
+    assert!(pack.has_valid_hash());
+
+    match pack.send_packet() {
+        ClientBoundPacket::ResourcePackSend {
+            hash,
+            forced,
+            has_prompt_message,
+            prompt_message,
+            ..
+        } => {
+            assert_eq!(hash, "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed");
+            assert!(forced);
+            assert!(has_prompt_message);
+            assert!(prompt_message.is_some());
+        }
+        _ => panic!("Expected a resource pack send packet"),
+    }
+
+    let declined = ResourcePackStatus::from_id(1).unwrap();
+    assert_eq!(declined, ResourcePackStatus::Declined);
+    assert!(pack.disconnect_reason(declined).is_some());
+    assert!(pack
+        .disconnect_reason(ResourcePackStatus::FailedDownload)
+        .is_some());
+    assert!(pack
+        .disconnect_reason(ResourcePackStatus::from_id(3).unwrap())
+        .is_none());
+    assert!(pack.disconnect_reason(ResourcePackStatus::Loaded).is_none());
+    assert_eq!(ResourcePackStatus::from_id(4), None);
+
+    pack.required = false;
+    assert!(pack.disconnect_reason(declined).is_none());
+
+    pack.sha1 = "not a hash".to_owned();
+    assert!(!pack.has_valid_hash());
+}
+
+#[test]
+fn declined_pack_removes_client() {
+    use crate::{
+        network::{AsyncWriteHandle, WrappedClientBoundPacket},
+        server::ClientList,
+    };
+
+    let pack = ResourcePack {
+        url: "https://example.com/pack.zip".to_owned(),
+        sha1: String::new(),
+        required: true,
+        prompt: None,
+    };
+    let (handle, mut packets) = AsyncWriteHandle::detached();
+    let mut client_list = ClientList::new();
+    client_list.add_client(5, handle);
+
+    let status = ResourcePackStatus::Declined;
+    client_list.set_resource_pack_status(5, status);
+    client_list.disconnect(5, pack.disconnect_reason(status).unwrap());
+
+    assert!(matches!(
+        packets.try_recv(),
+        Ok(WrappedClientBoundPacket::Singleton(
+            ClientBoundPacket::DisconnectPlay { .. }
+        ))
+    ));
+    assert!(matches!(
+        packets.try_recv(),
+        Ok(WrappedClientBoundPacket::Disconnect)
+    ));
+    assert!(client_list.create_write_handle(5).is_none());
+    assert_eq!(client_list.iter().count(), 0);
+}
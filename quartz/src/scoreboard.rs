@@ -0,0 +1,521 @@
+use crate::network::ClientBoundPacket;
+use quartz_chat::{color::Color, Component};
+use std::collections::HashMap;
+
+const OBJECTIVE_CREATE: i8 = 0;
+const OBJECTIVE_REMOVE: i8 = 1;
+const OBJECTIVE_UPDATE: i8 = 2;
+
+const SCORE_UPDATE: i8 = 0;
+const SCORE_REMOVE: i8 = 1;
+
+const TEAM_CREATE: i8 = 0;
+const TEAM_REMOVE: i8 = 1;
+const TEAM_UPDATE: i8 = 2;
+const TEAM_ADD_MEMBERS: i8 = 3;
+const TEAM_REMOVE_MEMBERS: i8 = 4;
+
+/// How the client renders the scores of an objective.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum ObjectiveRenderType {
+    Integer = 0,
+    Hearts = 1,
+}
+
+/// A place on the client's screen where an objective can be displayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i8)]
+pub enum DisplaySlot {
+    List = 0,
+    Sidebar = 1,
+    BelowName = 2,
+}
+
+impl DisplaySlot {
+    const COUNT: usize = 3;
+}
+
+/// A named set of scores.
+#[derive(Clone, Debug)]
+pub struct Objective {
+    name: String,
+    pub display_name: Component,
+    /// The criteria which updates the scores of this objective, such as `dummy` or `health`.
+    pub criteria: String,
+    pub render_type: ObjectiveRenderType,
+}
+
+impl Objective {
+    pub fn new(
+        name: impl Into<String>,
+        display_name: Component,
+        criteria: impl Into<String>,
+        render_type: ObjectiveRenderType,
+    ) -> Self {
+        Objective {
+            name: name.into(),
+            display_name,
+            criteria: criteria.into(),
+            render_type,
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn packet(&self, mode: i8) -> ClientBoundPacket {
+        let (objective_value, r#type) = if mode == OBJECTIVE_REMOVE {
+            (None, None)
+        } else {
+            (
+                Some(Box::new(self.display_name.clone())),
+                Some(self.render_type as i32),
+            )
+        };
+
+        ClientBoundPacket::ScoreboardObjective {
+            objective_name: self.name.clone(),
+            mode,
+            objective_value,
+            r#type,
+        }
+    }
+}
+
+/// Whose name tags are shown for the members of a team.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameTagVisibility {
+    Always,
+    Never,
+    HideForOtherTeams,
+    HideForOwnTeam,
+}
+
+impl NameTagVisibility {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            NameTagVisibility::Always => "always",
+            NameTagVisibility::Never => "never",
+            NameTagVisibility::HideForOtherTeams => "hideForOtherTeams",
+            NameTagVisibility::HideForOwnTeam => "hideForOwnTeam",
+        }
+    }
+}
+
+/// Which entities the members of a team collide with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionRule {
+    Always,
+    Never,
+    PushOtherTeams,
+    PushOwnTeam,
+}
+
+impl CollisionRule {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            CollisionRule::Always => "always",
+            CollisionRule::Never => "never",
+            CollisionRule::PushOtherTeams => "pushOtherTeams",
+            CollisionRule::PushOwnTeam => "pushOwnTeam",
+        }
+    }
+}
+
+/// A group of entries, usually player names, which share a color, name decorations and rules.
+#[derive(Clone, Debug)]
+pub struct Team {
+    name: String,
+    pub display_name: Component,
+    /// The color of the members' names. Custom colors are not supported and are sent as a reset.
+    pub color: Color,
+    pub prefix: Component,
+    pub suffix: Component,
+    pub friendly_fire: bool,
+    pub see_friendly_invisibles: bool,
+    pub name_tag_visibility: NameTagVisibility,
+    pub collision_rule: CollisionRule,
+    members: Vec<String>,
+}
+
+impl Team {
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        Team {
+            display_name: Component::text(&name),
+            name,
+            color: Color::Reset,
+            prefix: Component::empty(),
+            suffix: Component::empty(),
+            friendly_fire: true,
+            see_friendly_invisibles: true,
+            name_tag_visibility: NameTagVisibility::Always,
+            collision_rule: CollisionRule::Always,
+            members: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn members(&self) -> &[String] {
+        &self.members
+    }
+
+    fn friendly_flags(&self) -> i8 {
+        self.friendly_fire as i8 | (self.see_friendly_invisibles as i8) << 1
+    }
+
+    fn color_id(&self) -> i32 {
+        match self.color {
+            Color::Black => 0,
+            Color::DarkBlue => 1,
+            Color::DarkGreen => 2,
+            Color::DarkAqua => 3,
+            Color::DarkRed => 4,
+            Color::DarkPurple => 5,
+            Color::Gold => 6,
+            Color::Gray => 7,
+            Color::DarkGray => 8,
+            Color::Blue => 9,
+            Color::Green => 10,
+            Color::Aqua => 11,
+            Color::Red => 12,
+            Color::LightPurple => 13,
+            Color::Yellow => 14,
+            Color::White => 15,
+            Color::Reset | Color::Custom(..) => 21,
+        }
+    }
+
+    fn packet(&self, mode: i8, entities: Option<Vec<String>>) -> ClientBoundPacket {
+        // Only the create and update modes send the team's settings
+        let has_info = mode == TEAM_CREATE || mode == TEAM_UPDATE;
+
+        ClientBoundPacket::Teams {
+            team_name: self.name.clone(),
+            mode,
+            team_display_name: has_info.then(|| Box::new(self.display_name.clone())),
+            friendly_flags: has_info.then(|| self.friendly_flags()),
+            name_tag_visibility: has_info.then(|| self.name_tag_visibility.as_str().to_owned()),
+            collision_rule: has_info.then(|| self.collision_rule.as_str().to_owned()),
+            team_color: has_info.then(|| self.color_id()),
+            team_prefix: has_info.then(|| Box::new(self.prefix.clone())),
+            team_suffix: has_info.then(|| Box::new(self.suffix.clone())),
+            entities: entities.map(Vec::into_boxed_slice),
+        }
+    }
+}
+
+/// Tracks the objectives, scores and teams of a server. Each modification returns the packet which
+/// informs clients of the change, and which should be sent to every player viewing the scoreboard.
+#[derive(Default)]
+pub struct Scoreboard {
+    objectives: HashMap<String, Objective>,
+    /// The scores of each objective, keyed by entry.
+    scores: HashMap<String, HashMap<String, i32>>,
+    display_slots: [Option<String>; DisplaySlot::COUNT],
+    teams: HashMap<String, Team>,
+}
+
+impl Scoreboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn objective(&self, name: &str) -> Option<&Objective> {
+        self.objectives.get(name)
+    }
+
+    /// Adds the given objective, or returns `None` if an objective with the same name exists.
+    pub fn add_objective(&mut self, objective: Objective) -> Option<ClientBoundPacket> {
+        if self.objectives.contains_key(objective.name()) {
+            return None;
+        }
+
+        let packet = objective.packet(OBJECTIVE_CREATE);
+        self.scores.insert(objective.name.clone(), HashMap::new());
+        self.objectives.insert(objective.name.clone(), objective);
+        Some(packet)
+    }
+
+    /// Applies the given update to the display name or render type of an objective.
+    pub fn update_objective(
+        &mut self,
+        name: &str,
+        update: impl FnOnce(&mut Objective),
+    ) -> Option<ClientBoundPacket> {
+        let objective = self.objectives.get_mut(name)?;
+        update(objective);
+        Some(objective.packet(OBJECTIVE_UPDATE))
+    }
+
+    /// Removes the given objective along with its scores, clearing any display slots it occupied.
+    pub fn remove_objective(&mut self, name: &str) -> Option<ClientBoundPacket> {
+        let objective = self.objectives.remove(name)?;
+        self.scores.remove(name);
+        for slot in &mut self.display_slots {
+            if slot.as_deref() == Some(name) {
+                *slot = None;
+            }
+        }
+
+        Some(objective.packet(OBJECTIVE_REMOVE))
+    }
+
+    pub fn score(&self, objective: &str, entry: &str) -> Option<i32> {
+        self.scores.get(objective)?.get(entry).copied()
+    }
+
+    /// Sets the score of an entry for the given objective, or returns `None` if the objective
+    /// doesn't exist.
+    pub fn set_score(
+        &mut self,
+        objective: &str,
+        entry: impl Into<String>,
+        value: i32,
+    ) -> Option<ClientBoundPacket> {
+        let entry = entry.into();
+        self.scores.get_mut(objective)?.insert(entry.clone(), value);
+
+        Some(ClientBoundPacket::UpdateScore {
+            entity_name: entry,
+            action: SCORE_UPDATE,
+            objective_name: objective.to_owned(),
+            value: Some(value),
+        })
+    }
+
+    /// Removes the score of an entry for the given objective, or returns `None` if the entry had no
+    /// score.
+    pub fn reset_score(&mut self, objective: &str, entry: &str) -> Option<ClientBoundPacket> {
+        self.scores.get_mut(objective)?.remove(entry)?;
+
+        Some(ClientBoundPacket::UpdateScore {
+            entity_name: entry.to_owned(),
+            action: SCORE_REMOVE,
+            objective_name: objective.to_owned(),
+            value: None,
+        })
+    }
+
+    pub fn displayed_objective(&self, slot: DisplaySlot) -> Option<&Objective> {
+        self.display_slots[slot as usize]
+            .as_deref()
+            .and_then(|name| self.objectives.get(name))
+    }
+
+    /// Displays the given objective in a slot, or clears the slot if no objective is given. Returns
+    /// `None` if the objective doesn't exist.
+    pub fn set_display_slot(
+        &mut self,
+        slot: DisplaySlot,
+        objective: Option<&str>,
+    ) -> Option<ClientBoundPacket> {
+        if let Some(name) = objective {
+            if !self.objectives.contains_key(name) {
+                return None;
+            }
+        }
+
+        self.display_slots[slot as usize] = objective.map(str::to_owned);
+        Some(ClientBoundPacket::DisplayScoreboard {
+            position: slot as i8,
+            // An empty name clears the slot
+            score_name: objective.unwrap_or_default().to_owned(),
+        })
+    }
+
+    pub fn team(&self, name: &str) -> Option<&Team> {
+        self.teams.get(name)
+    }
+
+    /// Returns the team the given entry belongs to, if any.
+    pub fn team_of(&self, entry: &str) -> Option<&Team> {
+        self.teams
+            .values()
+            .find(|team| team.members.iter().any(|member| member == entry))
+    }
+
+    /// Adds the given team, or returns `None` if a team with the same name exists. Any members the
+    /// team was created with are removed from their previous teams.
+    pub fn add_team(&mut self, mut team: Team) -> Option<ClientBoundPacket> {
+        if self.teams.contains_key(team.name()) {
+            return None;
+        }
+
+        let members = std::mem::take(&mut team.members);
+        for team in self.teams.values_mut() {
+            team.members.retain(|member| !members.contains(member));
+        }
+        team.members = members;
+
+        let packet = team.packet(TEAM_CREATE, Some(team.members.clone()));
+        self.teams.insert(team.name.clone(), team);
+        Some(packet)
+    }
+
+    /// Applies the given update to the settings of a team.
+    pub fn update_team(
+        &mut self,
+        name: &str,
+        update: impl FnOnce(&mut Team),
+    ) -> Option<ClientBoundPacket> {
+        let team = self.teams.get_mut(name)?;
+        update(team);
+        Some(team.packet(TEAM_UPDATE, None))
+    }
+
+    pub fn remove_team(&mut self, name: &str) -> Option<ClientBoundPacket> {
+        let team = self.teams.remove(name)?;
+        Some(team.packet(TEAM_REMOVE, None))
+    }
+
+    /// Adds the given entries to a team. Like in vanilla, an entry can only be on one team at a time,
+    /// so the entries are removed from any other team first.
+    pub fn add_team_members(
+        &mut self,
+        name: &str,
+        entries: impl IntoIterator<Item = String>,
+    ) -> Option<ClientBoundPacket> {
+        if !self.teams.contains_key(name) {
+            return None;
+        }
+
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        for team in self.teams.values_mut() {
+            team.members.retain(|member| !entries.contains(member));
+        }
+
+        // Unwrap is safe since we checked that the team exists above
+        let team = self.teams.get_mut(name).unwrap();
+        team.members.extend(entries.iter().cloned());
+        Some(team.packet(TEAM_ADD_MEMBERS, Some(entries)))
+    }
+
+    /// Removes the given entries from a team. Entries which were not on the team are ignored.
+    pub fn remove_team_members(
+        &mut self,
+        name: &str,
+        entries: impl IntoIterator<Item = String>,
+    ) -> Option<ClientBoundPacket> {
+        let team = self.teams.get_mut(name)?;
+        let removed = entries
+            .into_iter()
+            .filter(|entry| {
+                let len = team.members.len();
+                team.members.retain(|member| member != entry);
+                team.members.len() != len
+            })
+            .collect();
+
+        Some(team.packet(TEAM_REMOVE_MEMBERS, Some(removed)))
+    }
+
+    /// Returns the packets which sync the entire scoreboard to a client that just joined.
+    pub fn sync_packets(&self) -> Vec<ClientBoundPacket> {
+        let mut packets = Vec::new();
+
+        for objective in self.objectives.values() {
+            packets.push(objective.packet(OBJECTIVE_CREATE));
+
+            for (entry, &value) in &self.scores[&objective.name] {
+                packets.push(ClientBoundPacket::UpdateScore {
+                    entity_name: entry.clone(),
+                    action: SCORE_UPDATE,
+                    objective_name: objective.name.clone(),
+                    value: Some(value),
+                });
+            }
+        }
+
+        for (slot, objective) in self.display_slots.iter().enumerate() {
+            if let Some(objective) = objective {
+                packets.push(ClientBoundPacket::DisplayScoreboard {
+                    position: slot as i8,
+                    score_name: objective.clone(),
+                });
+            }
+        }
+
+        for team in self.teams.values() {
+            packets.push(team.packet(TEAM_CREATE, Some(team.members.clone())));
+        }
+
+        packets
+    }
+}
+
+#[test]
+fn score_and_display_packets() {
+    let mut scoreboard = Scoreboard::new();
+    let packets = [
+        scoreboard.add_objective(Objective::new(
+            "kills",
+            Component::text("Kills"),
+            "dummy",
+            ObjectiveRenderType::Integer,
+        )),
+        scoreboard.set_score("kills", "Steve", 5),
+        scoreboard.set_display_slot(DisplaySlot::Sidebar, Some("kills")),
+    ]
+    .map(Option::unwrap);
+
+    assert!(matches!(
+        &packets[0],
+        ClientBoundPacket::ScoreboardObjective {
+            objective_name,
+            mode: OBJECTIVE_CREATE,
+            objective_value: Some(_),
+            r#type: Some(0),
+        } if objective_name == "kills"
+    ));
+    assert!(matches!(
+        &packets[1],
+        ClientBoundPacket::UpdateScore {
+            entity_name,
+            action: SCORE_UPDATE,
+            objective_name,
+            value: Some(5),
+        } if entity_name == "Steve" && objective_name == "kills"
+    ));
+    assert!(matches!(
+        &packets[2],
+        ClientBoundPacket::DisplayScoreboard { position: 1, score_name } if score_name == "kills"
+    ));
+
+    assert_eq!(scoreboard.score("kills", "Steve"), Some(5));
+    assert!(scoreboard.set_score("deaths", "Steve", 1).is_none());
+
+    scoreboard.remove_objective("kills").unwrap();
+    assert!(scoreboard
+        .displayed_objective(DisplaySlot::Sidebar)
+        .is_none());
+}
+
+#[test]
+fn team_membership_is_exclusive() {
+    let mut scoreboard = Scoreboard::new();
+    scoreboard.add_team(Team::new("red")).unwrap();
+    scoreboard.add_team(Team::new("blue")).unwrap();
+
+    scoreboard.add_team_members("red", ["Steve".to_owned()]);
+    let packet = scoreboard
+        .add_team_members("blue", ["Steve".to_owned()])
+        .unwrap();
+
+    assert!(matches!(packet, ClientBoundPacket::Teams {
+        mode: TEAM_ADD_MEMBERS,
+        team_display_name: None,
+        entities: Some(_),
+        ..
+    }));
+    assert!(scoreboard.team("red").unwrap().members().is_empty());
+    assert_eq!(scoreboard.team_of("Steve").unwrap().name(), "blue");
+}
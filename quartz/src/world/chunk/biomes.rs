@@ -0,0 +1,323 @@
+use crate::world::chunk::{bits_for_palette, pack_palette_indices, ChunkDecodeError};
+use once_cell::sync::Lazy;
+use qdat::{UlnStr, UnlocalizedName};
+use quartz_datapack::{NetworkBiome, Registry, RegistryCodec};
+use quartz_nbt::{NbtCompound, NbtList};
+
+/// The number of biomes stored for each section, one for every 4x4x4 cell.
+pub const BIOMES_PER_SECTION: usize = 64;
+
+/// The biomes in the dimension codec sent to clients when they join, which is where the numeric
+/// IDs in chunk packets come from.
+static NETWORK_BIOMES: Lazy<Registry<NetworkBiome>> = Lazy::new(|| {
+    quartz_nbt::snbt::parse(include_str!("../../../../assets/dimension_codec.snbt"))
+        .ok()
+        .and_then(|nbt| RegistryCodec::from_nbt(&nbt).ok())
+        .map(|codec| codec.biomes)
+        .unwrap_or_else(|| Registry::new(UnlocalizedName::minecraft("worldgen/biome")))
+});
+
+/// Returns the numeric ID clients know the given biome by.
+pub fn network_biome_id(biome: &UlnStr) -> Option<i32> {
+    NETWORK_BIOMES.id_of(biome)
+}
+
+/// Returns the biome clients know by the given numeric ID.
+pub fn biome_from_network_id(id: i32) -> Option<&'static UnlocalizedName> {
+    NETWORK_BIOMES
+        .entries
+        .iter()
+        .find(|entry| entry.id == id)
+        .map(|entry| &entry.name)
+}
+
+/// The biomes of a section, stored like block states as a palette of biome names and the palette
+/// index of every 4x4x4 cell packed into longs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BiomeContainer {
+    palette: Vec<UnlocalizedName>,
+    /// The number of bits used for each index, zero while the palette holds a single biome
+    bits: u32,
+    data: Vec<u64>,
+}
+
+impl BiomeContainer {
+    /// Creates a container where every cell has the given biome.
+    pub fn new(biome: UnlocalizedName) -> Self {
+        BiomeContainer {
+            palette: vec![biome],
+            bits: 0,
+            data: Vec::new(),
+        }
+    }
+
+    /// Reads the `biomes` compound of a section. Unlike block states, the entries are packed as
+    /// tightly as the palette allows.
+    pub(crate) fn from_anvil_nbt(nbt: &NbtCompound) -> Result<Self, ChunkDecodeError> {
+        let palette = nbt
+            .get::<_, &NbtList>("palette")?
+            .iter_map::<&str>()
+            .map(|name| {
+                let name = name?;
+                UnlocalizedName::from_str(name)
+                    .map_err(|_| ChunkDecodeError::UnknownBiome(name.to_owned()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut container = match palette.first() {
+            Some(biome) => BiomeContainer::new(biome.clone()),
+            None => return Err(ChunkDecodeError::UnknownBiome(String::new())),
+        };
+        // A section with a single biome leaves out its data
+        if palette.len() <= 1 {
+            return Ok(container);
+        }
+
+        let bits = bits_for_palette(palette.len());
+        let mut data = nbt
+            .get::<_, &[i64]>("data")?
+            .iter()
+            .map(|&long| long as u64)
+            .collect::<Vec<_>>();
+        data.resize(required_longs(bits), 0);
+
+        container.palette = palette;
+        container.bits = bits;
+        container.data = data;
+
+        // Every cell has to refer to a biome in the palette, or looking it up would panic
+        if let Some(index) = (0 .. BIOMES_PER_SECTION)
+            .map(|cell| container.index_at(cell))
+            .find(|&index| index >= container.palette.len())
+        {
+            return Err(ChunkDecodeError::InvalidBiomeIndex {
+                index,
+                palette_len: container.palette.len(),
+            });
+        }
+
+        Ok(container)
+    }
+
+    /// Creates a container from the numeric IDs clients know biomes by, one for each cell in the
+    /// order they are sent. Cells with an unknown ID are left as plains.
+    pub fn from_network_ids(ids: &[i32]) -> Self {
+        let mut container = BiomeContainer::default();
+        for (cell, &id) in ids.iter().take(BIOMES_PER_SECTION).enumerate() {
+            if let Some(biome) = biome_from_network_id(id) {
+                container.set_biome(cell & 3, cell >> 4, (cell >> 2) & 3, biome.clone());
+            }
+        }
+
+        container
+    }
+
+    /// Returns the numeric ID of every cell's biome in the order they are sent to clients, with
+    /// biomes clients don't know about sent as plains.
+    pub fn network_ids(&self) -> [i32; BIOMES_PER_SECTION] {
+        let ids = self
+            .palette
+            .iter()
+            .map(|biome| network_biome_id(biome).unwrap_or_else(plains_network_id))
+            .collect::<Vec<_>>();
+        std::array::from_fn(|cell| ids[self.index_at(cell)])
+    }
+
+    /// Writes this container in the format read by [`from_anvil_nbt`](Self::from_anvil_nbt),
+    /// leaving out biomes which are no longer used.
+    pub(crate) fn to_anvil_nbt(&self) -> NbtCompound {
+        let mut palette = Vec::new();
+        let indices = (0 .. BIOMES_PER_SECTION)
+            .map(|cell| {
+                let biome = &self.palette[self.index_at(cell)];
+                match palette.iter().position(|&entry| entry == biome) {
+                    Some(index) => index,
+                    None => {
+                        palette.push(biome);
+                        palette.len() - 1
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut nbt = NbtCompound::new();
+        if palette.len() > 1 {
+            nbt.insert(
+                "data",
+                pack_palette_indices(&indices, bits_for_palette(palette.len())),
+            );
+        }
+        nbt.insert(
+            "palette",
+            NbtList::from(
+                palette
+                    .into_iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+            ),
+        );
+
+        nbt
+    }
+
+    /// Returns the biome of the cell at the given coordinates, which are in cells rather than
+    /// blocks so each must be less than four.
+    pub fn get_biome(&self, x: usize, y: usize, z: usize) -> &UnlocalizedName {
+        &self.palette[self.index_at(cell_index(x, y, z))]
+    }
+
+    /// Sets the biome of the cell at the given coordinates, adding it to the palette if it isn't
+    /// there already, and returns the old biome.
+    pub fn set_biome(
+        &mut self,
+        x: usize,
+        y: usize,
+        z: usize,
+        biome: UnlocalizedName,
+    ) -> UnlocalizedName {
+        let cell = cell_index(x, y, z);
+        let old = self.palette[self.index_at(cell)].clone();
+
+        let index = match self.palette.iter().position(|entry| *entry == biome) {
+            Some(index) => index,
+            None => {
+                self.palette.push(biome);
+                self.resize(bits_for_palette(self.palette.len()));
+                self.palette.len() - 1
+            }
+        };
+        self.set_index_at(cell, index);
+
+        old
+    }
+
+    /// Returns the biomes which may appear in this container. Biomes stay in the palette after
+    /// they are replaced, until the container is saved.
+    pub fn palette(&self) -> &[UnlocalizedName] {
+        &self.palette
+    }
+
+    /// Returns the packed palette indices, which is empty while there is only one biome.
+    pub fn data(&self) -> &[u64] {
+        &self.data
+    }
+
+    fn index_at(&self, cell: usize) -> usize {
+        if self.bits == 0 {
+            return 0;
+        }
+
+        let per_long = (64 / self.bits) as usize;
+        let mask = (1u64 << self.bits) - 1;
+        ((self.data[cell / per_long] >> ((cell % per_long) as u32 * self.bits)) & mask) as usize
+    }
+
+    fn set_index_at(&mut self, cell: usize, index: usize) {
+        if self.bits == 0 {
+            return;
+        }
+
+        let per_long = (64 / self.bits) as usize;
+        let shift = (cell % per_long) as u32 * self.bits;
+        let mask = (1u64 << self.bits) - 1;
+        let long = &mut self.data[cell / per_long];
+        *long = (*long & !(mask << shift)) | (index as u64) << shift;
+    }
+
+    /// Repacks the indices with a new number of bits, which must be enough for the palette.
+    fn resize(&mut self, bits: u32) {
+        if bits == self.bits {
+            return;
+        }
+
+        let indices = (0 .. BIOMES_PER_SECTION)
+            .map(|cell| self.index_at(cell))
+            .collect::<Vec<_>>();
+        self.data = pack_palette_indices(&indices, bits)
+            .into_iter()
+            .map(|long| long as u64)
+            .collect();
+        self.bits = bits;
+    }
+}
+
+impl Default for BiomeContainer {
+    fn default() -> Self {
+        BiomeContainer::new(UnlocalizedName::minecraft("plains"))
+    }
+}
+
+/// Returns the numeric ID of plains, which stands in for biomes clients don't know about.
+pub fn plains_network_id() -> i32 {
+    network_biome_id(UlnStr::minecraft("plains")).unwrap_or(1)
+}
+
+#[inline]
+fn cell_index(x: usize, y: usize, z: usize) -> usize {
+    debug_assert!(x < 4 && y < 4 && z < 4, "Biome cell out of bounds");
+    y << 4 | z << 2 | x
+}
+
+#[inline]
+fn required_longs(bits: u32) -> usize {
+    BIOMES_PER_SECTION.div_ceil((64 / bits) as usize)
+}
+
+#[test]
+fn biome_palette_growth() {
+    let plains = UnlocalizedName::minecraft("plains");
+    let desert = UnlocalizedName::minecraft("desert");
+    let forest = UnlocalizedName::minecraft("forest");
+
+    let mut biomes = BiomeContainer::new(plains.clone());
+    assert!(biomes.data().is_empty());
+    assert_eq!(biomes.get_biome(3, 3, 3), &plains);
+
+    assert_eq!(biomes.set_biome(1, 0, 0, desert.clone()), plains);
+    assert_eq!(biomes.palette(), [plains.clone(), desert.clone()]);
+    // One bit for each of the 64 cells
+    assert_eq!(biomes.data(), [0b10]);
+
+    biomes.set_biome(0, 1, 0, forest.clone());
+    assert_eq!(biomes.palette().len(), 3);
+    // Two bits for each cell, so 32 cells to a long
+    assert_eq!(biomes.data(), [0b0100 | 0b10 << 32, 0]);
+    assert_eq!(biomes.get_biome(1, 0, 0), &desert);
+    assert_eq!(biomes.get_biome(0, 1, 0), &forest);
+    assert_eq!(biomes.get_biome(2, 0, 0), &plains);
+
+    // Desert is no longer used, so it is left out when saving
+    biomes.set_biome(1, 0, 0, plains.clone());
+    let nbt = biomes.to_anvil_nbt();
+    assert_eq!(
+        nbt.get::<_, &NbtList>("palette")
+            .unwrap()
+            .iter_map::<&str>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        ["minecraft:plains", "minecraft:forest"]
+    );
+    assert_eq!(nbt.get::<_, &[i64]>("data").unwrap(), [1 << 16]);
+
+    let loaded = BiomeContainer::from_anvil_nbt(&nbt).unwrap();
+    assert_eq!(loaded.get_biome(0, 1, 0), &forest);
+    assert_eq!(loaded.get_biome(1, 0, 0), &plains);
+}
+
+#[test]
+fn biome_palette_index_out_of_range() {
+    use quartz_nbt::compound;
+
+    // Two bits for each cell, where the second cell refers to a fourth biome which isn't there
+    let nbt = compound! {
+        "palette": ["minecraft:plains", "minecraft:desert", "minecraft:forest"],
+        "data": vec![0b1100i64]
+    };
+    assert!(matches!(
+        BiomeContainer::from_anvil_nbt(&nbt),
+        Err(ChunkDecodeError::InvalidBiomeIndex {
+            index: 3,
+            palette_len: 3
+        })
+    ));
+}
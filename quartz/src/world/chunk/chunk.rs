@@ -1,36 +1,81 @@
 use crate::{
-    base::{BlockState, StateID},
-    world::chunk::SectionStore,
+    base::{BlockEntity, BlockState, StateID},
+    block::entity::BlockEntity as _,
+    network::ClientBoundPacket,
+    world::chunk::{
+        plains_network_id,
+        ChunkDecodeError,
+        DataVersion,
+        Heightmap,
+        HeightmapType,
+        Section,
+        SectionInsertionError,
+        SectionStore,
+        BIOMES_PER_SECTION,
+    },
     Registry,
 };
-use qdat::world::{
-    lighting::LightBuffer,
-    location::{BlockPosition, Coordinate, CoordinatePair},
+use qdat::{
+    world::{
+        lighting::LightBuffer,
+        location::{BlockPosition, Coordinate, CoordinatePair, SectionPosition},
+    },
+    UnlocalizedName,
 };
 use quartz_nbt::{NbtCompound, NbtList};
 use quartz_net::{packet_data::SectionData, BitMask};
 use serde::Deserialize;
-use std::fmt::{self, Debug, Formatter};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    ops::Range,
+};
+
+/// The sections clients expect biomes for, from the bottom of the world up to a height of 256.
+const CLIENT_BIOME_SECTIONS: Range<i8> = 0 .. 16;
 
 pub struct Chunk {
     block_offset: CoordinatePair,
     section_store: SectionStore,
     // We store the heightmaps just as nbt, this could be improved in the future to reduce memory usage
     heightmaps: NbtCompound,
-    biomes: Box<[i32]>,
+    block_entities: HashMap<BlockPosition, BlockEntity>,
+    block_ticks: Vec<ScheduledTick>,
+    fluid_ticks: Vec<ScheduledTick>,
+}
+
+/// Whether a scheduled tick updates the block or the fluid at its position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TickType {
+    Block,
+    Fluid,
+}
+
+/// An update which has been scheduled to happen at a position after a delay, such as a repeater
+/// changing state or water flowing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScheduledTick {
+    /// The block or fluid the tick was scheduled for.
+    pub id: UnlocalizedName,
+    pub pos: BlockPosition,
+    /// The number of ticks left until the update happens.
+    pub delay: i32,
+    /// Ticks which are due at the same time happen in order of priority, lowest first.
+    pub priority: i32,
 }
 
 impl From<RawChunk> for Chunk {
     fn from(raw: RawChunk) -> Self {
-        let level = raw.level;
+        let mut level = raw.level;
         let block_offset = CoordinatePair::new(level.x_pos * 16, level.z_pos * 16);
 
-        Chunk {
-            block_offset,
-            section_store: level.sections,
-            heightmaps: level.heightmaps,
-            biomes: level.biomes,
+        // Older chunks store biomes by their numeric IDs rather than in each section
+        for section in level.sections.sections_mut() {
+            section.load_network_biomes(&level.biomes);
         }
+
+        Chunk::new(block_offset, level.sections, level.heightmaps)
     }
 }
 
@@ -39,14 +84,156 @@ impl Chunk {
         block_offset: CoordinatePair,
         section_store: SectionStore,
         heightmaps: NbtCompound,
-        biomes: Box<[i32]>,
     ) -> Chunk {
         Chunk {
             block_offset,
             section_store,
             heightmaps,
-            biomes,
+            block_entities: HashMap::new(),
+            block_ticks: Vec::new(),
+            fluid_ticks: Vec::new(),
+        }
+    }
+
+    /// Reads a chunk saved in the format used since 1.18, as it is stored in region files.
+    pub fn from_anvil_nbt(nbt: &NbtCompound) -> Result<Chunk, ChunkDecodeError> {
+        let chunk_x = nbt.get::<_, i32>("xPos")?;
+        let chunk_z = nbt.get::<_, i32>("zPos")?;
+        // Older chunks leave out yPos, their worlds started at zero
+        let min_section_y = nbt.get::<_, i32>("yPos").unwrap_or(0);
+
+        let raw_sections = nbt.get::<_, &NbtList>("sections")?;
+        // The lowest stored section only holds the light just below the world
+        let mut section_store = SectionStore::with_min_section(
+            raw_sections.len(),
+            (min_section_y - 1).clamp(i8::MIN as i32, i8::MAX as i32) as i8,
+        );
+        for raw in raw_sections.iter_map::<&NbtCompound>() {
+            let raw = raw?;
+            let section = Section::from_anvil_nbt(raw)?;
+
+            match section_store.insert(section) {
+                Ok(_) => {}
+                // The sections just above and below the world only store light, so we can do
+                // without them if they don't fit in the store
+                Err(SectionInsertionError::IndexOutOfRange(_))
+                    if !raw.contains_key("block_states") => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        let heightmaps = nbt
+            .get::<_, &NbtCompound>("Heightmaps")
+            .map(Clone::clone)
+            .unwrap_or_else(|_| NbtCompound::new());
+
+        let mut chunk = Chunk::new(
+            CoordinatePair::new(chunk_x * 16, chunk_z * 16),
+            section_store,
+            heightmaps,
+        );
+        if HeightmapType::ALL
+            .iter()
+            .any(|heightmap_type| !chunk.heightmaps.contains_key(heightmap_type.name()))
+        {
+            chunk.recalculate_heightmaps(min_section_y * 16);
+        }
+
+        if let Ok(block_entities) = nbt.get::<_, &NbtList>("block_entities") {
+            for raw in block_entities.iter_map::<&NbtCompound>() {
+                let raw = raw?;
+                let pos = read_block_position(raw)?;
+                // Block entities the server doesn't implement yet are dropped
+                if let Some(entity) = BlockEntity::load(pos, raw)? {
+                    chunk.add_block_entity(pos, entity);
+                }
+            }
+        }
+
+        for (tick_type, key) in [
+            (TickType::Block, "block_ticks"),
+            (TickType::Fluid, "fluid_ticks"),
+        ] {
+            if let Ok(ticks) = nbt.get::<_, &NbtList>(key) {
+                for raw in ticks.iter_map::<&NbtCompound>() {
+                    let raw = raw?;
+                    let raw_id = raw.get::<_, &str>("i")?;
+                    chunk.schedule_tick(tick_type, ScheduledTick {
+                        id: UnlocalizedName::from_str(raw_id).map_err(|_| {
+                            ChunkDecodeError::UnknownStateProperty(format!(
+                                "Invalid tick id {raw_id}"
+                            ))
+                        })?,
+                        pos: read_block_position(raw)?,
+                        delay: raw.get("t")?,
+                        priority: raw.get("p").unwrap_or(0),
+                    });
+                }
+            }
+        }
+
+        Ok(chunk)
+    }
+
+    /// Writes this chunk in the format read by [`from_anvil_nbt`](Self::from_anvil_nbt), with
+    /// each section's blocks and biomes stored as a palette and packed data array.
+    pub fn to_nbt(&self) -> NbtCompound {
+        let mut sections = self.section_store.sections().iter().collect::<Vec<_>>();
+        sections.sort_by_key(|section| section.y);
+
+        let mut nbt = NbtCompound::new();
+        nbt.insert("DataVersion", DataVersion::V1_18_2.0);
+        nbt.insert("xPos", self.block_offset.x >> 4);
+        // The lowest section in the store only holds light, the world starts just above it
+        nbt.insert("yPos", self.section_store.min_section() as i32 + 1);
+        nbt.insert("zPos", self.block_offset.z >> 4);
+        nbt.insert("Status", "full");
+
+        let sections = sections
+            .into_iter()
+            .map(Section::to_anvil_nbt)
+            .collect::<Vec<_>>();
+        nbt.insert("sections", NbtList::from(sections));
+
+        nbt.insert("Heightmaps", self.heightmaps.clone());
+
+        let block_entities = self
+            .block_entities
+            .iter()
+            .map(|(pos, entity)| {
+                let mut entity_nbt = NbtCompound::new();
+                entity_nbt.insert("id", entity.id());
+                entity_nbt.insert("x", pos.x);
+                entity_nbt.insert("y", pos.y as i32);
+                entity_nbt.insert("z", pos.z);
+                entity.write_nbt(&mut entity_nbt);
+                entity_nbt
+            })
+            .collect::<Vec<_>>();
+        nbt.insert("block_entities", NbtList::from(block_entities));
+
+        for (tick_type, key) in [
+            (TickType::Block, "block_ticks"),
+            (TickType::Fluid, "fluid_ticks"),
+        ] {
+            let ticks = self
+                .scheduled_ticks(tick_type)
+                .iter()
+                .map(|tick| {
+                    let mut tick_nbt = NbtCompound::new();
+                    tick_nbt.insert("i", tick.id.to_string());
+                    tick_nbt.insert("x", tick.pos.x);
+                    tick_nbt.insert("y", tick.pos.y as i32);
+                    tick_nbt.insert("z", tick.pos.z);
+                    tick_nbt.insert("t", tick.delay);
+                    tick_nbt.insert("p", tick.priority);
+                    tick_nbt
+                })
+                .collect::<Vec<_>>();
+            nbt.insert(key, NbtList::from(ticks));
         }
+
+        nbt
     }
 
     pub fn coordinates(&self) -> Coordinate {
@@ -57,12 +244,15 @@ impl Chunk {
     fn section_index_absolute(&self, pos: BlockPosition) -> usize {
         ((pos.x - self.block_offset.x)
             + (pos.z - self.block_offset.z) * 16
-            + (pos.y as i32 % 16) * 256) as usize
+            + (pos.y as i32).rem_euclid(16) * 256) as usize
     }
 
     #[inline]
     pub fn block_state_at(&self, absolute_position: BlockPosition) -> Option<&'static BlockState> {
-        match self.section_store.get(absolute_position.y as i8 >> 4) {
+        match i8::try_from(absolute_position.y >> 4)
+            .ok()
+            .and_then(|y| self.section_store.get(y))
+        {
             Some(section) => Registry::state_for_id(
                 section.block_state_at(self.section_index_absolute(absolute_position))?,
             ),
@@ -70,7 +260,8 @@ impl Chunk {
         }
     }
 
-    /// Sets the blockstate at the provided position to the new state
+    /// Sets the blockstate at the provided position to the new state, removing the block entity
+    /// there if the block is replaced by a different block
     ///
     /// Returns the old state
     pub fn set_block_state_at(
@@ -79,9 +270,126 @@ impl Chunk {
         state: StateID,
     ) -> Option<&'static BlockState> {
         let index = self.section_index_absolute(absolute_position);
-        match self.section_store.get_mut(absolute_position.y as i8 >> 4) {
-            Some(section) => Registry::state_for_id(section.set_block_state_at(index, state)?),
-            None => None,
+        let old = match i8::try_from(absolute_position.y >> 4)
+            .ok()
+            .and_then(|y| self.section_store.get_mut(y))
+        {
+            Some(section) => Registry::state_for_id(section.set_block_state_at(index, state)?)?,
+            None => return None,
+        };
+
+        // Changing the state of a block keeps its entity, such as a furnace being lit
+        let same_block = Registry::state_for_id(state)
+            .map(|new| std::ptr::eq(old.handle, new.handle))
+            .unwrap_or(false);
+        if !same_block {
+            self.block_entities.remove(&absolute_position);
+        }
+
+        Some(old)
+    }
+
+    pub fn block_entity_at(&self, absolute_position: BlockPosition) -> Option<&BlockEntity> {
+        self.block_entities.get(&absolute_position)
+    }
+
+    pub fn block_entity_at_mut(
+        &mut self,
+        absolute_position: BlockPosition,
+    ) -> Option<&mut BlockEntity> {
+        self.block_entities.get_mut(&absolute_position)
+    }
+
+    /// Adds a block entity at the given position, returning the entity it replaced if there was
+    /// one
+    pub fn add_block_entity(
+        &mut self,
+        absolute_position: BlockPosition,
+        entity: BlockEntity,
+    ) -> Option<BlockEntity> {
+        self.block_entities.insert(absolute_position, entity)
+    }
+
+    pub fn remove_block_entity(&mut self, absolute_position: BlockPosition) -> Option<BlockEntity> {
+        self.block_entities.remove(&absolute_position)
+    }
+
+    pub fn block_entities(&self) -> impl Iterator<Item = (BlockPosition, &BlockEntity)> {
+        self.block_entities
+            .iter()
+            .map(|(&pos, entity)| (pos, entity))
+    }
+
+    /// Schedules a block or fluid update in this chunk
+    pub fn schedule_tick(&mut self, tick_type: TickType, tick: ScheduledTick) {
+        self.ticks_mut(tick_type).push(tick);
+    }
+
+    /// Returns the block or fluid updates which have been scheduled and not yet run
+    pub fn scheduled_ticks(&self, tick_type: TickType) -> &[ScheduledTick] {
+        match tick_type {
+            TickType::Block => &self.block_ticks,
+            TickType::Fluid => &self.fluid_ticks,
+        }
+    }
+
+    /// Removes every scheduled update of the given type at a position, returning the number removed
+    pub fn cancel_ticks_at(
+        &mut self,
+        tick_type: TickType,
+        absolute_position: BlockPosition,
+    ) -> usize {
+        let ticks = self.ticks_mut(tick_type);
+        let len = ticks.len();
+        ticks.retain(|tick| tick.pos != absolute_position);
+        len - ticks.len()
+    }
+
+    fn ticks_mut(&mut self, tick_type: TickType) -> &mut Vec<ScheduledTick> {
+        match tick_type {
+            TickType::Block => &mut self.block_ticks,
+            TickType::Fluid => &mut self.fluid_ticks,
+        }
+    }
+
+    /// Creates the packet which updates a single block on the client.
+    pub fn block_change_packet(pos: BlockPosition, new_state: StateID) -> ClientBoundPacket {
+        ClientBoundPacket::BlockChange {
+            location: pos,
+            block_id: new_state as i32,
+        }
+    }
+
+    /// Creates the packet which updates several blocks within the same section on the client. Every
+    /// changed position must be within the given section.
+    pub fn multi_block_change_packet(
+        section_pos: SectionPosition,
+        changes: &[(BlockPosition, StateID)],
+    ) -> ClientBoundPacket {
+        let blocks = changes
+            .iter()
+            .map(|&(pos, state)| {
+                debug_assert_eq!(
+                    SectionPosition::of_block(pos),
+                    section_pos,
+                    "Block change outside of section"
+                );
+                (state as i64) << 12 | SectionPosition::pack_local(pos) as i64
+            })
+            .collect();
+
+        ClientBoundPacket::MultiBlockChange {
+            chunk_section_position: section_pos.as_i64(),
+            trust_edges_inverse: false,
+            blocks,
+        }
+    }
+
+    /// Creates the packet which tells the client to unload this chunk.
+    pub fn unload_packet(&self) -> ClientBoundPacket {
+        ClientBoundPacket::UnloadChunk {
+            chunk_x: self.block_offset.x >> 4,
+            chunk_z: self.block_offset.z >> 4,
         }
     }
 
@@ -89,12 +397,125 @@ impl Chunk {
         &self.section_store
     }
 
+    /// Returns the sections whose blocks changed since the chunk was loaded or last saved
+    pub fn dirty_sections(&self) -> impl Iterator<Item = &Section> {
+        self.section_store
+            .sections()
+            .iter()
+            .filter(|section| section.is_dirty())
+    }
+
+    /// Returns whether the chunk needs to be written when saving
+    pub fn is_dirty(&self) -> bool {
+        self.dirty_sections().next().is_some()
+    }
+
+    /// Marks every section as clean, should be called once the chunk has been written
+    pub fn mark_saved(&mut self) {
+        for section in self.section_store.sections_mut() {
+            section.mark_saved();
+        }
+    }
+
     pub fn get_heightmaps(&self) -> NbtCompound {
         self.heightmaps.clone()
     }
 
-    pub fn biomes(&self) -> &[i32] {
-        &self.biomes
+    /// Recalculates the `MOTION_BLOCKING` and `WORLD_SURFACE` heightmaps by scanning each column
+    /// from the top down, keeping any other heightmaps as they were. Heights are stored relative
+    /// to `min_y`, the bottom of the world, so blocks below it are ignored.
+    pub fn recalculate_heightmaps(&mut self, min_y: i32) {
+        let mut sections = self
+            .section_store
+            .sections()
+            .iter()
+            .filter(|section| i8::from(section.y) as i32 * 16 >= min_y)
+            .collect::<Vec<_>>();
+        sections.sort_by_key(|section| Reverse(section.y));
+
+        for heightmap_type in HeightmapType::ALL {
+            let mut heightmap = Heightmap::new();
+            // Sections only hold a handful of distinct states, so only look each one up once
+            let mut opaque = HashMap::new();
+
+            for z in 0 .. 16 {
+                for x in 0 .. 16 {
+                    'column: for section in &sections {
+                        for y in (0 .. 16).rev() {
+                            let state = match section.block_state_at(y * 256 + z * 16 + x) {
+                                Some(state) => state,
+                                None => continue 'column,
+                            };
+
+                            if *opaque
+                                .entry(state)
+                                .or_insert_with(|| heightmap_type.is_opaque(state))
+                            {
+                                let height = i8::from(section.y) as i32 * 16 + y as i32 - min_y + 1;
+                                heightmap.set(x, z, height as u16);
+                                break 'column;
+                            }
+                        }
+                    }
+                }
+            }
+
+            self.heightmaps
+                .insert(heightmap_type.name(), heightmap.to_packed());
+        }
+    }
+
+    /// Returns the numeric IDs of the biomes sent to clients, one for every 4x4x4 cell of the
+    /// sections clients expect biomes for. Cells in sections which aren't stored are plains.
+    pub fn biome_ids(&self) -> Box<[i32]> {
+        CLIENT_BIOME_SECTIONS
+            .flat_map(|y| match self.section_store.get(y) {
+                Some(section) => section.biomes().network_ids(),
+                None => [plains_network_id(); BIOMES_PER_SECTION],
+            })
+            .collect()
+    }
+
+    /// Returns the biome at the given position. Biomes are stored for 4x4x4 cells, so every block
+    /// in a cell has the same biome. Positions in sections which aren't stored are plains, the same
+    /// as they are sent to clients.
+    pub fn get_biome(&self, absolute_position: BlockPosition) -> UnlocalizedName {
+        let section = i8::try_from(absolute_position.y >> 4)
+            .ok()
+            .and_then(|y| self.section_store.get(y));
+
+        match (section, self.biome_cell(absolute_position)) {
+            (Some(section), Some((x, y, z))) => section.biomes().get_biome(x, y, z).clone(),
+            _ => UnlocalizedName::minecraft("plains"),
+        }
+    }
+
+    /// Sets the biome of the 4x4x4 cell containing the given position, returning the old biome, or
+    /// `None` if the section containing it isn't stored.
+    pub fn set_biome(
+        &mut self,
+        absolute_position: BlockPosition,
+        biome: UnlocalizedName,
+    ) -> Option<UnlocalizedName> {
+        let (x, y, z) = self.biome_cell(absolute_position)?;
+        self.section_store
+            .get_mut(i8::try_from(absolute_position.y >> 4).ok()?)
+            .map(|section| section.set_biome(x, y, z, biome))
+    }
+
+    /// Returns the coordinates of the cell containing the given position within its section.
+    fn biome_cell(&self, absolute_position: BlockPosition) -> Option<(usize, usize, usize)> {
+        let x = (absolute_position.x - self.block_offset.x) >> 2;
+        let z = (absolute_position.z - self.block_offset.z) >> 2;
+        if !(0 .. 4).contains(&x) || !(0 .. 4).contains(&z) {
+            return None;
+        }
+
+        Some((
+            x as usize,
+            ((absolute_position.y & 15) >> 2) as usize,
+            z as usize,
+        ))
     }
 
     pub fn gen_client_section_data(&self) -> (BitMask, SectionData) {
@@ -145,6 +566,14 @@ impl Chunk {
     }
 }
 
+fn read_block_position(nbt: &NbtCompound) -> Result<BlockPosition, ChunkDecodeError> {
+    Ok(BlockPosition {
+        x: nbt.get("x")?,
+        y: nbt.get::<_, i32>("y")? as i16,
+        z: nbt.get("z")?,
+    })
+}
+
 impl Debug for Chunk {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "Chunk@{:?}", self.block_offset)
@@ -155,7 +584,7 @@ impl Debug for Chunk {
 #[allow(dead_code)]
 pub(crate) struct RawChunk {
     #[serde(rename = "DataVersion")]
-    data_version: i32,
+    data_version: DataVersion,
     #[serde(rename = "Level")]
     level: RawChunkData,
 }
@@ -164,7 +593,7 @@ pub(crate) struct RawChunk {
 #[allow(dead_code)]
 pub(crate) struct RawClientChunk {
     #[serde(rename = "DataVersion")]
-    pub data_version: i32,
+    pub data_version: DataVersion,
     #[serde(rename = "Level")]
     pub level: RawClientChunkData,
 }
@@ -220,3 +649,488 @@ pub(crate) struct RawClientChunkData {
     #[serde(rename = "TileEntities")]
     pub tile_entities: Option<NbtList>,
 }
+
+#[test]
+fn multi_block_change_encoding() {
+    let section = SectionPosition::new(-1, 4, 2);
+    let changes = [
+        (
+            BlockPosition {
+                x: -16,
+                y: 64,
+                z: 32,
+            },
+            1,
+        ),
+        (
+            BlockPosition {
+                x: -1,
+                y: 79,
+                z: 47,
+            },
+            9,
+        ),
+    ];
+
+    match Chunk::multi_block_change_packet(section, &changes) {
+        ClientBoundPacket::MultiBlockChange {
+            chunk_section_position,
+            blocks,
+            ..
+        } => {
+            assert_eq!(chunk_section_position, 0x3FFFFF << 42 | 2 << 20 | 4);
+            assert_eq!(&*blocks, &[1 << 12, 9 << 12 | 0xFFF]);
+        }
+        packet => panic!("Unexpected packet {packet:?}"),
+    }
+}
+
+#[test]
+fn dirty_after_block_change() {
+    // Replacing a block looks up the old state in the registry
+    let _ = Registry::init();
+
+    let mut section_store = SectionStore::new(1);
+    section_store.insert(Section::empty(4)).unwrap();
+    let mut chunk = Chunk::new(CoordinatePair::new(0, 0), section_store, NbtCompound::new());
+    assert!(!chunk.is_dirty());
+
+    let pos = BlockPosition { x: 1, y: 70, z: 2 };
+    chunk.set_block_state_at(pos, qdat::block::states::BlockStateData::Stone.id());
+    assert_eq!(chunk.dirty_sections().count(), 1);
+    assert_eq!(i8::from(chunk.dirty_sections().next().unwrap().y), 4);
+
+    chunk.mark_saved();
+    assert!(!chunk.is_dirty());
+}
+
+#[test]
+fn replacing_furnace_removes_entity() {
+    use crate::block::{entities::FurnaceBlockEntity, BlockStateImpl, StateBuilder};
+    use qdat::{block::states::BlockStateData, UlnStr};
+
+    let _ = Registry::init();
+
+    let mut section_store = SectionStore::new(1);
+    section_store.insert(Section::empty(4)).unwrap();
+    let mut chunk = Chunk::new(CoordinatePair::new(0, 0), section_store, NbtCompound::new());
+
+    let furnace = |lit| {
+        let mut builder = BlockState::builder(UlnStr::minecraft("furnace")).unwrap();
+        builder.add_property("lit", lit).unwrap();
+        builder.build().id()
+    };
+    let pos = BlockPosition { x: 3, y: 70, z: 5 };
+    chunk.set_block_state_at(pos, furnace("false"));
+    chunk.add_block_entity(pos, FurnaceBlockEntity::new(pos, None).into());
+
+    chunk.set_block_state_at(pos, furnace("true"));
+    assert!(chunk.block_entity_at(pos).is_some());
+
+    chunk.set_block_state_at(pos, BlockStateData::Stone.id());
+    assert!(chunk.block_entity_at(pos).is_none());
+
+    chunk.schedule_tick(TickType::Block, ScheduledTick {
+        id: UnlocalizedName::minecraft("stone"),
+        pos,
+        delay: 2,
+        priority: 0,
+    });
+    assert_eq!(chunk.scheduled_ticks(TickType::Block).len(), 1);
+    assert!(chunk.scheduled_ticks(TickType::Fluid).is_empty());
+    assert_eq!(chunk.cancel_ticks_at(TickType::Block, pos), 1);
+    assert!(chunk.scheduled_ticks(TickType::Block).is_empty());
+}
+
+#[test]
+fn load_anvil_chunk() {
+    use crate::{
+        block::{BlockStateImpl, StateBuilder},
+        world::chunk::network_biome_id,
+    };
+    use qdat::{
+        block::states::{BlockStateData, AIR},
+        UlnStr,
+    };
+    use quartz_nbt::compound;
+
+    let _ = Registry::init();
+
+    // Laid out the way a 1.18 overworld chunk is saved: yPos is the lowest section with blocks,
+    // sections run from -4 to 19 and the light-only sections -5 and 20 sit just outside them
+    let mut sections = vec![compound! {
+        "Y": -5i8,
+        "SkyLight": vec![0u8; 2048]
+    }];
+    for y in -4i8 .. 20 {
+        let mut section = compound! {
+            "Y": y,
+            "biomes": {"palette": ["minecraft:plains"]}
+        };
+        let block_states = match y {
+            // The bottom layer is bedrock and the rest deepslate
+            -4 => {
+                let mut block_data = vec![0i64; 256];
+                block_data[.. 16].fill(0x1111_1111_1111_1111);
+                compound! {
+                    "palette": [
+                        {"Name": "minecraft:deepslate", "Properties": {"axis": "y"}},
+                        {"Name": "minecraft:bedrock"}
+                    ],
+                    "data": block_data
+                }
+            }
+            -3 ..= -1 => compound! {
+                "palette": [{"Name": "minecraft:deepslate", "Properties": {"axis": "y"}}]
+            },
+            // The stone is at (1, 70, 2) within the chunk, which is entry 1569 of section 4
+            4 => {
+                let mut block_data = vec![0i64; 256];
+                block_data[98] = 1 << 4;
+                section.insert("biomes", compound! {
+                    "palette": ["minecraft:plains", "minecraft:desert"],
+                    "data": vec![1i64 << 5]
+                });
+                compound! {
+                    "palette": [{"Name": "minecraft:air"}, {"Name": "minecraft:stone"}],
+                    "data": block_data
+                }
+            }
+            _ => compound! {
+                "palette": [{"Name": "minecraft:air"}]
+            },
+        };
+        section.insert("block_states", block_states);
+        sections.push(section);
+    }
+    sections.push(compound! {
+        "Y": 20i8,
+        "SkyLight": vec![0u8; 2048]
+    });
+
+    let mut nbt = compound! {
+        "DataVersion": 2975,
+        "xPos": 2,
+        "yPos": -4,
+        "zPos": -1,
+        "Status": "full",
+        "Heightmaps": {"MOTION_BLOCKING": vec![0i64; 37]},
+        "block_entities": [
+            {"id": "minecraft:furnace", "x": 33, "y": 70, "z": -14, "CookTime": 5}
+        ],
+        "block_ticks": [
+            {"i": "minecraft:stone", "x": 33, "y": 71, "z": -14, "t": 3, "p": 0}
+        ]
+    };
+    nbt.insert("sections", NbtList::from(sections));
+
+    let chunk = Chunk::from_anvil_nbt(&nbt).unwrap();
+
+    let pos = BlockPosition {
+        x: 33,
+        y: 70,
+        z: -14,
+    };
+    assert_eq!(
+        chunk.block_state_at(pos).map(|state| state.id()),
+        Some(BlockStateData::Stone.id())
+    );
+    assert_eq!(
+        chunk
+            .block_state_at(BlockPosition { x: 34, ..pos })
+            .map(|state| state.id()),
+        Some(AIR)
+    );
+
+    // Blocks below zero are found in the sections under the old bottom of the world
+    let bedrock = BlockState::builder(UlnStr::minecraft("bedrock"))
+        .unwrap()
+        .build()
+        .id();
+    let deepslate = BlockState::builder(UlnStr::minecraft("deepslate"))
+        .unwrap()
+        .build()
+        .id();
+    for (y, state) in [(-64, bedrock), (-63, deepslate), (-1, deepslate)] {
+        assert_eq!(
+            chunk
+                .block_state_at(BlockPosition { y, ..pos })
+                .map(|state| state.id()),
+            Some(state),
+            "block at y {y}"
+        );
+    }
+    assert_eq!(chunk.sections().min_section(), -5);
+    assert_eq!(chunk.sections().sections().len(), 26);
+
+    // Sections 0 to 3 are all plains
+    let plains = plains_network_id();
+    let desert = network_biome_id(UlnStr::minecraft("desert")).unwrap();
+    let ids = chunk.biome_ids();
+    assert_eq!(ids.len(), 1024);
+    assert!(ids[.. 256].iter().all(|&id| id == plains));
+    assert_eq!(&ids[256 .. 262], &[
+        plains, plains, plains, plains, plains, desert
+    ]);
+    assert_eq!(
+        chunk.get_biome(BlockPosition {
+            x: 37,
+            y: 65,
+            z: -10
+        }),
+        UnlocalizedName::minecraft("desert")
+    );
+    assert_eq!(
+        chunk.get_biome(BlockPosition {
+            x: 37,
+            y: 63,
+            z: -10
+        }),
+        UnlocalizedName::minecraft("plains")
+    );
+    assert!(chunk.get_heightmaps().contains_key("MOTION_BLOCKING"));
+    assert!(chunk.block_entity_at(pos).is_some());
+    assert_eq!(chunk.scheduled_ticks(TickType::Block)[0].delay, 3);
+}
+
+#[test]
+fn biome_cells() {
+    let mut section_store = SectionStore::new(1);
+    section_store.insert(Section::empty(4)).unwrap();
+    let mut chunk = Chunk::new(
+        CoordinatePair::new(-16, 32),
+        section_store,
+        NbtCompound::new(),
+    );
+
+    let cell = BlockPosition {
+        x: -8,
+        y: 70,
+        z: 36,
+    };
+    let desert = UnlocalizedName::minecraft("desert");
+    let plains = UnlocalizedName::minecraft("plains");
+    assert_eq!(chunk.set_biome(cell, desert.clone()), Some(plains.clone()));
+    assert!(chunk.is_dirty());
+    assert_eq!(
+        chunk.sections().get(4).unwrap().biomes().get_biome(2, 1, 1),
+        &desert
+    );
+
+    for x in -8 .. -4 {
+        for y in 68 .. 72 {
+            for z in 36 .. 40 {
+                assert_eq!(chunk.get_biome(BlockPosition { x, y, z }), desert);
+            }
+        }
+    }
+    assert_eq!(chunk.get_biome(BlockPosition { x: -9, ..cell }), plains);
+    assert_eq!(chunk.get_biome(BlockPosition { y: 72, ..cell }), plains);
+    assert_eq!(chunk.get_biome(BlockPosition { x: 0, ..cell }), plains);
+    assert_eq!(chunk.get_biome(BlockPosition { y: 256, ..cell }), plains);
+    // Heights past the range of section Y levels aren't wrapped around into a stored section
+    assert_eq!(chunk.get_biome(BlockPosition { y: 4166, ..cell }), plains);
+}
+
+#[test]
+fn save_anvil_chunk() {
+    use crate::block::BlockStateImpl;
+    use qdat::block::states::BlockStateData;
+
+    let _ = Registry::init();
+
+    let mut section_store = SectionStore::new(1);
+    section_store.insert(Section::empty(4)).unwrap();
+    let mut chunk = Chunk::new(
+        CoordinatePair::new(32, -16),
+        section_store,
+        NbtCompound::new(),
+    );
+    // Entries 1 and 17 of section 4
+    chunk.set_block_state_at(
+        BlockPosition {
+            x: 33,
+            y: 64,
+            z: -16,
+        },
+        BlockStateData::Stone.id(),
+    );
+    chunk.set_block_state_at(
+        BlockPosition {
+            x: 33,
+            y: 64,
+            z: -15,
+        },
+        BlockStateData::Dirt.id(),
+    );
+
+    chunk.set_biome(
+        BlockPosition {
+            x: 36,
+            y: 64,
+            z: -16,
+        },
+        UnlocalizedName::minecraft("desert"),
+    );
+
+    let block_tick = ScheduledTick {
+        id: UnlocalizedName::minecraft("stone"),
+        pos: BlockPosition {
+            x: 33,
+            y: 64,
+            z: -16,
+        },
+        delay: 3,
+        priority: -1,
+    };
+    let fluid_tick = ScheduledTick {
+        id: UnlocalizedName::minecraft("water"),
+        pos: BlockPosition {
+            x: 40,
+            y: 65,
+            z: -10,
+        },
+        delay: 5,
+        priority: 0,
+    };
+    chunk.schedule_tick(TickType::Block, block_tick.clone());
+    chunk.schedule_tick(TickType::Fluid, fluid_tick.clone());
+
+    let nbt = chunk.to_nbt();
+    assert_eq!(
+        nbt.get::<_, i32>("DataVersion").unwrap(),
+        DataVersion::V1_18_2.0
+    );
+    assert_eq!(nbt.get::<_, i32>("xPos").unwrap(), 2);
+    // The world starts at zero rather than at the lowest stored section
+    assert_eq!(nbt.get::<_, i32>("yPos").unwrap(), 0);
+    assert_eq!(nbt.get::<_, i32>("zPos").unwrap(), -1);
+    assert_eq!(nbt.get::<_, &str>("Status").unwrap(), "full");
+
+    let sections = nbt.get::<_, &NbtList>("sections").unwrap();
+    assert_eq!(sections.len(), 1);
+    let section = sections.get::<&NbtCompound>(0).unwrap();
+    let block_states = section.get::<_, &NbtCompound>("block_states").unwrap();
+    let names = block_states
+        .get::<_, &NbtList>("palette")
+        .unwrap()
+        .iter_map::<&NbtCompound>()
+        .map(|entry| entry.unwrap().get::<_, &str>("Name").unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, [
+        "minecraft:air",
+        "minecraft:stone",
+        "minecraft:dirt"
+    ]);
+
+    // Three states still take the minimum of four bits, sixteen to a long
+    let data = block_states.get::<_, &[i64]>("data").unwrap();
+    assert_eq!(data.len(), 256);
+    assert_eq!(data[0], 1 << 4);
+    assert_eq!(data[1], 2 << 4);
+    assert!(data[2 ..].iter().all(|&long| long == 0));
+
+    let biomes = section.get::<_, &NbtCompound>("biomes").unwrap();
+    assert_eq!(
+        biomes
+            .get::<_, &NbtList>("palette")
+            .unwrap()
+            .iter_map::<&str>()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap(),
+        ["minecraft:plains", "minecraft:desert"]
+    );
+    // Only the second cell is desert, one bit for each cell
+    assert_eq!(biomes.get::<_, &[i64]>("data").unwrap(), [0b10]);
+
+    let fluid_ticks = nbt.get::<_, &NbtList>("fluid_ticks").unwrap();
+    assert_eq!(fluid_ticks.len(), 1);
+    let raw_tick = fluid_ticks.get::<&NbtCompound>(0).unwrap();
+    assert_eq!(raw_tick.get::<_, &str>("i").unwrap(), "minecraft:water");
+    for (key, value) in [("x", 40), ("y", 65), ("z", -10), ("t", 5), ("p", 0)] {
+        assert_eq!(
+            raw_tick.get::<_, i32>(key).unwrap(),
+            value,
+            "tick field {key}"
+        );
+    }
+
+    // Reading the chunk back gives the same blocks
+    let loaded = Chunk::from_anvil_nbt(&nbt).unwrap();
+    assert_eq!(
+        loaded
+            .block_state_at(BlockPosition {
+                x: 33,
+                y: 64,
+                z: -15
+            })
+            .map(|state| state.id()),
+        Some(BlockStateData::Dirt.id())
+    );
+    assert_eq!(loaded.scheduled_ticks(TickType::Block), [block_tick]);
+    assert_eq!(loaded.scheduled_ticks(TickType::Fluid), [fluid_tick]);
+}
+
+#[test]
+fn palette_indices_do_not_span_longs() {
+    use crate::world::chunk::{bits_for_palette, pack_palette_indices};
+
+    // Twelve five bit entries fit in a long, the thirteenth starts the next one
+    let indices = (0 .. 14).collect::<Vec<_>>();
+    let data = pack_palette_indices(&indices, 5);
+    assert_eq!(data.len(), 2);
+    assert_eq!(
+        data[0],
+        (0 .. 12).fold(0, |long, i| long | (i as i64) << (i * 5))
+    );
+    assert_eq!(data[1], 12 | 13 << 5);
+
+    assert_eq!(bits_for_palette(2), 1);
+    assert_eq!(bits_for_palette(16), 4);
+    assert_eq!(bits_for_palette(17), 5);
+}
+
+#[test]
+fn heightmaps_from_columns() {
+    use crate::block::{BlockStateImpl, StateBuilder};
+    use qdat::block::states::BlockStateData;
+
+    let _ = Registry::init();
+
+    let mut section_store = SectionStore::new(1);
+    section_store.insert(Section::empty(4)).unwrap();
+    let mut chunk = Chunk::new(CoordinatePair::new(0, 0), section_store, NbtCompound::new());
+
+    // A stone pillar with a torch on top, which doesn't block motion
+    for y in 64 .. 67 {
+        chunk.set_block_state_at(BlockPosition { x: 2, y, z: 0 }, BlockStateData::Stone.id());
+    }
+    chunk.set_block_state_at(
+        BlockPosition { x: 2, y: 67, z: 0 },
+        BlockStateData::Torch.id(),
+    );
+    // Water counts as blocking motion
+    let water = BlockState::builder(UlnStr::minecraft("water"))
+        .unwrap()
+        .build()
+        .id();
+    chunk.set_block_state_at(BlockPosition { x: 3, y: 70, z: 0 }, water);
+    chunk.recalculate_heightmaps(0);
+
+    let heightmaps = chunk.get_heightmaps();
+    let motion_blocking = heightmaps.get::<_, &[i64]>("MOTION_BLOCKING").unwrap();
+    let world_surface = heightmaps.get::<_, &[i64]>("WORLD_SURFACE").unwrap();
+
+    // Seven nine bit heights fit in each long
+    assert_eq!(motion_blocking.len(), 37);
+    assert_eq!(motion_blocking[0], 67 << 18 | 71 << 27);
+    assert_eq!(world_surface[0], 68 << 18 | 71 << 27);
+    assert!(motion_blocking[1 ..].iter().all(|&long| long == 0));
+    assert!(world_surface[1 ..].iter().all(|&long| long == 0));
+
+    // With the world starting below zero the heights are counted from the bottom of the world
+    chunk.recalculate_heightmaps(-64);
+    let heightmaps = chunk.get_heightmaps();
+    let motion_blocking = heightmaps.get::<_, &[i64]>("MOTION_BLOCKING").unwrap();
+    assert_eq!(motion_blocking[0], 131 << 18 | 135 << 27);
+}
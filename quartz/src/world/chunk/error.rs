@@ -1,3 +1,4 @@
+use crate::world::chunk::SectionInsertionError;
 use qdat::{
     world::{lighting::LightingInitError, location::Coordinate},
     UnlocalizedName,
@@ -16,9 +17,18 @@ pub enum ChunkDecodeError {
     NbtRepr(NbtReprError),
     UnknownBlockState(UnlocalizedName),
     UnknownStateProperty(String),
+    UnknownBiome(String),
+    /// A cell of a section's biomes referred to an entry past the end of its palette.
+    InvalidBiomeIndex {
+        index: usize,
+        palette_len: usize,
+    },
     Lighting(LightingInitError),
+    InvalidSection(SectionInsertionError),
     ChunkRegionDesync(Coordinate),
     UnknownCompression(u8),
+    /// The chunk is stored outside of its region file, but the external file was not read.
+    UnresolvedExternalChunk,
 }
 
 impl Display for ChunkDecodeError {
@@ -30,13 +40,21 @@ impl Display for ChunkDecodeError {
             ChunkDecodeError::UnknownBlockState(state) =>
                 write!(f, "Unknown block state {state}"),
             ChunkDecodeError::UnknownStateProperty(msg) => Display::fmt(msg, f),
+            ChunkDecodeError::UnknownBiome(biome) => write!(f, "Unknown biome {biome}"),
+            ChunkDecodeError::InvalidBiomeIndex { index, palette_len } => write!(
+                f,
+                "Biome palette index {index} is out of range for a palette of {palette_len}"
+            ),
             ChunkDecodeError::Lighting(error) => Display::fmt(error, f),
+            ChunkDecodeError::InvalidSection(error) => Display::fmt(error, f),
             ChunkDecodeError::ChunkRegionDesync(coords) =>
                 write!(f, "Attempted to load chunk outside of region at {coords}"),
             ChunkDecodeError::UnknownCompression(id) => write!(
                 f,
-                "Encountered unknown compression scheme {id}, expected 1 or 2"
+                "Encountered unknown compression scheme {id}, expected 1, 2, 3 or 4"
             ),
+            ChunkDecodeError::UnresolvedExternalChunk =>
+                write!(f, "Chunk is stored outside of its region file"),
         }
     }
 }
@@ -48,6 +66,7 @@ impl Error for ChunkDecodeError {
             ChunkDecodeError::NbtIo(error) => Some(error),
             ChunkDecodeError::Lighting(error) => Some(error),
             ChunkDecodeError::NbtRepr(error) => Some(error),
+            ChunkDecodeError::InvalidSection(error) => Some(error),
             _ => None,
         }
     }
@@ -82,3 +101,9 @@ impl From<LightingInitError> for ChunkDecodeError {
         ChunkDecodeError::Lighting(x)
     }
 }
+
+impl From<SectionInsertionError> for ChunkDecodeError {
+    fn from(x: SectionInsertionError) -> Self {
+        ChunkDecodeError::InvalidSection(x)
+    }
+}
@@ -0,0 +1,250 @@
+use quartz_datapack::data::dimension::{AmountOrRangeArray, DimensionBiomeParameters};
+
+/// A point in climate space, sampled at some position in the world.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ClimatePoint {
+    pub temperature: f64,
+    pub humidity: f64,
+    pub continentalness: f64,
+    pub erosion: f64,
+    pub depth: f64,
+    pub weirdness: f64,
+}
+
+impl ClimatePoint {
+    fn as_array(&self) -> [f64; 6] {
+        [
+            self.temperature,
+            self.humidity,
+            self.continentalness,
+            self.erosion,
+            self.depth,
+            self.weirdness,
+        ]
+    }
+
+    fn from_array(
+        [temperature, humidity, continentalness, erosion, depth, weirdness]: [f64; 6],
+    ) -> Self {
+        ClimatePoint {
+            temperature,
+            humidity,
+            continentalness,
+            erosion,
+            depth,
+            weirdness,
+        }
+    }
+}
+
+/// Samples the climate at a given block position.
+pub trait ClimateSampler {
+    fn sample(&self, x: i32, y: i32, z: i32) -> ClimatePoint;
+}
+
+/// The range of values a single climate parameter of a biome covers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClimateParameter {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ClimateParameter {
+    pub const fn point(value: f64) -> Self {
+        ClimateParameter {
+            min: value,
+            max: value,
+        }
+    }
+
+    pub const fn span(min: f64, max: f64) -> Self {
+        ClimateParameter { min, max }
+    }
+
+    /// The distance from the given value to the nearest value in this range.
+    pub fn distance(&self, value: f64) -> f64 {
+        if value < self.min {
+            self.min - value
+        } else if value > self.max {
+            value - self.max
+        } else {
+            0.0
+        }
+    }
+}
+
+impl From<&AmountOrRangeArray> for ClimateParameter {
+    fn from(value: &AmountOrRangeArray) -> Self {
+        match *value {
+            AmountOrRangeArray::Singleton(value) => ClimateParameter::point(value as f64),
+            AmountOrRangeArray::Array([min, max]) => ClimateParameter::span(min as f64, max as f64),
+        }
+    }
+}
+
+/// The region of climate space a biome occupies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParameterPoint {
+    pub temperature: ClimateParameter,
+    pub humidity: ClimateParameter,
+    pub continentalness: ClimateParameter,
+    pub erosion: ClimateParameter,
+    pub depth: ClimateParameter,
+    pub weirdness: ClimateParameter,
+    pub offset: f64,
+}
+
+impl ParameterPoint {
+    /// Returns the squared distance from the given point to this region, lower is a better fit.
+    pub fn fitness(&self, point: &ClimatePoint) -> f64 {
+        let ranges = [
+            self.temperature,
+            self.humidity,
+            self.continentalness,
+            self.erosion,
+            self.depth,
+            self.weirdness,
+        ];
+
+        ranges
+            .iter()
+            .zip(point.as_array())
+            .map(|(range, value)| range.distance(value).powi(2))
+            .sum::<f64>()
+            + self.offset.powi(2)
+    }
+}
+
+impl From<&DimensionBiomeParameters> for ParameterPoint {
+    fn from(params: &DimensionBiomeParameters) -> Self {
+        ParameterPoint {
+            temperature: (&params.temperature).into(),
+            humidity: (&params.humidity).into(),
+            continentalness: (&params.continentalness).into(),
+            erosion: (&params.erosion).into(),
+            depth: (&params.depth).into(),
+            weirdness: (&params.weirdness).into(),
+            offset: ClimateParameter::from(&params.offset).min,
+        }
+    }
+}
+
+/// Averages climate samples around a position to smooth out the borders between biomes.
+#[derive(Debug, Clone, Copy)]
+pub struct Blending {
+    /// The distance in blocks from the center position to the outer samples.
+    pub radius: i32,
+}
+
+impl Blending {
+    /// Samples a 3x3 grid on the horizontal plane centered on the given position and returns the
+    /// average climate.
+    pub fn blend(&self, sampler: &impl ClimateSampler, x: i32, y: i32, z: i32) -> ClimatePoint {
+        let mut total = [0.0; 6];
+        let mut count = 0;
+
+        for dx in [-self.radius, 0, self.radius] {
+            for dz in [-self.radius, 0, self.radius] {
+                let sample = sampler.sample(x + dx, y, z + dz).as_array();
+                for (total, value) in total.iter_mut().zip(sample) {
+                    *total += value;
+                }
+                count += 1;
+            }
+        }
+
+        ClimatePoint::from_array(total.map(|total| total / count as f64))
+    }
+}
+
+/// Picks biomes by finding the entry whose climate parameters best fit the climate at a position.
+pub struct MultiNoiseBiomeSource<B> {
+    entries: Vec<(ParameterPoint, B)>,
+    blending: Option<Blending>,
+}
+
+impl<B> MultiNoiseBiomeSource<B> {
+    pub fn new(entries: Vec<(ParameterPoint, B)>) -> Self {
+        MultiNoiseBiomeSource {
+            entries,
+            blending: None,
+        }
+    }
+
+    /// Enables blending of climate samples within the given radius. This costs eight extra climate
+    /// samples per lookup.
+    pub fn with_blending(mut self, radius: i32) -> Self {
+        self.blending = Some(Blending { radius });
+        self
+    }
+
+    /// Returns the climate at the given position, blended if blending is enabled.
+    pub fn climate_at(
+        &self,
+        sampler: &impl ClimateSampler,
+        x: i32,
+        y: i32,
+        z: i32,
+    ) -> ClimatePoint {
+        match self.blending {
+            Some(blending) => blending.blend(sampler, x, y, z),
+            None => sampler.sample(x, y, z),
+        }
+    }
+
+    /// Returns the biome at the given position, or `None` if this source has no entries.
+    pub fn biome_at(&self, sampler: &impl ClimateSampler, x: i32, y: i32, z: i32) -> Option<&B> {
+        self.nearest(&self.climate_at(sampler, x, y, z))
+    }
+
+    /// Returns the biome whose parameters best fit the given climate.
+    pub fn nearest(&self, point: &ClimatePoint) -> Option<&B> {
+        self.entries
+            .iter()
+            .map(|(params, biome)| (params.fitness(point), biome))
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, biome)| biome)
+    }
+}
+
+#[cfg(test)]
+struct StepSampler;
+
+#[cfg(test)]
+impl ClimateSampler for StepSampler {
+    fn sample(&self, x: i32, _y: i32, _z: i32) -> ClimatePoint {
+        ClimatePoint {
+            temperature: if x < 0 { -1.0 } else { 1.0 },
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+fn temperature_entry(temperature: f64) -> ParameterPoint {
+    ParameterPoint {
+        temperature: ClimateParameter::point(temperature),
+        humidity: ClimateParameter::point(0.0),
+        continentalness: ClimateParameter::point(0.0),
+        erosion: ClimateParameter::point(0.0),
+        depth: ClimateParameter::point(0.0),
+        weirdness: ClimateParameter::point(0.0),
+        offset: 0.0,
+    }
+}
+
+#[test]
+fn blending_border_climate() {
+    let source = MultiNoiseBiomeSource::new(vec![
+        (temperature_entry(-1.0), "cold"),
+        (temperature_entry(1.0), "warm"),
+    ]);
+    assert_eq!(source.climate_at(&StepSampler, 0, 64, 0).temperature, 1.0);
+
+    let source = source.with_blending(4);
+    let temperature = source.climate_at(&StepSampler, 0, 64, 0).temperature;
+    assert!(temperature > -1.0 && temperature < 1.0);
+
+    assert_eq!(source.biome_at(&StepSampler, -16, 64, 0), Some(&"cold"));
+    assert_eq!(source.biome_at(&StepSampler, 16, 64, 0), Some(&"warm"));
+}
@@ -1,21 +1,1174 @@
-pub trait DensityFunction {
-    fn compute(context: impl FunctionContext) -> f64;
-    fn fill_array(array: &mut [f64], context_provider: impl ContextProvider);
-    fn map_all<I: DensityFunction, O: DensityFunction>(visitor: impl FnMut(I) -> O);
-    fn min_value() -> f64;
-    fn max_value() -> f64;
-    // fn clamp()
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    sync::Arc,
+};
+
+use qdat::UnlocalizedName;
+use quartz_datapack::data::{
+    density_function::{
+        DensityFunction as RawDensityFunction,
+        DensityFunctionProvider,
+        Spline as RawSpline,
+        SplinePoint as RawSplinePoint,
+        SplineValue as RawSplineValue,
+    },
+    noise_settings::NoiseRouter as RawNoiseRouter,
+};
+
+/// The block position a density function is computed at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FunctionContext {
+    pub block_x: i32,
+    pub block_y: i32,
+    pub block_z: i32,
+}
+
+impl FunctionContext {
+    pub const fn new(block_x: i32, block_y: i32, block_z: i32) -> Self {
+        FunctionContext {
+            block_x,
+            block_y,
+            block_z,
+        }
+    }
+}
+
+/// A source of noise which density functions sample from.
+pub trait NoiseSampler: Send + Sync {
+    fn sample(&self, x: f64, y: f64, z: f64) -> f64;
+}
+
+/// A node in a density function tree. Children are reference counted so that identical subtrees
+/// can be shared.
+#[derive(Clone)]
+pub enum DensityFunction {
+    Constant(f64),
+    Noise {
+        noise: Arc<dyn NoiseSampler>,
+        xz_scale: f64,
+        y_scale: f64,
+    },
+    Add(Arc<DensityFunction>, Arc<DensityFunction>),
+    Mul(Arc<DensityFunction>, Arc<DensityFunction>),
+    Min(Arc<DensityFunction>, Arc<DensityFunction>),
+    Max(Arc<DensityFunction>, Arc<DensityFunction>),
+    Abs(Arc<DensityFunction>),
+    Square(Arc<DensityFunction>),
+    Clamp {
+        input: Arc<DensityFunction>,
+        min: f64,
+        max: f64,
+    },
+    Spline(Arc<Spline>),
+    /// Maps the block y from the range `[from_y, to_y]` onto `[from_value, to_value]`, clamping it
+    /// to the closest end outside the range.
+    YClampedGradient {
+        from_y: i32,
+        to_y: i32,
+        from_value: f64,
+        to_value: f64,
+    },
+    /// Computes `when_in_range` if the input is within `[min, max)`, and `when_out_of_range`
+    /// otherwise. Only the chosen branch is computed.
+    RangeChoice {
+        input: Arc<DensityFunction>,
+        min: f64,
+        max: f64,
+        when_in_range: Arc<DensityFunction>,
+        when_out_of_range: Arc<DensityFunction>,
+    },
+    /// Samples noise at the scaled block position moved by the value of each shift function.
+    ShiftedNoise {
+        shift_x: Arc<DensityFunction>,
+        shift_y: Arc<DensityFunction>,
+        shift_z: Arc<DensityFunction>,
+        xz_scale: f64,
+        y_scale: f64,
+        noise: Arc<dyn NoiseSampler>,
+    },
+    /// Computes its input at most once per evaluation of the tree, storing the result in the given
+    /// cache slot.
+    Cache {
+        slot: usize,
+        input: Arc<DensityFunction>,
+    },
+}
+
+impl DensityFunction {
+    fn compute(&self, context: FunctionContext, cache: &mut [Option<f64>]) -> f64 {
+        match self {
+            &DensityFunction::Constant(value) => value,
+            &DensityFunction::Noise {
+                ref noise,
+                xz_scale,
+                y_scale,
+            } => noise.sample(
+                context.block_x as f64 * xz_scale,
+                context.block_y as f64 * y_scale,
+                context.block_z as f64 * xz_scale,
+            ),
+            DensityFunction::Add(a, b) => a.compute(context, cache) + b.compute(context, cache),
+            DensityFunction::Mul(a, b) => a.compute(context, cache) * b.compute(context, cache),
+            DensityFunction::Min(a, b) => a.compute(context, cache).min(b.compute(context, cache)),
+            DensityFunction::Max(a, b) => a.compute(context, cache).max(b.compute(context, cache)),
+            DensityFunction::Abs(input) => input.compute(context, cache).abs(),
+            DensityFunction::Square(input) => input.compute(context, cache).powi(2),
+            &DensityFunction::Clamp {
+                ref input,
+                min,
+                max,
+            } => input.compute(context, cache).clamp(min, max),
+            DensityFunction::Spline(spline) => spline.compute(context, cache) as f64,
+            &DensityFunction::YClampedGradient {
+                from_y,
+                to_y,
+                from_value,
+                to_value,
+            } => {
+                let t = (context.block_y - from_y) as f64 / (to_y - from_y) as f64;
+                from_value + t.clamp(0.0, 1.0) * (to_value - from_value)
+            }
+            &DensityFunction::RangeChoice {
+                ref input,
+                min,
+                max,
+                ref when_in_range,
+                ref when_out_of_range,
+            } =>
+                if (min .. max).contains(&input.compute(context, cache)) {
+                    when_in_range.compute(context, cache)
+                } else {
+                    when_out_of_range.compute(context, cache)
+                },
+            &DensityFunction::ShiftedNoise {
+                ref shift_x,
+                ref shift_y,
+                ref shift_z,
+                xz_scale,
+                y_scale,
+                ref noise,
+            } => noise.sample(
+                context.block_x as f64 * xz_scale + shift_x.compute(context, cache),
+                context.block_y as f64 * y_scale + shift_y.compute(context, cache),
+                context.block_z as f64 * xz_scale + shift_z.compute(context, cache),
+            ),
+            &DensityFunction::Cache { slot, ref input } => match cache[slot] {
+                Some(value) => value,
+                None => {
+                    let value = input.compute(context, cache);
+                    cache[slot] = Some(value);
+                    value
+                }
+            },
+        }
+    }
+
+    /// Rebuilds this function bottom-up, passing each node to the visitor after its children have
+    /// been visited.
+    pub fn map_all<V: DensityFunctionVisitor>(
+        self: &Arc<Self>,
+        visitor: &mut V,
+    ) -> Arc<DensityFunction> {
+        let mut map = |function: &Arc<DensityFunction>| function.map_all(visitor);
+        let mapped = match &**self {
+            DensityFunction::Constant(_)
+            | DensityFunction::Noise { .. }
+            | DensityFunction::YClampedGradient { .. } => self.clone(),
+            DensityFunction::Add(a, b) => Arc::new(DensityFunction::Add(map(a), map(b))),
+            DensityFunction::Mul(a, b) => Arc::new(DensityFunction::Mul(map(a), map(b))),
+            DensityFunction::Min(a, b) => Arc::new(DensityFunction::Min(map(a), map(b))),
+            DensityFunction::Max(a, b) => Arc::new(DensityFunction::Max(map(a), map(b))),
+            DensityFunction::Abs(input) => Arc::new(DensityFunction::Abs(map(input))),
+            DensityFunction::Square(input) => Arc::new(DensityFunction::Square(map(input))),
+            &DensityFunction::Clamp {
+                ref input,
+                min,
+                max,
+            } => Arc::new(DensityFunction::Clamp {
+                input: map(input),
+                min,
+                max,
+            }),
+            DensityFunction::Spline(spline) =>
+                Arc::new(DensityFunction::Spline(Arc::new(spline.map_all(visitor)))),
+            &DensityFunction::RangeChoice {
+                ref input,
+                min,
+                max,
+                ref when_in_range,
+                ref when_out_of_range,
+            } => Arc::new(DensityFunction::RangeChoice {
+                input: map(input),
+                min,
+                max,
+                when_in_range: map(when_in_range),
+                when_out_of_range: map(when_out_of_range),
+            }),
+            &DensityFunction::ShiftedNoise {
+                ref shift_x,
+                ref shift_y,
+                ref shift_z,
+                xz_scale,
+                y_scale,
+                ref noise,
+            } => Arc::new(DensityFunction::ShiftedNoise {
+                shift_x: map(shift_x),
+                shift_y: map(shift_y),
+                shift_z: map(shift_z),
+                xz_scale,
+                y_scale,
+                noise: noise.clone(),
+            }),
+            &DensityFunction::Cache { slot, ref input } => Arc::new(DensityFunction::Cache {
+                slot,
+                input: map(input),
+            }),
+        };
+
+        visitor.visit(mapped)
+    }
+}
+
+/// A cubic spline whose coordinate is given by a density function. The value of each point can
+/// itself be a spline, which is how vanilla shapes terrain from several climate parameters.
+#[derive(Clone)]
+pub struct Spline {
+    pub coordinate: Arc<DensityFunction>,
+    /// The points of the spline, sorted by location.
+    pub points: Vec<SplinePoint>,
+}
+
+#[derive(Clone)]
+pub struct SplinePoint {
+    pub location: f32,
+    pub value: SplineValue,
+    pub derivative: f32,
+}
+
+#[derive(Clone)]
+pub enum SplineValue {
+    Constant(f32),
+    Spline(Spline),
+}
+
+impl Spline {
+    fn compute(&self, context: FunctionContext, cache: &mut [Option<f64>]) -> f32 {
+        let coordinate = self.coordinate.compute(context, cache) as f32;
+        let last = match self.points.last() {
+            Some(last) => last,
+            None => return 0.0,
+        };
+
+        // Find the last point at or before the coordinate, extrapolating linearly beyond either end
+        let start = self
+            .points
+            .partition_point(|point| point.location <= coordinate);
+        if start == 0 {
+            let first = &self.points[0];
+            return first.value.compute(context, cache)
+                + first.derivative * (coordinate - first.location);
+        }
+        if start == self.points.len() {
+            return last.value.compute(context, cache)
+                + last.derivative * (coordinate - last.location);
+        }
+
+        let from = &self.points[start - 1];
+        let to = &self.points[start];
+        let width = to.location - from.location;
+        let t = (coordinate - from.location) / width;
+        let from_value = from.value.compute(context, cache);
+        let to_value = to.value.compute(context, cache);
+        let from_slope = from.derivative * width - (to_value - from_value);
+        let to_slope = -to.derivative * width + (to_value - from_value);
+
+        lerp(t, from_value, to_value) + t * (1.0 - t) * lerp(t, from_slope, to_slope)
+    }
+
+    fn map_all<V: DensityFunctionVisitor>(&self, visitor: &mut V) -> Spline {
+        Spline {
+            coordinate: self.coordinate.map_all(visitor),
+            points: self
+                .points
+                .iter()
+                .map(|point| SplinePoint {
+                    value: match &point.value {
+                        SplineValue::Spline(spline) => SplineValue::Spline(spline.map_all(visitor)),
+                        constant => constant.clone(),
+                    },
+                    ..*point
+                })
+                .collect(),
+        }
+    }
+
+    fn max_cache_slot(&self, tree: &DensityFunctionTree) -> usize {
+        self.points
+            .iter()
+            .filter_map(|point| match &point.value {
+                SplineValue::Spline(spline) => Some(spline.max_cache_slot(tree)),
+                SplineValue::Constant(_) => None,
+            })
+            .fold(tree.max_cache_slot(&self.coordinate), usize::max)
+    }
+}
+
+impl SplineValue {
+    fn compute(&self, context: FunctionContext, cache: &mut [Option<f64>]) -> f32 {
+        match self {
+            &SplineValue::Constant(value) => value,
+            SplineValue::Spline(spline) => spline.compute(context, cache),
+        }
+    }
+}
+
+#[inline]
+fn lerp(t: f32, from: f32, to: f32) -> f32 {
+    from + t * (to - from)
+}
+
+/// Transforms the nodes of a density function tree, see [`DensityFunction::map_all`].
+pub trait DensityFunctionVisitor {
+    fn visit(&mut self, function: Arc<DensityFunction>) -> Arc<DensityFunction>;
+}
+
+/// A density function along with the number of cache slots it uses.
+#[derive(Clone)]
+pub struct DensityFunctionTree {
+    root: Arc<DensityFunction>,
+    cache_slots: usize,
+}
+
+impl DensityFunctionTree {
+    pub fn new(root: DensityFunction) -> Self {
+        Self::from_root(Arc::new(root))
+    }
+
+    fn from_root(root: Arc<DensityFunction>) -> Self {
+        let mut tree = DensityFunctionTree {
+            root,
+            cache_slots: 0,
+        };
+        tree.cache_slots = tree.max_cache_slot(&tree.root);
+        tree
+    }
+
+    #[inline]
+    pub fn root(&self) -> &Arc<DensityFunction> {
+        &self.root
+    }
+
+    pub fn compute(&self, context: FunctionContext) -> f64 {
+        let mut cache = vec![None; self.cache_slots];
+        self.root.compute(context, &mut cache)
+    }
+
+    pub fn apply_visitor<V: DensityFunctionVisitor>(self, visitor: &mut V) -> Self {
+        let root = self.root.map_all(visitor);
+        let cache_slots = self.max_cache_slot(&root);
+        DensityFunctionTree { root, cache_slots }
+    }
+
+    /// Deduplicates identical subtrees and caches each noise node, so that noise referenced from
+    /// several places in the tree is only sampled once per evaluation.
+    pub fn optimize(self) -> Self {
+        self.apply_visitor(&mut Optimizer::default())
+    }
+
+    fn max_cache_slot(&self, function: &DensityFunction) -> usize {
+        let children_max = |children: &[&Arc<DensityFunction>]| {
+            children
+                .iter()
+                .map(|child| self.max_cache_slot(child))
+                .max()
+                .unwrap_or(0)
+        };
+
+        match function {
+            DensityFunction::Constant(_)
+            | DensityFunction::Noise { .. }
+            | DensityFunction::YClampedGradient { .. } => 0,
+            DensityFunction::Add(a, b)
+            | DensityFunction::Mul(a, b)
+            | DensityFunction::Min(a, b)
+            | DensityFunction::Max(a, b) => children_max(&[a, b]),
+            DensityFunction::Abs(input)
+            | DensityFunction::Square(input)
+            | DensityFunction::Clamp { input, .. } => children_max(&[input]),
+            DensityFunction::Spline(spline) => spline.max_cache_slot(self),
+            DensityFunction::RangeChoice {
+                input,
+                when_in_range,
+                when_out_of_range,
+                ..
+            } => children_max(&[input, when_in_range, when_out_of_range]),
+            DensityFunction::ShiftedNoise {
+                shift_x,
+                shift_y,
+                shift_z,
+                ..
+            } => children_max(&[shift_x, shift_y, shift_z]),
+            DensityFunction::Cache { slot, input } => (slot + 1).max(children_max(&[input])),
+        }
+    }
 }
 
-pub trait FunctionContext {
-    fn block_x() -> i32;
-    fn block_y() -> i32;
-    fn block_z() -> i32;
+/// An error encountered while converting a density function from a datapack into a tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DensityFunctionError {
+    /// A reference to a density function which is not registered.
+    UnknownFunction(UnlocalizedName),
+    /// A noise which the converter was not given a sampler for.
+    UnknownNoise(UnlocalizedName),
+    /// A type of density function which cannot be computed yet.
+    Unsupported(&'static str),
+    /// A density function which refers back to itself.
+    Cycle(UnlocalizedName),
+}
+
+impl Display for DensityFunctionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DensityFunctionError::UnknownFunction(name) =>
+                write!(f, "Unknown density function {name}"),
+            DensityFunctionError::UnknownNoise(name) => write!(f, "Unknown noise {name}"),
+            DensityFunctionError::Unsupported(kind) =>
+                write!(f, "Density functions of type {kind} are not supported"),
+            DensityFunctionError::Cycle(name) =>
+                write!(f, "Density function {name} refers to itself"),
+        }
+    }
+}
+
+impl Error for DensityFunctionError {}
 
-    // fn get_blender() -> Blender {};
+/// Converts density functions from a datapack into trees, looking up named functions and noises
+/// with the given functions. Each named function and noise is only converted once, so every
+/// reference to it shares the same node.
+pub struct DensityFunctionConverter<F, N> {
+    functions: F,
+    noises: N,
+    converted: HashMap<UnlocalizedName, Arc<DensityFunction>>,
+    samplers: HashMap<UnlocalizedName, Arc<dyn NoiseSampler>>,
+    /// The named functions currently being converted, used to detect cycles.
+    converting: Vec<UnlocalizedName>,
 }
 
-pub trait ContextProvider {
-    fn for_index<C: FunctionContext>(arr_index: i32) -> C;
-    fn fill_all_directly(array: &mut [f64], density: impl DensityFunction);
+impl<'a, F, N> DensityFunctionConverter<F, N>
+where
+    F: Fn(&UnlocalizedName) -> Option<&'a DensityFunctionProvider>,
+    N: FnMut(&UnlocalizedName) -> Option<Arc<dyn NoiseSampler>>,
+{
+    pub fn new(functions: F, noises: N) -> Self {
+        DensityFunctionConverter {
+            functions,
+            noises,
+            converted: HashMap::new(),
+            samplers: HashMap::new(),
+            converting: Vec::new(),
+        }
+    }
+
+    /// Converts the given function, along with every named function it refers to.
+    pub fn convert(
+        &mut self,
+        provider: &DensityFunctionProvider,
+    ) -> Result<DensityFunctionTree, DensityFunctionError> {
+        self.provider(provider).map(DensityFunctionTree::from_root)
+    }
+
+    fn provider(
+        &mut self,
+        provider: &DensityFunctionProvider,
+    ) -> Result<Arc<DensityFunction>, DensityFunctionError> {
+        match provider {
+            &DensityFunctionProvider::Constant(value) => Ok(constant(value)),
+            DensityFunctionProvider::Inline(function) => self.function(function),
+            DensityFunctionProvider::Reference(name) => self.reference(name),
+        }
+    }
+
+    fn reference(
+        &mut self,
+        name: &UnlocalizedName,
+    ) -> Result<Arc<DensityFunction>, DensityFunctionError> {
+        if let Some(function) = self.converted.get(name) {
+            return Ok(function.clone());
+        }
+        if self.converting.contains(name) {
+            return Err(DensityFunctionError::Cycle(name.clone()));
+        }
+
+        let provider = (self.functions)(name)
+            .ok_or_else(|| DensityFunctionError::UnknownFunction(name.clone()))?;
+        self.converting.push(name.clone());
+        let function = self.provider(provider);
+        self.converting.pop();
+
+        let function = function?;
+        self.converted.insert(name.clone(), function.clone());
+        Ok(function)
+    }
+
+    fn noise(
+        &mut self,
+        name: &UnlocalizedName,
+    ) -> Result<Arc<dyn NoiseSampler>, DensityFunctionError> {
+        if let Some(sampler) = self.samplers.get(name) {
+            return Ok(sampler.clone());
+        }
+
+        let sampler =
+            (self.noises)(name).ok_or_else(|| DensityFunctionError::UnknownNoise(name.clone()))?;
+        self.samplers.insert(name.clone(), sampler.clone());
+        Ok(sampler)
+    }
+
+    fn function(
+        &mut self,
+        function: &RawDensityFunction,
+    ) -> Result<Arc<DensityFunction>, DensityFunctionError> {
+        let function = match function {
+            // Caching and interpolation are left to the optimizer, and blending with chunks from
+            // older versions is not supported, so these are the functions without blending
+            RawDensityFunction::Cache2d { argument }
+            | RawDensityFunction::CacheAllInCell { argument }
+            | RawDensityFunction::CacheOnce { argument }
+            | RawDensityFunction::FlatCache { argument }
+            | RawDensityFunction::Interpolated { argument }
+            | RawDensityFunction::BendDensity { argument } => return self.provider(argument),
+            RawDensityFunction::Beardifier | RawDensityFunction::BlendOffset =>
+                DensityFunction::Constant(0.0),
+            RawDensityFunction::BlendAlpha => DensityFunction::Constant(1.0),
+            &RawDensityFunction::Constant { argument } => DensityFunction::Constant(argument),
+            RawDensityFunction::Abs { argument } => DensityFunction::Abs(self.provider(argument)?),
+            RawDensityFunction::Square { argument } =>
+                DensityFunction::Square(self.provider(argument)?),
+            RawDensityFunction::Cube { argument } => {
+                let input = self.provider(argument)?;
+                DensityFunction::Mul(input.clone(), Arc::new(DensityFunction::Square(input)))
+            }
+            RawDensityFunction::HalfNegative { argument } =>
+                scale_negative(self.provider(argument)?, 0.5),
+            RawDensityFunction::QuarterNegative { argument } =>
+                scale_negative(self.provider(argument)?, 0.25),
+            RawDensityFunction::Squeeze { argument } => {
+                // x / 2 - x^3 / 24 with the input clamped to [-1, 1]
+                let input = Arc::new(DensityFunction::Clamp {
+                    input: self.provider(argument)?,
+                    min: -1.0,
+                    max: 1.0,
+                });
+                let cube = Arc::new(DensityFunction::Mul(
+                    input.clone(),
+                    Arc::new(DensityFunction::Square(input.clone())),
+                ));
+                DensityFunction::Add(
+                    Arc::new(DensityFunction::Mul(input, constant(0.5))),
+                    Arc::new(DensityFunction::Mul(cube, constant(-1.0 / 24.0))),
+                )
+            }
+            RawDensityFunction::Add {
+                argument1,
+                argument2,
+            } => DensityFunction::Add(self.provider(argument1)?, self.provider(argument2)?),
+            RawDensityFunction::Mul {
+                argument1,
+                argument2,
+            } => DensityFunction::Mul(self.provider(argument1)?, self.provider(argument2)?),
+            RawDensityFunction::Min {
+                argument1,
+                argument2,
+            } => DensityFunction::Min(self.provider(argument1)?, self.provider(argument2)?),
+            RawDensityFunction::Max {
+                argument1,
+                argument2,
+            } => DensityFunction::Max(self.provider(argument1)?, self.provider(argument2)?),
+            &RawDensityFunction::Clamp {
+                ref input,
+                min,
+                max,
+            } => DensityFunction::Clamp {
+                input: self.provider(input)?,
+                min,
+                max,
+            },
+            &RawDensityFunction::Noise {
+                ref noise,
+                xz_scale,
+                y_scale,
+            } => DensityFunction::Noise {
+                noise: self.noise(noise)?,
+                xz_scale,
+                y_scale,
+            },
+            RawDensityFunction::Shift { argument } => shift(self.noise(argument)?, 0.25),
+            RawDensityFunction::ShiftA { argument } => shift(self.noise(argument)?, 0.0),
+            RawDensityFunction::ShiftB { argument } =>
+                shift(Arc::new(RotatedAxes(self.noise(argument)?)), 0.0),
+            &RawDensityFunction::ShiftedNoise {
+                ref noise,
+                xz_scale,
+                y_scale,
+                ref shift_x,
+                ref shift_y,
+                ref shift_z,
+            } => DensityFunction::ShiftedNoise {
+                shift_x: self.provider(shift_x)?,
+                shift_y: self.provider(shift_y)?,
+                shift_z: self.provider(shift_z)?,
+                xz_scale,
+                y_scale,
+                noise: self.noise(noise)?,
+            },
+            &RawDensityFunction::RangeChoice {
+                ref input,
+                min_inclusive,
+                max_exclusive,
+                ref when_in_range,
+                ref when_out_of_range,
+            } => DensityFunction::RangeChoice {
+                input: self.provider(input)?,
+                min: min_inclusive,
+                max: max_exclusive,
+                when_in_range: self.provider(when_in_range)?,
+                when_out_of_range: self.provider(when_out_of_range)?,
+            },
+            &RawDensityFunction::Spline {
+                ref spline,
+                min_value,
+                max_value,
+            } => DensityFunction::Clamp {
+                input: match spline {
+                    &RawSplineValue::Constant(value) => constant(value),
+                    RawSplineValue::Spline(spline) =>
+                        Arc::new(DensityFunction::Spline(Arc::new(self.spline(spline)?))),
+                },
+                min: min_value,
+                max: max_value,
+            },
+            &RawDensityFunction::YClampedGradient {
+                from_y,
+                to_y,
+                from_value,
+                to_value,
+            } => DensityFunction::YClampedGradient {
+                from_y: from_y as i32,
+                to_y: to_y as i32,
+                from_value,
+                to_value,
+            },
+            RawDensityFunction::EndIslands =>
+                return Err(DensityFunctionError::Unsupported("minecraft:end_islands")),
+            RawDensityFunction::OldBlendedNoise =>
+                return Err(DensityFunctionError::Unsupported(
+                    "minecraft:old_blended_noise",
+                )),
+            RawDensityFunction::Slide { .. } =>
+                return Err(DensityFunctionError::Unsupported("minecraft:slide")),
+            RawDensityFunction::TerrainShaperSpline { .. } =>
+                return Err(DensityFunctionError::Unsupported(
+                    "minecraft:terrain_shaper_spline",
+                )),
+            RawDensityFunction::WeirdScaledSampler { .. } =>
+                return Err(DensityFunctionError::Unsupported(
+                    "minecraft:weird_scaled_sampler",
+                )),
+        };
+
+        Ok(Arc::new(function))
+    }
+
+    fn spline(&mut self, spline: &RawSpline) -> Result<Spline, DensityFunctionError> {
+        Ok(Spline {
+            coordinate: self.provider(&spline.coordinate)?,
+            points: spline
+                .points
+                .iter()
+                .map(|point| self.spline_point(point))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn spline_point(
+        &mut self,
+        point: &RawSplinePoint,
+    ) -> Result<SplinePoint, DensityFunctionError> {
+        Ok(SplinePoint {
+            location: point.location as f32,
+            value: match &point.value {
+                &RawSplineValue::Constant(value) => SplineValue::Constant(value as f32),
+                RawSplineValue::Spline(spline) => SplineValue::Spline(self.spline(spline)?),
+            },
+            derivative: point.derivative as f32,
+        })
+    }
+}
+
+#[inline]
+fn constant(value: f64) -> Arc<DensityFunction> {
+    Arc::new(DensityFunction::Constant(value))
+}
+
+/// Multiplies the input by the given factor where it is negative.
+fn scale_negative(input: Arc<DensityFunction>, factor: f64) -> DensityFunction {
+    DensityFunction::Max(
+        input.clone(),
+        Arc::new(DensityFunction::Mul(input, constant(factor))),
+    )
+}
+
+/// Samples the noise at a quarter of the block position, scaled up by four, which is how vanilla
+/// offsets the position other noises are sampled at.
+fn shift(noise: Arc<dyn NoiseSampler>, y_scale: f64) -> DensityFunction {
+    DensityFunction::Mul(
+        constant(4.0),
+        Arc::new(DensityFunction::Noise {
+            noise,
+            xz_scale: 0.25,
+            y_scale,
+        }),
+    )
+}
+
+/// Samples the inner noise at `(z, x, y)`, used by `minecraft:shift_b`.
+struct RotatedAxes(Arc<dyn NoiseSampler>);
+
+impl NoiseSampler for RotatedAxes {
+    fn sample(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.0.sample(z, x, y)
+    }
+}
+
+/// The density functions sampled by a noise based generator, converted from the noise router of a
+/// datapack's noise settings.
+#[derive(Clone)]
+pub struct NoiseRouter {
+    pub barrier: DensityFunctionTree,
+    pub fluid_level_floodedness: DensityFunctionTree,
+    pub fluid_level_spread: DensityFunctionTree,
+    pub lava: DensityFunctionTree,
+    pub temperature: DensityFunctionTree,
+    pub vegetation: DensityFunctionTree,
+    pub continents: DensityFunctionTree,
+    pub erosion: DensityFunctionTree,
+    pub depth: DensityFunctionTree,
+    pub ridges: DensityFunctionTree,
+    pub initial_density_without_jaggedness: DensityFunctionTree,
+    pub final_density: DensityFunctionTree,
+    pub vein_toggle: DensityFunctionTree,
+    pub vein_ridged: DensityFunctionTree,
+    pub vein_gap: DensityFunctionTree,
+}
+
+impl NoiseRouter {
+    /// Converts every function of the given router, failing if any of them cannot be converted.
+    pub fn try_from_settings<E>(
+        router: &RawNoiseRouter,
+        mut convert: impl FnMut(&DensityFunctionProvider) -> Result<DensityFunctionTree, E>,
+    ) -> Result<Self, E> {
+        Ok(NoiseRouter {
+            barrier: convert(&router.barrier)?,
+            fluid_level_floodedness: convert(&router.fluid_level_floodedness)?,
+            fluid_level_spread: convert(&router.fluid_level_spread)?,
+            lava: convert(&router.lava)?,
+            temperature: convert(&router.temperature)?,
+            vegetation: convert(&router.vegetation)?,
+            continents: convert(&router.continents)?,
+            erosion: convert(&router.erosion)?,
+            depth: convert(&router.depth)?,
+            ridges: convert(&router.ridges)?,
+            initial_density_without_jaggedness: convert(
+                &router.initial_density_without_jaggedness,
+            )?,
+            final_density: convert(&router.final_density)?,
+            vein_toggle: convert(&router.vein_toggle)?,
+            vein_ridged: convert(&router.vein_ridged)?,
+            vein_gap: convert(&router.vein_gap)?,
+        })
+    }
+
+    /// Applies the same visitor to every function, in the order they are declared.
+    pub fn apply_visitor<V: DensityFunctionVisitor>(self, visitor: &mut V) -> Self {
+        self.map(|tree| tree.apply_visitor(visitor))
+    }
+
+    /// Optimizes every function with a single optimizer, so that subtrees which appear in several
+    /// functions are also deduplicated between them.
+    pub fn optimize(self) -> Self {
+        self.apply_visitor(&mut Optimizer::default())
+    }
+
+    fn map(self, mut f: impl FnMut(DensityFunctionTree) -> DensityFunctionTree) -> Self {
+        NoiseRouter {
+            barrier: f(self.barrier),
+            fluid_level_floodedness: f(self.fluid_level_floodedness),
+            fluid_level_spread: f(self.fluid_level_spread),
+            lava: f(self.lava),
+            temperature: f(self.temperature),
+            vegetation: f(self.vegetation),
+            continents: f(self.continents),
+            erosion: f(self.erosion),
+            depth: f(self.depth),
+            ridges: f(self.ridges),
+            initial_density_without_jaggedness: f(self.initial_density_without_jaggedness),
+            final_density: f(self.final_density),
+            vein_toggle: f(self.vein_toggle),
+            vein_ridged: f(self.vein_ridged),
+            vein_gap: f(self.vein_gap),
+        }
+    }
+}
+
+/// Identifies a node by its kind, its parameters and the addresses of its children. Since children
+/// are visited first, identical subtrees already share an address when their parent is keyed.
+#[derive(PartialEq, Eq, Hash)]
+struct NodeKey {
+    kind: u8,
+    children: [usize; 4],
+    params: [u64; 3],
+}
+
+#[derive(Default)]
+struct Optimizer {
+    nodes: HashMap<NodeKey, Arc<DensityFunction>>,
+    cache_slots: usize,
+}
+
+impl Optimizer {
+    fn key(function: &DensityFunction) -> NodeKey {
+        let address = |function: &Arc<DensityFunction>| Arc::as_ptr(function) as usize;
+        let (kind, children, params) = match function {
+            DensityFunction::Constant(value) => (0, [0; 4], [value.to_bits(), 0, 0]),
+            DensityFunction::Noise {
+                noise,
+                xz_scale,
+                y_scale,
+            } => (1, [Arc::as_ptr(noise) as *const () as usize, 0, 0, 0], [
+                xz_scale.to_bits(),
+                y_scale.to_bits(),
+                0,
+            ]),
+            DensityFunction::Add(a, b) => (2, [address(a), address(b), 0, 0], [0; 3]),
+            DensityFunction::Mul(a, b) => (3, [address(a), address(b), 0, 0], [0; 3]),
+            DensityFunction::Min(a, b) => (4, [address(a), address(b), 0, 0], [0; 3]),
+            DensityFunction::Max(a, b) => (5, [address(a), address(b), 0, 0], [0; 3]),
+            DensityFunction::Abs(input) => (6, [address(input), 0, 0, 0], [0; 3]),
+            DensityFunction::Square(input) => (7, [address(input), 0, 0, 0], [0; 3]),
+            DensityFunction::Clamp { input, min, max } => (8, [address(input), 0, 0, 0], [
+                min.to_bits(),
+                max.to_bits(),
+                0,
+            ]),
+            DensityFunction::Spline(spline) =>
+                (10, [Arc::as_ptr(spline) as usize, 0, 0, 0], [0; 3]),
+            DensityFunction::YClampedGradient {
+                from_y,
+                to_y,
+                from_value,
+                to_value,
+            } => (11, [0; 4], [
+                (*from_y as u32 as u64) << 32 | *to_y as u32 as u64,
+                from_value.to_bits(),
+                to_value.to_bits(),
+            ]),
+            DensityFunction::RangeChoice {
+                input,
+                min,
+                max,
+                when_in_range,
+                when_out_of_range,
+            } => (
+                12,
+                [
+                    address(input),
+                    address(when_in_range),
+                    address(when_out_of_range),
+                    0,
+                ],
+                [min.to_bits(), max.to_bits(), 0],
+            ),
+            DensityFunction::ShiftedNoise {
+                shift_x,
+                shift_y,
+                shift_z,
+                xz_scale,
+                y_scale,
+                noise,
+            } => (
+                13,
+                [
+                    address(shift_x),
+                    address(shift_y),
+                    address(shift_z),
+                    Arc::as_ptr(noise) as *const () as usize,
+                ],
+                [xz_scale.to_bits(), y_scale.to_bits(), 0],
+            ),
+            // Cache slots are reassigned, so only the input matters
+            DensityFunction::Cache { input, .. } => (9, [address(input), 0, 0, 0], [0; 3]),
+        };
+
+        NodeKey {
+            kind,
+            children,
+            params,
+        }
+    }
+
+    fn next_cache(&mut self, input: Arc<DensityFunction>) -> Arc<DensityFunction> {
+        let slot = self.cache_slots;
+        self.cache_slots += 1;
+        Arc::new(DensityFunction::Cache { slot, input })
+    }
+}
+
+impl DensityFunctionVisitor for Optimizer {
+    fn visit(&mut self, function: Arc<DensityFunction>) -> Arc<DensityFunction> {
+        let key = Self::key(&function);
+        if let Some(existing) = self.nodes.get(&key) {
+            return existing.clone();
+        }
+
+        let optimized = match &*function {
+            // Noise nodes are cached directly, and a cache around one is redundant
+            DensityFunction::Noise { .. } | DensityFunction::ShiftedNoise { .. } =>
+                self.next_cache(function),
+            DensityFunction::Cache { input, .. } =>
+                if matches!(&**input, DensityFunction::Cache { .. }) {
+                    input.clone()
+                } else {
+                    self.next_cache(input.clone())
+                },
+            _ => function,
+        };
+
+        self.nodes.insert(key, optimized.clone());
+        optimized
+    }
+}
+
+#[cfg(test)]
+struct CountingNoise(std::sync::atomic::AtomicUsize);
+
+#[cfg(test)]
+impl NoiseSampler for CountingNoise {
+    fn sample(&self, x: f64, _y: f64, z: f64) -> f64 {
+        self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        x + z
+    }
+}
+
+#[test]
+fn optimized_noise_sampled_once() {
+    use std::sync::atomic::Ordering;
+
+    let noise = Arc::new(CountingNoise(Default::default()));
+    let sample = || {
+        Arc::new(DensityFunction::Noise {
+            noise: noise.clone(),
+            xz_scale: 1.0,
+            y_scale: 0.0,
+        })
+    };
+    // abs(noise) + noise * noise, where every reference to the noise is a separate node
+    let tree = DensityFunctionTree::new(DensityFunction::Add(
+        Arc::new(DensityFunction::Abs(sample())),
+        Arc::new(DensityFunction::Mul(sample(), sample())),
+    ));
+    let context = FunctionContext::new(-3, 64, 1);
+
+    assert_eq!(tree.compute(context), 6.0);
+    assert_eq!(noise.0.swap(0, Ordering::Relaxed), 3);
+
+    let tree = tree.optimize();
+    assert_eq!(tree.compute(context), 6.0);
+    assert_eq!(noise.0.swap(0, Ordering::Relaxed), 1);
+}
+
+#[test]
+fn spline_with_constant_input() {
+    let point = |location, value, derivative| SplinePoint {
+        location,
+        value,
+        derivative,
+    };
+    let spline = |input: f64, points| {
+        DensityFunctionTree::new(DensityFunction::Spline(Arc::new(Spline {
+            coordinate: Arc::new(DensityFunction::Constant(input)),
+            points,
+        })))
+    };
+    let context = FunctionContext::new(0, 0, 0);
+
+    let points = || {
+        vec![
+            point(0.0, SplineValue::Constant(0.0), 0.0),
+            point(1.0, SplineValue::Constant(1.0), 0.0),
+        ]
+    };
+    assert_eq!(spline(0.5, points()).compute(context), 0.5);
+    assert_eq!(spline(0.25, points()).compute(context), 0.15625);
+
+    // Outside the points the spline continues along the derivative of the closest point
+    let tree = spline(3.0, vec![
+        point(0.0, SplineValue::Constant(0.0), 0.0),
+        point(1.0, SplineValue::Constant(1.0), 0.5),
+    ]);
+    assert_eq!(tree.compute(context), 2.0);
+
+    let nested = Spline {
+        coordinate: Arc::new(DensityFunction::Constant(-1.0)),
+        points: vec![point(0.0, SplineValue::Constant(4.0), 1.0)],
+    };
+    let tree = spline(1.0, vec![
+        point(0.0, SplineValue::Constant(0.0), 0.0),
+        point(1.0, SplineValue::Spline(nested), 0.0),
+    ]);
+    assert_eq!(tree.compute(context), 3.0);
+}
+
+#[test]
+fn y_clamped_gradient() {
+    let gradient = Arc::new(DensityFunction::YClampedGradient {
+        from_y: -64,
+        to_y: 320,
+        from_value: 1.5,
+        to_value: -1.5,
+    });
+    let tree = DensityFunctionTree::new(DensityFunction::Abs(gradient.clone()));
+    let at_y =
+        |y| DensityFunctionTree::new((*gradient).clone()).compute(FunctionContext::new(7, y, -2));
+
+    assert_eq!(at_y(-64), 1.5);
+    assert_eq!(at_y(32), 0.75);
+    assert_eq!(at_y(128), 0.0);
+    assert_eq!(at_y(320), -1.5);
+    assert_eq!(at_y(-200), 1.5);
+    assert_eq!(at_y(1000), -1.5);
+    assert_eq!(tree.compute(FunctionContext::new(0, 224, 0)), 0.75);
+
+    // The gradient picks which side of the range choice is computed
+    let choice = DensityFunctionTree::new(DensityFunction::RangeChoice {
+        input: gradient,
+        min: 0.0,
+        max: 1.0,
+        when_in_range: Arc::new(DensityFunction::Constant(1.0)),
+        when_out_of_range: Arc::new(DensityFunction::Constant(-1.0)),
+    });
+    assert_eq!(choice.compute(FunctionContext::new(0, 128, 0)), 1.0);
+    assert_eq!(choice.compute(FunctionContext::new(0, 0, 0)), -1.0);
+    assert_eq!(choice.compute(FunctionContext::new(0, 200, 0)), -1.0);
+}
+
+#[test]
+fn router_shares_subtrees() {
+    let router: RawNoiseRouter = serde_json::from_str(
+        r#"{
+            "barrier": 0.0,
+            "fluid_level_floodedness": 0.0,
+            "fluid_level_spread": 0.0,
+            "lava": 0.0,
+            "temperature": 0.0,
+            "vegetation": 0.0,
+            "continents": "minecraft:overworld/continents",
+            "erosion": 0.0,
+            "depth": "minecraft:overworld/continents",
+            "ridges": 0.0,
+            "initial_density_without_jaggedness": 0.0,
+            "final_density": 0.0,
+            "vein_toggle": 0.0,
+            "vein_ridged": 0.0,
+            "vein_gap": 0.0
+        }"#,
+    )
+    .unwrap();
+
+    let noise: Arc<dyn NoiseSampler> = Arc::new(CountingNoise(Default::default()));
+    let router = NoiseRouter::try_from_settings(&router, |provider| match provider {
+        &DensityFunctionProvider::Constant(value) =>
+            Ok(DensityFunctionTree::new(DensityFunction::Constant(value))),
+        DensityFunctionProvider::Reference(_) =>
+            Ok(DensityFunctionTree::new(DensityFunction::Noise {
+                noise: noise.clone(),
+                xz_scale: 1.0,
+                y_scale: 0.0,
+            })),
+        DensityFunctionProvider::Inline(_) => Err(()),
+    })
+    .unwrap();
+    assert!(!Arc::ptr_eq(router.continents.root(), router.depth.root()));
+
+    let router = router.optimize();
+    assert!(Arc::ptr_eq(router.continents.root(), router.depth.root()));
+    assert_eq!(router.depth.compute(FunctionContext::new(2, 0, 3)), 5.0);
+}
+
+#[test]
+fn convert_datapack_functions() {
+    let provider = |json: &str| serde_json::from_str::<DensityFunctionProvider>(json).unwrap();
+    let functions = HashMap::from([
+        (
+            UnlocalizedName::minecraft("gradient"),
+            provider(
+                r#"{
+                    "type": "minecraft:y_clamped_gradient",
+                    "from_y": 0,
+                    "to_y": 100,
+                    "from_value": 0.0,
+                    "to_value": 2.0
+                }"#,
+            ),
+        ),
+        (
+            UnlocalizedName::minecraft("loop"),
+            provider(r#"{"type": "minecraft:abs", "argument": "minecraft:loop"}"#),
+        ),
+    ]);
+    let noise: Arc<dyn NoiseSampler> = Arc::new(CountingNoise(Default::default()));
+    let mut converter = DensityFunctionConverter::new(
+        |name| functions.get(name),
+        |name| (name == &UnlocalizedName::minecraft("offset")).then(|| noise.clone()),
+    );
+    let mut convert = |json: &str| converter.convert(&provider(json));
+    let context = FunctionContext::new(8, 50, 4);
+
+    let squeeze = convert(r#"{"type": "minecraft:squeeze", "argument": "minecraft:gradient"}"#);
+    assert_eq!(squeeze.unwrap().compute(context), 0.5 - 1.0 / 24.0);
+    let half_negative = convert(r#"{"type": "minecraft:half_negative", "argument": -3.0}"#);
+    assert_eq!(half_negative.unwrap().compute(context), -1.5);
+    // The noise is sampled at (z, x, 0) / 4
+    let shift_b = convert(r#"{"type": "minecraft:shift_b", "argument": "minecraft:offset"}"#);
+    assert_eq!(shift_b.unwrap().compute(context), 4.0);
+
+    // Every reference to a named function shares its node
+    let first = convert(r#""minecraft:gradient""#).unwrap();
+    let second = convert(r#""minecraft:gradient""#).unwrap();
+    assert!(Arc::ptr_eq(first.root(), second.root()));
+
+    assert_eq!(
+        convert(r#""minecraft:loop""#).err(),
+        Some(DensityFunctionError::Cycle(UnlocalizedName::minecraft(
+            "loop"
+        )))
+    );
+    assert_eq!(
+        convert(r#""minecraft:missing""#).err(),
+        Some(DensityFunctionError::UnknownFunction(
+            UnlocalizedName::minecraft("missing")
+        ))
+    );
+    assert_eq!(
+        convert(r#"{"type": "minecraft:noise", "noise": "a", "xz_scale": 1.0, "y_scale": 1.0}"#)
+            .err(),
+        Some(DensityFunctionError::UnknownNoise(
+            UnlocalizedName::minecraft("a")
+        ))
+    );
+    assert_eq!(
+        convert(r#"{"type": "minecraft:end_islands"}"#).err(),
+        Some(DensityFunctionError::Unsupported("minecraft:end_islands"))
+    );
 }
@@ -1,12 +1,32 @@
-use std::mem::MaybeUninit;
+pub mod biome;
+pub mod density_function;
+pub mod noise_based;
+pub mod random;
+pub mod status;
+pub mod structure;
 
-use qdat::world::location::{BlockPosition, Coordinate};
+use qdat::{
+    block::{
+        states::{BlockStateData, WaterState},
+        StateID,
+    },
+    world::location::{BlockPosition, Coordinate},
+};
 use quartz_nbt::NbtCompound;
 
-use crate::world::chunk::{Section, SectionStore, MAX_SECTION_COUNT};
+use crate::world::chunk::{
+    plains_network_id,
+    BiomeContainer,
+    Section,
+    SectionStore,
+    MAX_SECTION_COUNT,
+};
 
 use noise::{NoiseFn, Perlin};
 
+/// The generation passes a chunk goes through, in order. Each status means the pass of that name
+/// has been run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ChunkStatus {
     Empty,
     Shaping,
@@ -15,52 +35,106 @@ pub enum ChunkStatus {
     Carving,
 }
 
+impl ChunkStatus {
+    /// Returns the status after this one, or `None` if this is the last status.
+    pub const fn next(self) -> Option<ChunkStatus> {
+        match self {
+            ChunkStatus::Empty => Some(ChunkStatus::Shaping),
+            ChunkStatus::Shaping => Some(ChunkStatus::Biomes),
+            ChunkStatus::Biomes => Some(ChunkStatus::Features),
+            ChunkStatus::Features => Some(ChunkStatus::Carving),
+            ChunkStatus::Carving => None,
+        }
+    }
+
+    /// Returns the status every chunk directly around a chunk must have reached before the pass for
+    /// this status can run on it.
+    pub const fn required_neighbor_status(self) -> Option<ChunkStatus> {
+        match self {
+            // Features can extend into neighboring chunks, which need to know their biomes
+            ChunkStatus::Features => Some(ChunkStatus::Biomes),
+            _ => None,
+        }
+    }
+}
+
 pub trait ChunkGenerator {
     fn start_chunk(coords: Coordinate) -> Self;
     fn shape_chunk(&mut self);
+    fn generate_biomes(&mut self) {}
+    fn place_features(&mut self) {}
+    fn carve(&mut self) {}
     fn finish_chunk(self) -> super::Chunk;
 }
 
+/// The blocks and sea level used to shape chunks, mirroring the fields of a datapack's noise
+/// generator settings.
+#[derive(Clone, Copy, Debug)]
+pub struct GeneratorSettings {
+    /// Air below this height is filled with the default fluid.
+    pub sea_level: i32,
+    pub default_block: StateID,
+    pub default_fluid: StateID,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        GeneratorSettings {
+            sea_level: 63,
+            default_block: BlockStateData::Stone.id(),
+            default_fluid: BlockStateData::Water(WaterState::const_default()).id(),
+        }
+    }
+}
+
 pub struct SimpleChunkGenerator {
     chunk: ProtoChunk,
     noise: Perlin,
+    settings: GeneratorSettings,
+}
+
+impl SimpleChunkGenerator {
+    pub fn with_settings(coords: Coordinate, settings: GeneratorSettings) -> Self {
+        SimpleChunkGenerator {
+            chunk: ProtoChunk::new(coords.as_chunk()),
+            noise: Perlin::new(),
+            settings,
+        }
+    }
+
+    /// Fills the given column with the default block up to and including the surface height, then
+    /// with the default fluid up to the sea level.
+    fn fill_column(&mut self, x: i32, z: i32, height: i16) {
+        for y in 0 ..= height {
+            self.chunk
+                .set_block_state(BlockPosition { x, y, z }, self.settings.default_block);
+        }
+
+        for y in height + 1 .. self.settings.sea_level as i16 {
+            self.chunk
+                .set_block_state(BlockPosition { x, y, z }, self.settings.default_fluid);
+        }
+    }
 }
 
 impl ChunkGenerator for SimpleChunkGenerator {
     fn start_chunk(coords: Coordinate) -> Self {
-        let chunk = ProtoChunk::new(coords.as_chunk());
-        let noise = Perlin::new();
-
-        Self { chunk, noise }
+        Self::with_settings(coords, GeneratorSettings::default())
     }
 
     fn shape_chunk(&mut self) {
-        let chunk = &mut self.chunk;
+        let chunk_pos = self.chunk.pos.as_block();
         for x in 0 .. 16 {
             for z in 0 .. 16 {
-                let y = (self.noise.get([
-                    (chunk.pos.as_block().x() + x) as f64 / 100.0,
-                    (chunk.pos.as_block().z() + z) as f64 / 100.0,
+                let height = (self.noise.get([
+                    (chunk_pos.x() + x) as f64 / 100.0,
+                    (chunk_pos.z() + z) as f64 / 100.0,
                 ]) * 40.0
                     + 60.0) as i16;
-                // let y = 70;
-                for i in 0 .. y {
-                    let curr_y = y - i;
-                    let section_index = curr_y >> 4;
-                    let block_index =
-                        chunk.section_index_absolute(BlockPosition { x, y: curr_y, z });
-                    chunk
-                        .sections
-                        .get_mut(section_index as usize)
-                        .unwrap()
-                        .set_block_state_at(
-                            block_index,
-                            qdat::block::states::BlockStateData::Stone.id(),
-                        );
-                }
+                self.fill_column(x, z, height);
             }
         }
-        chunk.state = ChunkState::Shaped;
+        self.chunk.state = ChunkState::Shaped;
     }
 
     fn finish_chunk(self) -> super::Chunk {
@@ -78,31 +152,38 @@ pub struct ProtoChunk {
     pub state: ChunkState,
     pub pos: Coordinate,
     pub sections: [Section; MAX_SECTION_COUNT],
-    // TODO: figure out biomes
+    /// The numeric ID of the biome of every 4x4x4 cell from the bottom of section zero, which are
+    /// moved into the sections once the chunk is finished.
     pub biomes: Box<[i32]>,
 }
 
 impl ProtoChunk {
     pub fn new(pos: Coordinate) -> ProtoChunk {
-        let mut sections: [MaybeUninit<Section>; MAX_SECTION_COUNT] =
-            // Safety: an array of maybe uninit does not need any initialization
-            unsafe { MaybeUninit::uninit().assume_init() };
-
-        for (i, s) in sections.iter_mut().enumerate() {
-            s.write(Section::empty(i as i8 - 1));
-        }
-
-        // Safety: we initialized the sections in the loop above
-        let sections = unsafe { std::mem::transmute::<_, [Section; MAX_SECTION_COUNT]>(sections) };
+        let sections = std::array::from_fn(|i| Section::empty(i as i8 - 1));
 
         ProtoChunk {
             state: ChunkState::Empty,
             pos,
             sections,
-            biomes: Box::new([0; 1024]),
+            biomes: vec![plains_network_id(); 1024].into_boxed_slice(),
         }
     }
 
+    /// Sets the block state at the given position, where x and z are relative to the chunk.
+    pub fn set_block_state(&mut self, pos: BlockPosition, state: StateID) -> Option<StateID> {
+        let block_index = self.section_index_absolute(pos);
+        // The first section is the one below the world
+        self.sections
+            .get_mut(((pos.y >> 4) + 1) as usize)?
+            .set_block_state_at(block_index, state)
+    }
+
+    pub fn block_state(&self, pos: BlockPosition) -> Option<StateID> {
+        self.sections
+            .get(((pos.y >> 4) + 1) as usize)?
+            .block_state_at(self.section_index_absolute(pos))
+    }
+
     // x and z have to be in 0-16
     fn section_index_absolute(&self, pos: BlockPosition) -> usize {
         (pos.x + pos.z * 16 + (pos.y as i32 % 16) * 256) as usize
@@ -112,21 +193,76 @@ impl ProtoChunk {
 #[allow(clippy::from_over_into)]
 impl Into<super::Chunk> for ProtoChunk {
     fn into(self) -> super::Chunk {
-        let chunk_size = self.sections.iter().filter(|s| !s.is_empty()).count();
+        let mut sections = self.sections;
+        for section in &mut sections {
+            section.load_network_biomes(&self.biomes);
+        }
+
+        // Empty sections are still kept if they have biomes other than the default
+        let default_biomes = BiomeContainer::default();
+        let keep = |s: &Section| !s.is_empty() || *s.biomes() != default_biomes;
+        let chunk_size = sections.iter().filter(|s| keep(*s)).count();
 
         let mut section_store = SectionStore::new(chunk_size);
 
-        for s in self.sections.into_iter().filter(|s| !s.is_empty()) {
+        for s in sections.into_iter().filter(keep) {
             // assunme insertion cannot fail
             section_store.insert(s).unwrap();
         }
 
-
-        super::Chunk::new(
+        let mut chunk = super::Chunk::new(
             self.pos.as_block().into(),
             section_store,
             NbtCompound::new(),
-            self.biomes,
-        )
+        );
+        // Generated chunks start at the bottom of section zero
+        chunk.recalculate_heightmaps(0);
+        chunk
+    }
+}
+
+#[test]
+fn proto_chunk_section_y() {
+    let chunk = ProtoChunk::new(Coordinate::chunk(0, 0));
+
+    for (i, section) in chunk.sections.iter().enumerate() {
+        assert_eq!(i8::from(section.y), i as i8 - 1);
     }
 }
+
+#[test]
+fn valley_filled_to_sea_level() {
+    let settings = GeneratorSettings {
+        sea_level: 63,
+        ..GeneratorSettings::default()
+    };
+    let mut generator = SimpleChunkGenerator::with_settings(Coordinate::chunk(0, 0), settings);
+    generator.fill_column(3, 5, 40);
+
+    let state_at = |y| generator.chunk.block_state(BlockPosition { x: 3, y, z: 5 });
+    assert_eq!(state_at(40), Some(settings.default_block));
+    for y in 41 .. 63 {
+        assert_eq!(state_at(y), Some(settings.default_fluid));
+    }
+    assert_eq!(state_at(63), Some(BlockStateData::Air.id()));
+}
+
+#[test]
+fn generated_biomes_are_kept_by_sections() {
+    use crate::world::chunk::{network_biome_id, Chunk};
+    use qdat::{UlnStr, UnlocalizedName};
+
+    let desert = network_biome_id(UlnStr::minecraft("desert")).unwrap();
+    let mut proto = ProtoChunk::new(Coordinate::chunk(1, 0));
+    // The first cell of section 5, which has no blocks
+    proto.biomes[5 * 64] = desert;
+
+    let chunk: Chunk = proto.into();
+    let pos = BlockPosition { x: 16, y: 80, z: 0 };
+    assert_eq!(chunk.get_biome(pos), UnlocalizedName::minecraft("desert"));
+    assert_eq!(
+        chunk.get_biome(BlockPosition { y: 64, ..pos }),
+        UnlocalizedName::minecraft("plains")
+    );
+    assert_eq!(chunk.biome_ids()[5 * 64], desert);
+}
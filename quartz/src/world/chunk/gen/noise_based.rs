@@ -0,0 +1,726 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    sync::Arc,
+};
+
+use qdat::{
+    block::{
+        states::{BlockStateData, GrassBlockState, WaterState, AIR},
+        StateID,
+    },
+    world::location::{BlockPosition, Coordinate},
+};
+use quartz_datapack::data::{
+    density_function::DensityFunctionProvider,
+    features::SurfaceType,
+    noise_settings::{
+        BlockState as RawBlockState,
+        HeightConditionProvider,
+        NoiseSettings,
+        SurfaceRule as RawSurfaceRule,
+    },
+};
+
+use super::{
+    density_function::{
+        DensityFunction,
+        DensityFunctionError,
+        DensityFunctionTree,
+        FunctionContext,
+        NoiseRouter,
+        NoiseSampler,
+    },
+    structure::TemplateState,
+    ChunkGenerator,
+    ChunkState,
+    GeneratorSettings,
+    ProtoChunk,
+};
+use crate::world::chunk::ChunkDecodeError;
+
+/// The settings of a noise based generator, mirroring a datapack's noise generator settings.
+#[derive(Clone)]
+pub struct NoiseGeneratorSettings {
+    pub generator: GeneratorSettings,
+    /// The lowest generated y level.
+    pub min_y: i32,
+    /// The number of generated y levels, which must be a multiple of the cell height.
+    pub height: i32,
+    /// Noise cells are `size_horizontal * 4` blocks wide, which must divide the width of a chunk.
+    pub size_horizontal: i32,
+    /// Noise cells are `size_vertical * 4` blocks tall.
+    pub size_vertical: i32,
+    pub router: NoiseRouter,
+    pub surface_rule: SurfaceRule,
+}
+
+impl NoiseGeneratorSettings {
+    /// Converts a datapack's noise generator settings, converting each function of its noise
+    /// router with the given function.
+    pub fn from_datapack(
+        settings: &NoiseSettings,
+        convert: impl FnMut(
+            &DensityFunctionProvider,
+        ) -> Result<DensityFunctionTree, DensityFunctionError>,
+    ) -> Result<Self, NoiseSettingsError> {
+        let min_y = settings.noise.min_y;
+        let height = settings.noise.height;
+        let settings = NoiseGeneratorSettings {
+            generator: GeneratorSettings {
+                sea_level: settings.sea_level,
+                default_block: resolve_block(&settings.default_block)?,
+                default_fluid: resolve_block(&settings.default_fluid)?,
+            },
+            min_y,
+            height,
+            size_horizontal: settings.noise.size_horizontal,
+            size_vertical: settings.noise.size_vertical,
+            router: NoiseRouter::try_from_settings(&settings.noise_router, convert)
+                .map_err(NoiseSettingsError::DensityFunction)?,
+            surface_rule: SurfaceRule::from_datapack(&settings.surface_rule, min_y, height)?,
+        };
+
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    #[inline]
+    pub fn cell_width(&self) -> i32 {
+        self.size_horizontal * 4
+    }
+
+    #[inline]
+    pub fn cell_height(&self) -> i32 {
+        self.size_vertical * 4
+    }
+
+    /// Checks that the noise cells evenly divide the width of a chunk and the generated height.
+    pub fn validate(&self) -> Result<(), NoiseSettingsError> {
+        let width = self.cell_width();
+        let height = self.cell_height();
+        if width <= 0 || 16 % width != 0 || height <= 0 || self.height % height != 0 {
+            return Err(NoiseSettingsError::InvalidCellSize { width, height });
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for NoiseGeneratorSettings {
+    /// Flat terrain with its surface at y 64, covered with grass and dirt.
+    fn default() -> Self {
+        let constant = || DensityFunctionTree::new(DensityFunction::Constant(0.0));
+
+        NoiseGeneratorSettings {
+            generator: GeneratorSettings::default(),
+            min_y: 0,
+            height: 256,
+            size_horizontal: 1,
+            size_vertical: 2,
+            router: NoiseRouter {
+                barrier: constant(),
+                fluid_level_floodedness: constant(),
+                fluid_level_spread: constant(),
+                lava: constant(),
+                temperature: constant(),
+                vegetation: constant(),
+                continents: constant(),
+                erosion: constant(),
+                depth: constant(),
+                ridges: constant(),
+                initial_density_without_jaggedness: constant(),
+                final_density: DensityFunctionTree::new(DensityFunction::Noise {
+                    noise: Arc::new(FlatGradient { surface_y: 64.0 }),
+                    xz_scale: 1.0,
+                    y_scale: 1.0,
+                }),
+                vein_toggle: constant(),
+                vein_ridged: constant(),
+                vein_gap: constant(),
+            },
+            surface_rule: SurfaceRule::Sequence(vec![
+                SurfaceRule::Condition {
+                    if_true: SurfaceCondition::StoneDepth {
+                        offset: 0,
+                        surface_type: SurfaceType::Floor,
+                    },
+                    then_run: Box::new(SurfaceRule::Sequence(vec![
+                        SurfaceRule::Condition {
+                            if_true: SurfaceCondition::Water { offset: 0 },
+                            then_run: Box::new(SurfaceRule::Block(
+                                BlockStateData::GrassBlock(GrassBlockState::const_default()).id(),
+                            )),
+                        },
+                        SurfaceRule::Block(BlockStateData::Dirt.id()),
+                    ])),
+                },
+                SurfaceRule::Condition {
+                    if_true: SurfaceCondition::StoneDepth {
+                        offset: 3,
+                        surface_type: SurfaceType::Floor,
+                    },
+                    then_run: Box::new(SurfaceRule::Block(BlockStateData::Dirt.id())),
+                },
+            ]),
+        }
+    }
+}
+
+/// An error encountered while converting a datapack's noise generator settings.
+#[derive(Debug)]
+pub enum NoiseSettingsError {
+    Block(ChunkDecodeError),
+    DensityFunction(DensityFunctionError),
+    /// A type of surface rule or condition which cannot be applied yet, or which uses a surface
+    /// depth.
+    UnsupportedSurfaceRule(&'static str),
+    /// A surface condition used as a rule, or a rule used as a condition.
+    MisplacedSurfaceRule(&'static str),
+    /// Noise cells which do not evenly divide the width of a chunk or the generated height.
+    InvalidCellSize {
+        width: i32,
+        height: i32,
+    },
+}
+
+impl Display for NoiseSettingsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NoiseSettingsError::Block(error) => Display::fmt(error, f),
+            NoiseSettingsError::DensityFunction(error) => Display::fmt(error, f),
+            NoiseSettingsError::UnsupportedSurfaceRule(kind) =>
+                write!(f, "Surface rule {kind} is not supported"),
+            NoiseSettingsError::MisplacedSurfaceRule(kind) =>
+                write!(f, "Surface rule {kind} cannot be used there"),
+            NoiseSettingsError::InvalidCellSize { width, height } => write!(
+                f,
+                "Noise cells of {width}x{height} blocks do not evenly divide the generated area"
+            ),
+        }
+    }
+}
+
+impl Error for NoiseSettingsError {}
+
+impl From<ChunkDecodeError> for NoiseSettingsError {
+    fn from(error: ChunkDecodeError) -> Self {
+        NoiseSettingsError::Block(error)
+    }
+}
+
+fn resolve_block(state: &RawBlockState) -> Result<StateID, ChunkDecodeError> {
+    TemplateState::resolve_with(&state.name, &state.properties)
+}
+
+/// A density which is positive below the given height and negative above it.
+struct FlatGradient {
+    surface_y: f64,
+}
+
+impl NoiseSampler for FlatGradient {
+    fn sample(&self, _x: f64, y: f64, _z: f64) -> f64 {
+        (self.surface_y - y) / 16.0
+    }
+}
+
+/// Decides which block replaces the default block at a position once the terrain is shaped.
+#[derive(Clone, Debug)]
+pub enum SurfaceRule {
+    Block(StateID),
+    /// Runs each rule in order until one of them places a block.
+    Sequence(Vec<SurfaceRule>),
+    Condition {
+        if_true: SurfaceCondition,
+        then_run: Box<SurfaceRule>,
+    },
+}
+
+impl SurfaceRule {
+    /// Converts a surface rule from a datapack, resolving heights against the generated range of
+    /// y levels.
+    pub fn from_datapack(
+        rule: &RawSurfaceRule,
+        min_y: i32,
+        height: i32,
+    ) -> Result<Self, NoiseSettingsError> {
+        Ok(match rule {
+            RawSurfaceRule::Block { result_state } =>
+                SurfaceRule::Block(resolve_block(result_state)?),
+            RawSurfaceRule::Sequnce { sequence } => SurfaceRule::Sequence(
+                sequence
+                    .iter()
+                    .map(|rule| SurfaceRule::from_datapack(rule, min_y, height))
+                    .collect::<Result<_, _>>()?,
+            ),
+            RawSurfaceRule::Condition { if_true, then_run } => SurfaceRule::Condition {
+                if_true: SurfaceCondition::from_datapack(if_true, min_y, height)?,
+                then_run: Box::new(SurfaceRule::from_datapack(then_run, min_y, height)?),
+            },
+            RawSurfaceRule::Bandlands =>
+                return Err(NoiseSettingsError::UnsupportedSurfaceRule(
+                    surface_rule_type(rule),
+                )),
+            _ =>
+                return Err(NoiseSettingsError::MisplacedSurfaceRule(surface_rule_type(
+                    rule,
+                ))),
+        })
+    }
+
+    /// Returns the block to place at the given position, or `None` to keep the default block.
+    pub fn apply(&self, context: &SurfaceContext) -> Option<StateID> {
+        match self {
+            &SurfaceRule::Block(state) => Some(state),
+            SurfaceRule::Sequence(rules) => rules.iter().find_map(|rule| rule.apply(context)),
+            SurfaceRule::Condition { if_true, then_run } =>
+                if if_true.test(context) {
+                    then_run.apply(context)
+                } else {
+                    None
+                },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum SurfaceCondition {
+    /// Whether the position is within `offset + 1` blocks of the top of the solid blocks it is
+    /// part of, or of the bottom for ceilings.
+    StoneDepth {
+        offset: i32,
+        surface_type: SurfaceType,
+    },
+    /// Whether the position is no more than `offset` blocks below the surface of the fluid directly
+    /// above its solid blocks. Always true if there is no such fluid.
+    Water {
+        offset: i32,
+    },
+    /// Whether the position is at or above the given y level.
+    YAbove(i32),
+    Not(Box<SurfaceCondition>),
+}
+
+impl SurfaceCondition {
+    /// Converts a surface condition from a datapack, resolving heights against the generated range
+    /// of y levels. Conditions which depend on the surface depth noise are not supported.
+    pub fn from_datapack(
+        condition: &RawSurfaceRule,
+        min_y: i32,
+        height: i32,
+    ) -> Result<Self, NoiseSettingsError> {
+        Ok(match condition {
+            &RawSurfaceRule::StoneDepth {
+                offset,
+                add_surface_depth: false,
+                secondary_depth_range: 0,
+                surface_type,
+            } => SurfaceCondition::StoneDepth {
+                offset,
+                surface_type,
+            },
+            // Vanilla tests `y >= water_height + offset` rather than `y + offset >= water_height`
+            &RawSurfaceRule::Water {
+                offset,
+                surface_depth_multiplier: 0,
+                add_stone_depth: false,
+            } => SurfaceCondition::Water { offset: -offset },
+            RawSurfaceRule::YAbove {
+                anchor,
+                surface_depth_multiplier: 0,
+                add_stone_depth: false,
+            } => SurfaceCondition::YAbove(match *anchor {
+                HeightConditionProvider::AboveBottom { above_bottom } => min_y + above_bottom,
+                HeightConditionProvider::Absolute { absolute } => absolute,
+                HeightConditionProvider::BelowTop { below_top } => min_y + height - 1 - below_top,
+            }),
+            RawSurfaceRule::Not { invert } => SurfaceCondition::Not(Box::new(
+                SurfaceCondition::from_datapack(invert, min_y, height)?,
+            )),
+            RawSurfaceRule::Block { .. }
+            | RawSurfaceRule::Sequnce { .. }
+            | RawSurfaceRule::Condition { .. }
+            | RawSurfaceRule::Bandlands =>
+                return Err(NoiseSettingsError::MisplacedSurfaceRule(surface_rule_type(
+                    condition,
+                ))),
+            _ =>
+                return Err(NoiseSettingsError::UnsupportedSurfaceRule(
+                    surface_rule_type(condition),
+                )),
+        })
+    }
+
+    pub fn test(&self, context: &SurfaceContext) -> bool {
+        match self {
+            &SurfaceCondition::StoneDepth {
+                offset,
+                surface_type,
+            } => {
+                let depth = match surface_type {
+                    SurfaceType::Floor => context.stone_depth_above,
+                    SurfaceType::Ceiling => context.stone_depth_below,
+                };
+                depth <= 1 + offset
+            }
+            &SurfaceCondition::Water { offset } => context
+                .water_height
+                .is_none_or(|height| context.y + offset >= height),
+            &SurfaceCondition::YAbove(y) => context.y >= y,
+            SurfaceCondition::Not(condition) => !condition.test(context),
+        }
+    }
+}
+
+fn surface_rule_type(rule: &RawSurfaceRule) -> &'static str {
+    match rule {
+        RawSurfaceRule::Condition { .. } => "minecraft:condition",
+        RawSurfaceRule::Block { .. } => "minecraft:block",
+        RawSurfaceRule::VerticalGradient { .. } => "minecraft:vertical_gradient",
+        RawSurfaceRule::AbovePreliminarySurface => "minecraft:above_preliminary_surface",
+        RawSurfaceRule::Sequnce { .. } => "minecraft:sequence",
+        RawSurfaceRule::StoneDepth { .. } => "minecraft:stone_depth",
+        RawSurfaceRule::Water { .. } => "minecraft:water",
+        RawSurfaceRule::Biome { .. } => "minecraft:biome",
+        RawSurfaceRule::YAbove { .. } => "minecraft:y_above",
+        RawSurfaceRule::Not { .. } => "minecraft:not",
+        RawSurfaceRule::NoiseThreshold { .. } => "minecraft:noise_threshold",
+        RawSurfaceRule::Steep => "minecraft:steep",
+        RawSurfaceRule::Hole => "minecraft:hole",
+        RawSurfaceRule::Bandlands => "minecraft:bandlands",
+        RawSurfaceRule::Temperature => "minecraft:temperature",
+    }
+}
+
+/// The position of a solid block in its column, used to evaluate surface rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SurfaceContext {
+    pub y: i32,
+    /// The number of solid blocks from this block up to the first non-solid block, including this
+    /// block.
+    pub stone_depth_above: i32,
+    /// The number of solid blocks from this block down to the first non-solid block, including
+    /// this block.
+    pub stone_depth_below: i32,
+    /// The y level above the fluid directly on top of this block's solid blocks, if any.
+    pub water_height: Option<i32>,
+}
+
+/// Shapes terrain by sampling the final density of a noise router, then replaces the top layers
+/// of the terrain according to surface rules.
+pub struct NoiseBasedChunkGenerator {
+    chunk: ProtoChunk,
+    settings: Arc<NoiseGeneratorSettings>,
+}
+
+impl NoiseBasedChunkGenerator {
+    /// # Panics
+    ///
+    /// Panics if the noise cells of the settings are invalid, see
+    /// [`NoiseGeneratorSettings::validate`].
+    pub fn with_settings(coords: Coordinate, settings: Arc<NoiseGeneratorSettings>) -> Self {
+        if let Err(error) = settings.validate() {
+            panic!("{error}");
+        }
+
+        NoiseBasedChunkGenerator {
+            chunk: ProtoChunk::new(coords.as_chunk()),
+            settings,
+        }
+    }
+
+    /// Computes the final density at the corners of each noise cell and interpolates it within the
+    /// cells, placing the default block where it is positive and the default fluid below the sea
+    /// level elsewhere.
+    fn fill_noise(&mut self) {
+        let settings = &*self.settings;
+        let origin = self.chunk.pos.as_block();
+        let cell_width = settings.cell_width();
+        let cell_height = settings.cell_height();
+        let cells_xz = (16 / cell_width) as usize;
+        let cells_y = (settings.height / cell_height) as usize;
+
+        let corner_index =
+            |x: usize, y: usize, z: usize| (y * (cells_xz + 1) + z) * (cells_xz + 1) + x;
+        let mut corners = vec![0.0; (cells_xz + 1) * (cells_xz + 1) * (cells_y + 1)];
+        for y in 0 ..= cells_y {
+            for z in 0 ..= cells_xz {
+                for x in 0 ..= cells_xz {
+                    corners[corner_index(x, y, z)] =
+                        settings.router.final_density.compute(FunctionContext::new(
+                            origin.x() + x as i32 * cell_width,
+                            settings.min_y + y as i32 * cell_height,
+                            origin.z() + z as i32 * cell_width,
+                        ));
+                }
+            }
+        }
+
+        let lerp = |t: f64, a: f64, b: f64| a + t * (b - a);
+        for local_y in 0 .. settings.height {
+            let (cell_y, fy) = split_cell(local_y, cell_height);
+            let y = settings.min_y + local_y;
+
+            for z in 0 .. 16 {
+                let (cell_z, fz) = split_cell(z, cell_width);
+
+                for x in 0 .. 16 {
+                    let (cell_x, fx) = split_cell(x, cell_width);
+                    let corner = |dx: usize, dy: usize, dz: usize| {
+                        corners[corner_index(cell_x + dx, cell_y + dy, cell_z + dz)]
+                    };
+
+                    let density = lerp(
+                        fz,
+                        lerp(
+                            fy,
+                            lerp(fx, corner(0, 0, 0), corner(1, 0, 0)),
+                            lerp(fx, corner(0, 1, 0), corner(1, 1, 0)),
+                        ),
+                        lerp(
+                            fy,
+                            lerp(fx, corner(0, 0, 1), corner(1, 0, 1)),
+                            lerp(fx, corner(0, 1, 1), corner(1, 1, 1)),
+                        ),
+                    );
+
+                    let state = if density > 0.0 {
+                        settings.generator.default_block
+                    } else if y < settings.generator.sea_level {
+                        settings.generator.default_fluid
+                    } else {
+                        continue;
+                    };
+                    self.chunk
+                        .set_block_state(BlockPosition { x, y: y as i16, z }, state);
+                }
+            }
+        }
+    }
+
+    /// Runs the surface rule on every default block, working down each column.
+    fn build_surface(&mut self) {
+        let settings = &*self.settings;
+        let height = settings.height as usize;
+
+        for z in 0 .. 16 {
+            for x in 0 .. 16 {
+                let column = (0 .. settings.height)
+                    .map(|local_y| {
+                        let pos = BlockPosition {
+                            x,
+                            y: (settings.min_y + local_y) as i16,
+                            z,
+                        };
+                        self.chunk.block_state(pos).unwrap_or(AIR)
+                    })
+                    .collect::<Vec<_>>();
+
+                // The lowest block of the solid run each block is part of
+                let mut run_bottoms = vec![0; height];
+                for i in 0 .. height {
+                    run_bottoms[i] = if i > 0 && column[i - 1] == settings.generator.default_block {
+                        run_bottoms[i - 1]
+                    } else {
+                        i
+                    };
+                }
+
+                let mut run_top = None;
+                let mut fluid_top = None;
+                let mut water_height = None;
+                for i in (0 .. height).rev() {
+                    let state = column[i];
+                    if state != settings.generator.default_block {
+                        run_top = None;
+                        if state != settings.generator.default_fluid {
+                            fluid_top = None;
+                        } else if fluid_top.is_none() {
+                            fluid_top = Some(i);
+                        }
+                        continue;
+                    }
+
+                    let top = *run_top.get_or_insert_with(|| {
+                        water_height = fluid_top.map(|top| settings.min_y + top as i32 + 1);
+                        i
+                    });
+                    fluid_top = None;
+
+                    let y = settings.min_y + i as i32;
+                    let context = SurfaceContext {
+                        y,
+                        stone_depth_above: (top - i) as i32 + 1,
+                        stone_depth_below: (i - run_bottoms[i]) as i32 + 1,
+                        water_height,
+                    };
+
+                    if let Some(state) = settings.surface_rule.apply(&context) {
+                        self.chunk
+                            .set_block_state(BlockPosition { x, y: y as i16, z }, state);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Splits a coordinate within a chunk into the index of its cell and its position in that cell.
+fn split_cell(coordinate: i32, cell_size: i32) -> (usize, f64) {
+    (
+        (coordinate / cell_size) as usize,
+        (coordinate % cell_size) as f64 / cell_size as f64,
+    )
+}
+
+impl ChunkGenerator for NoiseBasedChunkGenerator {
+    fn start_chunk(coords: Coordinate) -> Self {
+        Self::with_settings(coords, Arc::new(NoiseGeneratorSettings::default()))
+    }
+
+    fn shape_chunk(&mut self) {
+        self.fill_noise();
+        self.build_surface();
+        self.chunk.state = ChunkState::Shaped;
+    }
+
+    fn finish_chunk(self) -> super::super::Chunk {
+        self.chunk.into()
+    }
+}
+
+#[test]
+fn flat_noise_surface() {
+    let mut generator = NoiseBasedChunkGenerator::start_chunk(Coordinate::chunk(3, -2));
+    generator.shape_chunk();
+
+    let grass = BlockStateData::GrassBlock(GrassBlockState::const_default()).id();
+    let dirt = BlockStateData::Dirt.id();
+    let stone = BlockStateData::Stone.id();
+    for (x, z) in [(0, 0), (7, 3), (15, 15)] {
+        let state_at = |y| generator.chunk.block_state(BlockPosition { x, y, z });
+        assert_eq!(state_at(64), Some(AIR));
+        assert_eq!(state_at(63), Some(grass));
+        for y in 60 .. 63 {
+            assert_eq!(state_at(y), Some(dirt));
+        }
+        assert_eq!(state_at(59), Some(stone));
+        assert_eq!(state_at(0), Some(stone));
+    }
+
+    // Flooding the world puts dirt rather than grass under the water
+    let settings = NoiseGeneratorSettings {
+        generator: GeneratorSettings {
+            sea_level: 70,
+            ..GeneratorSettings::default()
+        },
+        ..NoiseGeneratorSettings::default()
+    };
+    let mut generator =
+        NoiseBasedChunkGenerator::with_settings(Coordinate::chunk(0, 0), Arc::new(settings));
+    generator.shape_chunk();
+
+    let state_at = |y| generator.chunk.block_state(BlockPosition { x: 4, y, z: 9 });
+    assert_eq!(state_at(63), Some(dirt));
+    assert_eq!(
+        state_at(69),
+        Some(BlockStateData::Water(WaterState::const_default()).id())
+    );
+}
+
+#[test]
+fn noise_settings_from_datapack() {
+    let input = r#"{
+        "sea_level": 50,
+        "disable_mob_generation": false,
+        "ore_veins_enabled": false,
+        "aquifers_enabled": false,
+        "legacy_random_source": false,
+        "default_block": {"Name": "minecraft:cobblestone"},
+        "default_fluid": {"Name": "minecraft:lava", "Properties": {"level": "0"}},
+        "noise": {
+            "min_y": -64,
+            "height": 384,
+            "size_horizontal": 1,
+            "size_vertical": 2,
+            "sampling": {"xz_scale": 1.0, "y_scale": 1.0, "xz_factor": 80.0, "y_factor": 160.0},
+            "top_slide": {"target": 0.0, "size": 0, "offset": 0},
+            "bottom_slide": {"target": 0.0, "size": 0, "offset": 0},
+            "terrain_shaper": {"offset": 0.0, "factor": 0.0, "jaggedness": 0.0}
+        },
+        "noise_router": {
+            "barrier": 0.0,
+            "fluid_level_floodedness": 0.0,
+            "fluid_level_spread": 0.0,
+            "lava": 0.0,
+            "temperature": 0.0,
+            "vegetation": 0.0,
+            "continents": 0.0,
+            "erosion": 0.0,
+            "depth": 0.0,
+            "ridges": 0.0,
+            "initial_density_without_jaggedness": 0.0,
+            "final_density": 1.0,
+            "vein_toggle": 0.0,
+            "vein_ridged": 0.0,
+            "vein_gap": 0.0
+        },
+        "surface_rule": {
+            "type": "minecraft:condition",
+            "if_true": {
+                "type": "minecraft:not",
+                "invert": {
+                    "type": "minecraft:y_above",
+                    "anchor": {"below_top": 10},
+                    "surface_depth_multiplier": 0,
+                    "add_stone_depth": false
+                }
+            },
+            "then_run": {"type": "minecraft:block", "result_state": {"Name": "minecraft:dirt"}}
+        }
+    }"#;
+    let mut raw: NoiseSettings = serde_json::from_str(input).unwrap();
+    let convert = |raw: &NoiseSettings| {
+        NoiseGeneratorSettings::from_datapack(raw, |provider| match provider {
+            &DensityFunctionProvider::Constant(value) =>
+                Ok(DensityFunctionTree::new(DensityFunction::Constant(value))),
+            _ => Err(DensityFunctionError::Unsupported("minecraft:noise")),
+        })
+    };
+
+    let settings = convert(&raw).unwrap();
+    assert_eq!(settings.generator.sea_level, 50);
+    assert_eq!(
+        settings.generator.default_block,
+        BlockStateData::Cobblestone.id()
+    );
+    let context = |y| SurfaceContext {
+        y,
+        stone_depth_above: 1,
+        stone_depth_below: 1,
+        water_height: None,
+    };
+    // The top generated level is 319, so the rule applies below y 309
+    assert_eq!(
+        settings.surface_rule.apply(&context(308)),
+        Some(BlockStateData::Dirt.id())
+    );
+    assert_eq!(settings.surface_rule.apply(&context(309)), None);
+
+    // Cells 12 blocks wide do not fit in a chunk
+    raw.noise.size_horizontal = 3;
+    assert!(matches!(
+        convert(&raw),
+        Err(NoiseSettingsError::InvalidCellSize {
+            width: 12,
+            height: 8
+        })
+    ));
+
+    raw.noise.size_horizontal = 1;
+    raw.surface_rule = serde_json::from_str(r#"{"type": "minecraft:steep"}"#).unwrap();
+    assert!(matches!(
+        convert(&raw),
+        Err(NoiseSettingsError::MisplacedSurfaceRule("minecraft:steep"))
+    ));
+}
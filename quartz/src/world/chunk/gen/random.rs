@@ -0,0 +1,246 @@
+/// A source of pseudo-random numbers used during world generation.
+///
+/// The bounded methods follow the conventions of vanilla's `RandomSource`, so a source seeded
+/// identically to vanilla's produces the same sequence of values.
+pub trait RandomSource {
+    /// Returns the next random 64-bit value.
+    fn next_long(&mut self) -> i64;
+
+    /// Returns the next random 32-bit value.
+    fn next_int(&mut self) -> i32 {
+        self.next_long() as i32
+    }
+
+    /// Returns a random value in the range `[0, bound)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bound` is not positive.
+    fn next_int_bounded(&mut self, bound: i32) -> i32 {
+        assert!(bound > 0, "Bound must be positive, found {bound}");
+        self.next_u32_bounded(bound as u32) as i32
+    }
+
+    /// Returns a random value in the inclusive range `[min, max]`, matching vanilla's
+    /// `nextIntBetweenInclusive`. The range may span the entire `i32` domain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min > max`.
+    fn next_int_in_range(&mut self, min: i32, max: i32) -> i32 {
+        assert!(min <= max, "Invalid range: {min} > {max}");
+
+        // Computed as an i64 so that spans wider than i32::MAX don't overflow
+        let span = max as i64 - min as i64 + 1;
+        if span > u32::MAX as i64 {
+            return self.next_int();
+        }
+
+        (min as i64 + self.next_u32_bounded(span as u32) as i64) as i32
+    }
+
+    /// Returns a random value in the range `[0, bound)` using Lemire's multiply-and-reject method,
+    /// which is how vanilla bounds the output of its xoroshiro source.
+    fn next_u32_bounded(&mut self, bound: u32) -> u32 {
+        debug_assert!(bound > 0);
+
+        let bound = bound as u64;
+        let mut product = (self.next_int() as u32 as u64) * bound;
+        let mut low = product & 0xFFFF_FFFF;
+
+        if low < bound {
+            let threshold = (bound.wrapping_neg() as u32 as u64) % bound;
+            while low < threshold {
+                product = (self.next_int() as u32 as u64) * bound;
+                low = product & 0xFFFF_FFFF;
+            }
+        }
+
+        (product >> 32) as u32
+    }
+
+    /// Returns a random value in the range `[0, 1)` with 24 bits of precision.
+    fn next_float(&mut self) -> f32 {
+        (self.next_long() as u64 >> 40) as f32 / (1 << 24) as f32
+    }
+
+    /// Returns a random value in the range `[0, 1)`.
+    fn next_double(&mut self) -> f64 {
+        (self.next_long() as u64 >> 11) as f64 * f64::EPSILON / 2.0
+    }
+
+    /// Returns a random boolean.
+    fn next_bool(&mut self) -> bool {
+        self.next_long() & 1 != 0
+    }
+}
+
+/// An implementation of the xoroshiro128++ generator used by vanilla world generation.
+#[derive(Debug, Clone)]
+pub struct XoroshiroRandom {
+    lo: i64,
+    hi: i64,
+}
+
+impl XoroshiroRandom {
+    const GOLDEN_RATIO_64: i64 = 0x9E37_79B9_7F4A_7C15_u64 as i64;
+    const SILVER_RATIO_64: i64 = 0x6A09_E667_F3BC_C909;
+
+    /// Creates a new source from the given seed, expanding it into 128 bits the same way vanilla does.
+    pub fn new(seed: i64) -> Self {
+        let lo = seed ^ Self::SILVER_RATIO_64;
+        let hi = lo.wrapping_add(Self::GOLDEN_RATIO_64);
+        Self::from_state(mix_stafford_13(lo), mix_stafford_13(hi))
+    }
+
+    /// Creates a new source with the given raw state. If both halves are zero then the state is
+    /// replaced with a fixed non-zero state, since an all-zero state only ever produces zero.
+    pub fn from_state(lo: i64, hi: i64) -> Self {
+        if lo == 0 && hi == 0 {
+            XoroshiroRandom {
+                lo: Self::GOLDEN_RATIO_64,
+                hi: Self::SILVER_RATIO_64,
+            }
+        } else {
+            XoroshiroRandom { lo, hi }
+        }
+    }
+}
+
+impl RandomSource for XoroshiroRandom {
+    fn next_long(&mut self) -> i64 {
+        let lo = self.lo;
+        let mut hi = self.hi;
+        let result = lo.wrapping_add(hi).rotate_left(17).wrapping_add(lo);
+
+        hi ^= lo;
+        self.lo = lo.rotate_left(49) ^ hi ^ (hi << 21);
+        self.hi = hi.rotate_left(28);
+
+        result
+    }
+}
+
+/// The linear congruential generator used by Java's `Random`, which vanilla still uses to place
+/// structures.
+#[derive(Debug, Clone)]
+pub struct LegacyRandom {
+    seed: i64,
+}
+
+impl LegacyRandom {
+    const INCREMENT: i64 = 0xB;
+    const MASK: i64 = (1 << 48) - 1;
+    const MULTIPLIER: i64 = 0x5_DEEC_E66D;
+
+    /// Creates a new source from the given seed, scrambling it the same way Java does.
+    pub fn new(seed: i64) -> Self {
+        LegacyRandom {
+            seed: (seed ^ Self::MULTIPLIER) & Self::MASK,
+        }
+    }
+
+    /// Returns a value made up of the given number of random bits, at most 32.
+    fn next_bits(&mut self, bits: u32) -> i32 {
+        self.seed = (self
+            .seed
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(Self::INCREMENT))
+            & Self::MASK;
+        (self.seed >> (48 - bits)) as i32
+    }
+}
+
+impl RandomSource for LegacyRandom {
+    fn next_long(&mut self) -> i64 {
+        ((self.next_bits(32) as i64) << 32).wrapping_add(self.next_bits(32) as i64)
+    }
+
+    fn next_int(&mut self) -> i32 {
+        self.next_bits(32)
+    }
+
+    /// Returns a random value in the range `[0, bound)` using Java's modulo-and-reject method.
+    fn next_u32_bounded(&mut self, bound: u32) -> u32 {
+        debug_assert!(bound > 0);
+
+        if bound.is_power_of_two() && bound <= 1 << 30 {
+            return ((bound as i64 * self.next_bits(31) as i64) >> 31) as u32;
+        }
+
+        // Bounds above i32::MAX can't come from vanilla, so any uniform method will do for those
+        let bits_used = if bound > i32::MAX as u32 { 32 } else { 31 };
+        loop {
+            let bits = self.next_bits(bits_used) as u32 as u64;
+            let value = bits % bound as u64;
+            if bits - value + (bound as u64 - 1) < 1 << bits_used {
+                return value as u32;
+            }
+        }
+    }
+
+    fn next_float(&mut self) -> f32 {
+        self.next_bits(24) as f32 / (1 << 24) as f32
+    }
+
+    fn next_double(&mut self) -> f64 {
+        (((self.next_bits(26) as i64) << 27) + self.next_bits(27) as i64) as f64
+            * (1.0 / (1_i64 << 53) as f64)
+    }
+
+    fn next_bool(&mut self) -> bool {
+        self.next_bits(1) != 0
+    }
+}
+
+fn mix_stafford_13(mut z: i64) -> i64 {
+    z = (z ^ ((z as u64) >> 30) as i64).wrapping_mul(0xBF58_476D_1CE4_E5B9_u64 as i64);
+    z = (z ^ ((z as u64) >> 27) as i64).wrapping_mul(0x94D0_49BB_1331_11EB_u64 as i64);
+    z ^ ((z as u64) >> 31) as i64
+}
+
+#[test]
+fn int_in_negative_range() {
+    let mut random = XoroshiroRandom::new(0);
+    for _ in 0 .. 1000 {
+        let value = random.next_int_in_range(-10, 10);
+        assert!((-10 ..= 10).contains(&value));
+    }
+}
+
+#[test]
+fn int_in_wide_range() {
+    let mut random = XoroshiroRandom::new(42);
+    for _ in 0 .. 1000 {
+        assert!(random.next_int_in_range(0, i32::MAX) >= 0);
+        assert!(random.next_int_in_range(-1, i32::MAX) >= -1);
+        random.next_int_in_range(i32::MIN, i32::MAX);
+    }
+}
+
+#[test]
+fn int_in_single_value_range() {
+    let mut random = XoroshiroRandom::new(1234);
+    for value in [i32::MIN, -5, 0, 7, i32::MAX] {
+        assert_eq!(random.next_int_in_range(value, value), value);
+    }
+}
+
+#[test]
+#[should_panic]
+fn int_in_inverted_range() {
+    XoroshiroRandom::new(0).next_int_in_range(10, -10);
+}
+
+#[test]
+fn legacy_matches_java() {
+    let mut random = LegacyRandom::new(42);
+    assert_eq!(random.next_int(), -1170105035);
+    assert_eq!(random.next_long(), 1008396158678580193);
+    assert_eq!(random.next_int_bounded(100), 84);
+    assert_eq!(random.next_int_bounded(64), 19);
+    assert_eq!(random.next_double(), 0.9420735430282128);
+    assert!(random.next_bool());
+
+    assert_eq!(LegacyRandom::new(42).next_float(), 0.7275637);
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use qdat::world::location::Coordinate;
+
+use super::{ChunkGenerator, ChunkStatus};
+use crate::world::chunk::Chunk;
+
+/// Drives chunks through the generation passes, only running a pass once the chunk and its
+/// neighbors are ready for it.
+pub struct ChunkStatusMachine<G> {
+    chunks: HashMap<Coordinate, (G, ChunkStatus)>,
+}
+
+impl<G: ChunkGenerator> ChunkStatusMachine<G> {
+    pub fn new() -> Self {
+        ChunkStatusMachine {
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Starts generating the chunk at the given coordinates. Does nothing if the chunk was already
+    /// started.
+    pub fn start(&mut self, coords: Coordinate) {
+        let coords = coords.as_chunk();
+        self.chunks
+            .entry(coords)
+            .or_insert_with(|| (G::start_chunk(coords), ChunkStatus::Empty));
+    }
+
+    pub fn status(&self, coords: Coordinate) -> Option<ChunkStatus> {
+        self.chunks
+            .get(&coords.as_chunk())
+            .map(|&(_, status)| status)
+    }
+
+    /// Runs the pass for the given status on a chunk, which must be at the status just before it.
+    pub fn run(&mut self, coords: Coordinate, status: ChunkStatus) -> Result<(), ChunkStatusError> {
+        let coords = coords.as_chunk();
+        let current = self
+            .status(coords)
+            .ok_or(ChunkStatusError::NotStarted(coords))?;
+        if current.next() != Some(status) {
+            return Err(ChunkStatusError::OutOfOrder {
+                current,
+                requested: status,
+            });
+        }
+
+        if let Some(required) = status.required_neighbor_status() {
+            for dx in -1 ..= 1 {
+                for dz in -1 ..= 1 {
+                    let neighbor = Coordinate::chunk(coords.x() + dx, coords.z() + dz);
+                    if neighbor == coords {
+                        continue;
+                    }
+
+                    match self.status(neighbor) {
+                        Some(found) if found >= required => {}
+                        found =>
+                            return Err(ChunkStatusError::NeighborNotReady {
+                                neighbor,
+                                required,
+                                found,
+                            }),
+                    }
+                }
+            }
+        }
+
+        // Unwrap is safe since we checked the chunk's status above
+        let (generator, current) = self.chunks.get_mut(&coords).unwrap();
+        match status {
+            ChunkStatus::Empty => {}
+            ChunkStatus::Shaping => generator.shape_chunk(),
+            ChunkStatus::Biomes => generator.generate_biomes(),
+            ChunkStatus::Features => generator.place_features(),
+            ChunkStatus::Carving => generator.carve(),
+        }
+        *current = status;
+        Ok(())
+    }
+
+    /// Runs the next pass on a chunk, returning the chunk's new status.
+    pub fn advance(&mut self, coords: Coordinate) -> Result<ChunkStatus, ChunkStatusError> {
+        let current = self
+            .status(coords)
+            .ok_or(ChunkStatusError::NotStarted(coords.as_chunk()))?;
+        let next = current.next().ok_or(ChunkStatusError::Finished)?;
+        self.run(coords, next)?;
+        Ok(next)
+    }
+
+    /// Removes a chunk which has gone through every pass and returns the generated chunk.
+    pub fn finish(&mut self, coords: Coordinate) -> Option<Chunk> {
+        let coords = coords.as_chunk();
+        if self.status(coords)? != ChunkStatus::Carving {
+            return None;
+        }
+
+        self.chunks
+            .remove(&coords)
+            .map(|(generator, _)| generator.finish_chunk())
+    }
+}
+
+impl<G: ChunkGenerator> Default for ChunkStatusMachine<G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ChunkStatusError {
+    NotStarted(Coordinate),
+    /// The chunk has already gone through every pass.
+    Finished,
+    OutOfOrder {
+        current: ChunkStatus,
+        requested: ChunkStatus,
+    },
+    NeighborNotReady {
+        neighbor: Coordinate,
+        required: ChunkStatus,
+        found: Option<ChunkStatus>,
+    },
+}
+
+impl Display for ChunkStatusError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkStatusError::NotStarted(coords) =>
+                write!(f, "Chunk at {coords} has not started generating"),
+            ChunkStatusError::Finished => write!(f, "Chunk has already finished generating"),
+            ChunkStatusError::OutOfOrder { current, requested } => write!(
+                f,
+                "Cannot run {requested:?} on a chunk at status {current:?}"
+            ),
+            ChunkStatusError::NeighborNotReady {
+                neighbor,
+                required,
+                found,
+            } => write!(
+                f,
+                "Neighbor at {neighbor} must be at status {required:?}, found {found:?}"
+            ),
+        }
+    }
+}
+
+impl Error for ChunkStatusError {}
+
+/// A generator which leaves chunks empty.
+#[cfg(test)]
+struct PassGenerator(Coordinate);
+
+#[cfg(test)]
+impl ChunkGenerator for PassGenerator {
+    fn start_chunk(coords: Coordinate) -> Self {
+        PassGenerator(coords)
+    }
+
+    fn shape_chunk(&mut self) {}
+
+    fn finish_chunk(self) -> Chunk {
+        super::ProtoChunk::new(self.0).into()
+    }
+}
+
+#[test]
+fn features_require_biomes() {
+    let mut machine = ChunkStatusMachine::<PassGenerator>::new();
+    for x in -1 ..= 1 {
+        for z in -1 ..= 1 {
+            let coords = Coordinate::chunk(x, z);
+            machine.start(coords);
+            machine.run(coords, ChunkStatus::Shaping).unwrap();
+        }
+    }
+
+    let center = Coordinate::chunk(0, 0);
+    assert_eq!(
+        machine.run(center, ChunkStatus::Features),
+        Err(ChunkStatusError::OutOfOrder {
+            current: ChunkStatus::Shaping,
+            requested: ChunkStatus::Features
+        })
+    );
+
+    machine.run(center, ChunkStatus::Biomes).unwrap();
+    assert!(matches!(
+        machine.run(center, ChunkStatus::Features),
+        Err(ChunkStatusError::NeighborNotReady {
+            required: ChunkStatus::Biomes,
+            found: Some(ChunkStatus::Shaping),
+            ..
+        })
+    ));
+
+    for x in -1 ..= 1 {
+        for z in -1 ..= 1 {
+            if x != 0 || z != 0 {
+                machine.advance(Coordinate::chunk(x, z)).unwrap();
+            }
+        }
+    }
+    assert_eq!(machine.advance(center), Ok(ChunkStatus::Features));
+}
@@ -0,0 +1,567 @@
+use std::collections::HashMap;
+
+use qdat::{
+    world::location::{BlockPosition, Coordinate},
+    UlnStr,
+    UnlocalizedName,
+};
+use quartz_datapack::data::{
+    structure::Structure,
+    structure_set::{SpreadType, StructurePlacementModifier},
+};
+
+use super::{
+    random::{LegacyRandom, RandomSource},
+    ProtoChunk,
+};
+use crate::{
+    block::{BlockStateImpl, StateBuilder},
+    world::chunk::ChunkDecodeError,
+    BlockState,
+    StateID,
+};
+
+/// Places at most one structure in each square region of chunks, at a random chunk within that
+/// region, the same way vanilla's `random_spread` placement does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RandomSpreadPlacement {
+    /// The width of a region in chunks.
+    pub spacing: i32,
+    /// The minimum number of chunks between structures in neighboring regions.
+    pub separation: i32,
+    /// Added to the seed so that different structure sets pick different chunks.
+    pub salt: i32,
+    pub spread_type: SpreadType,
+}
+
+impl RandomSpreadPlacement {
+    /// Creates a placement from the given structure set placement, returning `None` if it is not a
+    /// random spread placement or its separation is not smaller than its spacing, as vanilla
+    /// rejects.
+    pub fn from_modifier(modifier: &StructurePlacementModifier) -> Option<Self> {
+        match *modifier {
+            StructurePlacementModifier::RandomSpread {
+                spread_type,
+                spacing,
+                separation,
+                salt,
+                ..
+            } if separation < spacing => Some(RandomSpreadPlacement {
+                spacing: spacing as i32,
+                separation: separation as i32,
+                salt: salt as i32,
+                spread_type: spread_type.unwrap_or(SpreadType::Linear),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the chunk at which a structure starts in the region containing the given chunk.
+    pub fn potential_chunk(&self, world_seed: i64, chunk_x: i32, chunk_z: i32) -> Coordinate {
+        let region_x = chunk_x.div_euclid(self.spacing);
+        let region_z = chunk_z.div_euclid(self.spacing);
+
+        let mut random = LegacyRandom::new(
+            (region_x as i64)
+                .wrapping_mul(341873128712)
+                .wrapping_add((region_z as i64).wrapping_mul(132897987541))
+                .wrapping_add(world_seed)
+                .wrapping_add(self.salt as i64),
+        );
+
+        // The offsets have to be generated in this order to match vanilla
+        let range = self.spacing - self.separation;
+        let offset_x = self.offset(&mut random, range);
+        let offset_z = self.offset(&mut random, range);
+
+        Coordinate::chunk(
+            region_x * self.spacing + offset_x,
+            region_z * self.spacing + offset_z,
+        )
+    }
+
+    /// Returns whether a structure starts in the given chunk.
+    pub fn is_placement_chunk(&self, world_seed: i64, chunk_x: i32, chunk_z: i32) -> bool {
+        self.potential_chunk(world_seed, chunk_x, chunk_z) == Coordinate::chunk(chunk_x, chunk_z)
+    }
+
+    fn offset(&self, random: &mut LegacyRandom, range: i32) -> i32 {
+        match self.spread_type {
+            SpreadType::Linear => random.next_int_bounded(range),
+            SpreadType::Triangular =>
+                (random.next_int_bounded(range) + random.next_int_bounded(range)) / 2,
+        }
+    }
+}
+
+/// A rotation applied to a structure around its origin, clockwise when looking down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rotation {
+    None,
+    Clockwise90,
+    Clockwise180,
+    CounterClockwise90,
+}
+
+impl Rotation {
+    /// Returns the number of clockwise quarter turns this rotation makes.
+    const fn quarter_turns(self) -> usize {
+        match self {
+            Rotation::None => 0,
+            Rotation::Clockwise90 => 1,
+            Rotation::Clockwise180 => 2,
+            Rotation::CounterClockwise90 => 3,
+        }
+    }
+
+    /// Rotates a position relative to the structure's origin.
+    const fn transform(self, [x, y, z]: [i32; 3]) -> [i32; 3] {
+        match self {
+            Rotation::None => [x, y, z],
+            Rotation::Clockwise90 => [-z, y, x],
+            Rotation::Clockwise180 => [-x, y, -z],
+            Rotation::CounterClockwise90 => [z, y, -x],
+        }
+    }
+}
+
+/// A mirror applied to a structure before it is rotated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mirror {
+    None,
+    /// Flips the structure along the z axis.
+    LeftRight,
+    /// Flips the structure along the x axis.
+    FrontBack,
+}
+
+impl Mirror {
+    /// Mirrors a position relative to the structure's origin.
+    const fn transform(self, [x, y, z]: [i32; 3]) -> [i32; 3] {
+        match self {
+            Mirror::None => [x, y, z],
+            Mirror::LeftRight => [x, y, -z],
+            Mirror::FrontBack => [-x, y, z],
+        }
+    }
+
+    /// Returns the horizontal direction the given direction points in once mirrored.
+    fn direction(self, direction: &str) -> &str {
+        match (self, direction) {
+            (Mirror::LeftRight, "north") => "south",
+            (Mirror::LeftRight, "south") => "north",
+            (Mirror::FrontBack, "east") => "west",
+            (Mirror::FrontBack, "west") => "east",
+            _ => direction,
+        }
+    }
+}
+
+/// The horizontal directions in clockwise order.
+const HORIZONTAL_DIRECTIONS: [&str; 4] = ["north", "east", "south", "west"];
+
+/// Rotates a horizontal direction, leaving up and down unchanged.
+fn rotate_direction(direction: &str, rotation: Rotation) -> &str {
+    match HORIZONTAL_DIRECTIONS
+        .iter()
+        .position(|&horizontal| horizontal == direction)
+    {
+        Some(index) => HORIZONTAL_DIRECTIONS[(index + rotation.quarter_turns()) % 4],
+        None => direction,
+    }
+}
+
+/// Mirrors then rotates the direction-bearing properties of a block state, such as the facing of
+/// stairs, the axis of logs and the rotation of signs.
+pub fn transform_properties(
+    properties: &HashMap<String, String>,
+    rotation: Rotation,
+    mirror: Mirror,
+) -> HashMap<String, String> {
+    let mut transformed = HashMap::with_capacity(properties.len());
+    let facing_flipped = properties
+        .get("facing")
+        .map(|facing| mirror.direction(facing) != facing)
+        .unwrap_or(false);
+
+    for (name, value) in properties {
+        let (name, value) = match name.as_str() {
+            "facing" => (
+                name.clone(),
+                rotate_direction(mirror.direction(value), rotation),
+            ),
+            // Stairs which get flipped also swap which side their corner is on
+            "shape" if facing_flipped => (name.clone(), match value.as_str() {
+                "inner_left" => "inner_right",
+                "inner_right" => "inner_left",
+                "outer_left" => "outer_right",
+                "outer_right" => "outer_left",
+                value => value,
+            }),
+            "axis" if rotation.quarter_turns() % 2 == 1 => (name.clone(), match value.as_str() {
+                "x" => "z",
+                "z" => "x",
+                value => value,
+            }),
+            "rotation" => match value.parse::<i32>() {
+                Ok(angle) => {
+                    // Mirrored like vanilla, which first moves the angle into the range -7..=8
+                    let signed = if angle > 8 { angle - 16 } else { angle };
+                    let angle = match mirror {
+                        Mirror::None => angle,
+                        Mirror::LeftRight => (8 - signed + 16) % 16,
+                        Mirror::FrontBack => (16 - signed) % 16,
+                    };
+                    let angle = (angle + rotation.quarter_turns() as i32 * 4).rem_euclid(16);
+                    transformed.insert(name.clone(), angle.to_string());
+                    continue;
+                }
+                Err(_) => (name.clone(), value.as_str()),
+            },
+            // Connections to neighboring blocks, as on fences and walls
+            direction if HORIZONTAL_DIRECTIONS.contains(&direction) => (
+                rotate_direction(mirror.direction(direction), rotation).to_owned(),
+                value.as_str(),
+            ),
+            _ => (name.clone(), value.as_str()),
+        };
+
+        transformed.insert(name, value.to_owned());
+    }
+
+    transformed
+}
+
+/// A block in a structure's palette.
+#[derive(Clone, Debug)]
+pub struct TemplateState {
+    pub name: UnlocalizedName,
+    pub properties: HashMap<String, String>,
+}
+
+impl TemplateState {
+    /// Resolves this palette entry to a block state.
+    pub fn resolve(&self) -> Result<StateID, ChunkDecodeError> {
+        Self::resolve_with(&self.name, &self.properties)
+    }
+
+    /// Resolves this palette entry to a block state after mirroring and rotating its properties.
+    pub fn resolve_transformed(
+        &self,
+        rotation: Rotation,
+        mirror: Mirror,
+    ) -> Result<StateID, ChunkDecodeError> {
+        Self::resolve_with(
+            &self.name,
+            &transform_properties(&self.properties, rotation, mirror),
+        )
+    }
+
+    /// Resolves a block name and the values of its properties to a block state.
+    pub(crate) fn resolve_with(
+        name: &UlnStr,
+        properties: &HashMap<String, String>,
+    ) -> Result<StateID, ChunkDecodeError> {
+        let mut state = BlockState::builder(name)
+            .ok_or_else(|| ChunkDecodeError::UnknownBlockState(name.to_owned()))?;
+
+        for (name, value) in properties {
+            state
+                .add_property(name, value)
+                .map_err(ChunkDecodeError::UnknownStateProperty)?;
+        }
+
+        Ok(state.build().id())
+    }
+}
+
+/// A structure read from a datapack, ready to be placed in chunks.
+#[derive(Clone, Debug)]
+pub struct StructureTemplate {
+    /// The size of the structure along each axis.
+    pub size: [i32; 3],
+    pub palette: Vec<TemplateState>,
+    /// Each block of the structure as its position relative to the structure's origin and its
+    /// index in the palette.
+    pub blocks: Vec<([i32; 3], usize)>,
+}
+
+impl StructureTemplate {
+    /// Reads the palette of a structure read from a datapack, making sure every entry is a valid
+    /// block state.
+    pub fn from_structure(structure: &Structure) -> Result<Self, ChunkDecodeError> {
+        let palette = structure
+            .palette
+            .iter()
+            .flatten()
+            .map(|entry| {
+                let state = TemplateState {
+                    name: entry.name.clone(),
+                    properties: entry.properties.clone(),
+                };
+                state.resolve().map(|_| state)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let blocks = structure
+            .blocks
+            .iter()
+            .filter(|block| (block.state as usize) < palette.len())
+            .filter_map(|block| match block.pos[..] {
+                [x, y, z] => Some(([x, y, z], block.state as usize)),
+                _ => None,
+            })
+            .collect();
+
+        let size = match structure.size[..] {
+            [x, y, z] => [x, y, z],
+            _ => [0; 3],
+        };
+
+        Ok(StructureTemplate {
+            size,
+            palette,
+            blocks,
+        })
+    }
+
+    /// Writes the blocks of this structure which fall inside the given chunk, mirroring and then
+    /// rotating the structure around its origin.
+    pub fn place(
+        &self,
+        chunk: &mut ProtoChunk,
+        origin: BlockPosition,
+        rotation: Rotation,
+        mirror: Mirror,
+    ) {
+        // A rotated property value may not exist for every block, in which case the block is
+        // placed as it is stored
+        let states = self
+            .palette
+            .iter()
+            .map(|state| {
+                state
+                    .resolve_transformed(rotation, mirror)
+                    .or_else(|_| state.resolve())
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+
+        let chunk_pos = chunk.pos.as_block();
+        for &(pos, index) in &self.blocks {
+            let state = match states[index] {
+                Some(state) => state,
+                None => continue,
+            };
+
+            let [x, y, z] = rotation.transform(mirror.transform(pos));
+            let x = origin.x + x - chunk_pos.x();
+            let z = origin.z + z - chunk_pos.z();
+            if !(0 .. 16).contains(&x) || !(0 .. 16).contains(&z) {
+                continue;
+            }
+
+            if let Ok(y) = i16::try_from(origin.y as i32 + y) {
+                chunk.set_block_state(BlockPosition { x, y, z }, state);
+            }
+        }
+    }
+}
+
+/// Stamps a structure into every chunk it overlaps, starting at the chunks chosen by a placement.
+pub struct StructurePass {
+    pub placement: RandomSpreadPlacement,
+    pub template: StructureTemplate,
+    /// The height at which the bottom of the structure is placed.
+    pub y: i16,
+}
+
+impl StructurePass {
+    /// Places the parts of any structures which overlap the given chunk.
+    pub fn place(&self, world_seed: i64, chunk: &mut ProtoChunk) {
+        let chunk_pos = chunk.pos.as_chunk();
+        // Structures start at the corner of their chunk, so only chunks to the north-west of this
+        // one can start a structure which reaches into it
+        let reach_x = (self.template.size[0] + 15) >> 4;
+        let reach_z = (self.template.size[2] + 15) >> 4;
+
+        for start_x in chunk_pos.x() - reach_x + 1 ..= chunk_pos.x() {
+            for start_z in chunk_pos.z() - reach_z + 1 ..= chunk_pos.z() {
+                if !self
+                    .placement
+                    .is_placement_chunk(world_seed, start_x, start_z)
+                {
+                    continue;
+                }
+
+                let start = Coordinate::chunk(start_x, start_z).as_block();
+                self.template.place(
+                    chunk,
+                    BlockPosition {
+                        x: start.x(),
+                        y: self.y,
+                        z: start.z(),
+                    },
+                    Rotation::None,
+                    Mirror::None,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn random_spread_matches_vanilla() {
+    let village = RandomSpreadPlacement {
+        spacing: 32,
+        separation: 8,
+        salt: 10387312,
+        spread_type: SpreadType::Linear,
+    };
+    let end_city = RandomSpreadPlacement {
+        spacing: 20,
+        separation: 11,
+        salt: 10387313,
+        spread_type: SpreadType::Triangular,
+    };
+
+    let seed = 12345;
+    for (placement, chunk, expected) in [
+        (village, (0, 0), (7, 23)),
+        (village, (-1, -1), (-14, -24)),
+        (village, (40, -70), (45, -76)),
+        (village, (-100, 33), (-114, 36)),
+        (end_city, (0, 0), (3, 1)),
+        (end_city, (-1, -1), (-13, -15)),
+        (end_city, (40, -70), (44, -76)),
+        (end_city, (-100, 33), (-93, 26)),
+    ] {
+        assert_eq!(
+            placement.potential_chunk(seed, chunk.0, chunk.1),
+            Coordinate::chunk(expected.0, expected.1)
+        );
+    }
+
+    assert!(village.is_placement_chunk(seed, 7, 23));
+    assert!(!village.is_placement_chunk(seed, 0, 0));
+
+    let modifier = |spacing, separation| StructurePlacementModifier::RandomSpread {
+        spread_type: None,
+        spacing,
+        separation,
+        salt: 10387312,
+        locate_offset: None,
+    };
+    assert_eq!(
+        RandomSpreadPlacement::from_modifier(&modifier(32, 8)),
+        Some(village)
+    );
+    assert_eq!(RandomSpreadPlacement::from_modifier(&modifier(8, 8)), None);
+    assert_eq!(RandomSpreadPlacement::from_modifier(&modifier(0, 0)), None);
+}
+
+#[test]
+fn structure_spans_chunks() {
+    let placement = RandomSpreadPlacement {
+        spacing: 32,
+        separation: 8,
+        salt: 10387312,
+        spread_type: SpreadType::Linear,
+    };
+    let pass = StructurePass {
+        placement,
+        template: StructureTemplate {
+            size: [20, 1, 1],
+            palette: vec![template_state("stone", &[]), template_state("dirt", &[])],
+            blocks: vec![([0, 0, 0], 0), ([19, 0, 0], 1)],
+        },
+        y: 70,
+    };
+
+    let stone = pass.template.palette[0].resolve().unwrap();
+    let dirt = pass.template.palette[1].resolve().unwrap();
+
+    let mut start = ProtoChunk::new(Coordinate::chunk(7, 23));
+    pass.place(12345, &mut start);
+    assert_eq!(
+        start.block_state(BlockPosition { x: 0, y: 70, z: 0 }),
+        Some(stone)
+    );
+
+    let mut neighbor = ProtoChunk::new(Coordinate::chunk(8, 23));
+    pass.place(12345, &mut neighbor);
+    assert_eq!(
+        neighbor.block_state(BlockPosition { x: 3, y: 70, z: 0 }),
+        Some(dirt)
+    );
+}
+
+#[cfg(test)]
+fn template_state(name: &str, properties: &[(&str, &str)]) -> TemplateState {
+    TemplateState {
+        name: UnlocalizedName::minecraft(name),
+        properties: properties
+            .iter()
+            .map(|&(name, value)| (name.to_owned(), value.to_owned()))
+            .collect(),
+    }
+}
+
+#[test]
+fn rotated_structure() {
+    // An L of two stairs and a log along x, and two more logs along z
+    let template = StructureTemplate {
+        size: [2, 1, 3],
+        palette: vec![
+            template_state("oak_stairs", &[
+                ("facing", "north"),
+                ("shape", "outer_left"),
+            ]),
+            template_state("oak_log", &[("axis", "x")]),
+        ],
+        blocks: vec![
+            ([0, 0, 0], 0),
+            ([1, 0, 0], 1),
+            ([0, 0, 1], 1),
+            ([0, 0, 2], 0),
+        ],
+    };
+
+    let mut chunk = ProtoChunk::new(Coordinate::chunk(0, 0));
+    let origin = BlockPosition { x: 8, y: 64, z: 8 };
+    template.place(&mut chunk, origin, Rotation::Clockwise90, Mirror::None);
+
+    let stairs = template_state("oak_stairs", &[("facing", "east"), ("shape", "outer_left")])
+        .resolve()
+        .unwrap();
+    let log = template_state("oak_log", &[("axis", "z")])
+        .resolve()
+        .unwrap();
+    for (x, z, state) in [(8, 8, stairs), (8, 9, log), (7, 8, log), (6, 8, stairs)] {
+        assert_eq!(
+            chunk.block_state(BlockPosition { x, y: 64, z }),
+            Some(state)
+        );
+    }
+
+    let mirrored = transform_properties(
+        &template.palette[0].properties,
+        Rotation::None,
+        Mirror::LeftRight,
+    );
+    assert_eq!(mirrored["facing"], "south");
+    assert_eq!(mirrored["shape"], "outer_right");
+
+    let sign = transform_properties(
+        &template_state("oak_sign", &[("rotation", "3")]).properties,
+        Rotation::CounterClockwise90,
+        Mirror::FrontBack,
+    );
+    assert_eq!(sign["rotation"], "9");
+    let sign = transform_properties(
+        &template_state("oak_sign", &[("rotation", "12")]).properties,
+        Rotation::None,
+        Mirror::LeftRight,
+    );
+    assert_eq!(sign["rotation"], "12");
+}
@@ -0,0 +1,223 @@
+use crate::{
+    block::BlockStateImpl,
+    world::chunk::pack_palette_indices,
+    BlockState,
+    Registry,
+    StateID,
+};
+use qdat::block::states::is_air;
+
+/// The number of bits used for each column when a heightmap is packed into longs, enough for every
+/// height in a 512 block tall world.
+pub const HEIGHTMAP_BITS: u32 = 9;
+
+/// The kinds of heightmap kept for every chunk, each tracking the highest block of a column which
+/// matches a different condition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HeightmapType {
+    /// The highest block which blocks motion or holds a fluid, used for mob spawning and rain.
+    MotionBlocking,
+    /// The highest block which is not air.
+    WorldSurface,
+}
+
+impl HeightmapType {
+    pub const ALL: [HeightmapType; 2] =
+        [HeightmapType::MotionBlocking, HeightmapType::WorldSurface];
+
+    /// Returns the key this heightmap is stored under in the `Heightmaps` compound.
+    pub const fn name(self) -> &'static str {
+        match self {
+            HeightmapType::MotionBlocking => "MOTION_BLOCKING",
+            HeightmapType::WorldSurface => "WORLD_SURFACE",
+        }
+    }
+
+    /// Returns whether a column's height is set by the given state.
+    pub fn is_opaque(self, state: StateID) -> bool {
+        if is_air(state) {
+            return false;
+        }
+
+        match self {
+            HeightmapType::MotionBlocking => match Registry::state_for_id(state) {
+                Some(state) => blocks_motion(state) || has_fluid(state),
+                None => false,
+            },
+            HeightmapType::WorldSurface => true,
+        }
+    }
+}
+
+/// The height of each column in a chunk, counted from the bottom of the world and stored as one
+/// above the highest matching block so that a column without any is zero.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Heightmap {
+    heights: Box<[u16; 256]>,
+}
+
+impl Heightmap {
+    pub fn new() -> Self {
+        Heightmap {
+            heights: Box::new([0; 256]),
+        }
+    }
+
+    /// Returns the height of the column at the given chunk-relative coordinates.
+    #[inline]
+    pub fn get(&self, x: usize, z: usize) -> u16 {
+        self.heights[z * 16 + x]
+    }
+
+    #[inline]
+    pub fn set(&mut self, x: usize, z: usize, height: u16) {
+        self.heights[z * 16 + x] = height;
+    }
+
+    /// Packs the heights into a long array the way vanilla stores them, nine bits per column
+    /// without spanning longs.
+    pub fn to_packed(&self) -> Vec<i64> {
+        let heights = self
+            .heights
+            .iter()
+            .map(|&height| height as usize)
+            .collect::<Vec<_>>();
+        pack_palette_indices(&heights, HEIGHTMAP_BITS)
+    }
+}
+
+impl Default for Heightmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Blocks which can be walked through, so they don't stop motion, grouped like vanilla's
+/// non-solid materials.
+const PASSABLE_BLOCKS: &[&str] = &[
+    // Plants
+    "grass",
+    "fern",
+    "dead_bush",
+    "tall_grass",
+    "large_fern",
+    "dandelion",
+    "poppy",
+    "blue_orchid",
+    "allium",
+    "azure_bluet",
+    "red_tulip",
+    "orange_tulip",
+    "white_tulip",
+    "pink_tulip",
+    "oxeye_daisy",
+    "cornflower",
+    "lily_of_the_valley",
+    "wither_rose",
+    "sunflower",
+    "lilac",
+    "rose_bush",
+    "peony",
+    "brown_mushroom",
+    "red_mushroom",
+    "crimson_fungus",
+    "warped_fungus",
+    "crimson_roots",
+    "warped_roots",
+    "nether_sprouts",
+    "wheat",
+    "carrots",
+    "potatoes",
+    "beetroots",
+    "melon_stem",
+    "pumpkin_stem",
+    "attached_melon_stem",
+    "attached_pumpkin_stem",
+    "sugar_cane",
+    "sweet_berry_bush",
+    "nether_wart",
+    "vine",
+    "glow_lichen",
+    "weeping_vines",
+    "weeping_vines_plant",
+    "twisting_vines",
+    "twisting_vines_plant",
+    "cave_vines",
+    "cave_vines_plant",
+    "hanging_roots",
+    "spore_blossom",
+    "small_dripleaf",
+    "seagrass",
+    "tall_seagrass",
+    "kelp",
+    "kelp_plant",
+    // Decorations
+    "torch",
+    "wall_torch",
+    "soul_torch",
+    "soul_wall_torch",
+    "redstone_torch",
+    "redstone_wall_torch",
+    "redstone_wire",
+    "lever",
+    "repeater",
+    "comparator",
+    "tripwire",
+    "tripwire_hook",
+    "ladder",
+    "rail",
+    "powered_rail",
+    "detector_rail",
+    "activator_rail",
+    "flower_pot",
+    "moss_carpet",
+    // Other non-solid blocks
+    "snow",
+    "fire",
+    "soul_fire",
+    "cobweb",
+    "nether_portal",
+    "end_portal",
+    "end_gateway",
+    "light",
+    "structure_void",
+    "bubble_column",
+    "water",
+    "lava",
+];
+
+/// Returns whether entities are stopped by the given block state.
+pub fn blocks_motion(state: &BlockState) -> bool {
+    if is_air(state.id()) {
+        return false;
+    }
+
+    let name = state.handle.name.identifier();
+    !(PASSABLE_BLOCKS.contains(&name)
+        || name.ends_with("_sapling")
+        || name.ends_with("_carpet")
+        || name.ends_with("_button")
+        || name.starts_with("potted_")
+        || is_living_coral(name))
+}
+
+/// Coral plants and fans are underwater plants, but once dead they are solid like stone.
+fn is_living_coral(name: &str) -> bool {
+    !name.starts_with("dead_")
+        && (name.ends_with("_coral")
+            || name.ends_with("_coral_fan")
+            || name.ends_with("_coral_wall_fan"))
+}
+
+/// Returns whether the given block state holds water or lava, either being the fluid itself, a
+/// plant which always grows under water, or a waterlogged block.
+pub fn has_fluid(state: &BlockState) -> bool {
+    match state.handle.name.identifier() {
+        "water" | "lava" | "bubble_column" | "kelp" | "kelp_plant" | "seagrass"
+        | "tall_seagrass" => true,
+        _ => state
+            .handle
+            .state_properties(state.id())
+            .is_some_and(|properties| properties.contains(&("waterlogged", "true"))),
+    }
+}
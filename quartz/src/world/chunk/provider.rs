@@ -5,7 +5,28 @@ use crate::{
         ClientBoundPacket,
         WrappedClientBoundPacket,
     },
-    world::chunk::{chunk::RawChunk, gen::ChunkGenerator, Chunk, ChunkDecodeError, RawClientChunk},
+    world::chunk::{
+        chunk::RawChunk,
+        gen::ChunkGenerator,
+        region::{
+            check_chunk_length,
+            compress_chunk,
+            decompress_chunk,
+            external_chunk_path,
+            is_external_chunk,
+            resolve_external_chunk,
+            sectors_for_chunk,
+            ChunkCompression,
+            EXTERNAL_CHUNK_FLAG,
+            SECTOR_SIZE,
+        },
+        Chunk,
+        ChunkDecodeError,
+        ChunkTicket,
+        DataVersion,
+        RawClientChunk,
+        TicketQueue,
+    },
 };
 use byteorder::{BigEndian, ByteOrder};
 use dashmap::{
@@ -15,53 +36,70 @@ use dashmap::{
     },
     DashMap,
 };
-use flate2::write::{GzDecoder, ZlibDecoder};
 use futures_util::{poll, stream::FuturesUnordered, StreamExt};
 use log::{error, warn};
 use qdat::world::location::{Coordinate, CoordinatePair};
-use quartz_nbt::serde::deserialize_from_buffer;
+use quartz_nbt::{
+    io::{self as nbt_io, Flavor},
+    serde::deserialize_from_buffer,
+    NbtCompound,
+};
 use quartz_util::hash::NumHasher;
-use serde::Deserialize;
 use std::{
+    collections::HashMap,
     convert,
     fmt::{self, Display, Formatter},
     future::Future,
-    io::{self, Error as IoError, Write},
+    io::{self, Cursor, Error as IoError},
     marker::PhantomData,
     path::{Path, PathBuf},
     sync::Arc,
     task::Poll,
+    time::{SystemTime, UNIX_EPOCH},
 };
 use tokio::{
-    fs::{File, OpenOptions},
-    io::{AsyncReadExt, AsyncSeekExt, SeekFrom},
+    fs::OpenOptions,
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom},
     runtime::Runtime,
     sync::Mutex,
     task::{JoinError, JoinHandle},
 };
 
+/// The maximum number of ticketed loads which are handed to the runtime at once. Any other ticketed
+/// loads wait in the ticket queue so that higher priority loads can be served first.
+const MAX_TICKETED_LOADS: usize = 32;
+
 pub struct ChunkProvider<T: ChunkGenerator + 'static> {
     pub store: Arc<RegionHandler>,
     rt: Arc<Runtime>,
     pending: FuturesUnordered<JoinHandle<Result<ProviderResponse, ProviderError>>>,
+    queue: TicketQueue,
+    tickets: HashMap<CoordinatePair, ChunkTicket, NumHasher>,
     __generator: PhantomData<T>,
 }
 
 impl<T: ChunkGenerator + 'static> ChunkProvider<T> {
     /// Creates a chunk provider for the given root directory with the given number of threads.
-    pub fn new<P: AsRef<Path>>(rt: Arc<Runtime>, root_directory: P) -> io::Result<Self> {
+    /// Chunks are written to region files with the given compression.
+    pub fn new<P: AsRef<Path>>(
+        rt: Arc<Runtime>,
+        root_directory: P,
+        compression: ChunkCompression,
+    ) -> io::Result<Self> {
         let root_directory = root_directory.as_ref();
 
         // Ensure the root directory exists
         std::fs::create_dir_all(root_directory)?;
 
-        let store = Arc::new(RegionHandler::new(root_directory.to_owned()));
+        let store = Arc::new(RegionHandler::new(root_directory.to_owned(), compression));
         let pending = FuturesUnordered::new();
 
         Ok(ChunkProvider {
             store,
             rt,
             pending,
+            queue: TicketQueue::new(),
+            tickets: HashMap::with_hasher(NumHasher),
             __generator: PhantomData,
         })
     }
@@ -72,20 +110,54 @@ impl<T: ChunkGenerator + 'static> ChunkProvider<T> {
         self.pending.push(fut);
     }
 
+    /// Queues a full load of the chunk at the given coordinates. Loads are started in order of
+    /// their ticket's priority, and the ticket determines whether the chunk stays loaded. If the
+    /// chunk already has a pending load, then its ticket is upgraded if the new ticket keeps the
+    /// chunk loaded, and the load is moved up the queue if it hasn't started yet.
+    pub fn request_load_with_priority(&mut self, coords: Coordinate, ticket: ChunkTicket) {
+        match self.tickets.get_mut(&coords.as_chunk().into()) {
+            Some(existing) =>
+                if ticket.keeps_loaded() {
+                    *existing = ticket;
+                    self.queue.upgrade(coords, ticket);
+                },
+            None => {
+                self.tickets.insert(coords.as_chunk().into(), ticket);
+                self.queue.push(coords, ticket);
+            }
+        }
+    }
+
     pub async fn flush_ready(&mut self) {
         while let Poll::Ready(Some(task_result)) = poll!(self.pending.next()) {
             self.handle_task_result(task_result);
         }
+
+        self.dispatch_queued();
     }
 
     pub async fn join_pending(&mut self) {
-        while let Some(task_result) = self.pending.next().await {
-            self.handle_task_result(task_result);
+        loop {
+            self.dispatch_queued();
+
+            match self.pending.next().await {
+                Some(task_result) => self.handle_task_result(task_result),
+                None => break,
+            }
+        }
+    }
+
+    fn dispatch_queued(&mut self) {
+        while self.pending.len() < MAX_TICKETED_LOADS {
+            match self.queue.pop() {
+                Some((coords, _)) => self.request(ProviderRequest::LoadFull(coords)),
+                None => break,
+            }
         }
     }
 
     fn handle_task_result(
-        &self,
+        &mut self,
         task_result: Result<Result<ProviderResponse, ProviderError>, JoinError>,
     ) {
         let result = match task_result {
@@ -106,10 +178,16 @@ impl<T: ChunkGenerator + 'static> ChunkProvider<T> {
         };
 
         match response {
-            ProviderResponse::LoadedChunk(chunk) =>
+            ProviderResponse::LoadedChunk(coords, chunk) => {
+                let ticket = self.tickets.remove(&coords.as_chunk().into());
+
+                // Chunks loaded for generation are evicted right away
                 if let Some(chunk) = chunk {
-                    self.store.cache_chunk(chunk);
-                },
+                    if ticket.map(ChunkTicket::keeps_loaded).unwrap_or(true) {
+                        self.store.cache_chunk(chunk);
+                    }
+                }
+            }
             ProviderResponse::UnloadedChunk => {
                 // TODO: handle anything here if necessary
             }
@@ -126,7 +204,7 @@ impl<T: ChunkGenerator + 'static> ChunkProvider<T> {
         match request {
             ProviderRequest::LoadFull(coords) => Self::handle_load_full(coords, store)
                 .await
-                .map(ProviderResponse::LoadedChunk)
+                .map(|chunk| ProviderResponse::LoadedChunk(coords, chunk))
                 .map_err(|error| ProviderError::new(request, error)),
 
             ProviderRequest::MinLoadSend { coords, ref handle } =>
@@ -135,10 +213,10 @@ impl<T: ChunkGenerator + 'static> ChunkProvider<T> {
                     .map(|_| ProviderResponse::Ok)
                     .map_err(|error| ProviderError::new(request, error)),
 
-            ProviderRequest::Unload(coords) => {
-                Self::handle_unload(coords, store).await;
-                Ok(ProviderResponse::UnloadedChunk)
-            }
+            ProviderRequest::Unload(coords) => Self::handle_unload(coords, store)
+                .await
+                .map(|_| ProviderResponse::UnloadedChunk)
+                .map_err(|error| ProviderError::new(request, error)),
         }
     }
 
@@ -172,10 +250,13 @@ impl<T: ChunkGenerator + 'static> ChunkProvider<T> {
 
         match chunk_nbt {
             Some(chunk_nbt) => {
-                let chunk_nbt = chunk_nbt.await?;
-                Self::decode_chunk::<RawChunk, _, _>(chunk_nbt, Chunk::from)
-                    .await
-                    .map(Some)
+                let (decompressed, nbt) = read_chunk_nbt(&chunk_nbt.await?)?;
+                if DataVersion::from_nbt(&nbt)?.has_root_sections() {
+                    Chunk::from_anvil_nbt(&nbt).map(Some)
+                } else {
+                    let (raw, _) = deserialize_from_buffer::<RawChunk>(&decompressed)?;
+                    Ok(Some(raw.into()))
+                }
             }
             None => Ok(Some(Self::handle_chunk_gen(coords).await)),
         }
@@ -198,39 +279,7 @@ impl<T: ChunkGenerator + 'static> ChunkProvider<T> {
 
         // If it's already loaded we can just send it
         if let Some(chunk) = store.loaded_chunk_at(coords) {
-            let (primary_bit_mask, section_data) = chunk.gen_client_section_data();
-
-            packets.push(WrappedClientBoundPacket::Singleton(
-                ClientBoundPacket::ChunkData {
-                    chunk_x,
-                    chunk_z,
-                    primary_bit_mask,
-                    heightmaps: chunk.get_heightmaps(),
-                    biomes: Box::from(chunk.biomes()),
-                    // TODO: send block entities for chunk when we support them
-                    block_entities: vec![].into_boxed_slice(),
-                    data: section_data,
-                },
-            ));
-
-            let (sky_light_mask, empty_sky_light_mask, sky_light_arrays) = chunk.gen_sky_lights();
-            let (block_light_mask, empty_block_light_mask, block_light_arrays) =
-                chunk.gen_block_lights();
-
-            packets.push(WrappedClientBoundPacket::Singleton(
-                ClientBoundPacket::UpdateLight {
-                    chunk_x,
-                    chunk_z,
-                    trust_edges: true,
-                    sky_light_mask,
-                    block_light_mask,
-                    empty_sky_light_mask,
-                    empty_block_light_mask,
-                    sky_light_arrays,
-                    block_light_arrays,
-                },
-            ));
-
+            packets.extend(chunk_packets(&chunk));
             drop(chunk);
             handle.send_all(packets);
             return Ok(());
@@ -247,10 +296,15 @@ impl<T: ChunkGenerator + 'static> ChunkProvider<T> {
 
         match chunk_nbt {
             Some(chunk_nbt) => {
-                let chunk_nbt = chunk_nbt.await?;
-                let chunk =
-                    Self::decode_chunk(chunk_nbt, |raw_chunk: RawClientChunk| raw_chunk.level)
-                        .await?;
+                let (decompressed, nbt) = read_chunk_nbt(&chunk_nbt.await?)?;
+                if DataVersion::from_nbt(&nbt)?.has_root_sections() {
+                    packets.extend(chunk_packets(&Chunk::from_anvil_nbt(&nbt)?));
+                    handle.send_all(packets);
+                    return Ok(());
+                }
+
+                let (raw_chunk, _) = deserialize_from_buffer::<RawClientChunk>(&decompressed)?;
+                let chunk = raw_chunk.level;
 
                 let SectionAndLightData {
                     primary_bit_mask,
@@ -296,40 +350,7 @@ impl<T: ChunkGenerator + 'static> ChunkProvider<T> {
             }
             None => {
                 let chunk = Self::handle_chunk_gen(coords).await;
-
-                let (primary_bit_mask, section_data) = chunk.gen_client_section_data();
-
-                packets.push(WrappedClientBoundPacket::Singleton(
-                    ClientBoundPacket::ChunkData {
-                        chunk_x,
-                        chunk_z,
-                        primary_bit_mask,
-                        heightmaps: chunk.get_heightmaps(),
-                        biomes: Box::from(chunk.biomes()),
-                        // TODO: send block entities for chunk when we support them
-                        block_entities: vec![].into_boxed_slice(),
-                        data: section_data,
-                    },
-                ));
-
-                let (sky_light_mask, empty_sky_light_mask, sky_light_arrays) =
-                    chunk.gen_sky_lights();
-                let (block_light_mask, empty_block_light_mask, block_light_arrays) =
-                    chunk.gen_block_lights();
-
-                packets.push(WrappedClientBoundPacket::Singleton(
-                    ClientBoundPacket::UpdateLight {
-                        chunk_x,
-                        chunk_z,
-                        trust_edges: true,
-                        sky_light_mask,
-                        block_light_mask,
-                        empty_sky_light_mask,
-                        empty_block_light_mask,
-                        sky_light_arrays,
-                        block_light_arrays,
-                    },
-                ));
+                packets.extend(chunk_packets(&chunk));
             }
         }
 
@@ -337,54 +358,51 @@ impl<T: ChunkGenerator + 'static> ChunkProvider<T> {
         Ok(())
     }
 
-    async fn decode_chunk<D, R, F>(chunk_nbt: Vec<u8>, f: F) -> Result<R, ChunkDecodeError>
-    where
-        for<'a> D: Deserialize<'a>,
-        F: FnOnce(D) -> R,
-    {
-        let mut decompressed = Vec::new();
-
-        match chunk_nbt[0] {
-            2 => {
-                let mut decoder = ZlibDecoder::new(decompressed);
-                decoder.write_all(&chunk_nbt[1 ..])?;
-                decompressed = decoder.finish()?;
-            }
-            // GZip compression (not used in practice)
-            1 => {
-                let mut decoder = GzDecoder::new(decompressed);
-                decoder.write_all(&chunk_nbt[1 ..])?;
-                decompressed = decoder.finish()?;
+    async fn handle_unload(
+        coords: Coordinate,
+        store: Arc<RegionHandler>,
+    ) -> Result<(), ProviderErrorType> {
+        // Take a snapshot of the chunk if it changed, so the chunk isn't locked while it's written
+        let chunk_data = match store.loaded_chunk_at_mut(coords) {
+            Some(mut chunk) if chunk.is_dirty() => {
+                let mut nbt_data = Vec::new();
+                nbt_io::write_nbt(&mut nbt_data, None, &chunk.to_nbt(), Flavor::Uncompressed)
+                    .map_err(|error| IoError::new(io::ErrorKind::InvalidData, error))?;
+                let chunk_data = compress_chunk(&nbt_data, store.compression)?;
+                chunk.mark_saved();
+                Some(chunk_data)
             }
-            _ => return Err(ChunkDecodeError::UnknownCompression(chunk_nbt[0])),
-        }
-
-        let (raw, _) = deserialize_from_buffer::<D>(&decompressed)?;
-        Ok(f(raw))
-    }
+            _ => None,
+        };
 
-    async fn handle_unload(coords: Coordinate, store: Arc<RegionHandler>) {
         let mut region = match store.loaded_region_at_mut(coords) {
             Some(region) => region,
-            None => return,
+            None => return Ok(()),
         };
 
+        if let Some(chunk_data) = chunk_data {
+            let write = region.write_chunk(coords, chunk_data)?;
+            drop(region);
+            write.await?;
+
+            region = match store.loaded_region_at_mut(coords) {
+                Some(region) => region,
+                None => return Ok(()),
+            };
+        }
+
         // Mark the cached chunk data as inactive so that the region can potentially be unloaded
         region.mark_chunk_inactive(coords);
 
         if region.has_loaded_chunks() {
-            return;
+            return Ok(());
         }
 
-        // We can unload the region since it has no more loaded chunks
-
+        // We can unload the region since it has no more loaded chunks, and any changed chunks were
+        // already written above
         drop(region);
-        let _region = match store.remove_region(coords) {
-            Some(region) => region,
-            None => return,
-        };
-
-        // TODO: write region to disk
+        store.remove_region(coords);
+        Ok(())
     }
 
     async fn handle_chunk_gen(coords: Coordinate) -> Chunk {
@@ -394,6 +412,50 @@ impl<T: ChunkGenerator + 'static> ChunkProvider<T> {
     }
 }
 
+/// Decompresses chunk data read from a region file, returning the uncompressed NBT along with the
+/// parsed compound. The compound's data version tells whether the chunk was saved in the layout
+/// used since 1.18 or in the older one with a `Level` compound.
+fn read_chunk_nbt(chunk_data: &[u8]) -> Result<(Vec<u8>, NbtCompound), ChunkDecodeError> {
+    let decompressed = decompress_chunk(chunk_data)?;
+    let (nbt, _) = nbt_io::read_nbt(&mut Cursor::new(&decompressed), Flavor::Uncompressed)?;
+    Ok((decompressed, nbt))
+}
+
+/// Creates the chunk data and light packets which send the given chunk to a client.
+fn chunk_packets(chunk: &Chunk) -> [WrappedClientBoundPacket; 2] {
+    let CoordinatePair {
+        x: chunk_x,
+        z: chunk_z,
+    } = chunk.coordinates().as_chunk().into();
+    let (primary_bit_mask, section_data) = chunk.gen_client_section_data();
+    let (sky_light_mask, empty_sky_light_mask, sky_light_arrays) = chunk.gen_sky_lights();
+    let (block_light_mask, empty_block_light_mask, block_light_arrays) = chunk.gen_block_lights();
+
+    [
+        WrappedClientBoundPacket::Singleton(ClientBoundPacket::ChunkData {
+            chunk_x,
+            chunk_z,
+            primary_bit_mask,
+            heightmaps: chunk.get_heightmaps(),
+            biomes: chunk.biome_ids(),
+            // TODO: send block entities for chunk when we support them
+            block_entities: vec![].into_boxed_slice(),
+            data: section_data,
+        }),
+        WrappedClientBoundPacket::Singleton(ClientBoundPacket::UpdateLight {
+            chunk_x,
+            chunk_z,
+            trust_edges: true,
+            sky_light_mask,
+            block_light_mask,
+            empty_sky_light_mask,
+            empty_block_light_mask,
+            sky_light_arrays,
+            block_light_arrays,
+        }),
+    ]
+}
+
 #[derive(Clone, Debug)]
 pub enum ProviderRequest {
     LoadFull(Coordinate),
@@ -405,7 +467,7 @@ pub enum ProviderRequest {
 }
 
 enum ProviderResponse {
-    LoadedChunk(Option<Chunk>),
+    LoadedChunk(Coordinate, Option<Chunk>),
     // TODO: add more semantic information
     UnloadedChunk,
     Ok,
@@ -432,6 +494,7 @@ impl Display for ProviderError {
 
         match &self.error {
             ProviderErrorType::ChunkDecode(error) => Display::fmt(error, f),
+            ProviderErrorType::ChunkSave(error) => write!(f, "Failed to save chunk: {error}"),
         }
     }
 }
@@ -439,6 +502,7 @@ impl Display for ProviderError {
 #[derive(Debug)]
 pub enum ProviderErrorType {
     ChunkDecode(ChunkDecodeError),
+    ChunkSave(IoError),
 }
 
 impl From<ChunkDecodeError> for ProviderErrorType {
@@ -447,6 +511,12 @@ impl From<ChunkDecodeError> for ProviderErrorType {
     }
 }
 
+impl From<IoError> for ProviderErrorType {
+    fn from(error: IoError) -> Self {
+        Self::ChunkSave(error)
+    }
+}
+
 pub type Map<T> = DashMap<CoordinatePair, T, NumHasher>;
 pub type MapRef<'a, T> = Ref<'a, CoordinatePair, T, NumHasher>;
 pub type MapRefMut<'a, T> = RefMut<'a, CoordinatePair, T, NumHasher>;
@@ -460,15 +530,18 @@ pub struct RegionHandler {
     // gain exclusive access for the specific operation of inserting a region into the region map.
     // Correct usage of this mutex also ensures that we do not double-load a region.
     load_region: Mutex<()>,
+    /// The compression used for chunks written to region files.
+    compression: ChunkCompression,
 }
 
 impl RegionHandler {
-    fn new(root_directory: PathBuf) -> Self {
+    fn new(root_directory: PathBuf, compression: ChunkCompression) -> Self {
         RegionHandler {
             regions: Map::with_hasher(NumHasher),
             chunks: Map::with_hasher(NumHasher),
             root_directory,
             load_region: Mutex::new(()),
+            compression,
         }
     }
 
@@ -586,7 +659,10 @@ impl RegionHandler {
         match region.chunk_info_at_mut(coords) {
             Some(chunk_info) => {
                 chunk_info.cache_inhabited = true;
-                chunk_info.cache_active = true;
+                // The region counts active chunks so it knows when it can be unloaded
+                if !std::mem::replace(&mut chunk_info.cache_active, true) {
+                    region.loaded_count += 1;
+                }
             }
             None => {
                 // We explicitly panic here because this is a serious bug
@@ -607,10 +683,14 @@ impl RegionHandler {
 }
 
 pub struct Region {
-    file: Arc<Mutex<File>>,
+    file: Arc<Mutex<tokio::fs::File>>,
+    /// The directory containing the region file, where oversized chunks are stored.
+    directory: PathBuf,
     chunk_offset: CoordinatePair,
     loaded_count: usize,
     chunk_info: Box<[ChunkMetadata]>,
+    /// The first sector after every allocated sector, where relocated chunks are written.
+    next_sector: u32,
 }
 
 impl Region {
@@ -632,19 +712,32 @@ impl Region {
                 .await?;
             let mut region = Region {
                 file: Arc::new(Mutex::new(file)),
+                directory: root_directory.to_path_buf(),
                 chunk_offset,
                 chunk_info: chunk_info.into_boxed_slice(),
                 loaded_count: 0,
+                next_sector: 2,
             };
             region.read_file_header().await?;
 
             Ok(region)
         } else {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .open(file_path)
+                .await?;
+            // Write an empty location and timestamp table
+            file.write_all(&[0; 2 * SECTOR_SIZE as usize]).await?;
+
             Ok(Region {
-                file: Arc::new(Mutex::new(File::create(file_path).await?)),
+                file: Arc::new(Mutex::new(file)),
+                directory: root_directory.to_path_buf(),
                 chunk_offset,
                 chunk_info: chunk_info.into_boxed_slice(),
                 loaded_count: 0,
+                next_sector: 2,
             })
         }
     }
@@ -674,6 +767,12 @@ impl Region {
 
             // Big endian 4-byte integer
             chunk_info.last_saved = BigEndian::read_u32(&buffer[i .. i + 4]);
+
+            if chunk_info.sector_offset != 0 {
+                self.next_sector = self
+                    .next_sector
+                    .max(chunk_info.sector_offset + chunk_info.sector_count as u32);
+            }
         }
 
         Ok(())
@@ -732,6 +831,9 @@ impl Region {
 
         // The sector offset accounts for the tables at the beginning
         let seek_offset = (chunk_info.sector_offset as u64) * 4096;
+        let sector_count = chunk_info.sector_count;
+        let external_path =
+            external_chunk_path(&self.directory, absolute_position.as_chunk().into());
 
         Ok(Some(async move {
             let mut file_lock = file.lock().await;
@@ -741,13 +843,14 @@ impl Region {
             let mut buf = [0u8; 4];
             file_lock.read_exact(&mut buf).await?;
             let length = BigEndian::read_u32(&buf) as usize;
+            check_chunk_length(length, sector_count)?;
 
             // Safety:
             // This is not UB because while the memory is uninitalized, u8s are still valid
             // We are only ever reading from the file which does not depend on the memory in the Vec
             // This allows us to avoid UB as the read will initialize the memory with valid data
             #[allow(clippy::uninit_vec)]
-            let buf = {
+            let mut buf = {
                 let mut buf: Vec<u8> = Vec::with_capacity(length);
                 // Safety: see above
                 unsafe {
@@ -759,10 +862,94 @@ impl Region {
             };
             drop(file_lock);
 
+            if is_external_chunk(&buf) {
+                let external_data = tokio::fs::read(external_path).await?;
+                resolve_external_chunk(&mut buf, external_data);
+            }
+
             Ok(buf)
         }))
     }
 
+    /// Allocates sectors for the given compressed chunk data and updates the chunk's entry in the
+    /// header, returning a future which writes the data and header to the region file. Chunks
+    /// which still fit in their sectors are written in place, otherwise they are moved to the end
+    /// of the file and their old sectors are left unused. Chunks too large for a region file are
+    /// written to an external file instead.
+    fn write_chunk(
+        &mut self,
+        absolute_position: Coordinate,
+        mut chunk_data: Vec<u8>,
+    ) -> Result<impl Future<Output = Result<(), IoError>>, ChunkDecodeError> {
+        let index = self.index_absolute(absolute_position.as_chunk().into());
+        let external_path =
+            external_chunk_path(&self.directory, absolute_position.as_chunk().into());
+
+        // Only the compression scheme is kept in the region file for external chunks
+        let external_data = match sectors_for_chunk(chunk_data.len()) {
+            Some(_) => None,
+            None => {
+                let external_data = chunk_data.split_off(1);
+                chunk_data[0] |= EXTERNAL_CHUNK_FLAG;
+                Some(external_data)
+            }
+        };
+        // A single byte always fits in one sector
+        let sector_count = sectors_for_chunk(chunk_data.len()).unwrap_or(1);
+
+        let next_sector = &mut self.next_sector;
+        let chunk_info = match self.chunk_info.get_mut(index) {
+            Some(chunk_info) => chunk_info,
+            None =>
+                return Err(ChunkDecodeError::ChunkRegionDesync(
+                    absolute_position.as_chunk(),
+                )),
+        };
+
+        if chunk_info.sector_offset == 0 || chunk_info.sector_count < sector_count {
+            chunk_info.sector_offset = *next_sector;
+            *next_sector += sector_count as u32;
+        }
+        chunk_info.sector_count = sector_count;
+        chunk_info.last_saved = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |time| time.as_secs() as u32);
+
+        let sector_offset = chunk_info.sector_offset;
+        let last_saved = chunk_info.last_saved;
+        let file = self.file.clone();
+
+        Ok(async move {
+            match external_data {
+                Some(external_data) => tokio::fs::write(&external_path, external_data).await?,
+                None =>
+                    if tokio::fs::metadata(&external_path).await.is_ok() {
+                        tokio::fs::remove_file(&external_path).await?;
+                    },
+            }
+
+            let mut sectors = Vec::with_capacity(sector_count as usize * SECTOR_SIZE as usize);
+            sectors.extend_from_slice(&(chunk_data.len() as u32).to_be_bytes());
+            sectors.extend_from_slice(&chunk_data);
+            sectors.resize(sector_count as usize * SECTOR_SIZE as usize, 0);
+
+            let mut location = [0u8; 4];
+            BigEndian::write_u24(&mut location, sector_offset);
+            location[3] = sector_count;
+
+            let mut file = file.lock().await;
+            file.seek(SeekFrom::Start(sector_offset as u64 * SECTOR_SIZE))
+                .await?;
+            file.write_all(&sectors).await?;
+            file.seek(SeekFrom::Start(index as u64 * 4)).await?;
+            file.write_all(&location).await?;
+            file.seek(SeekFrom::Start(SECTOR_SIZE + index as u64 * 4))
+                .await?;
+            file.write_all(&last_saved.to_be_bytes()).await?;
+            file.flush().await
+        })
+    }
+
     fn mark_chunk_inactive(&mut self, absolute_position: Coordinate) {
         let chunk = match self.chunk_info_at_mut(absolute_position) {
             Some(chunk) => chunk,
@@ -807,3 +994,114 @@ impl ChunkMetadata {
         self.last_saved == 0
     }
 }
+
+#[test]
+fn write_region_chunks() {
+    use crate::world::chunk::region::RegionFile;
+
+    let directory = std::env::temp_dir().join("quartz_write_region_chunks");
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let chunk_data = |x_pos: i32, padding: usize| {
+        let mut nbt = NbtCompound::new();
+        nbt.insert("xPos", x_pos);
+        nbt.insert("padding", vec![0i64; padding]);
+        let mut data = Vec::new();
+        nbt_io::write_nbt(&mut data, None, &nbt, Flavor::Uncompressed).unwrap();
+        compress_chunk(&data, ChunkCompression::Uncompressed).unwrap()
+    };
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        let mut region = Region::new(&directory, Coordinate::chunk(0, 0))
+            .await
+            .unwrap();
+        // The second chunk grows past its sector and is moved, and the third is too large for
+        // the region file
+        for (x, padding) in [(0, 0), (1, 0), (1, 1000), (2, 200_000)] {
+            let write = region
+                .write_chunk(Coordinate::chunk(x, 0), chunk_data(x, padding))
+                .unwrap();
+            write.await.unwrap();
+        }
+        assert_eq!(region.next_sector, 7);
+    });
+
+    let mut region = RegionFile::open(directory.join("r.0.0.mca")).unwrap();
+    let chunks = region.iter_chunks().collect::<Result<Vec<_>, _>>();
+    let _ = std::fs::remove_dir_all(&directory);
+    let chunks = chunks.unwrap();
+
+    assert_eq!(chunks.len(), 3);
+    for (x_pos, (coords, nbt)) in chunks.into_iter().enumerate() {
+        assert_eq!(coords, CoordinatePair::new(x_pos as i32, 0));
+        assert_eq!(nbt.get::<_, i32>("xPos").unwrap(), x_pos as i32);
+    }
+}
+
+#[test]
+fn reload_saved_chunk() {
+    use crate::{
+        block::BlockStateImpl,
+        world::chunk::{gen::SimpleChunkGenerator, Section, SectionStore},
+        Registry,
+    };
+    use qdat::{block::states::BlockStateData, world::location::BlockPosition};
+
+    type Provider = ChunkProvider<SimpleChunkGenerator>;
+
+    let _ = Registry::init();
+
+    let directory =
+        std::env::temp_dir().join(format!("quartz_reload_saved_chunk_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let coords = Coordinate::chunk(2, -1);
+    let pos = BlockPosition {
+        x: 33,
+        y: 70,
+        z: -14,
+    };
+    let mut section_store = SectionStore::new(1);
+    section_store.insert(Section::empty(4)).unwrap();
+    let mut chunk = Chunk::new(
+        CoordinatePair::new(32, -16),
+        section_store,
+        NbtCompound::new(),
+    );
+    chunk.set_block_state_at(pos, BlockStateData::Stone.id());
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let loaded = rt.block_on(async {
+        let store = Arc::new(RegionHandler::new(
+            directory.clone(),
+            ChunkCompression::Zlib,
+        ));
+        drop(store.region_at_mut(coords).await.unwrap());
+        store.cache_chunk(chunk);
+
+        // Unloading the only chunk in the region writes it and unloads the region, so the chunk
+        // is read back from the region file
+        Provider::handle_unload(coords, Arc::clone(&store))
+            .await
+            .unwrap();
+        assert!(store.loaded_region_at(coords).is_none());
+        Provider::handle_load_full(coords, store).await
+    });
+    let _ = std::fs::remove_dir_all(&directory);
+
+    let loaded = loaded.unwrap().unwrap();
+    assert_eq!(
+        loaded.block_state_at(pos).map(|state| state.id()),
+        Some(BlockStateData::Stone.id())
+    );
+    assert!(!loaded.is_dirty());
+}
@@ -0,0 +1,557 @@
+use crate::world::chunk::ChunkDecodeError;
+use byteorder::{BigEndian, ByteOrder};
+use flate2::{
+    write::{GzDecoder, GzEncoder, ZlibDecoder, ZlibEncoder},
+    Compression,
+};
+use qdat::world::location::CoordinatePair;
+use quartz_nbt::{
+    io::{self as nbt_io, Flavor},
+    NbtCompound,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// The size of a sector in a region file in bytes.
+pub(crate) const SECTOR_SIZE: u64 = 4096;
+/// The bit set in the compression scheme of a chunk which was too large for its region file, and
+/// is instead stored in a separate `c.<x>.<z>.mcc` file.
+pub(crate) const EXTERNAL_CHUNK_FLAG: u8 = 0x80;
+
+/// The compression scheme of a chunk stored in a region file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkCompression {
+    /// GZip compression, which is not used in practice.
+    #[serde(rename = "gzip")]
+    GZip = 1,
+    /// Zlib compression, the default used by vanilla.
+    #[default]
+    Zlib = 2,
+    Uncompressed = 3,
+    /// LZ4 compression in the block stream format of lz4-java, which vanilla can be configured to
+    /// use since 1.20.5.
+    #[cfg(feature = "lz4")]
+    Lz4 = 4,
+}
+
+impl ChunkCompression {
+    /// Returns the compression scheme with the given ID, ignoring the external chunk flag.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id & !EXTERNAL_CHUNK_FLAG {
+            1 => Some(ChunkCompression::GZip),
+            2 => Some(ChunkCompression::Zlib),
+            3 => Some(ChunkCompression::Uncompressed),
+            #[cfg(feature = "lz4")]
+            4 => Some(ChunkCompression::Lz4),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            ChunkCompression::GZip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            ChunkCompression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            ChunkCompression::Uncompressed => Ok(data.to_vec()),
+            #[cfg(feature = "lz4")]
+            ChunkCompression::Lz4 => Ok(lz4::compress(data)),
+        }
+    }
+
+    pub fn decompress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            ChunkCompression::GZip => {
+                let mut decoder = GzDecoder::new(Vec::new());
+                decoder.write_all(data)?;
+                decoder.finish()
+            }
+            ChunkCompression::Zlib => {
+                let mut decoder = ZlibDecoder::new(Vec::new());
+                decoder.write_all(data)?;
+                decoder.finish()
+            }
+            ChunkCompression::Uncompressed => Ok(data.to_vec()),
+            #[cfg(feature = "lz4")]
+            ChunkCompression::Lz4 => lz4::decompress(data),
+        }
+    }
+}
+
+/// Decompresses the data of a chunk in a region file. The first byte of the data should be the
+/// compression scheme. Chunks stored in external files must be resolved with
+/// [`resolve_external_chunk`] first.
+pub(crate) fn decompress_chunk(chunk_data: &[u8]) -> Result<Vec<u8>, ChunkDecodeError> {
+    let (&compression, data) = chunk_data
+        .split_first()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+
+    if compression & EXTERNAL_CHUNK_FLAG != 0 {
+        return Err(ChunkDecodeError::UnresolvedExternalChunk);
+    }
+
+    let compression = ChunkCompression::from_id(compression)
+        .ok_or(ChunkDecodeError::UnknownCompression(compression))?;
+    Ok(compression.decompress(data)?)
+}
+
+/// Compresses the given NBT data with the given scheme, prefixing it with the scheme's ID so it
+/// can be written to a region file.
+pub fn compress_chunk(nbt_data: &[u8], compression: ChunkCompression) -> io::Result<Vec<u8>> {
+    let mut chunk_data = compression.compress(nbt_data)?;
+    chunk_data.insert(0, compression.id());
+    Ok(chunk_data)
+}
+
+/// Checks that the length read from the header of a chunk in a region file fits in the sectors
+/// allocated to the chunk, so that a corrupt header can't cause a huge allocation.
+pub(crate) fn check_chunk_length(length: usize, sector_count: u8) -> io::Result<()> {
+    // The length excludes the four bytes of the length itself
+    if length + 4 > sector_count as usize * SECTOR_SIZE as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Chunk length {length} is larger than the {sector_count} sectors allocated to it"
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the number of sectors needed to store chunk data of the given length in a region file,
+/// or `None` if the chunk is too large and must be stored in an external file.
+pub(crate) fn sectors_for_chunk(length: usize) -> Option<u8> {
+    // The data is prefixed with its length
+    let sectors = (length + 4).div_ceil(SECTOR_SIZE as usize);
+    u8::try_from(sectors).ok()
+}
+
+/// Returns the path of the file a chunk is stored in if it is too large for its region file.
+pub(crate) fn external_chunk_path(directory: &Path, chunk: CoordinatePair) -> PathBuf {
+    directory.join(format!("c.{}.{}.mcc", chunk.x, chunk.z))
+}
+
+/// Replaces the data of a chunk marked as stored externally with the contents of its external
+/// file.
+pub(crate) fn resolve_external_chunk(chunk_data: &mut Vec<u8>, external_data: Vec<u8>) {
+    chunk_data.truncate(1);
+    chunk_data[0] &= !EXTERNAL_CHUNK_FLAG;
+    chunk_data.extend(external_data);
+}
+
+/// Returns whether the given chunk data from a region file only marks the chunk as being stored
+/// in an external file.
+#[inline]
+pub(crate) fn is_external_chunk(chunk_data: &[u8]) -> bool {
+    chunk_data
+        .first()
+        .is_some_and(|&compression| compression & EXTERNAL_CHUNK_FLAG != 0)
+}
+
+/// A synchronous, read-only view of a region file, useful for analyzing worlds outside of the
+/// chunk provider.
+pub struct RegionFile<R> {
+    reader: R,
+    chunk_offset: CoordinatePair,
+    locations: Box<[u8]>,
+    /// The directory containing the region, where oversized chunks are stored.
+    directory: Option<PathBuf>,
+}
+
+impl RegionFile<File> {
+    /// Opens the region file at the given path. The region's coordinates are parsed from the file
+    /// name, which should be in the form `r.<x>.<z>.mca`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let region = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(parse_region_file_name)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid region file name {}", path.display()),
+                )
+            })?;
+
+        let mut region_file = Self::new(File::open(path)?, region)?;
+        region_file.directory = path.parent().map(Path::to_path_buf);
+        Ok(region_file)
+    }
+}
+
+impl<R: Read + Seek> RegionFile<R> {
+    /// Creates a region file view over the given reader, reading the location table in the
+    /// header. The given coordinates are the coordinates of the region, not of a chunk. Chunks
+    /// stored outside of the region file cannot be read through a region file created this way.
+    pub fn new(mut reader: R, region: CoordinatePair) -> io::Result<Self> {
+        let mut locations = vec![0; SECTOR_SIZE as usize];
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut locations)?;
+
+        Ok(RegionFile {
+            reader,
+            chunk_offset: CoordinatePair::new(region.x * 32, region.z * 32),
+            locations: locations.into_boxed_slice(),
+            directory: None,
+        })
+    }
+
+    /// Iterates over every chunk present in this region file, yielding the absolute chunk
+    /// coordinates and NBT data of each chunk. Empty entries in the location table are skipped.
+    pub fn iter_chunks(
+        &mut self,
+    ) -> impl Iterator<Item = Result<(CoordinatePair, NbtCompound), ChunkDecodeError>> + '_ {
+        let chunk_offset = self.chunk_offset;
+        let present = self
+            .locations
+            .chunks_exact(4)
+            .enumerate()
+            .filter(|(_, entry)| entry[3] != 0)
+            .map(|(index, entry)| {
+                let coords = CoordinatePair::new(
+                    chunk_offset.x + (index % 32) as i32,
+                    chunk_offset.z + (index / 32) as i32,
+                );
+                (coords, BigEndian::read_u24(entry), entry[3])
+            })
+            .collect::<Vec<_>>();
+        let reader = &mut self.reader;
+        let directory = self.directory.as_deref();
+
+        present
+            .into_iter()
+            .map(move |(coords, sector_offset, sector_count)| {
+                Self::read_chunk(reader, directory, coords, sector_offset, sector_count)
+                    .map(|nbt| (coords, nbt))
+            })
+    }
+
+    fn read_chunk(
+        reader: &mut R,
+        directory: Option<&Path>,
+        coords: CoordinatePair,
+        sector_offset: u32,
+        sector_count: u8,
+    ) -> Result<NbtCompound, ChunkDecodeError> {
+        reader.seek(SeekFrom::Start(sector_offset as u64 * SECTOR_SIZE))?;
+
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf)?;
+        let length = BigEndian::read_u32(&buf) as usize;
+        check_chunk_length(length, sector_count)?;
+
+        let mut chunk_data = vec![0; length];
+        reader.read_exact(&mut chunk_data)?;
+
+        if let (true, Some(directory)) = (is_external_chunk(&chunk_data), directory) {
+            let external_data = fs::read(external_chunk_path(directory, coords))?;
+            resolve_external_chunk(&mut chunk_data, external_data);
+        }
+
+        let decompressed = decompress_chunk(&chunk_data)?;
+        let (nbt, _) = nbt_io::read_nbt(&mut Cursor::new(decompressed), Flavor::Uncompressed)?;
+        Ok(nbt)
+    }
+}
+
+fn parse_region_file_name(name: &str) -> Option<CoordinatePair> {
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+
+    match parts.next() {
+        Some(_) => None,
+        None => Some(CoordinatePair::new(x, z)),
+    }
+}
+
+/// The block stream format written by lz4-java's `LZ4BlockOutputStream`, which vanilla uses for
+/// LZ4 compressed chunks.
+#[cfg(feature = "lz4")]
+mod lz4 {
+    use byteorder::{ByteOrder, LittleEndian};
+    use std::{hash::Hasher, io};
+    use twox_hash::XxHash32;
+
+    const MAGIC: &[u8] = b"LZ4Block";
+    const HEADER_LEN: usize = MAGIC.len() + 13;
+    const METHOD_RAW: u8 = 0x10;
+    const METHOD_LZ4: u8 = 0x20;
+    const BLOCK_SIZE: usize = 1 << 16;
+    /// The compression level lz4-java writes for blocks of `BLOCK_SIZE`.
+    const COMPRESSION_LEVEL: u8 = 6;
+    const CHECKSUM_SEED: u32 = 0x9747b28c;
+
+    fn checksum(data: &[u8]) -> u32 {
+        let mut hasher = XxHash32::with_seed(CHECKSUM_SEED);
+        hasher.write(data);
+        hasher.finish() as u32 & 0x0FFF_FFFF
+    }
+
+    fn invalid_data(message: &'static str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+
+    fn write_header(
+        output: &mut Vec<u8>,
+        method: u8,
+        compressed_len: usize,
+        original_len: usize,
+        checksum: u32,
+    ) {
+        let mut lengths = [0u8; 12];
+        LittleEndian::write_u32(&mut lengths[0 .. 4], compressed_len as u32);
+        LittleEndian::write_u32(&mut lengths[4 .. 8], original_len as u32);
+        LittleEndian::write_u32(&mut lengths[8 .. 12], checksum);
+
+        output.extend_from_slice(MAGIC);
+        output.push(method | COMPRESSION_LEVEL);
+        output.extend_from_slice(&lengths);
+    }
+
+    pub fn compress(data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+
+        for block in data.chunks(BLOCK_SIZE) {
+            let compressed = lz4_flex::block::compress(block);
+            let (method, body) = if compressed.len() < block.len() {
+                (METHOD_LZ4, compressed.as_slice())
+            } else {
+                (METHOD_RAW, block)
+            };
+
+            write_header(
+                &mut output,
+                method,
+                body.len(),
+                block.len(),
+                checksum(block),
+            );
+            output.extend_from_slice(body);
+        }
+
+        // An empty block marks the end of the stream
+        write_header(&mut output, METHOD_RAW, 0, 0, 0);
+        output
+    }
+
+    pub fn decompress(mut data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut output = Vec::new();
+
+        loop {
+            if data.len() < HEADER_LEN || !data.starts_with(MAGIC) {
+                return Err(invalid_data("Invalid LZ4 block header"));
+            }
+
+            let header = &data[MAGIC.len() .. HEADER_LEN];
+            let method = header[0] & 0xF0;
+            let compressed_len = LittleEndian::read_u32(&header[1 .. 5]) as usize;
+            let original_len = LittleEndian::read_u32(&header[5 .. 9]) as usize;
+            let expected_checksum = LittleEndian::read_u32(&header[9 .. 13]);
+            data = &data[HEADER_LEN ..];
+
+            if original_len == 0 {
+                return Ok(output);
+            }
+
+            let block = data
+                .get(.. compressed_len)
+                .ok_or_else(|| invalid_data("Truncated LZ4 block"))?;
+            data = &data[compressed_len ..];
+
+            let start = output.len();
+            match method {
+                METHOD_RAW => output.extend_from_slice(block),
+                METHOD_LZ4 => {
+                    output.resize(start + original_len, 0);
+                    let len = lz4_flex::block::decompress_into(block, &mut output[start ..])
+                        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                    if len != original_len {
+                        return Err(invalid_data("LZ4 block has the wrong decompressed length"));
+                    }
+                }
+                _ => return Err(invalid_data("Unknown LZ4 block compression method")),
+            }
+
+            if checksum(&output[start ..]) != expected_checksum {
+                return Err(invalid_data("LZ4 block checksum mismatch"));
+            }
+        }
+    }
+}
+
+#[test]
+fn iter_region_chunks() {
+    use quartz_nbt::NbtTag;
+
+    let mut file = vec![0u8; 2 * SECTOR_SIZE as usize];
+
+    // Place chunks at local positions (0, 0) and (1, 1), or indices 0 and 33
+    for (sector, (index, x_pos)) in [(0usize, 0i32), (33, 1)].into_iter().enumerate() {
+        let sector = sector as u32 + 2;
+
+        let mut nbt = NbtCompound::new();
+        nbt.insert("xPos", NbtTag::Int(x_pos));
+        let mut compressed = Vec::new();
+        nbt_io::write_nbt(&mut compressed, None, &nbt, Flavor::ZlibCompressed).unwrap();
+
+        BigEndian::write_u24(&mut file[index * 4 .. index * 4 + 3], sector);
+        file[index * 4 + 3] = 1;
+
+        file.resize(sector as usize * SECTOR_SIZE as usize, 0);
+        let mut length = [0u8; 4];
+        BigEndian::write_u32(&mut length, compressed.len() as u32 + 1);
+        file.extend_from_slice(&length);
+        file.push(2);
+        file.extend_from_slice(&compressed);
+    }
+
+    let mut region = RegionFile::new(Cursor::new(file), CoordinatePair::new(-1, 0)).unwrap();
+    let chunks = region.iter_chunks().collect::<Result<Vec<_>, _>>().unwrap();
+
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].0, CoordinatePair::new(-32, 0));
+    assert_eq!(chunks[0].1.get::<_, i32>("xPos").unwrap(), 0);
+    assert_eq!(chunks[1].0, CoordinatePair::new(-31, 1));
+    assert_eq!(chunks[1].1.get::<_, i32>("xPos").unwrap(), 1);
+}
+
+#[test]
+fn oversized_chunk_length() {
+    // A single sector claiming to hold a chunk of 4GiB
+    let mut file = vec![0u8; 3 * SECTOR_SIZE as usize];
+    BigEndian::write_u24(&mut file[0 .. 3], 2);
+    file[3] = 1;
+    BigEndian::write_u32(&mut file[2 * SECTOR_SIZE as usize ..], u32::MAX);
+
+    let mut region = RegionFile::new(Cursor::new(file), CoordinatePair::new(0, 0)).unwrap();
+    let chunks = region.iter_chunks().collect::<Vec<_>>();
+    assert!(matches!(
+        &chunks[..],
+        [Err(ChunkDecodeError::StdIo(error))] if error.kind() == io::ErrorKind::InvalidData
+    ));
+
+    assert!(check_chunk_length(4092, 1).is_ok());
+    assert!(check_chunk_length(4093, 1).is_err());
+
+    assert_eq!(sectors_for_chunk(4092), Some(1));
+    assert_eq!(sectors_for_chunk(4093), Some(2));
+    assert_eq!(sectors_for_chunk(255 * 4096 - 4), Some(255));
+    assert_eq!(sectors_for_chunk(255 * 4096 - 3), None);
+}
+
+#[test]
+fn region_file_names() {
+    assert_eq!(
+        parse_region_file_name("r.-1.2.mca"),
+        Some(CoordinatePair::new(-1, 2))
+    );
+    assert_eq!(parse_region_file_name("r.0.mca"), None);
+    assert_eq!(parse_region_file_name("level.dat"), None);
+}
+
+#[test]
+fn mixed_compression_region() {
+    let directory = std::env::temp_dir().join("quartz_mixed_compression_region");
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    let chunk_nbt = |x_pos: i32| {
+        let mut nbt = NbtCompound::new();
+        nbt.insert("xPos", x_pos);
+        let mut data = Vec::new();
+        nbt_io::write_nbt(&mut data, None, &nbt, Flavor::Uncompressed).unwrap();
+        data
+    };
+
+    let mut file = vec![0u8; 2 * SECTOR_SIZE as usize];
+    let chunks = [
+        compress_chunk(&chunk_nbt(0), ChunkCompression::Zlib).unwrap(),
+        compress_chunk(&chunk_nbt(1), ChunkCompression::GZip).unwrap(),
+        vec![ChunkCompression::Uncompressed.id() | EXTERNAL_CHUNK_FLAG],
+    ];
+    for (index, chunk_data) in chunks.iter().enumerate() {
+        let sector = index as u32 + 2;
+        BigEndian::write_u24(&mut file[index * 4 .. index * 4 + 3], sector);
+        file[index * 4 + 3] = 1;
+
+        file.resize(sector as usize * SECTOR_SIZE as usize, 0);
+        let mut length = [0u8; 4];
+        BigEndian::write_u32(&mut length, chunk_data.len() as u32);
+        file.extend_from_slice(&length);
+        file.extend_from_slice(chunk_data);
+    }
+    fs::write(directory.join("r.0.0.mca"), file).unwrap();
+    fs::write(
+        external_chunk_path(&directory, CoordinatePair::new(2, 0)),
+        chunk_nbt(2),
+    )
+    .unwrap();
+
+    let mut region = RegionFile::open(directory.join("r.0.0.mca")).unwrap();
+    let chunks = region.iter_chunks().collect::<Result<Vec<_>, _>>();
+    let _ = fs::remove_dir_all(&directory);
+    let chunks = chunks.unwrap();
+
+    assert_eq!(chunks.len(), 3);
+    for (x_pos, (coords, nbt)) in chunks.into_iter().enumerate() {
+        assert_eq!(coords, CoordinatePair::new(x_pos as i32, 0));
+        assert_eq!(nbt.get::<_, i32>("xPos").unwrap(), x_pos as i32);
+    }
+
+    #[cfg(feature = "lz4")]
+    {
+        let data = chunk_nbt(3).repeat(10_000);
+        let compressed = compress_chunk(&data, ChunkCompression::Lz4).unwrap();
+        assert_eq!(decompress_chunk(&compressed).unwrap(), data);
+    }
+
+    assert!(matches!(
+        decompress_chunk(&[5, 0]),
+        Err(ChunkDecodeError::UnknownCompression(5))
+    ));
+}
+
+#[test]
+fn chunk_nbt_preserves_key_order() {
+    let keys = ["zPos", "xPos", "Status", "DataVersion"];
+    let mut nbt = NbtCompound::new();
+    for (value, key) in keys.iter().enumerate() {
+        nbt.insert(*key, value as i32);
+    }
+
+    assert_eq!(
+        nbt.inner().keys().map(String::as_str).collect::<Vec<_>>(),
+        keys
+    );
+
+    let mut data = Vec::new();
+    nbt_io::write_nbt(&mut data, None, &nbt, Flavor::Uncompressed).unwrap();
+    let offsets = keys
+        .iter()
+        .map(|key| {
+            data.windows(key.len())
+                .position(|window| window == key.as_bytes())
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+}
@@ -1,21 +1,27 @@
 use crate::{
     block::{BlockStateImpl, StateBuilder},
     world::chunk::{
+        BiomeContainer,
         ChunkDecodeError,
         CompactStateBuffer,
         InsertionResult,
         Palette,
         RemovalResult,
+        BIOMES_PER_SECTION,
         DIRECT_PALETTE_THRESHOLD,
+        MIN_BITS_PER_BLOCK,
     },
     BlockState,
+    Registry,
     StateID,
 };
 use qdat::{
     block::states::{is_air, AIR},
     world::lighting::{LightBuffer, Lighting},
     UlnStr,
+    UnlocalizedName,
 };
+use quartz_nbt::{NbtCompound, NbtList};
 use quartz_net::{
     packet_data::{ClientSection, SectionAndLightData, SectionData},
     BitMask,
@@ -42,7 +48,9 @@ pub struct Section {
     is_pal_direct: bool,
     palette: Palette,
     states: CompactStateBuffer,
+    biomes: BiomeContainer,
     lighting: Lighting,
+    dirty: bool,
 }
 
 impl Section {
@@ -58,7 +66,9 @@ impl Section {
             is_pal_direct: false,
             palette,
             states,
+            biomes: BiomeContainer::default(),
             lighting: Lighting::new(),
+            dirty: false,
         }
     }
 
@@ -107,10 +117,138 @@ impl Section {
             is_pal_direct: palette.bits_per_block().get() >= DIRECT_PALETTE_THRESHOLD,
             palette,
             states,
+            biomes: BiomeContainer::default(),
             lighting,
+            dirty: false,
         })
     }
 
+    /// Reads a section in the format used since 1.18, where the block states are stored as a
+    /// palette and data array under `block_states`.
+    pub(crate) fn from_anvil_nbt(nbt: &NbtCompound) -> Result<Self, ChunkDecodeError> {
+        let y = SectionY::from(nbt.get::<_, i8>("Y")?);
+
+        let (palette, states) = match nbt.get::<_, &NbtCompound>("block_states") {
+            Ok(block_states) => {
+                let mut palette = Palette::new();
+
+                for entry in block_states
+                    .get::<_, &NbtList>("palette")?
+                    .iter_map::<&NbtCompound>()
+                {
+                    let entry = entry?;
+                    let raw_name = entry.get::<_, &str>("Name")?;
+                    let name = UlnStr::from_str(raw_name).map_err(|_| {
+                        ChunkDecodeError::UnknownStateProperty(format!(
+                            "Invalid block name {raw_name} in section {y}"
+                        ))
+                    })?;
+                    let mut state = BlockState::builder(name)
+                        .ok_or_else(|| ChunkDecodeError::UnknownBlockState(name.to_owned()))?;
+
+                    if let Ok(properties) = entry.get::<_, &NbtCompound>("Properties") {
+                        for (name, value) in properties.inner() {
+                            let value = <&str>::try_from(value)?;
+                            state
+                                .add_property(name, value)
+                                .map_err(ChunkDecodeError::UnknownStateProperty)?;
+                        }
+                    }
+
+                    palette.insert(state.build().id());
+                }
+
+                // A section made up of a single state leaves out its data
+                let data = match block_states.get::<_, &[i64]>("data") {
+                    Ok(data) => data.iter().map(|&x| x as u64).collect(),
+                    Err(_) =>
+                        vec![
+                            0;
+                            CompactStateBuffer::required_capacity(palette.bits_per_block().get())
+                        ],
+                };
+
+                let states = CompactStateBuffer::new(data, palette.bits_per_block());
+                (palette, states)
+            }
+            Err(_) => (Palette::new(), CompactStateBuffer::empty()),
+        };
+
+        let biomes = match nbt.get::<_, &NbtCompound>("biomes") {
+            Ok(biomes) => BiomeContainer::from_anvil_nbt(biomes)?,
+            Err(_) => BiomeContainer::default(),
+        };
+
+        let mut lighting = Lighting::new();
+        if let Ok(block_light) = nbt.get::<_, &[u8]>("BlockLight") {
+            lighting.init_block(block_light)?;
+        }
+        if let Ok(sky_light) = nbt.get::<_, &[u8]>("SkyLight") {
+            lighting.init_sky(sky_light)?;
+        }
+
+        Ok(Section {
+            y,
+            is_pal_direct: palette.bits_per_block().get() >= DIRECT_PALETTE_THRESHOLD,
+            palette,
+            states,
+            biomes,
+            lighting,
+            dirty: false,
+        })
+    }
+
+    /// Writes this section in the format read by [`from_anvil_nbt`](Self::from_anvil_nbt). The
+    /// palette is rebuilt from the states actually present, so the data array is left out if there
+    /// is only one.
+    pub(crate) fn to_anvil_nbt(&self) -> NbtCompound {
+        let mut nbt = NbtCompound::new();
+        nbt.insert("Y", i8::from(self.y));
+
+        if !self.states.is_empty() {
+            let mut palette = Vec::new();
+            let mut palette_indices = HashMap::new();
+            let indices = (0 .. 4096)
+                .map(|index| {
+                    let state = self.block_state_at(index).unwrap_or(AIR);
+                    *palette_indices.entry(state).or_insert_with(|| {
+                        palette.push(state);
+                        palette.len() - 1
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut block_states = NbtCompound::new();
+            block_states.insert(
+                "palette",
+                NbtList::from(
+                    palette
+                        .into_iter()
+                        .map(state_to_anvil_nbt)
+                        .collect::<Vec<_>>(),
+                ),
+            );
+            if palette_indices.len() > 1 {
+                let bits = u32::max(
+                    MIN_BITS_PER_BLOCK as u32,
+                    bits_for_palette(palette_indices.len()),
+                );
+                block_states.insert("data", pack_palette_indices(&indices, bits));
+            }
+            nbt.insert("block_states", block_states);
+            nbt.insert("biomes", self.biomes.to_anvil_nbt());
+        }
+
+        if let Some(block_light) = self.lighting.block_light() {
+            nbt.insert("BlockLight", block_light.data.to_vec());
+        }
+        if let Some(sky_light) = self.lighting.sky_light() {
+            nbt.insert("SkyLight", sky_light.data.to_vec());
+        }
+
+        nbt
+    }
+
     pub fn is_empty(&self) -> bool {
         let quick_check = self.palette.states().all(is_air);
 
@@ -164,9 +302,21 @@ impl Section {
         self.add_state_to_palette(state);
 
         self.set_state_internal(index, state);
+        self.dirty = true;
         last_state_id
     }
 
+    /// Returns whether the blocks in this section changed since it was loaded or last saved
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    #[inline]
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
+    }
+
     /// Removes all unused states from the palette
     ///
     /// If in indirect mode, adjusts the indexes in the [CompactStateBuffer] to compensate
@@ -272,6 +422,42 @@ impl Section {
         }
     }
 
+    /// Returns the biome of every 4x4x4 cell in this section
+    pub fn biomes(&self) -> &BiomeContainer {
+        &self.biomes
+    }
+
+    /// Sets the biome of the 4x4x4 cell at the given cell coordinates, marking the section as
+    /// changed if the biome is different. Returns the old biome.
+    pub fn set_biome(
+        &mut self,
+        x: usize,
+        y: usize,
+        z: usize,
+        biome: UnlocalizedName,
+    ) -> UnlocalizedName {
+        let old = self.biomes.set_biome(x, y, z, biome);
+        if old != *self.biomes.get_biome(x, y, z) {
+            self.dirty = true;
+        }
+        old
+    }
+
+    /// Replaces the biomes of this section from the numeric IDs of a whole chunk, starting at the
+    /// bottom of section zero, the way older chunks and the generator store them. Sections outside
+    /// of the IDs keep their biomes, and the section isn't marked as changed.
+    pub(crate) fn load_network_biomes(&mut self, ids: &[i32]) {
+        let y = i8::from(self.y);
+        if y < 0 {
+            return;
+        }
+
+        let start = y as usize * BIOMES_PER_SECTION;
+        if let Some(ids) = ids.get(start .. start + BIOMES_PER_SECTION) {
+            self.biomes = BiomeContainer::from_network_ids(ids);
+        }
+    }
+
     pub fn lighting(&self) -> &Lighting {
         &self.lighting
     }
@@ -348,18 +534,330 @@ impl WriteToPacket for Section {
     }
 }
 
+/// Writes a block state as a palette entry, with its name and the value of each of its properties.
+fn state_to_anvil_nbt(state: StateID) -> NbtCompound {
+    let mut nbt = NbtCompound::new();
+    let state = match Registry::state_for_id(state) {
+        Some(state) => state,
+        None => {
+            nbt.insert("Name", "minecraft:air");
+            return nbt;
+        }
+    };
+
+    nbt.insert("Name", state.handle.name.to_string());
+    let properties = state
+        .handle
+        .state_properties(state.id())
+        .unwrap_or_default();
+    if !properties.is_empty() {
+        let mut properties_nbt = NbtCompound::new();
+        for (name, value) in properties {
+            properties_nbt.insert(name, value);
+        }
+        nbt.insert("Properties", properties_nbt);
+    }
+
+    nbt
+}
+
+/// Returns the number of bits needed to index a palette of the given length.
+#[inline]
+pub(crate) fn bits_for_palette(len: usize) -> u32 {
+    usize::BITS - len.saturating_sub(1).leading_zeros()
+}
+
+/// Packs palette indices into longs the way they have been stored since 1.16, where each long
+/// holds as many whole entries as fit and the remaining high bits are left unused.
+pub(crate) fn pack_palette_indices(indices: &[usize], bits: u32) -> Vec<i64> {
+    let per_long = (64 / bits) as usize;
+    indices
+        .chunks(per_long)
+        .map(|entries| {
+            entries.iter().enumerate().fold(0u64, |long, (i, &index)| {
+                long | (index as u64) << (i as u32 * bits)
+            }) as i64
+        })
+        .collect()
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SectionY {
     pub raw: i8,
 }
 
-impl SectionY {
+impl Display for SectionY {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.raw, f)
+    }
+}
+
+impl Debug for SectionY {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl From<i8> for SectionY {
+    fn from(raw: i8) -> Self {
+        SectionY { raw }
+    }
+}
+
+impl From<SectionY> for i8 {
+    fn from(y: SectionY) -> Self {
+        y.raw
+    }
+}
+
+impl Serialize for SectionY {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: serde::Serializer {
+        serializer.serialize_i8(self.raw)
+    }
+}
+
+impl<'de> Deserialize<'de> for SectionY {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: serde::Deserializer<'de> {
+        Ok(SectionY {
+            raw: Deserialize::deserialize(deserializer)?,
+        })
+    }
+}
+
+const OPT_SECTION_INDEX_NONE_NICHE: u8 = u8::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct OptionalSectionIndex {
+    repr: u8,
+}
+
+impl OptionalSectionIndex {
     #[inline]
-    pub fn as_index(&self) -> usize {
-        (self.raw + 1) as u8 as usize
+    const fn none() -> Self {
+        OptionalSectionIndex {
+            repr: OPT_SECTION_INDEX_NONE_NICHE,
+        }
+    }
+
+    #[inline]
+    fn some(index: usize) -> Self {
+        if cfg!(debug_assertions) && index >= OPT_SECTION_INDEX_NONE_NICHE as usize {
+            panic!(
+                "Attempted to construct an optional section index with an illegal index: {index}"
+            );
+        }
+
+        OptionalSectionIndex { repr: index as u8 }
+    }
+
+    #[inline]
+    fn as_option(&self) -> Option<usize> {
+        if self.repr != OPT_SECTION_INDEX_NONE_NICHE {
+            Some(self.repr as usize)
+        } else {
+            None
+        }
     }
 }
 
+pub struct SectionStore {
+    section_mapping: [OptionalSectionIndex; MAX_SECTION_COUNT],
+    sections: Vec<Section>,
+    min_section: i8,
+}
+
+impl SectionStore {
+    /// Creates a store for a world starting at zero, so with the light-only section at Y -1 as its
+    /// lowest section.
+    pub fn new(size: usize) -> SectionStore {
+        Self::with_min_section(size, -1)
+    }
+
+    /// Creates a store whose lowest section is at the given Y, such as -5 for the light-only
+    /// section below a world starting at -64.
+    pub fn with_min_section(size: usize, min_section: i8) -> SectionStore {
+        SectionStore {
+            section_mapping: [OptionalSectionIndex::none(); MAX_SECTION_COUNT],
+            sections: Vec::with_capacity(size),
+            min_section,
+        }
+    }
+
+    /// The Y of the lowest section this store can hold.
+    #[inline]
+    pub fn min_section(&self) -> i8 {
+        self.min_section
+    }
+
+    #[inline]
+    fn index_of(&self, y: SectionY) -> Option<usize> {
+        usize::try_from(y.raw as i16 - self.min_section as i16)
+            .ok()
+            .filter(|&index| index < MAX_SECTION_COUNT)
+    }
+
+    pub fn insert(&mut self, section: Section) -> Result<&mut Section, SectionInsertionError> {
+        let index = match self
+            .index_of(section.y)
+            .map(|index| &mut self.section_mapping[index])
+        {
+            Some(index) => index,
+            None => return Err(SectionInsertionError::IndexOutOfRange(section.y)),
+        };
+
+        if index.as_option().is_some() {
+            return Err(SectionInsertionError::AlreadyPresent(section.y));
+        }
+
+        *index = OptionalSectionIndex::some(self.sections.len());
+        self.sections.push(section);
+        Ok(self.sections.last_mut().unwrap())
+    }
+
+    pub fn get(&self, y: i8) -> Option<&Section> {
+        self.index_of(SectionY::from(y))
+            .and_then(|index| self.section_mapping[index].as_option())
+            .map(|index| &self.sections[index])
+    }
+
+    pub fn get_mut(&mut self, y: i8) -> Option<&mut Section> {
+        self.index_of(SectionY::from(y))
+            .and_then(|index| self.section_mapping[index].as_option())
+            .map(|index| &mut self.sections[index])
+    }
+
+    /// Builds a mask of the sections `f` returns true for, numbered the way clients expect for a
+    /// world starting at zero. With the boundary sections included bit 0 is the light-only
+    /// section at Y -1, otherwise bit 0 is section 0.
+    pub fn gen_bit_mask<F>(&self, include_boundary_sections: bool, mut f: F) -> BitMask
+    where F: FnMut(&Section) -> bool {
+        let (offset, bits) = if include_boundary_sections {
+            (1, 0 .. MAX_SECTION_COUNT as i16)
+        } else {
+            (0, 0 .. MAX_SECTION_COUNT as i16 - 2)
+        };
+
+        let mut mask = BitMask::new();
+        for (index, &map_index) in self.section_mapping.iter().enumerate() {
+            let bit = index as i16 + self.min_section as i16 + offset;
+            if let Some(map_index) = map_index.as_option() {
+                if bits.contains(&bit) && f(&self.sections[map_index]) {
+                    mask.set(bit as usize);
+                }
+            }
+        }
+
+        mask
+    }
+
+    pub fn into_packet_data(self) -> (ClientSection, Option<LightBuffer>, Option<LightBuffer>) {
+        let block_count = self.block_count() as i16;
+        let bits_per_block = self.palette.bits_per_block().get();
+        let palette = if self.is_pal_direct {
+            None
+        } else {
+            Some(
+                self.palette
+                    .index_to_state
+                    .into_iter()
+                    .map(|state| state as i32)
+                    .collect(),
+            )
+        };
+        let data = self.states.into_inner().into_boxed_slice();
+
+        (
+            ClientSection {
+                block_count,
+                bits_per_block,
+                palette,
+                data,
+            },
+            self.lighting.block,
+            self.lighting.sky,
+        )
+    }
+
+    #[inline]
+    fn map_state_entry(&self, entry: usize) -> Option<StateID> {
+        if !self.is_pal_direct {
+            self.palette.state_for(entry)
+        } else {
+            Some(entry as StateID)
+        }
+    }
+}
+
+impl WriteToPacket for Section {
+    fn write_to(&self, buffer: &mut PacketBuffer) {
+        buffer.write(&(self.block_count() as i16));
+        buffer.write_one(self.palette.bits_per_block().get());
+        if !self.is_pal_direct {
+            buffer.write_varying(&(self.palette.len() as i32));
+            self.palette
+                .states()
+                .for_each(|state| buffer.write_varying(&(state as i32)));
+        }
+        let data = self.states.inner();
+        buffer.write_varying(&(data.len() as i32));
+        buffer.write_array(data);
+    }
+}
+
+/// Writes a block state as a palette entry, with its name and the value of each of its properties.
+fn state_to_anvil_nbt(state: StateID) -> NbtCompound {
+    let mut nbt = NbtCompound::new();
+    let state = match Registry::state_for_id(state) {
+        Some(state) => state,
+        None => {
+            nbt.insert("Name", "minecraft:air");
+            return nbt;
+        }
+    };
+
+    nbt.insert("Name", state.handle.name.to_string());
+    let properties = state
+        .handle
+        .state_properties(state.id())
+        .unwrap_or_default();
+    if !properties.is_empty() {
+        let mut properties_nbt = NbtCompound::new();
+        for (name, value) in properties {
+            properties_nbt.insert(name, value);
+        }
+        nbt.insert("Properties", properties_nbt);
+    }
+
+    nbt
+}
+
+/// Returns the number of bits needed to index a palette of the given length.
+#[inline]
+pub(crate) fn bits_for_palette(len: usize) -> u32 {
+    usize::BITS - len.saturating_sub(1).leading_zeros()
+}
+
+/// Packs palette indices into longs the way they have been stored since 1.16, where each long
+/// holds as many whole entries as fit and the remaining high bits are left unused.
+pub(crate) fn pack_palette_indices(indices: &[usize], bits: u32) -> Vec<i64> {
+    let per_long = (64 / bits) as usize;
+    indices
+        .chunks(per_long)
+        .map(|entries| {
+            entries.iter().enumerate().fold(0u64, |long, (i, &index)| {
+                long | (index as u64) << (i as u32 * bits)
+            }) as i64
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SectionY {
+    pub raw: i8,
+}
+
 impl Display for SectionY {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         Display::fmt(&self.raw, f)
@@ -439,18 +937,44 @@ impl OptionalSectionIndex {
 pub struct SectionStore {
     section_mapping: [OptionalSectionIndex; MAX_SECTION_COUNT],
     sections: Vec<Section>,
+    min_section: i8,
 }
 
 impl SectionStore {
+    /// Creates a store for a world starting at zero, so with the light-only section at Y -1 as its
+    /// lowest section.
     pub fn new(size: usize) -> SectionStore {
+        Self::with_min_section(size, -1)
+    }
+
+    /// Creates a store whose lowest section is at the given Y, such as -5 for the light-only
+    /// section below a world starting at -64.
+    pub fn with_min_section(size: usize, min_section: i8) -> SectionStore {
         SectionStore {
             section_mapping: [OptionalSectionIndex::none(); MAX_SECTION_COUNT],
             sections: Vec::with_capacity(size),
+            min_section,
         }
     }
 
+    /// The Y of the lowest section this store can hold.
+    #[inline]
+    pub fn min_section(&self) -> i8 {
+        self.min_section
+    }
+
+    #[inline]
+    fn index_of(&self, y: SectionY) -> Option<usize> {
+        usize::try_from(y.raw as i16 - self.min_section as i16)
+            .ok()
+            .filter(|&index| index < MAX_SECTION_COUNT)
+    }
+
     pub fn insert(&mut self, section: Section) -> Result<&mut Section, SectionInsertionError> {
-        let index = match self.section_mapping.get_mut(section.y.as_index()) {
+        let index = match self
+            .index_of(section.y)
+            .map(|index| &mut self.section_mapping[index])
+        {
             Some(index) => index,
             None => return Err(SectionInsertionError::IndexOutOfRange(section.y)),
         };
@@ -465,16 +989,14 @@ impl SectionStore {
     }
 
     pub fn get(&self, y: i8) -> Option<&Section> {
-        self.section_mapping
-            .get(SectionY::from(y).as_index())
-            .and_then(OptionalSectionIndex::as_option)
+        self.index_of(SectionY::from(y))
+            .and_then(|index| self.section_mapping[index].as_option())
             .map(|index| &self.sections[index])
     }
 
     pub fn get_mut(&mut self, y: i8) -> Option<&mut Section> {
-        self.section_mapping
-            .get(SectionY::from(y).as_index())
-            .and_then(OptionalSectionIndex::as_option)
+        self.index_of(SectionY::from(y))
+            .and_then(|index| self.section_mapping[index].as_option())
             .map(|index| &mut self.sections[index])
     }
 
@@ -503,15 +1025,19 @@ impl SectionStore {
         let mut block_light_mask = BitMask::new();
         let mut sky_light_mask = BitMask::new();
 
+        // Clients count from the light-only section below a world starting at zero
+        self.sections.retain(|section| section.y.raw >= -1);
+        let index_of = |y: SectionY| (y.raw as i16 + 1) as usize;
+
         self.sections.sort_by_key(|section| section.y);
-        let max_idx = self.sections.last().map(|section| section.y.as_index());
+        let max_idx = self.sections.last().map(|section| index_of(section.y));
 
         let mut sections = Vec::with_capacity(self.sections.len().max(2) - 2);
         let mut block_light = Vec::new();
         let mut sky_light = Vec::new();
 
         for section in self.sections {
-            let index = section.y.as_index();
+            let index = index_of(section.y);
             let is_empty = section.is_empty();
 
             let (section, block, sky) = section.into_packet_data();
@@ -550,6 +1076,10 @@ impl SectionStore {
     pub fn sections(&self) -> &[Section] {
         &self.sections
     }
+
+    pub fn sections_mut(&mut self) -> &mut [Section] {
+        &mut self.sections
+    }
 }
 
 impl<'de> Deserialize<'de> for SectionStore {
@@ -596,7 +1126,8 @@ impl Display for SectionInsertionError {
             ),
             Self::IndexOutOfRange(y) => write!(
                 f,
-                "attempted to insert a section at y={y} which is out of range (max {MAX_SECTION_COUNT})"
+                "attempted to insert a section at y={y} which is out of range (max \
+                 {MAX_SECTION_COUNT})"
             ),
         }
     }
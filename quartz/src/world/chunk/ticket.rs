@@ -0,0 +1,194 @@
+use qdat::world::location::{Coordinate, CoordinatePair};
+use quartz_util::hash::NumHasher;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+/// A ticket attached to a chunk load which determines how urgently the load is handled and whether
+/// the chunk stays loaded once it's ready.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkTicket {
+    /// The chunk is near a player. These loads are served first, and the chunk stays loaded until
+    /// it is explicitly unloaded.
+    Player,
+    /// The chunk is being generated ahead of time. These loads are served once no player loads are
+    /// queued, and the chunk is evicted as soon as it is ready.
+    Generation,
+}
+
+impl ChunkTicket {
+    /// The priority of this ticket, higher priority loads are served first.
+    pub const fn priority(self) -> u8 {
+        match self {
+            ChunkTicket::Player => 1,
+            ChunkTicket::Generation => 0,
+        }
+    }
+
+    /// Whether or not a chunk loaded with this ticket should stay loaded.
+    pub const fn keeps_loaded(self) -> bool {
+        matches!(self, ChunkTicket::Player)
+    }
+}
+
+struct QueuedLoad {
+    coords: Coordinate,
+    ticket: ChunkTicket,
+    sequence: u64,
+}
+
+impl PartialEq for QueuedLoad {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedLoad {}
+
+impl PartialOrd for QueuedLoad {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedLoad {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Loads with equal priority are served in the order they were queued
+        self.ticket
+            .priority()
+            .cmp(&other.ticket.priority())
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// A queue of chunk loads ordered by the priority of their tickets. Each chunk is queued at most
+/// once, and raising the priority of a queued load leaves a stale entry in the heap which is
+/// skipped when popped.
+#[derive(Default)]
+pub struct TicketQueue {
+    heap: BinaryHeap<QueuedLoad>,
+    /// The sequence number and ticket of the current entry of each queued chunk.
+    queued: HashMap<CoordinatePair, (u64, ChunkTicket), NumHasher>,
+    next_sequence: u64,
+}
+
+impl TicketQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a load of the given chunk. If the chunk is already queued, then the load keeps the
+    /// ticket with the higher priority.
+    pub fn push(&mut self, coords: Coordinate, ticket: ChunkTicket) {
+        if !self.upgrade(coords, ticket) && !self.contains(coords) {
+            self.push_entry(coords, ticket);
+        }
+    }
+
+    /// Raises the priority of the queued load of the given chunk if the given ticket has a higher
+    /// priority, returning whether the load was changed. Chunks which aren't queued are ignored.
+    pub fn upgrade(&mut self, coords: Coordinate, ticket: ChunkTicket) -> bool {
+        match self.queued.get(&coords.as_chunk().into()) {
+            Some(&(_, current)) if current.priority() < ticket.priority() => {
+                self.push_entry(coords, ticket);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns whether a load of the given chunk is queued.
+    #[inline]
+    pub fn contains(&self, coords: Coordinate) -> bool {
+        self.queued.contains_key(&coords.as_chunk().into())
+    }
+
+    fn push_entry(&mut self, coords: Coordinate, ticket: ChunkTicket) {
+        self.queued
+            .insert(coords.as_chunk().into(), (self.next_sequence, ticket));
+        self.heap.push(QueuedLoad {
+            coords,
+            ticket,
+            sequence: self.next_sequence,
+        });
+        self.next_sequence += 1;
+    }
+
+    /// Removes and returns the load with the highest priority.
+    pub fn pop(&mut self) -> Option<(Coordinate, ChunkTicket)> {
+        while let Some(load) = self.heap.pop() {
+            let key = load.coords.as_chunk().into();
+
+            // Skip entries replaced by a higher priority ticket
+            if self
+                .queued
+                .get(&key)
+                .is_some_and(|&(sequence, _)| sequence == load.sequence)
+            {
+                self.queued.remove(&key);
+                return Some((load.coords, load.ticket));
+            }
+        }
+
+        None
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.queued.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+}
+
+#[test]
+fn player_ticket_jumps_queue() {
+    let mut queue = TicketQueue::new();
+    queue.push(Coordinate::chunk(0, 0), ChunkTicket::Generation);
+    queue.push(Coordinate::chunk(1, 0), ChunkTicket::Generation);
+    queue.push(Coordinate::chunk(2, 0), ChunkTicket::Player);
+
+    assert_eq!(
+        queue.pop(),
+        Some((Coordinate::chunk(2, 0), ChunkTicket::Player))
+    );
+    assert_eq!(
+        queue.pop(),
+        Some((Coordinate::chunk(0, 0), ChunkTicket::Generation))
+    );
+    assert_eq!(
+        queue.pop(),
+        Some((Coordinate::chunk(1, 0), ChunkTicket::Generation))
+    );
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn upgraded_ticket_is_reprioritized() {
+    let mut queue = TicketQueue::new();
+    queue.push(Coordinate::chunk(0, 0), ChunkTicket::Generation);
+    queue.push(Coordinate::chunk(1, 0), ChunkTicket::Generation);
+
+    assert!(queue.upgrade(Coordinate::chunk(1, 0), ChunkTicket::Player));
+    assert!(!queue.upgrade(Coordinate::chunk(1, 0), ChunkTicket::Player));
+    assert!(!queue.upgrade(Coordinate::chunk(2, 0), ChunkTicket::Player));
+    // Pushing a queued chunk again doesn't queue a second load
+    queue.push(Coordinate::chunk(0, 0), ChunkTicket::Generation);
+    assert_eq!(queue.len(), 2);
+
+    assert_eq!(
+        queue.pop(),
+        Some((Coordinate::chunk(1, 0), ChunkTicket::Player))
+    );
+    assert_eq!(
+        queue.pop(),
+        Some((Coordinate::chunk(0, 0), ChunkTicket::Generation))
+    );
+    // The stale entry for the upgraded chunk is skipped
+    assert_eq!(queue.pop(), None);
+    assert!(queue.is_empty());
+}
@@ -0,0 +1,49 @@
+use quartz_nbt::{NbtCompound, NbtReprError};
+use serde::{Deserialize, Serialize};
+
+/// The version of the data format a chunk or entity was saved with, stored under the
+/// `DataVersion` tag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DataVersion(pub i32);
+
+impl DataVersion {
+    /// The first snapshot (21w43a) to store sections at the root of the chunk compound.
+    pub const ROOT_SECTIONS: DataVersion = DataVersion(2844);
+    /// Minecraft 1.16.5.
+    pub const V1_16_5: DataVersion = DataVersion(2586);
+    /// Minecraft 1.17.1, the last release to store sections under the `Level` compound.
+    pub const V1_17_1: DataVersion = DataVersion(2730);
+    /// Minecraft 1.18.
+    pub const V1_18: DataVersion = DataVersion(2860);
+    /// Minecraft 1.18.2.
+    pub const V1_18_2: DataVersion = DataVersion(2975);
+    /// Minecraft 1.19.
+    pub const V1_19: DataVersion = DataVersion(3105);
+
+    /// Reads the data version of the given compound.
+    pub fn from_nbt(compound: &NbtCompound) -> Result<Self, NbtReprError> {
+        compound.get::<_, i32>("DataVersion").map(DataVersion)
+    }
+
+    /// Returns whether data of this version stores its sections in the 1.18 format, with paletted
+    /// biomes and no `Level` compound.
+    #[inline]
+    pub fn has_root_sections(self) -> bool {
+        self >= Self::ROOT_SECTIONS
+    }
+}
+
+#[test]
+fn version_from_chunk() {
+    let mut chunk = NbtCompound::new();
+    chunk.insert("DataVersion", 2975);
+
+    let version = DataVersion::from_nbt(&chunk).unwrap();
+    assert_eq!(version, DataVersion::V1_18_2);
+    assert!(version >= DataVersion::V1_18);
+    assert!(version.has_root_sections());
+    assert!(!DataVersion::V1_17_1.has_root_sections());
+
+    assert!(DataVersion::from_nbt(&NbtCompound::new()).is_err());
+}
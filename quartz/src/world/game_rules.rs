@@ -0,0 +1,24 @@
+/// The game rules which are shared by every world on the server.
+pub struct GameRules {
+    /// Whether the results of commands run by players are sent back to them, equivalent to the
+    /// `sendCommandFeedback` game rule. The console always receives command feedback.
+    pub send_command_feedback: bool,
+    /// Whether or not the time of day advances each tick, equivalent to the `doDaylightCycle` game
+    /// rule.
+    pub do_daylight_cycle: bool,
+}
+
+impl GameRules {
+    pub const fn new() -> Self {
+        GameRules {
+            send_command_feedback: true,
+            do_daylight_cycle: true,
+        }
+    }
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,18 +1,33 @@
 pub mod chunk {
+    mod biomes;
     mod chunk;
     mod error;
     pub mod gen;
+    mod heightmap;
     mod palette;
     pub mod provider;
+    pub mod region;
     mod section;
     mod states;
+    mod ticket;
+    mod version;
 
+    pub use biomes::*;
     pub use chunk::*;
     pub use error::*;
+    pub use heightmap::*;
     pub use palette::*;
     pub use provider::ChunkProvider;
     pub use section::*;
     pub use states::*;
+    pub use ticket::*;
+    pub use version::*;
 }
 
+pub mod game_rules;
+pub mod particle;
+pub mod predicate;
+pub mod seed;
+pub mod sound;
+pub mod time;
 pub mod world;
@@ -0,0 +1,180 @@
+use crate::{
+    block::BlockStateImpl,
+    item::ItemStack,
+    network::{packet_data::ParticleData, ClientBoundPacket},
+};
+use qdat::block::StateID;
+
+/// A particle, with typed variants for the particles which carry extra data.
+#[derive(Clone)]
+pub enum Particle {
+    /// Redstone dust with the given color, where each component is between zero and one.
+    Dust { color: [f32; 3], scale: f32 },
+    /// Dust which fades from one color to another.
+    DustColorTransition {
+        from: [f32; 3],
+        to: [f32; 3],
+        scale: f32,
+    },
+    /// The particles created when the given block state is broken.
+    Block(StateID),
+    /// Dust falling from the bottom of the given block state.
+    FallingDust(StateID),
+    /// The particles created when the given item breaks.
+    Item(ItemStack),
+    /// Any other particle, given by its network data.
+    Other(ParticleData),
+}
+
+impl Particle {
+    /// The particles created when the given block state is broken.
+    pub fn block_break<S: BlockStateImpl>(state: &S) -> Self {
+        Particle::Block(state.id())
+    }
+
+    /// Returns the network data of this particle.
+    pub fn to_data(&self) -> ParticleData {
+        match self {
+            &Particle::Dust {
+                color: [red, green, blue],
+                scale,
+            } => ParticleData::Dust {
+                red,
+                green,
+                blue,
+                scale,
+            },
+            &Particle::DustColorTransition { from, to, scale } =>
+                ParticleData::DustColorTransition {
+                    from_red: from[0],
+                    from_green: from[1],
+                    from_blue: from[2],
+                    scale,
+                    to_red: to[0],
+                    to_green: to[1],
+                    to_blue: to[2],
+                },
+            &Particle::Block(state) => ParticleData::Block(state as i32),
+            &Particle::FallingDust(state) => ParticleData::FallingDust(state as i32),
+            Particle::Item(stack) => ParticleData::Item(stack.into()),
+            Particle::Other(data) => data.clone(),
+        }
+    }
+}
+
+impl From<ParticleData> for Particle {
+    fn from(data: ParticleData) -> Self {
+        Particle::Other(data)
+    }
+}
+
+/// A particle effect at a position, which is converted into a particle packet.
+pub struct ParticleEffect {
+    particle: Particle,
+    position: [f64; 3],
+    offset: [f32; 3],
+    speed: f32,
+    count: i32,
+    long_distance: bool,
+}
+
+impl ParticleEffect {
+    /// Creates an effect which spawns a single particle at the given position.
+    pub fn new(particle: impl Into<Particle>, x: f64, y: f64, z: f64) -> Self {
+        ParticleEffect {
+            particle: particle.into(),
+            position: [x, y, z],
+            offset: [0.0; 3],
+            speed: 0.0,
+            count: 1,
+            long_distance: false,
+        }
+    }
+
+    /// Sets the spread of the particles along each axis. The client multiplies each offset by a
+    /// random gaussian value for each particle.
+    pub fn offset(mut self, x: f32, y: f32, z: f32) -> Self {
+        self.offset = [x, y, z];
+        self
+    }
+
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn count(mut self, count: i32) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Sets whether the particles are visible from up to 65536 blocks away rather than 256.
+    pub fn long_distance(mut self, long_distance: bool) -> Self {
+        self.long_distance = long_distance;
+        self
+    }
+
+    pub fn packet(&self) -> ClientBoundPacket {
+        let data = self.particle.to_data();
+        let [x, y, z] = self.position;
+        let [offset_x, offset_y, offset_z] = self.offset;
+
+        ClientBoundPacket::Particle {
+            particle_id: data.id(),
+            long_distance: self.long_distance,
+            x,
+            y,
+            z,
+            offset_x,
+            offset_y,
+            offset_z,
+            particle_data: self.speed,
+            particle_count: self.count,
+            data,
+        }
+    }
+}
+
+#[test]
+fn dust_and_block_particles() {
+    let packet = ParticleEffect::new(
+        Particle::Dust {
+            color: [1.0, 0.0, 0.0],
+            scale: 1.5,
+        },
+        0.5,
+        64.0,
+        0.5,
+    )
+    .count(10)
+    .packet();
+
+    assert!(matches!(
+        packet,
+        ClientBoundPacket::Particle {
+            particle_id: 15,
+            particle_count: 10,
+            data: ParticleData::Dust {
+                red,
+                green,
+                blue,
+                scale,
+            },
+            ..
+        } if red == 1.0 && green == 0.0 && blue == 0.0 && scale == 1.5
+    ));
+
+    let packet = ParticleEffect::new(Particle::Block(1), 0.0, 0.0, 0.0)
+        .offset(0.25, 0.25, 0.25)
+        .packet();
+
+    assert!(matches!(
+        packet,
+        ClientBoundPacket::Particle {
+            particle_id: 4,
+            offset_x,
+            data: ParticleData::Block(1),
+            ..
+        } if offset_x == 0.25
+    ));
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use qdat::UnlocalizedName;
+use quartz_datapack::data::{loot_tables::LootTableCondition, predicate::Predicate};
+
+use crate::world::chunk::gen::random::RandomSource;
+
+/// The state a predicate or loot condition is evaluated against.
+///
+/// Every condition which rolls a chance draws exactly one float from `random`, in the order the
+/// conditions are evaluated, so identically seeded sources always produce the same results.
+pub struct PredicateContext<'a> {
+    pub random: &'a mut dyn RandomSource,
+    /// The looting level of the weapon used to kill the entity being looted.
+    pub looting: i32,
+    pub killed_by_player: bool,
+    /// The radius of the explosion which destroyed the block or entity, if any.
+    pub explosion_radius: Option<f32>,
+    /// The enchantment levels of the tool being used.
+    pub tool_enchantments: HashMap<UnlocalizedName, i32>,
+    pub raining: bool,
+    pub thundering: bool,
+}
+
+impl<'a> PredicateContext<'a> {
+    pub fn new(random: &'a mut dyn RandomSource) -> Self {
+        PredicateContext {
+            random,
+            looting: 0,
+            killed_by_player: false,
+            explosion_radius: None,
+            tool_enchantments: HashMap::new(),
+            raining: false,
+            thundering: false,
+        }
+    }
+
+    /// Rolls a chance in the same way vanilla does, passing if a random float is below it.
+    fn roll(&mut self, chance: f32) -> bool {
+        self.random.next_float() < chance
+    }
+
+    fn survives_explosion(&mut self) -> bool {
+        match self.explosion_radius {
+            Some(radius) => self.random.next_float() <= 1.0 / radius,
+            None => true,
+        }
+    }
+
+    fn table_bonus(&mut self, enchantment: &UnlocalizedName, chances: &[f32]) -> bool {
+        let level = self
+            .tool_enchantments
+            .get(enchantment)
+            .copied()
+            .unwrap_or(0)
+            .max(0) as usize;
+        match chances.get(level).or_else(|| chances.last()) {
+            Some(&chance) => self.roll(chance),
+            None => false,
+        }
+    }
+
+    /// Evaluates the given predicate, returning `None` if it depends on state which cannot be
+    /// evaluated yet. Alternatives stop at the first passing term, so later terms roll nothing.
+    pub fn test(&mut self, predicate: &Predicate) -> Option<bool> {
+        match predicate {
+            Predicate::AllOf { terms } => self.test_all(terms),
+            Predicate::Alternative { terms } | Predicate::AnyOf { terms } => {
+                for term in terms {
+                    if self.test(term)? {
+                        return Some(true);
+                    }
+                }
+                Some(false)
+            }
+            Predicate::Inverted { term } => self.test(term).map(|passed| !passed),
+            &Predicate::KilledByPlayer { inverse } => Some(self.killed_by_player != inverse),
+            &Predicate::RandomChance { chance } => Some(self.roll(chance)),
+            &Predicate::RandomChanceWithLooting {
+                chance,
+                looting_multiplier,
+            } => Some(self.roll(chance + self.looting as f32 * looting_multiplier)),
+            Predicate::SurvivesExplosion => Some(self.survives_explosion()),
+            Predicate::TableBonus {
+                enchantment,
+                chances,
+            } => Some(self.table_bonus(enchantment, chances)),
+            &Predicate::WeatherCheck {
+                raining,
+                thundering,
+            } => Some(
+                raining.is_none_or(|raining| self.raining == raining)
+                    && thundering.is_none_or(|thundering| self.thundering == thundering),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Evaluates the given loot table condition, returning `None` if it depends on state which
+    /// cannot be evaluated yet.
+    pub fn test_loot_condition(&mut self, condition: &LootTableCondition) -> Option<bool> {
+        match condition {
+            LootTableCondition::Alternative { terms } => {
+                for term in terms {
+                    if self.test_loot_condition(term)? {
+                        return Some(true);
+                    }
+                }
+                Some(false)
+            }
+            LootTableCondition::Inverted { term } => self.test(term).map(|passed| !passed),
+            &LootTableCondition::RandomChance { chance } => Some(self.roll(chance)),
+            LootTableCondition::SurvivesExplosion => Some(self.survives_explosion()),
+            LootTableCondition::TableBonus {
+                enchantment,
+                chances,
+            } => Some(self.table_bonus(enchantment, chances)),
+            &LootTableCondition::WeatherCheck {
+                raining,
+                thundering,
+            } => Some(
+                raining.is_none_or(|raining| self.raining == raining)
+                    && thundering.is_none_or(|thundering| self.thundering == thundering),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Evaluates every condition in order, passing only if all of them pass. Evaluation stops at
+    /// the first failing condition.
+    pub fn test_all(&mut self, conditions: &[Predicate]) -> Option<bool> {
+        for condition in conditions {
+            if !self.test(condition)? {
+                return Some(false);
+            }
+        }
+        Some(true)
+    }
+}
+
+#[test]
+fn seeded_predicates_are_deterministic() {
+    use crate::world::chunk::gen::random::XoroshiroRandom;
+
+    let predicates = [
+        Predicate::RandomChance { chance: 0.5 },
+        Predicate::RandomChanceWithLooting {
+            chance: 0.1,
+            looting_multiplier: 0.2,
+        },
+        Predicate::Alternative {
+            terms: vec![
+                Predicate::RandomChance { chance: 0.3 },
+                Predicate::Inverted {
+                    term: Box::new(Predicate::RandomChance { chance: 0.6 }),
+                },
+            ],
+        },
+        Predicate::SurvivesExplosion,
+    ];
+
+    let evaluate = |seed| {
+        let mut random = XoroshiroRandom::new(seed);
+        let mut context = PredicateContext::new(&mut random);
+        context.looting = 2;
+        context.explosion_radius = Some(4.0);
+
+        (0 .. 64)
+            .flat_map(|_| {
+                predicates
+                    .iter()
+                    .map(|p| context.test(p).unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let results = evaluate(1234);
+    assert_eq!(results, evaluate(1234));
+    assert_ne!(results, evaluate(5678));
+    assert!(results.contains(&true) && results.contains(&false));
+
+    let mut random = XoroshiroRandom::new(0);
+    let mut context = PredicateContext::new(&mut random);
+    assert_eq!(
+        context.test(&Predicate::RandomChance { chance: 1.0 }),
+        Some(true)
+    );
+    assert_eq!(
+        context.test(&Predicate::RandomChance { chance: 0.0 }),
+        Some(false)
+    );
+    assert_eq!(context.test(&Predicate::SurvivesExplosion), Some(true));
+}
@@ -0,0 +1,32 @@
+/// Converts a seed entered by a user into a world seed the same way vanilla does. Numbers are used
+/// as they are, any other text is hashed with Java's `String::hashCode`, and an empty seed is
+/// replaced with a random one.
+pub fn parse_seed(input: &str) -> i64 {
+    if input.is_empty() {
+        return rand::random();
+    }
+
+    match input.parse::<i64>() {
+        // Vanilla treats a seed of zero as if no seed was given
+        Ok(0) => rand::random(),
+        Ok(seed) => seed,
+        Err(_) => java_string_hash(input) as i64,
+    }
+}
+
+/// Hashes a string the same way Java's `String::hashCode` does, which works on UTF-16 code units.
+fn java_string_hash(input: &str) -> i32 {
+    input.encode_utf16().fold(0i32, |hash, unit| {
+        hash.wrapping_mul(31).wrapping_add(unit as i32)
+    })
+}
+
+#[test]
+fn text_and_numeric_seeds() {
+    assert_eq!(parse_seed("glacier"), 108181935);
+    assert_eq!(parse_seed("hello world"), 1794106052);
+    assert_eq!(parse_seed("12345"), 12345);
+    assert_eq!(parse_seed("-12"), -12);
+    // Too large for a long, so it's hashed like any other text
+    assert_eq!(parse_seed("9223372036854775808"), -1773151197);
+}
@@ -0,0 +1,92 @@
+use crate::network::ClientBoundPacket;
+use qdat::UnlocalizedName;
+
+/// The volume slider in the client's settings which controls a sound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum SoundCategory {
+    Master = 0,
+    Music = 1,
+    Records = 2,
+    Weather = 3,
+    Blocks = 4,
+    Hostile = 5,
+    Neutral = 6,
+    Players = 7,
+    Ambient = 8,
+    Voice = 9,
+}
+
+/// A sound which can be played to clients.
+#[derive(Clone, Debug)]
+pub enum Sound {
+    /// A built-in sound, given by its id in the sound event registry.
+    Id(i32),
+    /// A sound given by name, which can refer to sounds added by resource packs.
+    Named(UnlocalizedName),
+}
+
+impl Sound {
+    /// Creates the packet which plays this sound at the given position.
+    pub fn packet(
+        &self,
+        category: SoundCategory,
+        x: f64,
+        y: f64,
+        z: f64,
+        volume: f32,
+        pitch: f32,
+    ) -> ClientBoundPacket {
+        // Positions are sent as fixed-point numbers with three fractional bits
+        let effect_position_x = (x * 8.0) as i32;
+        let effect_position_y = (y * 8.0) as i32;
+        let effect_position_z = (z * 8.0) as i32;
+
+        match self {
+            &Sound::Id(sound_id) => ClientBoundPacket::SoundEffect {
+                sound_id,
+                sound_category: category as i32,
+                effect_position_x,
+                effect_position_y,
+                effect_position_z,
+                volume,
+                pitch,
+            },
+            Sound::Named(sound_name) => ClientBoundPacket::NamedSoundEffect {
+                sound_name: sound_name.clone(),
+                sound_category: category as i32,
+                effect_position_x,
+                effect_position_y,
+                effect_position_z,
+                volume,
+                pitch,
+            },
+        }
+    }
+}
+
+impl From<UnlocalizedName> for Sound {
+    fn from(name: UnlocalizedName) -> Self {
+        Sound::Named(name)
+    }
+}
+
+#[test]
+fn sound_position_is_fixed_point() {
+    let packet = Sound::Named(UnlocalizedName::minecraft("block.note_block.harp")).packet(
+        SoundCategory::Records,
+        1.5,
+        -2.0,
+        0.25,
+        1.0,
+        1.0,
+    );
+
+    assert!(matches!(packet, ClientBoundPacket::NamedSoundEffect {
+        sound_category: 2,
+        effect_position_x: 12,
+        effect_position_y: -16,
+        effect_position_z: 2,
+        ..
+    }));
+}
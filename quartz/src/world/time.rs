@@ -0,0 +1,110 @@
+use crate::{network::ClientBoundPacket, world::game_rules::GameRules};
+
+/// The length of a full day/night cycle in ticks.
+pub const DAY_LENGTH: i64 = 24000;
+/// The number of ticks between each time update sent to clients.
+const TIME_UPDATE_INTERVAL: i64 = 20;
+/// The range of the time of day during which it is night.
+const NIGHT: std::ops::Range<i64> = 13000 .. 23000;
+
+/// Tracks the age of a world and its time of day.
+pub struct WorldTime {
+    world_age: i64,
+    time_of_day: i64,
+}
+
+impl WorldTime {
+    pub const fn new() -> Self {
+        WorldTime {
+            world_age: 0,
+            time_of_day: 0,
+        }
+    }
+
+    /// Advances the world by one tick. The time of day only advances if the `doDaylightCycle` game
+    /// rule is enabled.
+    pub fn tick(&mut self, game_rules: &GameRules) {
+        self.world_age += 1;
+
+        if game_rules.do_daylight_cycle {
+            self.time_of_day = (self.time_of_day + 1) % DAY_LENGTH;
+        }
+    }
+
+    #[inline]
+    pub fn world_age(&self) -> i64 {
+        self.world_age
+    }
+
+    #[inline]
+    pub fn time_of_day(&self) -> i64 {
+        self.time_of_day
+    }
+
+    /// Sets the time of day, wrapping the given number of ticks into a single day.
+    pub fn set_time(&mut self, ticks: i64) {
+        self.time_of_day = ticks.rem_euclid(DAY_LENGTH);
+    }
+
+    pub fn is_day(&self) -> bool {
+        !self.is_night()
+    }
+
+    pub fn is_night(&self) -> bool {
+        NIGHT.contains(&self.time_of_day)
+    }
+
+    /// Whether or not a time update should be sent to clients this tick.
+    pub fn should_send_update(&self) -> bool {
+        self.world_age % TIME_UPDATE_INTERVAL == 0
+    }
+
+    /// Creates a time update packet for the current time.
+    pub fn update_packet(&self, game_rules: &GameRules) -> ClientBoundPacket {
+        // A negative time of day tells the client not to advance the time on its own
+        let time_of_day = if game_rules.do_daylight_cycle {
+            self.time_of_day
+        } else {
+            -self.time_of_day.max(1)
+        };
+
+        ClientBoundPacket::TimeUpdate {
+            world_age: self.world_age,
+            time_of_day,
+        }
+    }
+}
+
+impl Default for WorldTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn time_wraps_after_day() {
+    let mut game_rules = GameRules::new();
+    let mut time = WorldTime::new();
+    for _ in 0 .. DAY_LENGTH {
+        time.tick(&game_rules);
+    }
+
+    assert_eq!(time.time_of_day(), 0);
+    assert_eq!(time.world_age(), DAY_LENGTH);
+
+    game_rules.do_daylight_cycle = false;
+    time.tick(&game_rules);
+    assert_eq!(time.time_of_day(), 0);
+    assert_eq!(time.world_age(), DAY_LENGTH + 1);
+}
+
+#[test]
+fn set_time_night() {
+    let mut time = WorldTime::new();
+    time.set_time(18000);
+    assert!(time.is_night());
+    assert!(!time.is_day());
+
+    time.set_time(6000);
+    assert!(time.is_day());
+}
@@ -14,16 +14,22 @@ use hecs::{Bundle, Entity, World as EntityStore};
 use qdat::{world::location::Coordinate, Gamemode, UnlocalizedName};
 
 use crate::{
+    config,
     entities::{
         player::{Player, PlayerInventory},
         Position,
     },
     network::AsyncWriteHandle,
-    server::ClientId,
-    world::chunk::{
-        provider::{MapRef, MapRefMut, ProviderRequest},
-        Chunk,
-        ChunkProvider,
+    server::{ClientId, ClientList},
+    world::{
+        chunk::{
+            provider::{MapRef, MapRefMut, ProviderRequest},
+            Chunk,
+            ChunkProvider,
+            ChunkTicket,
+        },
+        game_rules::GameRules,
+        time::WorldTime,
     },
 };
 
@@ -31,6 +37,7 @@ pub struct World {
     entities: Arc<RwLock<EntityStore>>,
     curr_players: HashMap<ClientId, Entity>,
     chunk_provider: ChunkProvider<super::chunk::gen::SimpleChunkGenerator>,
+    time: WorldTime,
 }
 
 
@@ -38,12 +45,13 @@ impl World {
     fn new<P: AsRef<Path>>(rt: Arc<Runtime>, world_path: P) -> std::io::Result<Self> {
         let entities = Arc::new(RwLock::new(EntityStore::new()));
         let curr_players = HashMap::new();
-        let chunk_provider = ChunkProvider::new(rt, world_path)?;
+        let chunk_provider = ChunkProvider::new(rt, world_path, config().read().chunk_compression)?;
 
         Ok(Self {
             entities,
             curr_players,
             chunk_provider,
+            time: WorldTime::new(),
         })
     }
 
@@ -130,6 +138,11 @@ impl World {
             .request(ProviderRequest::LoadFull(coords))
     }
 
+    pub fn load_chunk_with_priority(&mut self, coords: Coordinate, ticket: ChunkTicket) {
+        self.chunk_provider
+            .request_load_with_priority(coords, ticket)
+    }
+
     pub fn load_send_chunk(&self, coords: Coordinate, handle: AsyncWriteHandle) {
         self.chunk_provider
             .request(ProviderRequest::MinLoadSend { coords, handle })
@@ -151,6 +164,14 @@ impl World {
         self.chunk_provider.join_pending().await;
     }
 
+    pub fn time(&self) -> &WorldTime {
+        &self.time
+    }
+
+    pub fn time_mut(&mut self) -> &mut WorldTime {
+        &mut self.time
+    }
+
     pub fn get_players(&self) -> std::collections::hash_map::Iter<'_, ClientId, Entity> {
         self.curr_players.iter()
     }
@@ -251,6 +272,21 @@ impl WorldStore {
         })
     }
 
+    /// Advances the time in every world, periodically sending time updates to the players in each
+    /// world
+    pub fn tick(&mut self, clients: &ClientList, game_rules: &GameRules) {
+        for world in self.worlds.values_mut() {
+            world.time.tick(game_rules);
+
+            if world.time.should_send_update() {
+                clients.send_to_filtered(
+                    |_| world.time.update_packet(game_rules),
+                    |id| world.curr_players.contains_key(id),
+                );
+            }
+        }
+    }
+
     /// Flushes all the ready chunks into storage
     pub async fn flush_ready(&mut self) {
         for w in self.worlds.values_mut() {
@@ -300,3 +336,27 @@ pub enum Dimension {
     End,
     Custom(UnlocalizedName),
 }
+
+#[test]
+fn daylight_cycle_rule_stops_world_time() {
+    let mut server = crate::QuartzServer::for_test();
+    server.game_rules.do_daylight_cycle = false;
+    for _ in 0 .. 100 {
+        server
+            .world_store
+            .tick(&server.client_list, &server.game_rules);
+    }
+
+    for dim in [Dimension::Overworld, Dimension::Nether, Dimension::End] {
+        let world = server.world_store.get_world(dim).unwrap();
+        assert_eq!(world.time.time_of_day(), 0);
+        assert_eq!(world.time.world_age(), 100);
+    }
+
+    server.game_rules.do_daylight_cycle = true;
+    server
+        .world_store
+        .tick(&server.client_list, &server.game_rules);
+    let overworld = server.world_store.get_world(Dimension::Overworld).unwrap();
+    assert_eq!(overworld.time.time_of_day(), 1);
+}
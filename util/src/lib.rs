@@ -49,6 +49,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn target_levels_test() {
+        use log::{Level, LevelFilter};
+        use logging::TargetLevels;
+
+        let mut levels = TargetLevels::new(LevelFilter::Info);
+        levels.set("quartz::network", LevelFilter::Debug);
+
+        assert!(levels.enabled("quartz::network", Level::Debug));
+        assert!(levels.enabled("quartz::network::handler", Level::Debug));
+        assert!(!levels.enabled("quartz::networking", Level::Debug));
+        assert!(!levels.enabled("quartz::world::chunk", Level::Debug));
+        assert!(levels.enabled("quartz::world::chunk", Level::Info));
+        assert_eq!(levels.max_level(), LevelFilter::Debug);
+
+        levels.set("quartz::network::handler", LevelFilter::Warn);
+        assert!(!levels.enabled("quartz::network::handler", Level::Info));
+        assert!(levels.enabled("quartz::network::packet", Level::Debug));
+    }
+
     #[test]
     fn id_list_test() {
         let value1 = Identifiable::new(1);
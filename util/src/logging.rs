@@ -4,7 +4,7 @@ use std::{
     fs::{read_dir, remove_file, rename, File},
     io,
     path::Path,
-    sync::{Arc, Mutex as StdMutex},
+    sync::{Arc, Mutex as StdMutex, PoisonError, RwLock, RwLockReadGuard},
     thread,
 };
 
@@ -40,6 +40,8 @@ const LEVEL_FILTER: LevelFilter = LevelFilter::Debug;
 #[cfg(not(debug_assertions))]
 const LEVEL_FILTER: LevelFilter = LevelFilter::Info;
 
+static TARGET_LEVELS: RwLock<TargetLevels> = RwLock::new(TargetLevels::new(LEVEL_FILTER));
+
 /// Configures the log4rs crate to replicate the logging system for official minecraft servers.
 ///
 /// Console output is filtered so that only logs from the specified crate are accepted. Messages are
@@ -71,22 +73,26 @@ pub fn init_logger(
     let config = Config::builder()
         .appender(
             Appender::builder()
+                .filter(Box::new(TargetLevelFilter))
                 .filter(Box::new(CrateFilter::new(crate_filter)))
                 .build("console", Box::new(console)),
         )
         .appender(
             Appender::builder()
+                .filter(Box::new(TargetLevelFilter))
                 .filter(Box::new(CrateFilter::new(crate_filter)))
                 .build("logfile", Box::new(logfile)),
         )
+        // Levels are checked per target by the appender filters so they can be changed at runtime
         .build(
             Root::builder()
                 .appender("console")
                 .appender("logfile")
-                .build(LEVEL_FILTER),
+                .build(LevelFilter::Trace),
         )?;
 
     log4rs::init_config(config)?;
+    log::set_max_level(target_levels().max_level());
 
     Ok(())
 }
@@ -98,6 +104,91 @@ pub fn cleanup() {
     let _ = CustomLogRoller::new().roll_threaded(Path::new("./logs/latest.log"), false);
 }
 
+/// Sets the most verbose level logged for the given target and every module within it, such as
+/// `quartz::network`. The change applies immediately to both the console and the log files.
+pub fn set_level(target_prefix: &str, level: LevelFilter) {
+    let mut levels = TARGET_LEVELS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    levels.set(target_prefix, level);
+    log::set_max_level(levels.max_level());
+}
+
+fn target_levels() -> RwLockReadGuard<'static, TargetLevels> {
+    TARGET_LEVELS.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The log levels of individual targets, where the most specific target matching a record decides
+/// whether it is logged.
+#[derive(Debug, Clone)]
+pub struct TargetLevels {
+    default: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl TargetLevels {
+    /// Creates a set of levels where every target uses the given level.
+    pub const fn new(default: LevelFilter) -> Self {
+        TargetLevels {
+            default,
+            targets: Vec::new(),
+        }
+    }
+
+    /// Sets the level of the given target and every module within it, replacing any level
+    /// previously set for the same target.
+    pub fn set(&mut self, target_prefix: &str, level: LevelFilter) {
+        match self
+            .targets
+            .iter_mut()
+            .find(|(target, _)| target == target_prefix)
+        {
+            Some((_, old_level)) => *old_level = level,
+            None => self.targets.push((target_prefix.to_owned(), level)),
+        }
+    }
+
+    /// Returns the level of the given target, taken from the longest matching target prefix.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(prefix, _)| {
+                target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    /// Returns whether a record of the given level and target should be logged.
+    pub fn enabled(&self, target: &str, level: Level) -> bool {
+        level <= self.level_for(target)
+    }
+
+    /// Returns the most verbose level of any target.
+    pub fn max_level(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|&(_, level)| level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+// Rejects records more verbose than the level of their target
+#[derive(Debug)]
+struct TargetLevelFilter;
+
+impl Filter for TargetLevelFilter {
+    fn filter(&self, record: &Record) -> Response {
+        if target_levels().enabled(record.target(), record.level()) {
+            Response::Neutral
+        } else {
+            Response::Reject
+        }
+    }
+}
+
 // Only allow logging from out crate
 struct CrateFilter {
     filter: fn(&str) -> bool,